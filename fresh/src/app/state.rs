@@ -1,6 +1,6 @@
 use egui::{Context, Id};
-use crate::core::{Database, TableInfo};
-use crate::ui::{Sidebar, SidebarAction, QueryWindow, CsvImportDialog, FileConfigDialog, HomeScreen, PlotWindow, DuplicateDetectionDialog, DuplicateResultsViewer, TransformationDialog, TransformationManager};
+use crate::core::{Database, DateTimeFormat, QueryExecutor, QueryLibrary, QueryParameter, TableInfo};
+use crate::ui::{Sidebar, SidebarAction, QueryWindow, QueryHistoryWindow, QueryHistoryAction, TableProfileWindow, DataQualityWindow, CsvImportDialog, FileConfigDialog, HomeScreen, PlotSource, PlotWindow, DuplicateDetectionDialog, DuplicateResultsViewer, TransformationDialog, TransformationManager};
 use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -21,6 +21,10 @@ pub struct FreshApp<'a> {
     database_path: Option<std::path::PathBuf>,
     tables: Vec<TableInfo>,
     views: Vec<String>,
+    query_library: QueryLibrary,
+    query_history_window: QueryHistoryWindow,
+    table_profile_window: TableProfileWindow,
+    data_quality_window: DataQualityWindow,
     sidebar: Sidebar,
     home_screen: HomeScreen,
     query_windows: Vec<QueryWindow>,
@@ -32,6 +36,10 @@ pub struct FreshApp<'a> {
     transformation_dialog: TransformationDialog,
     transformation_manager: TransformationManager,
     next_window_id: usize,
+    // How date-times are shown; applied to every project opened
+    datetime_format: DateTimeFormat,
+    // strftime pattern being typed for a custom date-time format
+    datetime_pattern: String,
     error: Option<String>,
 }
 
@@ -43,6 +51,10 @@ impl<'a> FreshApp<'a> {
             database_path: None,
             tables: Vec::new(),
             views: Vec::new(),
+            query_library: QueryLibrary::in_memory(),
+            query_history_window: QueryHistoryWindow::new(),
+            table_profile_window: TableProfileWindow::new(),
+            data_quality_window: DataQualityWindow::new(),
             sidebar: Sidebar::new(),
             home_screen: HomeScreen::new(),
             query_windows: Vec::new(),
@@ -54,6 +66,8 @@ impl<'a> FreshApp<'a> {
             transformation_dialog: TransformationDialog::new(),
             transformation_manager: TransformationManager::new(),
             next_window_id: 0,
+            datetime_format: DateTimeFormat::default(),
+            datetime_pattern: DateTimeFormat::default().pattern().to_string(),
             error: None,
         }
     }
@@ -68,6 +82,32 @@ impl<'a> FreshApp<'a> {
                 ui.label(egui::RichText::new("Fresh").size(16.0).strong());
                 ui.separator();
                 
+                ui.menu_button("Date Format", |ui| {
+                    for format in DateTimeFormat::PRESETS {
+                        if ui.radio(self.datetime_format == format, format.label()).clicked() {
+                            self.datetime_pattern = format.pattern().to_string();
+                            self.set_datetime_format(format);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Custom:");
+                        ui.text_edit_singleline(&mut self.datetime_pattern)
+                            .on_hover_text("chrono strftime pattern, e.g. %Y-%m-%d %H:%M:%S%.f");
+                        if ui.button("Apply").clicked() {
+                            self.set_datetime_format(DateTimeFormat::Custom(self.datetime_pattern.clone()));
+                            ui.close_menu();
+                        }
+                    });
+                    ui.label(egui::RichText::new("Applies to results queried from now on").size(11.0).color(egui::Color32::from_gray(150)));
+                });
+                
+                if self.database.as_ref().is_some_and(|db| db.is_readonly()) {
+                    ui.label(egui::RichText::new("🔒 Read-only").color(egui::Color32::from_gray(180)))
+                        .on_hover_text("This project was opened in Viewer mode; only queries are allowed");
+                }
+                
             });
         });
         
@@ -94,10 +134,19 @@ impl<'a> FreshApp<'a> {
                     // Set darker background for the sidebar panel
                     ui.visuals_mut().widgets.noninteractive.bg_fill = egui::Color32::from_gray(30);
                     
-                    match self.sidebar.show(ctx, ui, &self.tables, &self.views) {
+                    let readonly = self.database.as_ref().is_some_and(|db| db.is_readonly());
+                    match self.sidebar.show(ctx, ui, &self.tables, &self.views, self.query_library.saved_queries(), readonly) {
                         SidebarAction::OpenTable(table_name) => {
                             self.open_query_window(&table_name);
                         }
+                        SidebarAction::ProfileTable(table_name) => {
+                            if let Some(db) = &self.database {
+                                self.table_profile_window.open(db.clone(), &table_name);
+                            }
+                        }
+                        SidebarAction::OpenDataQuality(table_name) => {
+                            self.data_quality_window.open(&table_name);
+                        }
                         SidebarAction::OpenDuplicateDetection => {
                             self.duplicate_detection_dialog.visible = true;
                             if let Some(db) = &self.database {
@@ -128,6 +177,38 @@ impl<'a> FreshApp<'a> {
                                 self.transformation_dialog.update_available_tables(db);
                             }
                         }
+                        SidebarAction::OpenQueryHistory => {
+                            self.query_history_window.visible = true;
+                        }
+                        SidebarAction::OpenSavedQuery(name) => {
+                            if let Some(query) = self.query_library.saved_query(&name) {
+                                let (sql, parameters) = (query.sql.clone(), query.parameters.clone());
+                                self.open_query_window_with_parameters(&name, sql, parameters);
+                            }
+                        }
+                        SidebarAction::DeleteSavedQuery(name) => {
+                            if let Err(e) = self.query_library.delete_saved_query(&name) {
+                                self.error = Some(format!("Failed to delete saved query: {}", e));
+                            }
+                        }
+                        SidebarAction::RenameTable { table, new_name } => {
+                            self.manage_table(|db| db.rename_table(&table, &new_name), "rename table");
+                        }
+                        SidebarAction::CopyTable { source, target } => {
+                            self.manage_table(|db| db.copy_table(&source, &target), "duplicate table");
+                        }
+                        SidebarAction::TruncateTable(table) => {
+                            self.manage_table(|db| db.truncate_table(&table), "truncate table");
+                        }
+                        SidebarAction::DropTable(table) => {
+                            self.manage_table(|db| db.drop_table(&table), "drop table");
+                        }
+                        SidebarAction::RefreshTable(table) => {
+                            self.manage_table(|db| db.refresh_table(&table), "refresh table");
+                        }
+                        SidebarAction::SetTableKeys { table, keys } => {
+                            self.manage_table(|db| db.set_table_keys(&table, keys), "declare table keys");
+                        }
                         SidebarAction::None => {}
                     }
                 });
@@ -171,19 +252,61 @@ impl<'a> FreshApp<'a> {
             for window in &mut self.query_windows {
                 if window.check_plot_request() {
                     if let Some(result) = window.get_current_result() {
-                        plot_requests.push(result.clone());
+                        plot_requests.push((result.clone(), window.plot_source()));
                     }
                 }
             }
             
             // Show query windows
+            let mut views_changed = false;
+            let mut history_entries = Vec::new();
+            let mut saved_queries = Vec::new();
             self.query_windows.retain_mut(|window| {
-                window.show(ctx, db.clone())
+                let open = window.show(ctx, db.clone());
+                views_changed |= window.check_views_changed();
+                history_entries.extend(window.take_history_entries());
+                saved_queries.extend(window.check_save_query_request());
+                open
             });
+            if views_changed {
+                self.load_tables();
+            }
+            
+            // Record executed statements and promoted queries in the project
+            for entry in history_entries {
+                if let Err(e) = self.query_library.record(entry) {
+                    eprintln!("[App] Failed to record query history: {}", e);
+                }
+            }
+            for query in saved_queries {
+                if let Err(e) = self.query_library.save_query_with_parameters(&query.name, &query.sql, &query.parameters) {
+                    self.error = Some(format!("Failed to save query: {}", e));
+                }
+            }
+            
+            match self.query_history_window.show(ctx, &self.query_library) {
+                Some(QueryHistoryAction::Open(sql)) => {
+                    self.open_query_window_with_sql("History", sql);
+                }
+                Some(QueryHistoryAction::SaveQuery { name, sql }) => {
+                    if let Err(e) = self.query_library.save_query(&name, &sql) {
+                        self.error = Some(format!("Failed to save query: {}", e));
+                    }
+                }
+                None => {}
+            }
+            
+            self.table_profile_window.show(ctx);
+            
+            if let Some(db) = self.database.clone() {
+                if self.data_quality_window.show(ctx, &db, &self.tables) {
+                    self.load_tables();
+                }
+            }
             
             // Create plot windows for requests (after query windows are processed)
-            for result in plot_requests {
-                self.create_plot_window(result);
+            for (result, source) in plot_requests {
+                self.create_plot_window(result, source);
             }
         }
         
@@ -211,6 +334,18 @@ impl<'a> FreshApp<'a> {
             self.plot_windows.remove(i);
         }
         
+        // Re-run the source query of plots whose parameters changed
+        if let Some(db) = &self.database {
+            for window in &mut self.plot_windows {
+                if let Some(source) = window.take_refresh_request() {
+                    match QueryExecutor::execute_with_params(db, &source.sql, &source.parameters) {
+                        Ok(result) => window.update_data(result),
+                        Err(e) => self.error = Some(format!("Failed to refresh plot: {}", e)),
+                    }
+                }
+            }
+        }
+        
         // Show CSV import dialog if active
         if let Some(dialog) = &mut self.csv_import_dialog {
             if !dialog.show(ctx) {
@@ -246,6 +381,7 @@ impl<'a> FreshApp<'a> {
             match Database::open_writable(&path) {
                 Ok(db) => {
                     self.database = Some(Arc::new(db));
+                    self.apply_datetime_format();
                     self.database_path = Some(path.clone());
                     
                     // Try to load tables from persistence
@@ -264,6 +400,7 @@ impl<'a> FreshApp<'a> {
                     }
                     
                     self.load_tables();
+                    self.load_query_library();
                     self.error = None;
                     
                     // Force a repaint to ensure the UI updates
@@ -276,6 +413,25 @@ impl<'a> FreshApp<'a> {
         }
     }
     
+    fn set_datetime_format(&mut self, format: DateTimeFormat) {
+        match format.validate() {
+            Ok(()) => {
+                self.datetime_format = format;
+                self.apply_datetime_format();
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    // Show the open project's results in the chosen date-time format
+    fn apply_datetime_format(&mut self) {
+        if let Some(db) = &self.database {
+            if let Err(e) = db.set_datetime_format(self.datetime_format.clone()) {
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+    
     fn open_database(&mut self) {
         if let Some(path) = rfd::FileDialog::new()
                             .add_filter("Project Folders", &["*"])
@@ -289,6 +445,7 @@ impl<'a> FreshApp<'a> {
             } {
                 Ok(db) => {
                     self.database = Some(Arc::new(db));
+                    self.apply_datetime_format();
                     self.database_path = Some(path.clone());
                     
                     // Try to load tables from persistence
@@ -307,6 +464,7 @@ impl<'a> FreshApp<'a> {
                     }
                     
                     self.load_tables();
+                    self.load_query_library();
                 }
                 Err(e) => {
                     self.error = Some(format!("Failed to open project: {}", e));
@@ -325,6 +483,7 @@ impl<'a> FreshApp<'a> {
                             match Database::open_readonly(&path) {
                 Ok(db) => {
                     self.database = Some(Arc::new(db));
+                    self.apply_datetime_format();
                     self.database_path = Some(path.clone());
                     
                     // Try to load tables from persistence
@@ -333,6 +492,7 @@ impl<'a> FreshApp<'a> {
                     }
                     
                     self.load_tables();
+                    self.load_query_library();
                     self.error = None;
                 }
                     Err(e) => {
@@ -345,6 +505,7 @@ impl<'a> FreshApp<'a> {
                     Ok(db) => {
                         // Use the same writable connection for both operations
                         self.database = Some(Arc::new(db));
+                        self.apply_datetime_format();
                         self.database_path = Some(path.clone());
                         
                         // Try to load tables from persistence
@@ -353,6 +514,7 @@ impl<'a> FreshApp<'a> {
                         }
                         
                         self.load_tables();
+                        self.load_query_library();
                         self.error = None;
                     }
                     Err(e) => {
@@ -364,18 +526,40 @@ impl<'a> FreshApp<'a> {
     }
     
     fn open_query_window(&mut self, table_name: &str) {
+        self.open_query_window_with_sql(table_name, format!("SELECT * FROM \"{}\"", table_name));
+    }
+    
+    fn open_query_window_with_sql(&mut self, title: &str, sql: String) {
+        self.open_query_window_with_parameters(title, sql, Vec::new());
+    }
+    
+    fn open_query_window_with_parameters(&mut self, title: &str, sql: String, parameters: Vec<QueryParameter>) {
         if let Some(_db) = &self.database {
             let window = QueryWindow::new(
                 self.next_window_id,
-                table_name.to_string(),
-                format!("SELECT * FROM \"{}\"", table_name),
-            );
+                title.to_string(),
+                sql,
+            ).with_parameters(parameters);
             self.query_windows.push(window);
             self.next_window_id += 1;
         }
     }
     
-    fn create_plot_window(&mut self, data: crate::core::QueryResult) {
+    /// Load the query history and saved queries of the opened project
+    fn load_query_library(&mut self) {
+        let (Some(db), Some(path)) = (&self.database, &self.database_path) else {
+            return;
+        };
+        self.query_library = match QueryLibrary::open(path, db.is_readonly()) {
+            Ok(library) => library,
+            Err(e) => {
+                self.error = Some(format!("Failed to load query history: {}", e));
+                QueryLibrary::in_memory()
+            }
+        };
+    }
+    
+    fn create_plot_window(&mut self, data: crate::core::QueryResult, source: PlotSource) {
         let window_id = self.next_window_id;
         self.next_window_id += 1;
         
@@ -389,8 +573,9 @@ impl<'a> FreshApp<'a> {
             pollster::block_on(plot_window.initialize_gpu_renderer());
         }
         
-        // Set the initial data
+        // Set the initial data and remember the query it came from
         plot_window.update_data(data);
+        plot_window.set_source(source);
         
         self.plot_windows.push(plot_window);
     }
@@ -405,20 +590,16 @@ impl<'a> FreshApp<'a> {
 
     // === HYBRID PERSISTENCE METHODS ===
 
-    /// Save all current tables in Arrow IPC format
+    /// Save all current tables in Arrow IPC and Parquet formats
     pub fn save_all_tables(&mut self) -> Result<Vec<String>, String> {
         if let Some(db) = &self.database {
             if let Some(path) = &self.database_path {
                 // Use the project folder directly for data storage
                 let data_dir = path;
                 
-                // Clone the database for mutable operations
-                let mut db_clone = (**db).clone();
-                match db_clone.save_all_tables(&data_dir) {
+                match db.save_all_tables(data_dir) {
                     Ok(saved_tables) => {
                         println!("[App] Saved {} tables to {:?}", saved_tables.len(), data_dir);
-                        // Update the stored database with the modified version
-                        self.database = Some(Arc::new(db_clone));
                         Ok(saved_tables)
                     }
                     Err(e) => Err(format!("Failed to save tables: {}", e))
@@ -438,13 +619,9 @@ impl<'a> FreshApp<'a> {
                 // Look for data files directly in the project folder
                 let data_dir = path;
                 
-                // Clone the database for mutable operations
-                let mut db_clone = (**db).clone();
-                match db_clone.load_all_tables_from_directory(&data_dir) {
+                match db.load_all_tables_from_directory(data_dir) {
                     Ok(loaded_tables) => {
                         println!("[App] Loaded {} tables from {:?}", loaded_tables.len(), data_dir);
-                        // Update the stored database with the modified version
-                        self.database = Some(Arc::new(db_clone));
                         // Refresh the tables list
                         self.load_tables();
                         Ok(loaded_tables)
@@ -465,13 +642,9 @@ impl<'a> FreshApp<'a> {
             if let Some(path) = &self.database_path {
                 let data_dir = path;
                 
-                // Clone the database for mutable operations
-                let mut db_clone = (**db).clone();
-                match db_clone.save_table_dual(table_name, &data_dir) {
+                match db.save_table_dual(table_name, data_dir) {
                     Ok(_) => {
                         println!("[App] Saved table '{}' in both formats", table_name);
-                        // Update the stored database with the modified version
-                        self.database = Some(Arc::new(db_clone));
                         Ok(())
                     }
                     Err(e) => Err(format!("Failed to save table '{}': {}", table_name, e))
@@ -488,17 +661,13 @@ impl<'a> FreshApp<'a> {
     pub fn import_csv_with_persistence(&mut self, csv_path: &std::path::Path, table_name: &str, 
                                      delimiter: char, has_header: bool) -> Result<(), String> {
         if let Some(db) = &self.database {
-            // Clone the database for mutable operations
-            let mut db_clone = (**db).clone();
             // Import CSV into DataFusion
-            match db_clone.stream_insert_csv(table_name, csv_path, delimiter, has_header) {
+            match db.stream_insert_csv(table_name, csv_path, delimiter, has_header) {
                 Ok(_) => {
                     // Save in both formats immediately
-                    match db_clone.save_table_dual(table_name, &self.database_path.as_ref().unwrap()) {
+                    match db.save_table_dual(table_name, self.database_path.as_ref().unwrap()) {
                         Ok(_) => {
                             println!("[App] Imported and saved table '{}'", table_name);
-                            // Update the stored database with the modified version
-                            self.database = Some(Arc::new(db_clone));
                             // Refresh tables list
                             self.load_tables();
                             Ok(())
@@ -513,6 +682,17 @@ impl<'a> FreshApp<'a> {
         }
     }
 
+    // Run a table operation from the sidebar and refresh the table list
+    fn manage_table(&mut self, operation: impl FnOnce(&Database) -> crate::core::error::Result<()>, description: &str) {
+        let Some(db) = &self.database else {
+            return;
+        };
+        match operation(db) {
+            Ok(()) => self.load_tables(),
+            Err(e) => self.error = Some(format!("Failed to {}: {}", description, e)),
+        }
+    }
+
     fn load_tables(&mut self) {
         if let Some(db) = &self.database {
             match db.get_tables() {
@@ -538,18 +718,14 @@ impl<'a> FreshApp<'a> {
 
     fn refresh_database(&mut self) {
         if let Some(db_path) = &self.database_path {
-            if let Some(db) = &mut self.database {
-                // Clone the database for mutable operations
-                let mut db_clone = (**db).clone();
-                match db_clone.load_all_tables_from_directory(db_path) {
+            if let Some(db) = &self.database {
+                match db.load_all_tables_from_directory(db_path) {
                     Ok(loaded_tables) => {
                         if !loaded_tables.is_empty() {
-                            // Update the stored database with the modified version
-                            self.database = Some(Arc::new(db_clone));
                             self.load_tables();
                             self.error = Some(format!("Refreshed database: loaded {} new tables", loaded_tables.len()));
                         } else {
-                            self.error = Some("No new Arrow files found in database directory".to_string());
+                            self.error = Some("No new Arrow or Parquet files found in database directory".to_string());
                         }
                     }
                     Err(e) => {
@@ -0,0 +1,535 @@
+//! Headless subcommands, so pipelines built in the GUI can run from shell scripts and
+//! cron jobs: `fresh import`, `query`, `tables`, `schema`, `dedupe` and `transform`.
+//! Results go to stdout, progress and statement statuses to stderr.
+
+use std::path::{Path, PathBuf};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::pretty::pretty_format_batches;
+use crate::core::database::quote_identifier;
+use crate::core::error::{FreshError, Result};
+use crate::core::{export, file_import, params, script, CsvIngestOptions, Database, DataTransformer, DuplicateDetectionConfig, DuplicateDetector, ExportFormat, ExportOptions, FileConfig, ParquetCompression, QueryParameter, StatementKind, StatementStatus, TransformationConfig};
+
+/// Exit code of a command that failed
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code for unknown commands and malformed arguments
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "\
+Usage: fresh <command> [options]
+
+Commands:
+  import <project> <file.csv>...   Import CSV files as tables
+      --table <name>               Table name (one file only; default: file name)
+      --delimiter <char>           Field delimiter (default: detected)
+      --header-row <n>             Line of the header, from 1 (default: 1)
+      --null <value>               Value read as NULL; repeat for several
+      --time-zone <zone>           Zone of date-times written without an offset
+      --primary-key <a,b>          Columns that identify each row
+      --compression <codec>        Parquet codec: uncompressed, snappy, gzip, lz4, zstd
+      --batch-size <rows>          Rows decoded per batch
+      --memory-limit-mb <mb>       Abort a table that grows past this size
+  query <project> [sql]            Run SQL (or a script) and print the last result
+      --file <path>                Read the SQL from a file
+      --param <name=value>         Value of a :name or $1 parameter; repeat for several
+      --output <path>              Write all rows to a file instead of printing them
+      --format <format>            csv, parquet, arrow, arrows, ndjson or json
+                                   (default: from the output extension)
+      --delimiter, --quote <char>  CSV delimiter and quote character
+      --null <value>               CSV text for NULL
+      --no-header                  CSV without a header line
+      --read-only                  Refuse statements that change the project
+  tables <project>                 List tables with their row and column counts
+  schema <project> [table]         Show the columns, types and keys of tables
+  dedupe <project> <table>         Find rows that repeat within groups
+      --config <path>              JSON duplicate detection settings
+      --output <path>              Write the rows without duplicates as an Arrow file
+  transform <project> <table>      Add derived columns to a table
+      --config <path>              JSON transformation step, or a list of steps
+      --table <name>               Save the result as a table in the project
+      --output <path>              Write the result to a file (format options as for query)
+  help                             Show this message
+
+Without a command the graphical application starts.";
+
+/// Run the command in `args` (the arguments after the program name) and return the
+/// process exit code
+pub fn run(args: &[String]) -> i32 {
+    let command = match Command::parse(args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    match command.run() {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+enum Command {
+    Import {
+        project: PathBuf,
+        files: Vec<FileConfig>,
+        primary_key: Vec<String>,
+        compression: ParquetCompression,
+        ingest_options: CsvIngestOptions,
+    },
+    Query {
+        project: PathBuf,
+        sql: String,
+        parameters: Vec<(String, String)>,
+        output: Option<PathBuf>,
+        export_options: ExportOptions,
+        read_only: bool,
+    },
+    Tables {
+        project: PathBuf,
+    },
+    Schema {
+        project: PathBuf,
+        table: Option<String>,
+    },
+    Dedupe {
+        project: PathBuf,
+        table: String,
+        config: DuplicateDetectionConfig,
+        output: Option<PathBuf>,
+    },
+    Transform {
+        project: PathBuf,
+        table: String,
+        steps: Vec<TransformationConfig>,
+        target_table: Option<String>,
+        output: Option<PathBuf>,
+        export_options: ExportOptions,
+    },
+    Help,
+}
+
+impl Command {
+    fn parse(args: &[String]) -> std::result::Result<Self, String> {
+        let Some((name, rest)) = args.split_first() else {
+            return Ok(Command::Help);
+        };
+        match name.as_str() {
+            "import" => {
+                let args = Arguments::parse(rest, &[
+                    "table", "delimiter", "header-row", "null", "time-zone", "primary-key",
+                    "compression", "batch-size", "memory-limit-mb",
+                ], &[])?;
+                let (project, paths) = args.positional.split_first()
+                    .filter(|(_, paths)| !paths.is_empty())
+                    .ok_or("import needs a project folder and at least one CSV file")?;
+                if args.value("table").is_some() && paths.len() > 1 {
+                    return Err("--table can only be used when importing one file".to_string());
+                }
+
+                let mut files = Vec::new();
+                for path in paths {
+                    let mut config = FileConfig::new(PathBuf::from(path));
+                    if let Some(table) = args.value("table") {
+                        config.table_name = table.to_string();
+                    }
+                    if let Some(delimiter) = args.value("delimiter") {
+                        config.delimiter = Some(parse_char(delimiter)? as char);
+                    }
+                    if let Some(row) = args.value("header-row") {
+                        config.header_row = parse_number(row, "--header-row")?;
+                    }
+                    if !args.values("null").is_empty() {
+                        config.null_values = args.values("null").iter().map(|v| v.to_string()).collect();
+                    }
+                    config.time_zone = args.value("time-zone").map(str::to_string);
+                    files.push(config);
+                }
+
+                let mut ingest_options = CsvIngestOptions::default();
+                if let Some(rows) = args.value("batch-size") {
+                    ingest_options.batch_size = parse_number(rows, "--batch-size")?;
+                }
+                if let Some(mb) = args.value("memory-limit-mb") {
+                    ingest_options.memory_limit_bytes = Some(parse_number::<usize>(mb, "--memory-limit-mb")? * 1024 * 1024);
+                }
+                Ok(Command::Import {
+                    project: PathBuf::from(project),
+                    files,
+                    primary_key: args.value("primary-key").map(split_list).unwrap_or_default(),
+                    compression: args.value("compression").map(parse_compression).transpose()?.unwrap_or_default(),
+                    ingest_options,
+                })
+            }
+            "query" => {
+                let args = Arguments::parse(rest, &[
+                    "file", "param", "output", "format", "delimiter", "quote", "null",
+                ], &["no-header", "read-only"])?;
+                let (project, sql) = match (args.positional.as_slice(), args.value("file")) {
+                    ([project], Some(file)) => (project, std::fs::read_to_string(file)
+                        .map_err(|e| format!("Failed to read {}: {}", file, e))?),
+                    ([project, sql], None) => (project, sql.clone()),
+                    _ => return Err("query needs a project folder and either SQL or --file".to_string()),
+                };
+                let parameters = args.values("param").iter()
+                    .map(|param| param.split_once('=')
+                        .map(|(name, value)| (name.trim_start_matches([':', '$']).to_string(), value.to_string()))
+                        .ok_or_else(|| format!("Parameter '{}' should be written as name=value", param)))
+                    .collect::<std::result::Result<_, _>>()?;
+                let output = args.value("output").map(PathBuf::from);
+                Ok(Command::Query {
+                    project: PathBuf::from(project),
+                    sql,
+                    parameters,
+                    export_options: export_options(&args, output.as_deref())?,
+                    output,
+                    read_only: args.has("read-only"),
+                })
+            }
+            "tables" => match Arguments::parse(rest, &[], &[])?.positional.as_slice() {
+                [project] => Ok(Command::Tables { project: PathBuf::from(project) }),
+                _ => Err("tables needs a project folder".to_string()),
+            },
+            "schema" => match Arguments::parse(rest, &[], &[])?.positional.as_slice() {
+                [project] => Ok(Command::Schema { project: PathBuf::from(project), table: None }),
+                [project, table] => Ok(Command::Schema { project: PathBuf::from(project), table: Some(table.clone()) }),
+                _ => Err("schema needs a project folder and optionally a table".to_string()),
+            },
+            "dedupe" => {
+                let args = Arguments::parse(rest, &["config", "output"], &[])?;
+                let [project, table] = args.positional.as_slice() else {
+                    return Err("dedupe needs a project folder and a table".to_string());
+                };
+                let config = args.value("config").ok_or("dedupe needs --config")?;
+                Ok(Command::Dedupe {
+                    project: PathBuf::from(project),
+                    table: table.clone(),
+                    config: read_json(config)?,
+                    output: args.value("output").map(PathBuf::from),
+                })
+            }
+            "transform" => {
+                let args = Arguments::parse(rest, &[
+                    "config", "table", "output", "format", "delimiter", "quote", "null",
+                ], &["no-header"])?;
+                let [project, table] = args.positional.as_slice() else {
+                    return Err("transform needs a project folder and a table".to_string());
+                };
+                let config = args.value("config").ok_or("transform needs --config")?;
+                // One step or a list of steps applied in order
+                let steps = match read_json::<Vec<TransformationConfig>>(config) {
+                    Ok(steps) => steps,
+                    Err(_) => vec![read_json::<TransformationConfig>(config)?],
+                };
+                let output = args.value("output").map(PathBuf::from);
+                Ok(Command::Transform {
+                    project: PathBuf::from(project),
+                    table: table.clone(),
+                    steps,
+                    target_table: args.value("table").map(str::to_string),
+                    export_options: export_options(&args, output.as_deref())?,
+                    output,
+                })
+            }
+            "help" | "--help" | "-h" => Ok(Command::Help),
+            other => Err(format!("Unknown command '{}'", other)),
+        }
+    }
+
+    fn run(self) -> Result<()> {
+        match self {
+            Command::Import { project, mut files, primary_key, compression, ingest_options } => {
+                std::fs::create_dir_all(&project)
+                    .map_err(|e| FreshError::Custom(format!("Failed to create project folder: {}", e)))?;
+                let db = open_project(&project, false)?;
+                db.set_parquet_compression(compression);
+
+                // Types are inferred while importing; the header is only read here to find
+                // the key columns
+                for config in files.iter_mut().filter(|_| !primary_key.is_empty()) {
+                    config.load_preview().map_err(FreshError::Custom)?;
+                    let file_name = config.file_name();
+                    for key in &primary_key {
+                        let column = config.columns.iter_mut().find(|c| &c.name == key).ok_or_else(|| {
+                            FreshError::Custom(format!("Column '{}' not found in {}", key, file_name))
+                        })?;
+                        column.primary_key = true;
+                    }
+                }
+                if let Some(problem) = file_import::validate_files(&files) {
+                    return Err(FreshError::Custom(problem));
+                }
+
+                file_import::import_files(&db, &project, &mut files, &ingest_options, &mut |_, _| {})
+                    .map_err(FreshError::Custom)?;
+                for config in &files {
+                    let rows = db.execute_count_query(&format!("SELECT COUNT(*) FROM {}", quote_identifier(&config.table_name)))?;
+                    eprintln!("Imported {} rows from {} into '{}'", rows, config.file_name(), config.table_name);
+                }
+                Ok(())
+            }
+            Command::Query { project, sql, parameters, output, export_options, read_only } => {
+                let db = open_project(&project, read_only)?;
+                let parameters = bind_parameters(&db, &sql, &parameters)?;
+                run_query(&db, &sql, &parameters, output.as_deref(), &export_options)
+            }
+            Command::Tables { project } => {
+                let db = open_project(&project, true)?;
+                println!("table\trows\tcolumns");
+                for table in db.get_tables()? {
+                    let rows = if table.row_count < 0 { "external".to_string() } else { table.row_count.to_string() };
+                    println!("{}\t{}\t{}", table.name, rows, table.columns.len());
+                }
+                for view in db.get_views()? {
+                    println!("{}\tview\t", view.name);
+                }
+                Ok(())
+            }
+            Command::Schema { project, table } => {
+                let db = open_project(&project, true)?;
+                let tables: Vec<_> = db.get_tables()?.into_iter()
+                    .filter(|t| table.is_none() || table.as_ref() == Some(&t.name))
+                    .collect();
+                if let (Some(name), true) = (&table, tables.is_empty()) {
+                    return Err(FreshError::Custom(format!("Table '{}' not found", name)));
+                }
+                for (i, table) in tables.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    println!("{}", table.name);
+                    for column in &table.columns {
+                        let nullable = if column.is_nullable { "" } else { " NOT NULL" };
+                        let key = if column.is_primary_key { " PRIMARY KEY" } else { "" };
+                        println!("  {}\t{}{}{}", column.name, column.data_type, nullable, key);
+                    }
+                    for unique in &table.keys.unique {
+                        println!("  UNIQUE ({})", unique.join(", "));
+                    }
+                    if let Some(lineage) = &table.lineage {
+                        println!("  -- materialized {} from: {}", lineage.materialized_at_display(), lineage.sql);
+                    }
+                }
+                Ok(())
+            }
+            Command::Dedupe { project, table, mut config, output } => {
+                let db = open_project(&project, true)?;
+                if config.group_column.is_empty() {
+                    return Err(FreshError::Custom("The dedupe config needs a group_column".to_string()));
+                }
+                // As in the dialog, the table's key differs between otherwise identical rows
+                if config.ignore_columns.is_empty() {
+                    config.ignore_columns = DuplicateDetectionConfig::for_key(&db.match_key(&table)).ignore_columns;
+                }
+
+                let batch = db.get_table_arrow_batch(&table)?;
+                let detector = DuplicateDetector::new(config);
+                let result = detector.detect_duplicates(&batch)?;
+                println!(
+                    "{} duplicate groups, {} duplicate rows in {} rows",
+                    result.total_duplicates, result.total_duplicate_rows, batch.num_rows()
+                );
+                if let Some(output) = output {
+                    let kept = detector.create_clean_arrow_file(&batch, &result, &output)?;
+                    eprintln!("Wrote {} rows to {}", kept, output.display());
+                }
+                Ok(())
+            }
+            Command::Transform { project, table, steps, target_table, output, export_options } => {
+                let db = open_project(&project, target_table.is_none())?;
+                let transformer = DataTransformer::new();
+                let mut batch = db.get_table_arrow_batch(&table)?.as_ref().clone();
+                for step in &steps {
+                    batch = transformer.apply(&batch, step)
+                        .map_err(|e| FreshError::Custom(format!("Failed to apply {}: {}", step.output_column_name, e)))?;
+                }
+
+                if let Some(target) = &target_table {
+                    db.insert_record_batches(target, vec![batch.clone()])?;
+                    db.save_table_dual(target, &project)?;
+                    eprintln!("Saved {} rows as table '{}'", batch.num_rows(), target);
+                }
+                match output {
+                    Some(output) => write_batches(&[batch], &output, &export_options),
+                    None if target_table.is_none() => print_batches(&[batch]),
+                    None => Ok(()),
+                }
+            }
+            Command::Help => {
+                println!("{}", USAGE);
+                Ok(())
+            }
+        }
+    }
+}
+
+// Positional arguments and `--name value` options of a command
+struct Arguments {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Arguments {
+    // `options` take a value, as `--name value` or `--name=value`; `flags` don't
+    fn parse(args: &[String], options: &[&str], flags: &[&str]) -> std::result::Result<Self, String> {
+        let mut parsed = Arguments { positional: Vec::new(), options: Vec::new(), flags: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            let (name, inline_value) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (option, None),
+            };
+            if flags.contains(&name) && inline_value.is_none() {
+                parsed.flags.push(name.to_string());
+            } else if options.contains(&name) {
+                let value = inline_value.or_else(|| args.next().cloned())
+                    .ok_or_else(|| format!("Option --{} needs a value", name))?;
+                parsed.options.push((name.to_string(), value));
+            } else {
+                return Err(format!("Unknown option '--{}'", name));
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    fn values(&self, name: &str) -> Vec<&str> {
+        self.options.iter().filter(|(n, _)| n == name).map(|(_, v)| v.as_str()).collect()
+    }
+
+    fn has(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+// Format and CSV settings of a command that can write its result to a file
+fn export_options(args: &Arguments, output: Option<&Path>) -> std::result::Result<ExportOptions, String> {
+    let mut options = ExportOptions::default();
+    let format = args.value("format")
+        .or_else(|| output.and_then(|path| path.extension()).and_then(|ext| ext.to_str()));
+    if let Some(format) = format {
+        options.format = ExportFormat::from_extension(format)
+            .ok_or_else(|| format!("Unknown export format '{}'", format))?;
+    }
+    if let Some(delimiter) = args.value("delimiter") {
+        options.csv.delimiter = parse_char(delimiter)?;
+    }
+    if let Some(quote) = args.value("quote") {
+        options.csv.quote = parse_char(quote)?;
+    }
+    if let Some(null) = args.value("null") {
+        options.csv.null_value = null.to_string();
+    }
+    options.csv.header = !args.has("no-header");
+    Ok(options)
+}
+
+fn open_project(project: &Path, read_only: bool) -> Result<Database> {
+    let db = if read_only {
+        Database::open_readonly(project)?
+    } else {
+        Database::open_writable(project)?
+    };
+    db.load_all_tables_from_directory(project)?;
+    Ok(db)
+}
+
+// Typed parameters of the query with the values given on the command line
+fn bind_parameters(db: &Database, sql: &str, values: &[(String, String)]) -> Result<Vec<QueryParameter>> {
+    let mut parameters = db.query_parameters(sql, &[]);
+    for (name, value) in values {
+        let parameter = parameters.iter_mut().find(|p| &p.name == name)
+            .ok_or_else(|| FreshError::Custom(format!("The query has no parameter '{}'", name)))?;
+        parameter.value = value.clone();
+    }
+    for parameter in &parameters {
+        parameter.validate()
+            .map_err(|e| FreshError::Custom(format!("Parameter {}: {}", parameter.label(), e)))?;
+    }
+    Ok(parameters)
+}
+
+fn run_query(db: &Database, sql: &str, parameters: &[QueryParameter], output: Option<&Path>, options: &ExportOptions) -> Result<()> {
+    let single_query = !script::is_script(sql)
+        && db.classify_statement(&params::prepare_sql(sql))
+            .is_ok_and(|kind| kind == StatementKind::Query);
+    if single_query {
+        // Rows go straight from DataFusion to the file
+        return match output {
+            Some(output) => {
+                let rows = db.export_query(sql, parameters, output, options)?;
+                eprintln!("Exported {} rows to {}", rows, output.display());
+                Ok(())
+            }
+            None => print_batches(&db.execute_query_batches_with_params(sql, parameters)?),
+        };
+    }
+
+    let result = script::run_script(db, sql, parameters);
+    for statement in &result.statements {
+        match &statement.status {
+            StatementStatus::Succeeded(message) => eprintln!("line {}: {}", statement.line, message),
+            // Reported as the command's error below
+            StatementStatus::Failed(_) => {}
+            StatementStatus::Skipped => eprintln!("line {}: skipped", statement.line),
+        }
+    }
+    if let Some(error) = result.error() {
+        return Err(FreshError::Custom(error));
+    }
+    match (result.result, output) {
+        (Some(rows), Some(output)) => write_batches(&rows.batches, output, options),
+        (Some(rows), None) => print_batches(&rows.batches),
+        (None, _) => Ok(()),
+    }
+}
+
+fn write_batches(batches: &[RecordBatch], output: &Path, options: &ExportOptions) -> Result<()> {
+    let rows = export::export_batches(batches, output, options)?;
+    eprintln!("Exported {} rows to {}", rows, output.display());
+    Ok(())
+}
+
+fn print_batches(batches: &[RecordBatch]) -> Result<()> {
+    let table = pretty_format_batches(batches)
+        .map_err(|e| FreshError::Custom(format!("Failed to format results: {}", e)))?;
+    println!("{}", table);
+    Ok(())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> std::result::Result<T, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path, e))
+}
+
+// A single ASCII character; "tab" or "\t" for a tab
+fn parse_char(value: &str) -> std::result::Result<u8, String> {
+    match value {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format!("'{}' should be a single character", value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, option: &str) -> std::result::Result<T, String> {
+    value.parse().map_err(|_| format!("{} should be a number, got '{}'", option, value))
+}
+
+fn parse_compression(name: &str) -> std::result::Result<ParquetCompression, String> {
+    ParquetCompression::all().into_iter()
+        .find(|c| c.display_name().eq_ignore_ascii_case(name) || format!("{:?}", c).eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("Unknown compression '{}'", name))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
@@ -0,0 +1,400 @@
+//! Streaming CSV ingestion built on arrow's CSV reader.
+//!
+//! Files are decoded in fixed-size batches of text columns that are then converted
+//! to the types picked by `TypeInferrer`, so memory use during decoding depends on
+//! the batch size rather than on the size of the file.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use datafusion::arrow::array::{Array, ArrayRef, BooleanArray, Date32Array, Float64Array, Int64Array, StringArray, Time64MicrosecondArray, Time64NanosecondArray, TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray};
+use datafusion::arrow::csv::reader::{Reader as ArrowCsvReader, ReaderBuilder};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use crate::core::database::Database;
+use crate::core::datetime::{self, DEFAULT_TIME_ZONE};
+use crate::core::error::{Result, FreshError};
+use crate::infer::TypeInferrer;
+
+/// Settings for streaming a CSV file into a table
+#[derive(Debug, Clone)]
+pub struct CsvIngestOptions {
+    /// Field delimiter
+    pub delimiter: char,
+    /// Detect the delimiter from the header line instead of using `delimiter`
+    pub infer_delimiter: bool,
+    /// 0-based line of the header row; lines before it are skipped. `None` means no header
+    pub header_row: Option<usize>,
+    /// Values treated as NULL
+    pub null_values: Vec<String>,
+    /// Rows decoded per Arrow batch
+    pub batch_size: usize,
+    /// Abort the import once the decoded table uses more than this many bytes
+    pub memory_limit_bytes: Option<usize>,
+    /// Rows sampled for type inference
+    pub sample_rows: usize,
+    /// Zone of date-times written without an offset. `None` uses the offset the sampled
+    /// values carry, or UTC when they carry none
+    pub time_zone: Option<String>,
+}
+
+impl Default for CsvIngestOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            infer_delimiter: false,
+            header_row: Some(0),
+            null_values: vec!["".to_string(), "null".to_string(), "NULL".to_string(), "N/A".to_string()],
+            batch_size: 8192,
+            memory_limit_bytes: None,
+            sample_rows: 1000,
+            time_zone: None,
+        }
+    }
+}
+
+/// Progress of a running import
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IngestProgress {
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub rows_read: usize,
+}
+
+impl IngestProgress {
+    /// Fraction of the file consumed, between 0.0 and 1.0
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            (self.bytes_read as f64 / self.total_bytes as f64).min(1.0) as f32
+        }
+    }
+}
+
+// Reader that counts the bytes handed to the CSV decoder
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/// Iterator over typed record batches decoded from a CSV file
+pub struct CsvBatchStream {
+    reader: ArrowCsvReader<CountingReader<BufReader<File>>>,
+    schema: SchemaRef,
+    null_values: Vec<String>,
+    delimiter: char,
+    bytes_read: Arc<AtomicU64>,
+    data_offset: u64,
+    total_bytes: u64,
+    rows_read: usize,
+}
+
+impl CsvBatchStream {
+    /// Locate the header, infer column types from a sample and open the batch reader
+    pub fn open(path: &Path, options: &CsvIngestOptions) -> Result<Self> {
+        let total_bytes = std::fs::metadata(path)
+            .map_err(|e| FreshError::Custom(format!("Failed to open CSV file: {}", e)))?
+            .len();
+        let mut file = File::open(path)
+            .map_err(|e| FreshError::Custom(format!("Failed to open CSV file: {}", e)))?;
+
+        // Skip the lines above the header row
+        let mut header_offset = 0u64;
+        {
+            let mut reader = BufReader::new(&mut file);
+            let mut line = Vec::new();
+            for _ in 0..options.header_row.unwrap_or(0) {
+                line.clear();
+                let n = reader.read_until(b'\n', &mut line)?;
+                if n == 0 {
+                    return Err(FreshError::Custom("Header row exceeds file length".to_string()));
+                }
+                header_offset += n as u64;
+            }
+        }
+
+        file.seek(SeekFrom::Start(header_offset))?;
+        let delimiter = if options.infer_delimiter {
+            let mut first_line = String::new();
+            BufReader::new(&mut file).read_line(&mut first_line)?;
+            infer_delimiter(&first_line)
+        } else {
+            options.delimiter
+        };
+
+        // Read the header and a sample of rows with the csv crate
+        file.seek(SeekFrom::Start(header_offset))?;
+        let mut sample_reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter as u8)
+            .has_headers(false)
+            .flexible(true)
+            .comment(Some(b'#'))
+            .from_reader(BufReader::new(&mut file));
+        let mut records = sample_reader.records();
+
+        let (headers, data_offset) = if options.header_row.is_some() {
+            let header = records.next()
+                .ok_or_else(|| FreshError::Custom("No header row found".to_string()))??;
+            let headers: Vec<String> = header.iter().map(|s| s.trim().to_string()).collect();
+            (headers, header_offset + records.reader().position().byte())
+        } else {
+            (Vec::new(), header_offset)
+        };
+
+        let mut samples = Vec::new();
+        for record in records.take(options.sample_rows) {
+            let record = record?;
+            samples.push(record.iter().map(|s| s.trim().to_string()).collect::<Vec<String>>());
+        }
+
+        let headers = if headers.is_empty() {
+            (0..samples.first().map(|r| r.len()).unwrap_or(0))
+                .map(|i| format!("col_{}", i))
+                .collect()
+        } else {
+            deduplicate_headers(&headers)
+        };
+        if headers.is_empty() {
+            return Err(FreshError::Custom("CSV file has no columns".to_string()));
+        }
+
+        // Use the same type inference system as the UI (with null awareness)
+        let inferred_types = TypeInferrer::infer_column_types_with_nulls(&headers, &samples, &options.null_values);
+        let time_zone = match &options.time_zone {
+            Some(zone) => datetime::parse_time_zone(zone).map(|_| zone.trim().to_string())?,
+            None => {
+                let datetime_values = inferred_types.iter().enumerate()
+                    .filter(|(_, (_, col_type))| matches!(col_type.to_arrow_type(), DataType::Timestamp(_, _)))
+                    .flat_map(|(i, _)| samples.iter().filter_map(move |row| row.get(i)))
+                    .map(|value| value.as_str());
+                datetime::detect_time_zone(datetime_values).unwrap_or_else(|| DEFAULT_TIME_ZONE.to_string())
+            }
+        };
+        let schema = Arc::new(Schema::new(
+            inferred_types.iter()
+                .map(|(name, col_type)| {
+                    let data_type = match col_type.to_arrow_type() {
+                        DataType::Timestamp(unit, Some(_)) => DataType::Timestamp(unit, Some(time_zone.as_str().into())),
+                        data_type => data_type,
+                    };
+                    Field::new(name, data_type, true)
+                })
+                .collect::<Vec<_>>()
+        ));
+
+        // Decode every column as text; conversion to the inferred types happens per batch
+        let text_schema = Arc::new(Schema::new(
+            headers.iter()
+                .map(|name| Field::new(name, DataType::Utf8, true))
+                .collect::<Vec<_>>()
+        ));
+
+        file.seek(SeekFrom::Start(data_offset))?;
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        let counting = CountingReader {
+            inner: BufReader::new(file),
+            count: bytes_read.clone(),
+        };
+        let reader = ReaderBuilder::new(text_schema)
+            .with_header(false)
+            .with_delimiter(delimiter as u8)
+            .with_comment(b'#')
+            .with_truncated_rows(true)
+            .with_batch_size(options.batch_size.max(1))
+            .build(counting)?;
+
+        Ok(Self {
+            reader,
+            schema,
+            null_values: options.null_values.clone(),
+            delimiter,
+            bytes_read,
+            data_offset,
+            total_bytes,
+            rows_read: 0,
+        })
+    }
+
+    /// Schema of the produced batches
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Delimiter used for decoding (after inference)
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    pub fn progress(&self) -> IngestProgress {
+        IngestProgress {
+            bytes_read: (self.data_offset + self.bytes_read.load(Ordering::Relaxed)).min(self.total_bytes),
+            total_bytes: self.total_bytes,
+            rows_read: self.rows_read,
+        }
+    }
+
+    fn convert_batch(&self, text_batch: &RecordBatch) -> Result<RecordBatch> {
+        let arrays = text_batch.columns().iter()
+            .zip(self.schema.fields().iter())
+            .map(|(column, field)| {
+                let values = column.as_any().downcast_ref::<StringArray>()
+                    .ok_or_else(|| FreshError::Custom("CSV column was not decoded as text".to_string()))?;
+                convert_string_column(values, field.data_type(), &self.null_values)
+            })
+            .collect::<Result<Vec<ArrayRef>>>()?;
+
+        Ok(RecordBatch::try_new(self.schema.clone(), arrays)?)
+    }
+}
+
+impl Iterator for CsvBatchStream {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text_batch = match self.reader.next()? {
+            Ok(batch) => batch,
+            Err(e) => return Some(Err(FreshError::Custom(format!(
+                "Failed to read CSV near row {}: {}", self.rows_read + 1, e
+            )))),
+        };
+
+        let result = self.convert_batch(&text_batch);
+        if let Ok(batch) = &result {
+            self.rows_read += batch.num_rows();
+        }
+        Some(result)
+    }
+}
+
+/// Infer the most likely delimiter from a header line
+pub fn infer_delimiter(header_line: &str) -> char {
+    let delimiters = [',', '\t', ';', '|'];
+    let mut best_delimiter = ',';
+    let mut max_fields = 0;
+
+    for &delimiter in &delimiters {
+        let fields = csv::ReaderBuilder::new()
+            .delimiter(delimiter as u8)
+            .has_headers(false)
+            .from_reader(header_line.as_bytes())
+            .records()
+            .next()
+            .and_then(|r| r.ok())
+            .map(|r| r.len())
+            .unwrap_or(0);
+        if fields > max_fields && fields > 1 {
+            max_fields = fields;
+            best_delimiter = delimiter;
+        }
+    }
+
+    best_delimiter
+}
+
+// Deduplicate column names to avoid DataFusion errors
+fn deduplicate_headers(headers: &[String]) -> Vec<String> {
+    let mut seen_names = std::collections::HashSet::new();
+    let mut deduplicated_headers = Vec::new();
+
+    for (i, header) in headers.iter().enumerate() {
+        let header = if header.is_empty() { format!("col_{}", i) } else { header.clone() };
+        let mut unique_name = header.clone();
+        let mut counter = 1;
+
+        while seen_names.contains(&unique_name) {
+            unique_name = format!("{}_{}", header, counter);
+            counter += 1;
+        }
+
+        seen_names.insert(unique_name.clone());
+        deduplicated_headers.push(unique_name);
+    }
+
+    deduplicated_headers
+}
+
+/// Convert a text column to `data_type`; null tokens and unparsable values become NULL
+pub(crate) fn convert_string_column(values: &StringArray, data_type: &DataType, null_values: &[String]) -> Result<ArrayRef> {
+    let cell = |i: usize| -> Option<&str> {
+        if values.is_null(i) {
+            return None;
+        }
+        let value = values.value(i).trim();
+        if value.is_empty() || null_values.iter().any(|n| n == value) {
+            None
+        } else {
+            Some(value)
+        }
+    };
+    let len = values.len();
+
+    let array: ArrayRef = match data_type {
+        DataType::Int64 => Arc::new((0..len)
+            .map(|i| cell(i).and_then(|v| v.parse::<i64>().ok()))
+            .collect::<Int64Array>()),
+        DataType::Float64 => Arc::new((0..len)
+            .map(|i| cell(i).and_then(|v| v.parse::<f64>().ok()))
+            .collect::<Float64Array>()),
+        DataType::Boolean => Arc::new((0..len)
+            .map(|i| cell(i).and_then(|v| match v.to_lowercase().as_str() {
+                "true" | "1" | "yes" | "y" => Some(true),
+                "false" | "0" | "no" | "n" => Some(false),
+                _ => None,
+            }))
+            .collect::<BooleanArray>()),
+        DataType::Date32 => Arc::new((0..len)
+            .map(|i| cell(i).and_then(Database::parse_date_string))
+            .collect::<Date32Array>()),
+        DataType::Timestamp(unit, zone) => {
+            // Naive timestamps hold wall-clock values, which is what reading them as UTC keeps
+            let tz = datetime::parse_time_zone(zone.as_deref().unwrap_or(DEFAULT_TIME_ZONE))?;
+            let parsed = (0..len).map(|i| cell(i).and_then(|v| datetime::parse_timestamp(v, unit, &tz)));
+            let zone = zone.clone();
+            match unit {
+                TimeUnit::Second => Arc::new(parsed.collect::<TimestampSecondArray>().with_timezone_opt(zone)),
+                TimeUnit::Millisecond => Arc::new(parsed.collect::<TimestampMillisecondArray>().with_timezone_opt(zone)),
+                TimeUnit::Microsecond => Arc::new(parsed.collect::<TimestampMicrosecondArray>().with_timezone_opt(zone)),
+                TimeUnit::Nanosecond => Arc::new(parsed.collect::<TimestampNanosecondArray>().with_timezone_opt(zone)),
+            }
+        }
+        DataType::Time64(unit @ (TimeUnit::Microsecond | TimeUnit::Nanosecond)) => {
+            let parsed = (0..len).map(|i| cell(i).and_then(|v| parse_time_of_day(v, unit)));
+            match unit {
+                TimeUnit::Microsecond => Arc::new(parsed.collect::<Time64MicrosecondArray>()),
+                _ => Arc::new(parsed.collect::<Time64NanosecondArray>()),
+            }
+        }
+        _ => Arc::new((0..len)
+            .map(|i| cell(i).map(|v| v.to_string()))
+            .collect::<StringArray>()),
+    };
+
+    Ok(array)
+}
+
+// Time-of-day strings ("HH:MM" or "HH:MM:SS[.fraction]") become offsets since midnight;
+// plain integers are seconds since midnight
+pub(crate) fn parse_time_of_day(value: &str, unit: &TimeUnit) -> Option<i64> {
+    let parsed = if value.matches(':').count() == 1 {
+        Database::parse_time_string_to_timestamp(&format!("{}:00", value), unit)
+    } else {
+        Database::parse_time_string_to_timestamp(value, unit)
+    };
+    if parsed.is_some() {
+        return parsed;
+    }
+
+    let seconds = value.parse::<i64>().ok().filter(|s| (0..86_400).contains(s))?;
+    Some(seconds * datetime::units_per_second(unit))
+}
@@ -1,14 +1,58 @@
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
 use datafusion::prelude::*;
 use datafusion::arrow::record_batch::RecordBatch;
-use datafusion::arrow::datatypes::{DataType, Field, Schema};
-use datafusion::arrow::array::{StringArray, Int64Array, Float64Array, BooleanArray, Date32Array, TimestampNanosecondArray, TimestampSecondArray, TimestampMillisecondArray, TimestampMicrosecondArray};
+use datafusion::arrow::compute::concat_batches;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::array::{StringArray, Int64Array, Float64Array};
 use datafusion::arrow::datatypes::TimeUnit;
 use tokio::runtime::Runtime;
+use tokio::task::AbortHandle;
+use futures::StreamExt;
 use crate::core::error::{Result, FreshError};
-use crate::infer::{TypeInferrer, ColumnType};
+use crate::core::csv_ingest::{CsvBatchStream, CsvIngestOptions, IngestProgress};
+use crate::core::datetime::{self, DateTimeFormat};
+use crate::core::explain::QueryPlan;
+use crate::core::export::{BatchExporter, ExportOptions};
+use crate::core::external_table::{ExternalTable, ExternalTableOptions};
+use crate::core::functions::{builtin_functions, SqlFunction};
+use crate::core::keys::TableKeys;
+use crate::core::TableLineage;
+use crate::core::params::{self, QueryParameter};
+use crate::core::script;
+use crate::core::sql_assist::SqlSchema;
+use crate::core::profile::TableProfile;
+use crate::core::quality::{QualityReport, QualityRule};
+use serde::{Deserialize, Serialize};
+
+/// Aggregate and window functions DataFusion plans itself instead of through its registry
+const BUILTIN_AGGREGATE_AND_WINDOW_FUNCTIONS: [&str; 22] = [
+    "approx_distinct", "approx_median", "approx_percentile_cont", "array_agg", "avg",
+    "bool_and", "bool_or", "corr", "count", "max", "min", "stddev", "sum", "var",
+    "row_number", "rank", "dense_rank", "percent_rank", "cume_dist", "ntile", "lag", "lead",
+];
+
+/// File inside the project folder that stores view definitions
+const VIEWS_FILE: &str = "views.json";
+/// File inside the project folder that stores attached file/folder tables
+const EXTERNAL_TABLES_FILE: &str = "external_tables.json";
+/// File inside the project folder that stores the data-quality rules of every table
+const QUALITY_RULES_FILE: &str = "quality_rules.json";
+/// File inside the project folder that stores the primary keys and unique constraints of every table
+const TABLE_KEYS_FILE: &str = "table_keys.json";
+/// File inside the project folder that stores the queries materialized tables were built from
+const LINEAGE_FILE: &str = "lineage.json";
+/// Schema metadata key of table files recording the version of their format
+const FORMAT_VERSION_KEY: &str = "fresh.format_version";
+/// Current table file format; version 2 stores times of day as `Time64`
+const FORMAT_VERSION: &str = "2";
+/// Folder inside the project where a committing transaction stages table files
+const TRANSACTION_STAGING_DIR: &str = ".fresh_transaction";
+/// Folder inside the project where a committing transaction keeps the files it replaces
+const TRANSACTION_BACKUP_DIR: &str = ".fresh_transaction_backup";
 
 #[derive(Debug, Clone)]
 pub struct ColumnInfo {
@@ -22,44 +66,236 @@ pub struct ColumnInfo {
 pub struct TableInfo {
     pub name: String,
     pub columns: Vec<ColumnInfo>,
+    /// Number of rows; -1 for external tables, which are not counted without a scan
     pub row_count: i64,
+    /// Attached from files on disk instead of held in memory
+    pub is_external: bool,
+    /// Violations found by the last data-quality validation; `None` when not validated
+    pub quality_violations: Option<usize>,
+    /// Declared primary key and unique constraints
+    pub keys: TableKeys,
+    /// Query the table was materialized from, if it was
+    pub lineage: Option<TableLineage>,
 }
 
+/// What executing one statement did
 #[derive(Debug, Clone)]
+pub enum StatementOutcome {
+    /// Rows returned by a query
+    Rows(Vec<RecordBatch>),
+    /// A statement that changed tables, views or the session, with a summary of the change
+    Done(String),
+}
+
+/// A `CREATE [OR REPLACE] TABLE [IF NOT EXISTS] name AS query` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateTableAs {
+    pub name: String,
+    pub query: String,
+    pub or_replace: bool,
+    pub if_not_exists: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ViewInfo {
     pub name: String,
     pub sql: String,
 }
 
+/// Compression codec used when persisting tables as Parquet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Lz4,
+    #[default]
+    Zstd,
+}
+
+impl ParquetCompression {
+    pub fn all() -> [ParquetCompression; 5] {
+        [
+            ParquetCompression::Uncompressed,
+            ParquetCompression::Snappy,
+            ParquetCompression::Gzip,
+            ParquetCompression::Lz4,
+            ParquetCompression::Zstd,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ParquetCompression::Uncompressed => "Uncompressed",
+            ParquetCompression::Snappy => "Snappy",
+            ParquetCompression::Gzip => "Gzip",
+            ParquetCompression::Lz4 => "LZ4",
+            ParquetCompression::Zstd => "Zstandard",
+        }
+    }
+
+    pub(crate) fn to_parquet(self) -> datafusion::parquet::basic::Compression {
+        use datafusion::parquet::basic::{Compression, GzipLevel, ZstdLevel};
+        match self {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Lz4 => Compression::LZ4_RAW,
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+/// What a SQL statement does, derived from its DataFusion logical plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    /// Reads data only (SELECT, EXPLAIN, DESCRIBE, ...)
+    Query,
+    /// Changes the catalog (CREATE/DROP TABLE, VIEW, FUNCTION, ...)
+    Ddl(&'static str),
+    /// Changes table contents (INSERT, UPDATE, DELETE, ...)
+    Dml(&'static str),
+    /// COPY ... TO, which writes files
+    Copy,
+    /// Session statements (SET, transactions, ...)
+    Statement(&'static str),
+}
+
+impl StatementKind {
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, StatementKind::Query)
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            StatementKind::Query => "query".to_string(),
+            StatementKind::Ddl(name) => format!("DDL statement ({})", name),
+            StatementKind::Dml(name) => format!("DML statement ({})", name),
+            StatementKind::Copy => "COPY statement".to_string(),
+            StatementKind::Statement(name) => format!("{} statement", name),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DataBatch {
     pub columns: Vec<String>,
     pub rows: Vec<Vec<String>>,
 }
 
+/// State captured by `begin_transaction` and restored by `rollback_transaction`
+struct Transaction {
+    registered_tables: HashMap<String, Vec<RecordBatch>>,
+    views: BTreeMap<String, String>,
+    external_tables: BTreeMap<String, (ExternalTable, SchemaRef)>,
+    quality_rules: BTreeMap<String, Vec<QualityRule>>,
+    table_keys: BTreeMap<String, TableKeys>,
+    lineage: BTreeMap<String, TableLineage>,
+    // Every provider in the DataFusion catalog (tables, views and attached files)
+    catalog: HashMap<String, Arc<dyn TableProvider>>,
+    // Tables saved with save_table_dual during the transaction; written on commit
+    pending_saves: BTreeMap<String, PathBuf>,
+    // Tables dropped or renamed during the transaction; their files are deleted on commit
+    pending_removals: BTreeMap<String, PathBuf>,
+}
+
+/// Tables, views and attached files registered in a DataFusion context.
+/// All state sits behind locks, so an `Arc<Database>` can import, save and load tables.
 pub struct Database {
     // DataFusion context for in-memory analytics
     ctx: SessionContext,
     runtime: Runtime,
     // Cache for loaded data batches
-    batch_cache: HashMap<String, DataBatch>,
-    // Track registered tables
-    registered_tables: HashMap<String, Arc<RecordBatch>>,
+    batch_cache: RwLock<HashMap<String, DataBatch>>,
+    // Column profiles of in-memory tables, dropped whenever the table changes
+    profiles: RwLock<HashMap<String, Arc<TableProfile>>>,
+    // Track registered tables (every batch of each table, in order)
+    registered_tables: RwLock<HashMap<String, Vec<RecordBatch>>>,
+    // Codec for the Parquet half of save_table_dual
+    parquet_compression: RwLock<ParquetCompression>,
+    // How date-times of this database's results are shown
+    datetime_format: RwLock<DateTimeFormat>,
+    // Project folder the database was opened from (used to persist views)
+    project_path: Option<PathBuf>,
+    // View definitions (name -> SELECT statement); views can be created through Arc<Database>
+    views: RwLock<BTreeMap<String, String>>,
+    // Tables scanned from their source files, with the schema they were registered with
+    external_tables: RwLock<BTreeMap<String, (ExternalTable, SchemaRef)>>,
+    // Data-quality rules per table, and the report of their last validation
+    quality_rules: RwLock<BTreeMap<String, Vec<QualityRule>>>,
+    quality_reports: RwLock<HashMap<String, QualityReport>>,
+    // Declared keys per table, checked whenever the table is registered with new rows
+    table_keys: RwLock<BTreeMap<String, TableKeys>>,
+    // Source query of every table materialized from one
+    lineage: RwLock<BTreeMap<String, TableLineage>>,
+    // Functions added with register_function, registered again in every clone
+    functions: RwLock<Vec<SqlFunction>>,
+    // Open transaction, if any; project files are only written when it commits
+    transaction: RwLock<Option<Transaction>>,
+    // Viewer projects reject every statement that is not a plain query
+    readonly: bool,
 }
 
 impl Clone for Database {
+    /// An independent copy with every table, attached file and view registered in a
+    /// new context, so the clone can be queried and modified on its own
     fn clone(&self) -> Self {
-        // Create a new runtime and context
         let runtime = Runtime::new()
             .expect("Failed to create tokio runtime for Database clone");
-        let ctx = SessionContext::new();
+        let ctx = Self::new_context();
+        let functions = read_lock(&self.functions).clone();
+        for function in &functions {
+            function.register(&ctx);
+        }
+        
+        let registered_tables = read_lock(&self.registered_tables).clone();
+        let views = self.views.read().map(|v| v.clone()).unwrap_or_default();
+        let catalog = self.catalog_providers().unwrap_or_else(|e| {
+            eprintln!("[Database] Failed to read the catalog while cloning: {}", e);
+            HashMap::new()
+        });
+        for (name, provider) in catalog {
+            // Views are planned again against the new context below
+            if views.contains_key(&name) {
+                continue;
+            }
+            // In-memory tables get their own MemTable so INSERTs don't leak between copies;
+            // attached files and other providers are read-only and can be shared
+            let provider = match registered_tables.get(&name).and_then(|batches| {
+                let schema = batches.first()?.schema();
+                MemTable::try_new(schema, vec![batches.clone()]).ok()
+            }) {
+                Some(table) => Arc::new(table) as Arc<dyn TableProvider>,
+                None => provider,
+            };
+            if let Err(e) = ctx.register_table(name.as_str(), provider) {
+                eprintln!("[Database] Failed to register table '{}' in clone: {}", name, e);
+            }
+        }
         
-        Self {
+        let clone = Self {
             ctx,
             runtime,
-            batch_cache: self.batch_cache.clone(),
-            registered_tables: self.registered_tables.clone(),
-        }
+            batch_cache: RwLock::new(read_lock(&self.batch_cache).clone()),
+            profiles: RwLock::new(read_lock(&self.profiles).clone()),
+            registered_tables: RwLock::new(registered_tables),
+            parquet_compression: RwLock::new(self.parquet_compression()),
+            datetime_format: RwLock::new(self.datetime_format()),
+            project_path: self.project_path.clone(),
+            views: RwLock::new(BTreeMap::new()),
+            external_tables: RwLock::new(read_lock(&self.external_tables).clone()),
+            quality_rules: RwLock::new(read_lock(&self.quality_rules).clone()),
+            quality_reports: RwLock::new(read_lock(&self.quality_reports).clone()),
+            table_keys: RwLock::new(read_lock(&self.table_keys).clone()),
+            lineage: RwLock::new(read_lock(&self.lineage).clone()),
+            functions: RwLock::new(functions),
+            transaction: RwLock::new(None),
+            readonly: self.readonly,
+        };
+        clone.register_views(views.into_iter()
+            .map(|(name, sql)| ViewInfo { name, sql })
+            .collect());
+        clone
     }
 }
 
@@ -70,42 +306,8 @@ impl Drop for Database {
 }
 
 impl Database {
-    fn parse_csv_line(line: &str, delimiter: char) -> Vec<String> {
-        let mut row = Vec::new();
-        let mut current_field = String::new();
-        let mut in_quotes = false;
-        let mut chars = line.chars().peekable();
-        
-        while let Some(ch) = chars.next() {
-            match ch {
-                '"' => {
-                    if in_quotes {
-                        // End of quoted field
-                        in_quotes = false;
-                    } else {
-                        // Start of quoted field
-                        in_quotes = true;
-                    }
-                },
-                c if c == delimiter && !in_quotes => {
-                    // End of field
-                    row.push(current_field.trim().to_string());
-                    current_field.clear();
-                },
-                _ => {
-                    current_field.push(ch);
-                }
-            }
-        }
-        
-        // Add the last field
-        row.push(current_field.trim().to_string());
-        row
-    }
-
-    /// Normalize time data by padding all entries to the highest precision found
-    /// Parse a time string in HH:MM:SS format to a timestamp in the specified unit
-    fn parse_time_string_to_timestamp(time_str: &str, unit: &TimeUnit) -> Option<i64> {
+    /// Parse a time string in HH:MM:SS[.fff] format to an offset since midnight in the specified unit
+    pub(crate) fn parse_time_string_to_timestamp(time_str: &str, unit: &TimeUnit) -> Option<i64> {
         let parts: Vec<&str> = time_str.split(':').collect();
         if parts.len() != 3 {
             return None;
@@ -116,16 +318,19 @@ impl Database {
         let minute = parts[1].parse::<u8>().ok()?;
         let seconds_part = parts[2];
         
-        // Parse seconds and optional fractional part
-        let (second, fraction) = if let Some(dot_pos) = seconds_part.find('.') {
-            let second_str = &seconds_part[..dot_pos];
+        // Parse seconds and the optional fraction, padded to nanoseconds so that
+        // "1.5" and "1.500" mean the same thing
+        let (second, nanos) = if let Some(dot_pos) = seconds_part.find('.') {
+            let second = seconds_part[..dot_pos].parse::<u8>().ok()?;
             let fraction_str = &seconds_part[dot_pos + 1..];
-            let second = second_str.parse::<u8>().ok()?;
-            let fraction = fraction_str.parse::<u64>().unwrap_or(0);
-            (second, fraction)
+            if fraction_str.is_empty() || !fraction_str.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let digits: String = fraction_str.chars().take(9).collect();
+            let nanos = format!("{:0<9}", digits).parse::<i64>().ok()?;
+            (second, nanos)
         } else {
-            let second = seconds_part.parse::<u8>().ok()?;
-            (second, 0)
+            (seconds_part.parse::<u8>().ok()?, 0)
         };
         
         // Validate time components
@@ -139,213 +344,398 @@ impl Database {
         // Convert to the requested unit
         let timestamp = match unit {
             TimeUnit::Second => total_seconds,
-            TimeUnit::Millisecond => total_seconds * 1_000 + (fraction / 1_000_000) as i64,
-            TimeUnit::Microsecond => total_seconds * 1_000_000 + (fraction / 1_000) as i64,
-            TimeUnit::Nanosecond => total_seconds * 1_000_000_000 + fraction as i64,
+            TimeUnit::Millisecond => total_seconds * 1_000 + nanos / 1_000_000,
+            TimeUnit::Microsecond => total_seconds * 1_000_000 + nanos / 1_000,
+            TimeUnit::Nanosecond => total_seconds * 1_000_000_000 + nanos,
         };
         
         Some(timestamp)
     }
 
-    fn normalize_time_column(values: &[String]) -> Vec<String> {
-        let mut max_fraction_digits = 0;
-        
-        // First pass: find the maximum fraction digits
-        for value in values {
-            if value.is_empty() || value.to_lowercase() == "null" {
-                continue;
-            }
-            
-            let parts: Vec<&str> = value.split(':').collect();
-            if parts.len() == 3 {
-                let seconds_part = parts[2];
-                if let Some(dot_pos) = seconds_part.find('.') {
-                    let fraction_str = &seconds_part[dot_pos + 1..];
-                    max_fraction_digits = max_fraction_digits.max(fraction_str.len());
-                }
-            }
-        }
-        
-        // Second pass: pad all values to the maximum precision
-        let mut normalized_values = Vec::new();
-        for value in values {
-            if value.is_empty() || value.to_lowercase() == "null" {
-                normalized_values.push(value.clone());
-                continue;
-            }
-            
-            let parts: Vec<&str> = value.split(':').collect();
-            if parts.len() == 3 {
-                let seconds_part = parts[2];
-                if let Some(dot_pos) = seconds_part.find('.') {
-                    // Has fractional part - pad to max precision
-                    let seconds_str = &seconds_part[..dot_pos];
-                    let fraction_str = &seconds_part[dot_pos + 1..];
-                    let padded_fraction = format!("{:0<width$}", fraction_str, width = max_fraction_digits);
-                    normalized_values.push(format!("{}:{}:{}.{}", parts[0], parts[1], seconds_str, padded_fraction));
-                } else {
-                    // No fractional part - add zeros
-                    let padded_fraction = "0".repeat(max_fraction_digits);
-                    normalized_values.push(format!("{}:{}:{}.{}", parts[0], parts[1], seconds_part, padded_fraction));
-                }
-            } else {
-                // Not a valid time format, keep as is
-                normalized_values.push(value.clone());
-            }
-        }
-        
-        normalized_values
-    }
-
-    /// Infer the most likely delimiter from a header line
-    fn infer_delimiter_from_header(header_line: &str) -> char {
-        let delimiters = [',', '\t', ';', '|'];
-        let mut best_delimiter = ',';
-        let mut max_fields = 0;
-        
-        for &delimiter in &delimiters {
-            let fields = Self::parse_csv_line(header_line, delimiter);
-            if fields.len() > max_fields && fields.len() > 1 {
-                max_fields = fields.len();
-                best_delimiter = delimiter;
-            }
-        }
-        
-        best_delimiter
-    }
-
-    /// Check if a line appears to be valid CSV data based on delimiter
-    fn is_valid_csv_line(line: &str, delimiter: char, expected_columns: usize) -> bool {
-        // Skip empty lines
-        if line.trim().is_empty() {
-            return false;
-        }
-        
-        // Skip comment lines
-        if line.trim().starts_with('#') {
-            return false;
-        }
-        
-        // Parse the line and check if it has the expected number of columns
-        let fields = Self::parse_csv_line(line, delimiter);
-        
-        // If we have a specific expected column count, validate against it
-        if expected_columns > 0 {
-            return fields.len() == expected_columns;
-        }
-        
-        // Otherwise, just check if it has multiple fields (at least 2)
-        fields.len() >= 2
+    /// Parse a YYYY-MM-DD (or YYYY/MM/DD) date to days since the Unix epoch
+    pub(crate) fn parse_date_string(date_str: &str) -> Option<i32> {
+        let date = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+            .or_else(|_| chrono::NaiveDate::parse_from_str(date_str, "%Y/%m/%d"))
+            .ok()?;
+        let epoch = chrono::NaiveDate::from_ymd_opt(1970, 1, 1)?;
+        i32::try_from(date.signed_duration_since(epoch).num_days()).ok()
     }
 
-    fn parse_date_string(date_str: &str) -> Option<i32> {
-        // Parse date in YYYY-MM-DD format
-        let parts: Vec<&str> = date_str.split('-').collect();
-        if parts.len() != 3 {
-            return None;
-        }
-        
-        let year: i32 = parts[0].parse().ok()?;
-        let month: u32 = parts[1].parse().ok()?;
-        let day: u32 = parts[2].parse().ok()?;
-        
-        // Validate date
-        if month < 1 || month > 12 || day < 1 || day > 31 {
-            return None;
-        }
-        
-        // Convert to days since epoch (1970-01-01)
-        // This is a simplified conversion - in production you'd want a proper date library
-        let days_since_epoch = (year - 1970) * 365 + (year - 1969) / 4; // Approximate leap year calculation
-        
-        // Add days for months (simplified)
-        let month_days = [0, 31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
-        let mut days_in_year: i32 = 0;
-        for i in 1..month {
-            days_in_year += month_days[i as usize];
+    pub fn open_readonly<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.is_dir() {
+            return Err(FreshError::Custom(format!("Project folder '{}' does not exist", path.display())));
         }
-        days_in_year += (day - 1) as i32;
         
-        Some(days_since_epoch + days_in_year)
+        let mut db = Self::open_writable(path)?;
+        db.readonly = true;
+        Ok(db)
     }
 
-    pub fn open_readonly<P: AsRef<Path>>(_path: P) -> Result<Self> {
+    pub fn open_writable<P: AsRef<Path>>(path: P) -> Result<Self> {
         // DataFusion is in-memory, so we don't need file paths for now
         let runtime = Runtime::new()
             .map_err(|e| FreshError::Custom(format!("Failed to create tokio runtime: {}", e)))?;
         
-        let ctx = SessionContext::new();
+        let ctx = Self::new_context();
         
         Ok(Self {
             ctx,
             runtime,
-            batch_cache: HashMap::new(),
-            registered_tables: HashMap::new(),
+            batch_cache: RwLock::new(HashMap::new()),
+            profiles: RwLock::new(HashMap::new()),
+            registered_tables: RwLock::new(HashMap::new()),
+            parquet_compression: RwLock::new(ParquetCompression::default()),
+            datetime_format: RwLock::new(DateTimeFormat::default()),
+            project_path: Some(path.as_ref().to_path_buf()),
+            views: RwLock::new(BTreeMap::new()),
+            external_tables: RwLock::new(BTreeMap::new()),
+            quality_rules: RwLock::new(BTreeMap::new()),
+            quality_reports: RwLock::new(HashMap::new()),
+            table_keys: RwLock::new(BTreeMap::new()),
+            lineage: RwLock::new(BTreeMap::new()),
+            functions: RwLock::new(Vec::new()),
+            transaction: RwLock::new(None),
+            readonly: false,
         })
     }
 
-    pub fn open_writable<P: AsRef<Path>>(_path: P) -> Result<Self> {
-        // DataFusion is in-memory, so we don't need file paths for now
-        let runtime = Runtime::new()
-            .map_err(|e| FreshError::Custom(format!("Failed to create tokio runtime: {}", e)))?;
-        
+    // DataFusion context with the function library registered
+    fn new_context() -> SessionContext {
         let ctx = SessionContext::new();
-        
-        Ok(Self {
-            ctx,
-            runtime,
-            batch_cache: HashMap::new(),
-            registered_tables: HashMap::new(),
-        })
+        for function in builtin_functions() {
+            function.register(&ctx);
+        }
+        ctx
+    }
+
+    /// Make a user-defined function callable from this database's SQL, replacing any
+    /// function with the same name. Clones of the database keep it.
+    pub fn register_function(&self, function: impl Into<SqlFunction>) {
+        let function = function.into();
+        function.register(&self.ctx);
+        let mut functions = write_lock(&self.functions);
+        functions.retain(|f| f.name() != function.name());
+        functions.push(function);
     }
 
     pub fn is_readonly(&self) -> bool {
-        false // DataFusion is always writable in-memory
+        self.readonly
+    }
+
+    // Fail with a clear message when a mutating operation runs on a read-only project
+    fn ensure_writable(&self, action: &str) -> Result<()> {
+        if self.readonly {
+            return Err(FreshError::Custom(format!("Cannot {} in a read-only project", action)));
+        }
+        Ok(())
+    }
+
+    // SQL options matching the project mode; read-only projects only allow queries
+    fn sql_options(&self) -> SQLOptions {
+        SQLOptions::new()
+            .with_allow_ddl(!self.readonly)
+            .with_allow_dml(!self.readonly)
+            .with_allow_statements(!self.readonly)
+    }
+
+    /// Classify a SQL statement by inspecting DataFusion's logical plan (including subqueries)
+    pub fn classify_statement(&self, sql: &str) -> Result<StatementKind> {
+        use datafusion::common::tree_node::TreeNodeRecursion;
+        use datafusion::logical_expr::LogicalPlan;
+        
+        let state = self.ctx.state();
+        let plan = self.runtime.block_on(async {
+            state.create_logical_plan(sql).await
+        }).map_err(|e| FreshError::Database(e.to_string()))?;
+        
+        let mut kind = StatementKind::Query;
+        plan.apply_with_subqueries(|node| {
+            kind = match node {
+                LogicalPlan::Ddl(ddl) => StatementKind::Ddl(Self::ddl_name(ddl)),
+                LogicalPlan::Dml(dml) => StatementKind::Dml(Self::write_op_name(&dml.op)),
+                LogicalPlan::Copy(_) => StatementKind::Copy,
+                LogicalPlan::Statement(statement) => StatementKind::Statement(Self::session_statement_name(statement)),
+                _ => return Ok(TreeNodeRecursion::Continue),
+            };
+            Ok(TreeNodeRecursion::Stop)
+        }).map_err(|e| FreshError::Database(e.to_string()))?;
+        
+        Ok(kind)
+    }
+
+    fn ddl_name(ddl: &datafusion::logical_expr::DdlStatement) -> &'static str {
+        use datafusion::logical_expr::DdlStatement;
+        match ddl {
+            DdlStatement::CreateExternalTable(_) => "CREATE EXTERNAL TABLE",
+            DdlStatement::CreateMemoryTable(_) => "CREATE TABLE",
+            DdlStatement::CreateView(_) => "CREATE VIEW",
+            DdlStatement::CreateCatalogSchema(_) => "CREATE SCHEMA",
+            DdlStatement::CreateCatalog(_) => "CREATE DATABASE",
+            DdlStatement::DropTable(_) => "DROP TABLE",
+            DdlStatement::DropView(_) => "DROP VIEW",
+            DdlStatement::DropCatalogSchema(_) => "DROP SCHEMA",
+            DdlStatement::CreateFunction(_) => "CREATE FUNCTION",
+            DdlStatement::DropFunction(_) => "DROP FUNCTION",
+        }
+    }
+
+    fn write_op_name(op: &datafusion::logical_expr::WriteOp) -> &'static str {
+        use datafusion::logical_expr::WriteOp;
+        match op {
+            WriteOp::InsertOverwrite => "INSERT OVERWRITE",
+            WriteOp::InsertInto => "INSERT INTO",
+            WriteOp::Delete => "DELETE",
+            WriteOp::Update => "UPDATE",
+            WriteOp::Ctas => "CREATE TABLE AS",
+        }
+    }
+
+    fn session_statement_name(statement: &datafusion::logical_expr::Statement) -> &'static str {
+        use datafusion::logical_expr::Statement;
+        match statement {
+            Statement::TransactionStart(_) => "BEGIN TRANSACTION",
+            Statement::TransactionEnd(_) => "COMMIT/ROLLBACK",
+            Statement::SetVariable(_) => "SET",
+        }
+    }
+
+    pub fn parquet_compression(&self) -> ParquetCompression {
+        *read_lock(&self.parquet_compression)
+    }
+
+    pub fn set_parquet_compression(&self, compression: ParquetCompression) {
+        *write_lock(&self.parquet_compression) = compression;
+    }
+
+    /// Format date-times are shown in by results queried from this database
+    pub fn datetime_format(&self) -> DateTimeFormat {
+        read_lock(&self.datetime_format).clone()
+    }
+
+    pub fn set_datetime_format(&self, format: DateTimeFormat) -> Result<()> {
+        format.validate()?;
+        *write_lock(&self.datetime_format) = format;
+        Ok(())
+    }
+
+    // Forget the cached rows, profile and quality report of a table whose data changed
+    fn invalidate_caches(&self, table_name: &str) {
+        write_lock(&self.batch_cache).remove(table_name);
+        write_lock(&self.profiles).remove(table_name);
+        write_lock(&self.quality_reports).remove(table_name);
+    }
+
+    /// Profile every column of a table, view or attached table. Profiles of
+    /// in-memory tables are cached until the table is replaced or dropped.
+    pub fn profile_table(&self, name: &str) -> Result<Arc<TableProfile>> {
+        if let Some(profile) = read_lock(&self.profiles).get(name) {
+            return Ok(profile.clone());
+        }
+        
+        let profile = Arc::new(crate::core::profile::profile_table(self, name)?);
+        if self.ensure_in_memory_table(name).is_ok() {
+            write_lock(&self.profiles).insert(name.to_string(), profile.clone());
+        }
+        Ok(profile)
     }
 
     // Load a table into memory as a DataBatch (cached)
-    pub fn load_table_batch(&mut self, table_name: &str) -> Result<DataBatch> {
+    pub fn load_table_batch(&self, table_name: &str) -> Result<DataBatch> {
         // Check cache first
-        if let Some(batch) = self.batch_cache.get(table_name) {
+        if let Some(batch) = read_lock(&self.batch_cache).get(table_name) {
             return Ok(batch.clone());
         }
         
         // Load from DataFusion into DataBatch
-        let query = format!("SELECT * FROM '{}'", table_name);
+        let query = format!("SELECT * FROM {}", quote_identifier(table_name));
         let batch = self.execute_query_batch(&query)?;
         
         // Cache the batch
-        self.batch_cache.insert(table_name.to_string(), batch.clone());
+        write_lock(&self.batch_cache).insert(table_name.to_string(), batch.clone());
         
         Ok(batch)
     }
 
-    // Execute a DataFusion query and return as DataBatch
-    pub fn execute_query_batch(&self, query: &str) -> Result<DataBatch> {
+    /// Start a query on the runtime's worker threads without blocking the caller.
+    /// `on_batch` receives each batch as DataFusion produces it and `on_done` is called
+    /// with the result schema (or the error) at the end. Aborting the returned handle
+    /// drops the DataFusion stream, which stops the query.
+    pub fn spawn_query<B, D>(&self, query: &str, on_batch: B, on_done: D) -> AbortHandle
+    where
+        B: FnMut(RecordBatch) + Send + 'static,
+        D: FnOnce(Result<SchemaRef>) + Send + 'static,
+    {
+        self.spawn_query_with_params(query, &[], on_batch, on_done)
+    }
+
+    /// Like [`Database::spawn_query`], binding `parameters` to the query's placeholders
+    pub fn spawn_query_with_params<B, D>(
+        &self,
+        query: &str,
+        parameters: &[QueryParameter],
+        mut on_batch: B,
+        on_done: D,
+    ) -> AbortHandle
+    where
+        B: FnMut(RecordBatch) + Send + 'static,
+        D: FnOnce(Result<SchemaRef>) + Send + 'static,
+    {
         let ctx = self.ctx.clone();
+        let options = self.sql_options();
+        let query = query.to_string();
+        let parameters = parameters.to_vec();
+        
+        let task = self.runtime.spawn(async move {
+            let result = async {
+                let df = Self::plan_query(&ctx, &query, options, &parameters).await?;
+                let mut stream = df.execute_stream().await
+                    .map_err(|e| FreshError::Custom(format!("Failed to execute query: {}", e)))?;
+                let schema = stream.schema();
+                while let Some(batch) = stream.next().await {
+                    let batch = batch.map_err(|e| FreshError::Custom(format!("Failed to collect results: {}", e)))?;
+                    on_batch(batch);
+                }
+                Ok(schema)
+            }.await;
+            on_done(result);
+        });
         
-        let result = self.runtime.block_on(async {
-            ctx.sql(query).await
-        }).map_err(|e| FreshError::Custom(format!("Failed to execute query: {}", e)))?;
+        task.abort_handle()
+    }
+
+    /// Execute a query and return its Arrow batches. An empty result still
+    /// yields one empty batch so the schema is available.
+    pub fn execute_query_batches(&self, query: &str) -> Result<Vec<RecordBatch>> {
+        self.execute_query_batches_with_params(query, &[])
+    }
+
+    /// Like [`Database::execute_query_batches`], binding `parameters` to the query's placeholders
+    pub fn execute_query_batches_with_params(&self, query: &str, parameters: &[QueryParameter]) -> Result<Vec<RecordBatch>> {
+        let ctx = self.ctx.clone();
+        let options = self.sql_options();
         
-        let record_batches = self.runtime.block_on(async {
+        let result = self.runtime.block_on(Self::plan_query(&ctx, query, options, parameters))?;
+        let schema: Schema = result.schema().into();
+        
+        let mut record_batches = self.runtime.block_on(async {
             result.collect().await
         }).map_err(|e| FreshError::Custom(format!("Failed to collect results: {}", e)))?;
         
         if record_batches.is_empty() {
-            return Ok(DataBatch {
-                columns: vec![],
-                rows: vec![],
-            });
+            record_batches.push(RecordBatch::new_empty(Arc::new(schema)));
+        }
+        Ok(record_batches)
+    }
+
+    // Plan a query; with parameters, `:name` placeholders are rewritten to `$name` and
+    // the values are bound as scalars typed after what the plan expects
+    async fn plan_query(
+        ctx: &SessionContext,
+        query: &str,
+        options: SQLOptions,
+        parameters: &[QueryParameter],
+    ) -> Result<DataFrame> {
+        if parameters.is_empty() {
+            return ctx.sql_with_options(query, options).await
+                .map_err(|e| FreshError::Custom(format!("Failed to execute query: {}", e)));
+        }
+        
+        let df = ctx.sql_with_options(&params::prepare_sql(query), options).await
+            .map_err(|e| FreshError::Custom(format!("Failed to execute query: {}", e)))?;
+        let types = df.logical_plan().get_parameter_types()
+            .map_err(|e| FreshError::Custom(format!("Failed to read query parameters: {}", e)))?;
+        df.with_param_values(params::param_values(parameters, &types)?)
+            .map_err(|e| FreshError::Custom(format!("Failed to bind query parameters: {}", e)))
+    }
+
+    /// Run a query and write its rows to `path` batch by batch as DataFusion produces
+    /// them. Returns the number of rows written; a partly written file is removed
+    /// when the query or the export fails.
+    pub fn export_query(&self, query: &str, parameters: &[QueryParameter], path: &Path, options: &ExportOptions) -> Result<usize> {
+        self.export_query_cancellable(query, parameters, path, options, &AtomicBool::new(false))
+    }
+
+    /// `export_query` that stops before the next batch once `cancelled` is set,
+    /// removing what was written so far.
+    pub fn export_query_cancellable(&self, query: &str, parameters: &[QueryParameter], path: &Path, options: &ExportOptions, cancelled: &AtomicBool) -> Result<usize> {
+        let ctx = self.ctx.clone();
+        let sql_options = self.sql_options();
+        
+        let result = self.runtime.block_on(async {
+            let df = Self::plan_query(&ctx, query, sql_options, parameters).await?;
+            let mut stream = df.execute_stream().await
+                .map_err(|e| FreshError::Custom(format!("Failed to execute query: {}", e)))?;
+            let mut exporter = BatchExporter::create(path, &stream.schema(), options)?;
+            while let Some(batch) = stream.next().await {
+                if cancelled.load(Ordering::Relaxed) {
+                    return Err(FreshError::Custom("Export cancelled".to_string()));
+                }
+                let batch = batch.map_err(|e| FreshError::Custom(format!("Failed to collect results: {}", e)))?;
+                exporter.write(&batch)?;
+            }
+            exporter.finish()
+        });
+        
+        if result.is_err() && path.exists() {
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("[Database] Failed to remove incomplete export {}: {}", path.display(), e);
+            }
         }
+        result
+    }
+
+    /// Parameters of a query, typed from `@param` comments or from how the query uses
+    /// them. Values entered in `previous` are kept; when the query cannot be planned yet
+    /// the types of `previous` are kept too.
+    pub fn query_parameters(&self, query: &str, previous: &[QueryParameter]) -> Vec<QueryParameter> {
+        let state = self.ctx.state();
+        let inferred = self.runtime.block_on(async {
+            state.create_logical_plan(&params::prepare_sql(query)).await
+        })
+            .and_then(|plan| plan.get_parameter_types())
+            .unwrap_or_default();
+        params::resolve_parameters(query, &inferred, previous)
+    }
+
+    /// Run a query to completion the way EXPLAIN ANALYZE does and return its physical
+    /// plan with the rows, compute time and partitions of every operator. The rows
+    /// themselves are discarded.
+    pub fn explain_analyze(&self, query: &str, parameters: &[QueryParameter]) -> Result<QueryPlan> {
+        let ctx = self.ctx.clone();
+        let options = self.sql_options();
+        
+        self.runtime.block_on(async {
+            let started = std::time::Instant::now();
+            let df = Self::plan_query(&ctx, query, options, parameters).await?;
+            let task_ctx = Arc::new(df.task_ctx());
+            let plan = df.create_physical_plan().await
+                .map_err(|e| FreshError::Custom(format!("Failed to plan query: {}", e)))?;
+            
+            // Operators record their metrics as the stream is drained
+            let mut stream = datafusion::physical_plan::execute_stream(plan.clone(), task_ctx)
+                .map_err(|e| FreshError::Custom(format!("Failed to execute query: {}", e)))?;
+            while let Some(batch) = stream.next().await {
+                batch.map_err(|e| FreshError::Custom(format!("Failed to execute query: {}", e)))?;
+            }
+            Ok(QueryPlan::from_execution_plan(plan.as_ref(), started.elapsed()))
+        })
+    }
+
+    // Execute a DataFusion query and return as DataBatch
+    pub fn execute_query_batch(&self, query: &str) -> Result<DataBatch> {
+        let record_batches = self.execute_query_batches(query)?;
         
-        // Convert first batch to DataBatch
-        let batch = &record_batches[0];
-        let columns: Vec<String> = batch.schema().fields().iter()
+        // Convert every batch to rows, preserving batch order
+        let columns: Vec<String> = record_batches[0].schema().fields().iter()
             .map(|field| field.name().clone())
             .collect();
         
-        let rows = self.record_batch_to_rows(batch)?;
+        let total_rows = record_batches.iter().map(|b| b.num_rows()).sum();
+        let mut rows = Vec::with_capacity(total_rows);
+        for batch in &record_batches {
+            rows.extend(Self::record_batch_to_rows(batch, &self.datetime_format())?);
+        }
         
         Ok(DataBatch {
             columns,
@@ -378,7 +768,7 @@ impl Database {
     }
 
     // Create table in DataFusion (register an empty table)
-    pub fn create_table(&mut self, table_name: &str, columns: &[(&str, &str)]) -> Result<()> {
+    pub fn create_table(&self, table_name: &str, columns: &[(&str, &str)]) -> Result<()> {
         // Convert SQL types to Arrow types
         let fields: Vec<Field> = columns.iter()
             .map(|(name, sql_type)| {
@@ -398,22 +788,24 @@ impl Database {
     }
 
     // Insert data into DataFusion table
-    pub fn insert_data(&mut self, table_name: &str, values: &[Vec<String>]) -> Result<()> {
+    pub fn insert_data(&self, table_name: &str, values: &[Vec<String>]) -> Result<()> {
         if values.is_empty() {
             return Ok(());
         }
         
         // Clear cache for this table since data changed
-        self.batch_cache.remove(table_name);
+        self.invalidate_caches(table_name);
         
         // Convert string values to Arrow arrays
-        let (columns, schema) = if let Some(existing_batch) = self.registered_tables.get(table_name) {
+        let existing_schema = read_lock(&self.registered_tables).get(table_name)
+            .and_then(|b| b.first())
+            .map(|b| b.schema());
+        let (columns, schema) = if let Some(existing_schema) = existing_schema {
             // Use existing schema to preserve column names and types
-            let existing_schema = existing_batch.schema();
             let columns: Vec<String> = existing_schema.fields().iter()
                 .map(|field| field.name().clone())
                 .collect();
-            (columns, existing_schema.clone())
+            (columns, existing_schema)
         } else {
             // Create default column names if table doesn't exist
             let columns: Vec<String> = (0..values[0].len()).map(|i| format!("col_{}", i)).collect();
@@ -429,86 +821,128 @@ impl Database {
             .map_err(|e| FreshError::Custom(format!("Failed to create record batch: {}", e)))?;
         
         // Safely register or replace the table
-        self.register_or_replace_table(table_name, batch.clone())?;
-        
-        // Store in our cache
-        self.registered_tables.insert(table_name.to_string(), Arc::new(batch));
+        self.register_or_replace_table(table_name, vec![batch])?;
         
         Ok(())
     }
 
-    // Helper method to safely register or replace a table
-    fn register_or_replace_table(&mut self, table_name: &str, batch: RecordBatch) -> Result<()> {
-        // Check if table already exists in our tracking
-        if self.registered_tables.contains_key(table_name) {
-            // Remove from our internal tracking first
-            self.registered_tables.remove(table_name);
-        }
-        
-        // Try to register the table
-        match self.ctx.register_batch(table_name, batch.clone()) {
-            Ok(_) => Ok(()),
-            Err(_) => {
-                // If registration fails, the table already exists in DataFusion
-                // We need to create a new context to replace it
-                // This is a workaround since DataFusion doesn't support table replacement
-                
-                // Create a new context
-                let new_ctx = SessionContext::new();
-                
-                // Register all existing tables except the one we're replacing
-                for (existing_name, existing_batch) in &self.registered_tables {
-                    if existing_name != table_name {
-                        new_ctx.register_batch(existing_name, existing_batch.as_ref().clone())
-                            .map_err(|e| FreshError::Custom(format!("Failed to re-register table {}: {}", existing_name, e)))?;
-                    }
+    // Helper method to safely register or replace a table.
+    // All batches must share the schema of the first one; an empty list is not allowed,
+    // use a single empty batch to register a table without rows.
+    // Rows breaking a declared key are rejected and the previous rows are kept.
+    fn register_or_replace_table(&self, table_name: &str, batches: Vec<RecordBatch>) -> Result<()> {
+        let keys = read_lock(&self.table_keys).get(table_name).cloned();
+        let Some(keys) = keys else {
+            return self.replace_table_batches(table_name, batches);
+        };
+        
+        let previous = read_lock(&self.registered_tables).get(table_name).cloned();
+        self.replace_table_batches(table_name, batches)?;
+        if let Err(e) = crate::core::keys::check_keys(self, table_name, &keys) {
+            match previous {
+                Some(previous) => self.replace_table_batches(table_name, previous)?,
+                None => {
+                    self.ctx.deregister_table(table_name)
+                        .map_err(|e| FreshError::Custom(format!("Failed to deregister table {}: {}", table_name, e)))?;
+                    write_lock(&self.registered_tables).remove(table_name);
+                    self.invalidate_caches(table_name);
                 }
-                
-                // Register the new table
-                new_ctx.register_batch(table_name, batch)
-                    .map_err(|e| FreshError::Custom(format!("Failed to register data: {}", e)))?;
-                
-                // Replace the context
-                self.ctx = new_ctx;
-                
-                Ok(())
             }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn replace_table_batches(&self, table_name: &str, batches: Vec<RecordBatch>) -> Result<()> {
+        if read_lock(&self.external_tables).contains_key(table_name) {
+            return Err(FreshError::Custom(format!(
+                "Table '{}' is attached from files and cannot be modified",
+                table_name
+            )));
         }
+        let schema = batches.first()
+            .map(|b| b.schema())
+            .ok_or_else(|| FreshError::Custom(format!("No record batches to register for table '{}'", table_name)))?;
+        
+        // Drop any previous registration so the new data replaces it
+        self.ctx.deregister_table(table_name)
+            .map_err(|e| FreshError::Custom(format!("Failed to deregister table {}: {}", table_name, e)))?;
+        
+        // MemTable keeps every batch as one partition, so nothing is truncated
+        let provider = MemTable::try_new(schema, vec![batches.clone()])
+            .map_err(|e| FreshError::Custom(format!("Failed to create table {}: {}", table_name, e)))?;
+        self.ctx.register_table(table_name, Arc::new(provider))
+            .map_err(|e| FreshError::Custom(format!("Failed to register data: {}", e)))?;
+        
+        self.invalidate_caches(table_name);
+        write_lock(&self.registered_tables).insert(table_name.to_string(), batches);
+        
+        Ok(())
     }
 
     // Get table information from DataFusion
     pub fn get_tables(&self) -> Result<Vec<TableInfo>> {
         let mut tables = Vec::new();
+        let reports = read_lock(&self.quality_reports);
         
-        for (table_name, batch) in &self.registered_tables {
-            let columns: Vec<ColumnInfo> = batch.schema().fields().iter()
-                .map(|field| ColumnInfo {
-                    name: field.name().clone(),
-                    data_type: self.arrow_type_to_sql_type(field.data_type()).to_string(),
-                    is_nullable: field.is_nullable(),
-                    is_primary_key: false, // DataFusion doesn't track primary keys
-                })
-                .collect();
+        let table_keys = read_lock(&self.table_keys);
+        let lineage = read_lock(&self.lineage);
+        
+        for (table_name, batches) in read_lock(&self.registered_tables).iter() {
+            let schema = match batches.first() {
+                Some(batch) => batch.schema(),
+                None => continue,
+            };
+            let keys = table_keys.get(table_name).cloned().unwrap_or_default();
             
             tables.push(TableInfo {
                 name: table_name.clone(),
-                columns,
-                row_count: batch.num_rows() as i64,
+                columns: self.column_infos(&schema, &keys),
+                row_count: batches.iter().map(|b| b.num_rows() as i64).sum(),
+                is_external: false,
+                quality_violations: reports.get(table_name).map(|r| r.violation_count()),
+                keys,
+                lineage: lineage.get(table_name).cloned(),
+            });
+        }
+        
+        for (table_name, (_, schema)) in read_lock(&self.external_tables).iter() {
+            let keys = table_keys.get(table_name).cloned().unwrap_or_default();
+            tables.push(TableInfo {
+                name: table_name.clone(),
+                columns: self.column_infos(schema, &keys),
+                row_count: -1,
+                is_external: true,
+                quality_violations: reports.get(table_name).map(|r| r.violation_count()),
+                keys,
+                lineage: None,
             });
         }
         
         Ok(tables)
     }
 
+    fn column_infos(&self, schema: &SchemaRef, keys: &TableKeys) -> Vec<ColumnInfo> {
+        schema.fields().iter()
+            .map(|field| ColumnInfo {
+                name: field.name().clone(),
+                data_type: self.arrow_type_to_sql_type(field.data_type()).to_string(),
+                is_nullable: field.is_nullable(),
+                // DataFusion doesn't track primary keys, so they come from the declarations
+                is_primary_key: keys.primary_key.contains(field.name()),
+            })
+            .collect()
+    }
+
     // Helper methods for DataFusion integration
-    fn record_batch_to_rows(&self, batch: &RecordBatch) -> Result<Vec<Vec<String>>> {
+    pub(crate) fn record_batch_to_rows(batch: &RecordBatch, format: &DateTimeFormat) -> Result<Vec<Vec<String>>> {
         let mut rows = Vec::new();
         
         for row_idx in 0..batch.num_rows() {
             let mut row = Vec::new();
             for col_idx in 0..batch.num_columns() {
                 let array = batch.column(col_idx);
-                let value = self.array_value_to_string(array, row_idx)?;
+                let value = Self::array_value_to_string(array, row_idx, format)?;
                 row.push(value);
             }
             rows.push(row);
@@ -517,119 +951,56 @@ impl Database {
         Ok(rows)
     }
 
-    fn array_value_to_string(&self, array: &datafusion::arrow::array::ArrayRef, index: usize) -> Result<String> {
-        use datafusion::arrow::array::*;
+    /// Format one value for display and text export. NULL is always rendered as an
+    /// empty string (never as the placeholder value stored under the null slot).
+    /// Timestamps are shown with their date in `format` and times of day as
+    /// HH:MM:SS[.fraction]; every other type uses Arrow's display formatting, with
+    /// NULLs nested in lists and structs shown as `NULL`.
+    pub(crate) fn array_value_to_string(array: &datafusion::arrow::array::ArrayRef, index: usize, format: &DateTimeFormat) -> Result<String> {
+        use datafusion::arrow::array::AsArray;
+        use datafusion::arrow::datatypes::{
+            Float32Type, Float64Type, Time32MillisecondType, Time32SecondType, Time64MicrosecondType,
+            Time64NanosecondType, TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+            TimestampSecondType,
+        };
+        use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+        
+        if index >= array.len() {
+            return Err(FreshError::Custom(format!("Row {} is out of range for a column of {} values", index, array.len())));
+        }
+        if array.is_null(index) {
+            return Ok(String::new());
+        }
         
         match array.data_type() {
-            DataType::Utf8 => {
-                let string_array = array.as_any().downcast_ref::<StringArray>().unwrap();
-                Ok(string_array.value(index).to_string())
-            }
-            DataType::Int64 => {
-                let int_array = array.as_any().downcast_ref::<Int64Array>().unwrap();
-                Ok(int_array.value(index).to_string())
-            }
-            DataType::Float64 => {
-                let float_array = array.as_any().downcast_ref::<Float64Array>().unwrap();
-                Ok(float_array.value(index).to_string())
-            }
-            DataType::Boolean => {
-                let bool_array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
-                Ok(bool_array.value(index).to_string())
-            }
-            DataType::Timestamp(unit, _) => {
-                // Handle timestamp arrays - convert back to time-only format
-                match unit {
-                    TimeUnit::Second => {
-                        let timestamp_array = array.as_any().downcast_ref::<TimestampSecondArray>().unwrap();
-                        if timestamp_array.is_null(index) {
-                            Ok("".to_string())
-                        } else {
-                            let timestamp = timestamp_array.value(index);
-                            // Convert seconds since midnight back to HH:MM:SS format
-                            let hours = (timestamp / 3600) % 24;
-                            let minutes = (timestamp / 60) % 60;
-                            let seconds = timestamp % 60;
-                            Ok(format!("{:02}:{:02}:{:02}", hours, minutes, seconds))
-                        }
-                    }
-                    TimeUnit::Millisecond => {
-                        let timestamp_array = array.as_any().downcast_ref::<TimestampMillisecondArray>().unwrap();
-                        if timestamp_array.is_null(index) {
-                            Ok("".to_string())
-                        } else {
-                            let timestamp = timestamp_array.value(index);
-                            // Convert milliseconds since midnight back to HH:MM:SS.mmm format
-                            let total_seconds = timestamp / 1_000;
-                            let milliseconds = timestamp % 1_000;
-                            let hours = (total_seconds / 3600) % 24;
-                            let minutes = (total_seconds / 60) % 60;
-                            let seconds = total_seconds % 60;
-                            Ok(format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, milliseconds))
-                        }
-                    }
-                    TimeUnit::Microsecond => {
-                        let timestamp_array = array.as_any().downcast_ref::<TimestampMicrosecondArray>().unwrap();
-                        if timestamp_array.is_null(index) {
-                            Ok("".to_string())
-                        } else {
-                            let timestamp = timestamp_array.value(index);
-                            // Convert microseconds since midnight back to HH:MM:SS.mmmmmm format
-                            let total_seconds = timestamp / 1_000_000;
-                            let microseconds = timestamp % 1_000_000;
-                            let hours = (total_seconds / 3600) % 24;
-                            let minutes = (total_seconds / 60) % 60;
-                            let seconds = total_seconds % 60;
-                            Ok(format!("{:02}:{:02}:{:02}.{:06}", hours, minutes, seconds, microseconds))
-                        }
-                    }
-                    TimeUnit::Nanosecond => {
-                        let timestamp_array = array.as_any().downcast_ref::<TimestampNanosecondArray>().unwrap();
-                        if timestamp_array.is_null(index) {
-                            Ok("".to_string())
-                        } else {
-                            let timestamp = timestamp_array.value(index);
-                            // Convert nanoseconds since midnight back to HH:MM:SS.nnnnnnnnn format
-                            let total_seconds = timestamp / 1_000_000_000;
-                            let nanoseconds = timestamp % 1_000_000_000;
-                            let hours = (total_seconds / 3600) % 24;
-                            let minutes = (total_seconds / 60) % 60;
-                            let seconds = total_seconds % 60;
-                            Ok(format!("{:02}:{:02}:{:02}.{:09}", hours, minutes, seconds, nanoseconds))
-                        }
-                    }
-                }
-            }
-            DataType::Date32 => {
-                let date_array = array.as_any().downcast_ref::<Date32Array>().unwrap();
-                if date_array.is_null(index) {
-                    Ok("".to_string())
-                } else {
-                    let days = date_array.value(index);
-                    // Convert days since epoch (1970-01-01) to readable date
-                    // This is a simplified conversion - in production you'd want a proper date library
-                    let year = 1970 + (days / 365);
-                    let remaining_days = days % 365;
-                    
-                    // Simplified month/day calculation
-                    let month_days = [0, 31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
-                    let mut month = 1;
-                    let mut day = remaining_days;
-                    
-                    for &days_in_month in &month_days[1..] {
-                        if day < days_in_month {
-                            break;
-                        }
-                        day -= days_in_month;
-                        month += 1;
-                    }
-                    
-                    Ok(format!("{:04}-{:02}-{:02}", year, month, day + 1))
-                }
+            // Rust's float formatting keeps whole numbers short ("2" rather than "2.0")
+            DataType::Float64 => Ok(array.as_primitive::<Float64Type>().value(index).to_string()),
+            DataType::Float32 => Ok(array.as_primitive::<Float32Type>().value(index).to_string()),
+            DataType::Timestamp(unit, zone) => {
+                let value = match unit {
+                    TimeUnit::Second => array.as_primitive::<TimestampSecondType>().value(index),
+                    TimeUnit::Millisecond => array.as_primitive::<TimestampMillisecondType>().value(index),
+                    TimeUnit::Microsecond => array.as_primitive::<TimestampMicrosecondType>().value(index),
+                    TimeUnit::Nanosecond => array.as_primitive::<TimestampNanosecondType>().value(index),
+                };
+                Ok(datetime::format_timestamp(value, unit, zone.as_deref(), format))
             }
+            DataType::Time32(TimeUnit::Second) => Ok(datetime::format_time_of_day(
+                array.as_primitive::<Time32SecondType>().value(index) as i64, &TimeUnit::Second
+            )),
+            DataType::Time32(TimeUnit::Millisecond) => Ok(datetime::format_time_of_day(
+                array.as_primitive::<Time32MillisecondType>().value(index) as i64, &TimeUnit::Millisecond
+            )),
+            DataType::Time64(TimeUnit::Microsecond) => Ok(datetime::format_time_of_day(
+                array.as_primitive::<Time64MicrosecondType>().value(index), &TimeUnit::Microsecond
+            )),
+            DataType::Time64(TimeUnit::Nanosecond) => Ok(datetime::format_time_of_day(
+                array.as_primitive::<Time64NanosecondType>().value(index), &TimeUnit::Nanosecond
+            )),
             _ => {
-                // For other types, convert to string representation
-                Ok(format!("{:?}", array))
+                let options = FormatOptions::default().with_null("NULL");
+                let formatter = ArrayFormatter::try_new(array.as_ref(), &options)?;
+                Ok(formatter.value(index).to_string())
             }
         }
     }
@@ -718,543 +1089,1470 @@ impl Database {
         Ok(arrays)
     }
 
-    fn string_rows_to_arrow_arrays_with_schema(&self, columns: &[String], values: &[Vec<String>], schema: &Schema) -> Result<Vec<datafusion::arrow::array::ArrayRef>> {
-        let mut arrays = Vec::new();
+    fn sql_type_to_arrow_type(&self, sql_type: &str) -> DataType {
+        match sql_type.to_uppercase().as_str() {
+            "INTEGER" | "INT" | "BIGINT" => DataType::Int64,
+            "REAL" | "DOUBLE" | "FLOAT" => DataType::Float64,
+            "BOOLEAN" | "BOOL" => DataType::Boolean,
+            "TEXT" | "VARCHAR" | "STRING" => DataType::Utf8,
+            "TIMESTAMP" => DataType::Timestamp(TimeUnit::Nanosecond, None),
+            "TIME" => DataType::Time64(TimeUnit::Nanosecond),
+            _ => DataType::Utf8, // Default to string
+        }
+    }
+
+    fn arrow_type_to_sql_type(&self, arrow_type: &DataType) -> &'static str {
+        match arrow_type {
+            DataType::Int64 => "INTEGER",
+            DataType::Float64 => "REAL",
+            DataType::Boolean => "BOOLEAN",
+            DataType::Utf8 => "TEXT",
+            DataType::Timestamp(_, _) => "TIMESTAMP",
+            DataType::Time32(_) | DataType::Time64(_) => "TIME",
+            _ => "TEXT",
+        }
+    }
+
+    /// Tables, views and SQL functions, for completion in the query editor
+    pub fn sql_schema(&self) -> Result<SqlSchema> {
+        let mut tables = self.get_tables()?;
+        for view in self.get_views()? {
+            let ctx = self.ctx.clone();
+            let name = view.name.clone();
+            let Ok(df) = self.runtime.block_on(async move { ctx.table(name.as_str()).await }) else {
+                continue;
+            };
+            let schema: Schema = df.schema().into();
+            tables.push(TableInfo {
+                name: view.name,
+                columns: self.column_infos(&Arc::new(schema), &TableKeys::default()),
+                row_count: -1,
+                is_external: false,
+                quality_violations: None,
+                keys: TableKeys::default(),
+                lineage: None,
+            });
+        }
+        tables.sort_by_key(|t| t.name.to_lowercase());
         
-        for (col_idx, field) in schema.fields().iter().enumerate() {
-            let data_type = field.data_type();
-            
-            match data_type {
-                DataType::Int64 => {
-                    // Create Int64 array
-                    let mut int_values = Vec::new();
-                    for row in values {
-                        if col_idx < row.len() {
-                            let value = &row[col_idx];
-                            if value.is_empty() || value.to_lowercase() == "null" || value == "-" || value.to_lowercase() == "n/a" {
-                                int_values.push(None);
-                            } else {
-                                match value.parse::<i64>() {
-                                    Ok(int_val) => int_values.push(Some(int_val)),
-                                    Err(_) => int_values.push(None),
-                                }
-                            }
-                        } else {
-                            int_values.push(None);
-                        }
-                    }
-                    let array = Int64Array::from(int_values);
-                    arrays.push(Arc::new(array) as datafusion::arrow::array::ArrayRef);
-                },
-                DataType::Float64 => {
-                    // Create Float64 array
-                    let mut float_values = Vec::new();
-                    for row in values {
-                        if col_idx < row.len() {
-                            let value = &row[col_idx];
-                            if value.is_empty() || value.to_lowercase() == "null" || value == "-" || value.to_lowercase() == "n/a" {
-                                float_values.push(None);
-                            } else {
-                                match value.parse::<f64>() {
-                                    Ok(float_val) => float_values.push(Some(float_val)),
-                                    Err(_) => float_values.push(None),
-                                }
-                            }
-                        } else {
-                            float_values.push(None);
-                        }
-                    }
-                    let array = Float64Array::from(float_values);
-                    arrays.push(Arc::new(array) as datafusion::arrow::array::ArrayRef);
-                },
-                DataType::Boolean => {
-                    // Create Boolean array
-                    let mut bool_values = Vec::new();
-                    for row in values {
-                        if col_idx < row.len() {
-                            let value = &row[col_idx];
-                            if value.is_empty() || value.to_lowercase() == "null" || value == "-" || value.to_lowercase() == "n/a" {
-                                bool_values.push(None);
-                            } else {
-                                let lower = value.to_lowercase();
-                                match lower.as_str() {
-                                    "true" | "1" | "yes" | "y" => bool_values.push(Some(true)),
-                                    "false" | "0" | "no" | "n" => bool_values.push(Some(false)),
-                                    _ => bool_values.push(None),
-                                }
-                            }
-                        } else {
-                            bool_values.push(None);
-                        }
-                    }
-                    let array = BooleanArray::from(bool_values);
-                    arrays.push(Arc::new(array) as datafusion::arrow::array::ArrayRef);
-                },
-                DataType::Timestamp(unit, tz) => {
-                    // Create Timestamp array with the correct precision
-                    let mut timestamp_values = Vec::new();
-                    for row in values {
-                        if col_idx < row.len() {
-                            let value = &row[col_idx];
-                            if value.is_empty() || value.to_lowercase() == "null" || value == "-" || value.to_lowercase() == "n/a" {
-                                timestamp_values.push(None);
-                            } else {
-                                // Try to parse time string in HH:MM:SS format
-                                if let Some(timestamp) = Self::parse_time_string_to_timestamp(value, unit) {
-                                    timestamp_values.push(Some(timestamp));
-                                } else {
-                                    // Try to parse as regular timestamp number
-                                    match value.parse::<i64>() {
-                                        Ok(ts) => {
-                                            // Convert to the correct unit
-                                            let converted_ts = match unit {
-                                                TimeUnit::Second => ts,
-                                                TimeUnit::Millisecond => ts * 1_000,
-                                                TimeUnit::Microsecond => ts * 1_000_000,
-                                                TimeUnit::Nanosecond => ts * 1_000_000_000,
-                                            };
-                                            timestamp_values.push(Some(converted_ts));
-                                        }
-                                        Err(_) => {
-                                            // If not a number, try to parse as date string
-                                            // For now, just store as null if we can't parse
-                                            timestamp_values.push(None);
-                                        }
-                                    }
-                                }
-                            }
-                        } else {
-                            timestamp_values.push(None);
-                        }
-                    }
-                    
-                    // Create the appropriate timestamp array based on the unit
-                    let array: datafusion::arrow::array::ArrayRef = match unit {
-                        TimeUnit::Second => {
-                            let array = TimestampSecondArray::from(timestamp_values);
-                            Arc::new(array)
-                        },
-                        TimeUnit::Millisecond => {
-                            let array = TimestampMillisecondArray::from(timestamp_values);
-                            Arc::new(array)
-                        },
-                        TimeUnit::Microsecond => {
-                            let array = TimestampMicrosecondArray::from(timestamp_values);
-                            Arc::new(array)
-                        },
-                        TimeUnit::Nanosecond => {
-                            let array = TimestampNanosecondArray::from(timestamp_values);
-                            Arc::new(array)
-                        },
-                    };
-                    arrays.push(array);
-                },
-                DataType::Date32 => {
-                    // Create Date32 array
-                    let mut date_values = Vec::new();
-                    for row in values {
-                        if col_idx < row.len() {
-                            let value = &row[col_idx];
-                            if value.is_empty() || value.to_lowercase() == "null" || value == "-" || value.to_lowercase() == "n/a" {
-                                date_values.push(None);
-                            } else {
-                                // Try to parse as date string (YYYY-MM-DD format)
-                                match Self::parse_date_string(value) {
-                                    Some(days) => date_values.push(Some(days)),
-                                    None => date_values.push(None),
-                                }
-                            }
-                        } else {
-                            date_values.push(None);
-                        }
-                    }
-                    let array = Date32Array::from(date_values);
-                    arrays.push(Arc::new(array) as datafusion::arrow::array::ArrayRef);
-                },
-                _ => {
-                    // Default to String array for all other types
-                    let mut string_values = Vec::new();
-                    for row in values {
-                        if col_idx < row.len() {
-                            let value = &row[col_idx];
-                            // Treat null values as empty strings for display
-                            if value.is_empty() || value.to_lowercase() == "null" || value == "-" || value.to_lowercase() == "n/a" {
-                                string_values.push(String::new());
-                            } else {
-                                string_values.push(value.clone());
-                            }
-                        } else {
-                            string_values.push(String::new());
-                        }
-                    }
-                    let array = StringArray::from(string_values);
-                    arrays.push(Arc::new(array) as datafusion::arrow::array::ArrayRef);
+        Ok(SqlSchema {
+            tables,
+            functions: self.function_names(),
+        })
+    }
+    
+    /// Names of the SQL functions queries can call, built in or registered
+    pub fn function_names(&self) -> Vec<String> {
+        let state = self.ctx.state();
+        let mut names: Vec<String> = state.scalar_functions().keys()
+            .chain(state.aggregate_functions().keys())
+            .chain(state.window_functions().keys())
+            .cloned()
+            .chain(BUILTIN_AGGREGATE_AND_WINDOW_FUNCTIONS.iter().map(|f| f.to_string()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+    
+    /// Plan a query without running it, to report mistakes while it is being written
+    pub fn check_query(&self, query: &str) -> Result<()> {
+        let state = self.ctx.state();
+        self.runtime.block_on(async {
+            state.create_logical_plan(&params::prepare_sql(query)).await
+        }).map_err(|e| FreshError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_views(&self) -> Result<Vec<ViewInfo>> {
+        let views = self.views.read()
+            .map_err(|_| FreshError::Database("View registry lock poisoned".to_string()))?;
+        Ok(views.iter()
+            .map(|(name, sql)| ViewInfo { name: name.clone(), sql: sql.clone() })
+            .collect())
+    }
+
+    /// Register (or replace) a view in the DataFusion context and persist it in the project
+    pub fn create_view(&self, name: &str, sql: &str) -> Result<()> {
+        self.ensure_writable("create a view")?;
+        self.register_view(name, sql)?;
+        self.save_views()
+    }
+
+    // Register a view in the DataFusion context without persisting it
+    fn register_view(&self, name: &str, sql: &str) -> Result<()> {
+        let sql = sql.trim().trim_end_matches(';').trim();
+        if name.trim().is_empty() {
+            return Err(FreshError::Custom("View name cannot be empty".to_string()));
+        }
+        if read_lock(&self.registered_tables).contains_key(name) || read_lock(&self.external_tables).contains_key(name) {
+            return Err(FreshError::Custom(format!("A table named '{}' already exists", name)));
+        }
+        
+        let statement = format!("CREATE OR REPLACE VIEW {} AS {}", quote_identifier(name), sql);
+        let ctx = self.ctx.clone();
+        self.runtime.block_on(async {
+            ctx.sql(&statement).await
+        }).map_err(|e| FreshError::Custom(format!("Failed to create view: {}", e)))?;
+        
+        self.views.write()
+            .map_err(|_| FreshError::Database("View registry lock poisoned".to_string()))?
+            .insert(name.to_string(), sql.to_string());
+        
+        Ok(())
+    }
+
+    /// Remove a view from the DataFusion context and the project
+    pub fn drop_view(&self, name: &str) -> Result<()> {
+        self.ensure_writable("drop a view")?;
+        let removed = self.views.write()
+            .map_err(|_| FreshError::Database("View registry lock poisoned".to_string()))?
+            .remove(name);
+        if removed.is_none() {
+            return Err(FreshError::Custom(format!("View '{}' does not exist", name)));
+        }
+        
+        self.ctx.deregister_table(name)
+            .map_err(|e| FreshError::Custom(format!("Failed to drop view: {}", e)))?;
+        
+        self.save_views()
+    }
+
+    /// If `sql` is a `CREATE [OR REPLACE] VIEW name AS query` statement, return the view name and query
+    pub fn parse_create_view(sql: &str) -> Option<(String, String)> {
+        use datafusion::sql::parser::{DFParser, Statement as DFStatement};
+        use datafusion::sql::sqlparser::ast::Statement;
+        
+        let mut statements = DFParser::parse_sql(sql).ok()?;
+        if statements.len() != 1 {
+            return None;
+        }
+        
+        match statements.pop_front()? {
+            DFStatement::Statement(statement) => match *statement {
+                Statement::CreateView { name, query, .. } => {
+                    let view_name = name.0.last()?.value.clone();
+                    Some((view_name, query.to_string()))
                 }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// If `sql` is a `CREATE [OR REPLACE] TABLE [IF NOT EXISTS] name AS query` statement, return its parts
+    pub fn parse_create_table_as(sql: &str) -> Option<CreateTableAs> {
+        use datafusion::sql::parser::{DFParser, Statement as DFStatement};
+        use datafusion::sql::sqlparser::ast::Statement;
+        
+        let mut statements = DFParser::parse_sql(sql).ok()?;
+        if statements.len() != 1 {
+            return None;
+        }
+        
+        match statements.pop_front()? {
+            DFStatement::Statement(statement) => match *statement {
+                Statement::CreateTable { name, query: Some(query), or_replace, if_not_exists, .. } => {
+                    Some(CreateTableAs {
+                        name: name.0.last()?.value.clone(),
+                        query: query.to_string(),
+                        or_replace,
+                        if_not_exists,
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Write the view definitions to the project folder
+    fn save_views(&self) -> Result<()> {
+        // Written by commit_transaction instead
+        if self.in_transaction() {
+            return Ok(());
+        }
+        let Some(project_path) = &self.project_path else {
+            return Ok(());
+        };
+        
+        let views = self.get_views()?;
+        let path = project_path.join(VIEWS_FILE);
+        if views.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .map_err(|e| FreshError::Custom(format!("Failed to remove views file: {}", e)))?;
             }
+            return Ok(());
         }
         
-        Ok(arrays)
+        std::fs::create_dir_all(project_path)
+            .map_err(|e| FreshError::Custom(format!("Failed to create directory: {}", e)))?;
+        let json = serde_json::to_string_pretty(&views)
+            .map_err(|e| FreshError::Custom(format!("Failed to serialize views: {}", e)))?;
+        std::fs::write(&path, json)
+            .map_err(|e| FreshError::Custom(format!("Failed to write views file: {}", e)))?;
+        
+        Ok(())
     }
 
-    fn sql_type_to_arrow_type(&self, sql_type: &str) -> DataType {
-        match sql_type.to_uppercase().as_str() {
-            "INTEGER" | "INT" | "BIGINT" => DataType::Int64,
-            "REAL" | "DOUBLE" | "FLOAT" => DataType::Float64,
-            "BOOLEAN" | "BOOL" => DataType::Boolean,
-            "TEXT" | "VARCHAR" | "STRING" => DataType::Utf8,
-            "TIMESTAMP" => DataType::Timestamp(TimeUnit::Nanosecond, None),
-            _ => DataType::Utf8, // Default to string
+    /// Re-register the views saved in a project folder
+    pub fn load_views_from_directory(&self, directory: &Path) -> Result<Vec<String>> {
+        let path = directory.join(VIEWS_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
         }
+        
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| FreshError::Custom(format!("Failed to read views file: {}", e)))?;
+        let views: Vec<ViewInfo> = serde_json::from_str(&json)
+            .map_err(|e| FreshError::Custom(format!("Failed to parse views file: {}", e)))?;
+        
+        Ok(self.register_views(views))
     }
 
-    fn arrow_type_to_sql_type(&self, arrow_type: &DataType) -> &'static str {
-        match arrow_type {
-            DataType::Int64 => "INTEGER",
-            DataType::Float64 => "REAL",
-            DataType::Boolean => "BOOLEAN",
-            DataType::Utf8 => "TEXT",
-            DataType::Timestamp(_, _) => "TIMESTAMP",
-            _ => "TEXT",
+    // Register views in dependency order: a view that fails is retried after the
+    // others, since it may select from a view that comes later alphabetically
+    fn register_views(&self, mut pending: Vec<ViewInfo>) -> Vec<String> {
+        let mut registered = Vec::new();
+        loop {
+            let registered_before = registered.len();
+            let mut failed = Vec::new();
+            for view in pending {
+                match self.register_view(&view.name, &view.sql) {
+                    Ok(_) => registered.push(view.name),
+                    Err(e) => failed.push((view, e)),
+                }
+            }
+            
+            if failed.is_empty() || registered.len() == registered_before {
+                for (view, e) in &failed {
+                    eprintln!("[Database] Failed to load view '{}': {}", view.name, e);
+                }
+                return registered;
+            }
+            pending = failed.into_iter().map(|(view, _)| view).collect();
         }
     }
 
-    // Placeholder methods for compatibility
-    pub fn get_views(&self) -> Result<Vec<ViewInfo>> {
-        Ok(vec![]) // DataFusion doesn't have views in the same way
+    /// Attach a file or a folder of files as a table that is scanned on every query
+    /// instead of being loaded into memory, and remember it in the project
+    pub fn attach_external_table(&self, name: &str, location: &Path, options: &ExternalTableOptions) -> Result<()> {
+        self.ensure_writable("attach a table")?;
+        if name.trim().is_empty() {
+            return Err(FreshError::Custom("Table name cannot be empty".to_string()));
+        }
+        if self.table_exists(name)? {
+            return Err(FreshError::Custom(format!("A table or view named '{}' already exists", name)));
+        }
+        
+        let table = ExternalTable::new(name, location, options)?;
+        self.register_external_table(table)?;
+        self.save_external_tables()
+    }
+
+    /// Stop querying an attached table; its files are left untouched
+    pub fn detach_external_table(&self, name: &str) -> Result<()> {
+        self.ensure_writable("detach a table")?;
+        if write_lock(&self.external_tables).remove(name).is_none() {
+            return Err(FreshError::Custom(format!("Table '{}' is not an attached table", name)));
+        }
+        self.ctx.deregister_table(name)
+            .map_err(|e| FreshError::Custom(format!("Failed to detach table: {}", e)))?;
+        self.invalidate_caches(name);
+        self.save_external_tables()
+    }
+
+    /// Definitions of the attached tables, sorted by name
+    pub fn external_tables(&self) -> Vec<ExternalTable> {
+        read_lock(&self.external_tables).values().map(|(table, _)| table.clone()).collect()
+    }
+
+    fn register_external_table(&self, table: ExternalTable) -> Result<()> {
+        let ctx = self.ctx.clone();
+        let provider = self.runtime.block_on(table.provider(&ctx))?;
+        let schema = datafusion::datasource::TableProvider::schema(provider.as_ref());
+        
+        self.ctx.deregister_table(&table.name)
+            .map_err(|e| FreshError::Custom(format!("Failed to deregister table {}: {}", table.name, e)))?;
+        self.ctx.register_table(table.name.as_str(), provider)
+            .map_err(|e| FreshError::Custom(format!("Failed to register external table: {}", e)))?;
+        
+        self.invalidate_caches(&table.name);
+        write_lock(&self.external_tables).insert(table.name.clone(), (table, schema));
+        Ok(())
+    }
+
+    // Write the attached table definitions to the project folder
+    fn save_external_tables(&self) -> Result<()> {
+        if self.in_transaction() {
+            return Ok(());
+        }
+        let Some(project_path) = &self.project_path else {
+            return Ok(());
+        };
+        
+        let path = project_path.join(EXTERNAL_TABLES_FILE);
+        if read_lock(&self.external_tables).is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .map_err(|e| FreshError::Custom(format!("Failed to remove external tables file: {}", e)))?;
+            }
+            return Ok(());
+        }
+        
+        std::fs::create_dir_all(project_path)
+            .map_err(|e| FreshError::Custom(format!("Failed to create directory: {}", e)))?;
+        let json = serde_json::to_string_pretty(&self.external_tables())
+            .map_err(|e| FreshError::Custom(format!("Failed to serialize external tables: {}", e)))?;
+        std::fs::write(&path, json)
+            .map_err(|e| FreshError::Custom(format!("Failed to write external tables file: {}", e)))?;
+        
+        Ok(())
+    }
+
+    /// Re-attach the tables saved in a project folder; missing files are skipped
+    pub fn load_external_tables_from_directory(&self, directory: &Path) -> Result<Vec<String>> {
+        let path = directory.join(EXTERNAL_TABLES_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| FreshError::Custom(format!("Failed to read external tables file: {}", e)))?;
+        let tables: Vec<ExternalTable> = serde_json::from_str(&json)
+            .map_err(|e| FreshError::Custom(format!("Failed to parse external tables file: {}", e)))?;
+        
+        let mut attached = Vec::new();
+        for table in tables {
+            let name = table.name.clone();
+            match self.register_external_table(table) {
+                Ok(_) => attached.push(name),
+                Err(e) => eprintln!("[Database] Failed to attach table '{}': {}", name, e),
+            }
+        }
+        
+        Ok(attached)
+    }
+
+    /// Remove a table from the catalog and delete its files from the project.
+    /// Attached tables are detached instead; their source files are left untouched.
+    pub fn drop_table(&self, name: &str) -> Result<()> {
+        self.ensure_writable("drop a table")?;
+        self.ensure_no_dependent_views(name, "drop")?;
+        if read_lock(&self.external_tables).contains_key(name) {
+            self.detach_external_table(name)?;
+            self.remove_table_keys(name)?;
+            return self.remove_quality_rules(name);
+        }
+        self.ensure_in_memory_table(name)?;
+        
+        self.ctx.deregister_table(name)
+            .map_err(|e| FreshError::Custom(format!("Failed to drop table {}: {}", name, e)))?;
+        write_lock(&self.registered_tables).remove(name);
+        self.invalidate_caches(name);
+        self.remove_table_files(name)?;
+        self.remove_table_keys(name)?;
+        self.remove_lineage(name)?;
+        self.remove_quality_rules(name)
+    }
+
+    /// Give a table a new name, renaming its files in the project
+    pub fn rename_table(&self, name: &str, new_name: &str) -> Result<()> {
+        self.ensure_writable("rename a table")?;
+        let new_name = new_name.trim();
+        self.ensure_new_table_name(new_name)?;
+        self.ensure_no_dependent_views(name, "rename")?;
+        
+        let external = read_lock(&self.external_tables).get(name).map(|(table, _)| table.clone());
+        if let Some(mut table) = external {
+            table.name = new_name.to_string();
+            self.register_external_table(table)?;
+            write_lock(&self.external_tables).remove(name);
+            self.ctx.deregister_table(name)
+                .map_err(|e| FreshError::Custom(format!("Failed to rename table {}: {}", name, e)))?;
+            self.save_external_tables()?;
+            self.move_table_keys(name, new_name)?;
+            return self.move_quality_rules(name, new_name);
+        }
+        self.ensure_in_memory_table(name)?;
+        
+        let batches = read_lock(&self.registered_tables).get(name).cloned().unwrap_or_default();
+        self.register_or_replace_table(new_name, batches)?;
+        self.ctx.deregister_table(name)
+            .map_err(|e| FreshError::Custom(format!("Failed to rename table {}: {}", name, e)))?;
+        write_lock(&self.registered_tables).remove(name);
+        self.invalidate_caches(name);
+        self.rename_table_files(name, new_name)?;
+        self.move_table_keys(name, new_name)?;
+        self.move_lineage(name, new_name)?;
+        self.move_quality_rules(name, new_name)
+    }
+
+    /// Views whose query selects from `table`
+    pub fn dependent_views(&self, table: &str) -> Vec<String> {
+        let state = self.ctx.state();
+        read_lock(&self.views).iter()
+            .filter(|(_, sql)| {
+                state.sql_to_statement(sql, "generic")
+                    .and_then(|statement| state.resolve_table_references(&statement))
+                    .is_ok_and(|references| references.iter().any(|r| r.table() == table))
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    // Views would stop working, and fail to load with the project, without the table
+    fn ensure_no_dependent_views(&self, table: &str, action: &str) -> Result<()> {
+        let views = self.dependent_views(table);
+        if views.is_empty() {
+            return Ok(());
+        }
+        Err(FreshError::Custom(format!(
+            "Cannot {} table '{}' because views depend on it: {}",
+            action,
+            table,
+            views.join(", ")
+        )))
+    }
+
+    /// Create `target` holding a copy of the rows of `source` (a table, view or
+    /// attached table) and save it in the project
+    pub fn copy_table(&self, source: &str, target: &str) -> Result<()> {
+        self.ensure_writable("copy a table")?;
+        let target = target.trim();
+        self.ensure_new_table_name(target)?;
+        if !self.table_exists(source)? {
+            return Err(FreshError::Custom(format!("Table '{}' does not exist", source)));
+        }
+        
+        let batches = match read_lock(&self.registered_tables).get(source) {
+            Some(batches) => batches.clone(),
+            None => self.execute_query_batches(&format!("SELECT * FROM {}", quote_identifier(source)))?,
+        };
+        self.register_or_replace_table(target, batches)?;
+        self.save_table_to_project(target)
+    }
+
+    /// Run `sql` to completion and save its rows as the new table `name`, recording
+    /// the query so the table can be refreshed later
+    pub fn materialize_query(&self, name: &str, sql: &str, parameters: &[QueryParameter]) -> Result<()> {
+        self.ensure_writable("create a table")?;
+        let name = name.trim();
+        self.ensure_new_table_name(name)?;
+        self.write_materialized_table(name, TableLineage::new(sql, parameters))
+    }
+
+    /// Run the query a table was materialized from again and replace its rows
+    pub fn refresh_table(&self, name: &str) -> Result<()> {
+        self.ensure_writable("refresh a table")?;
+        let lineage = self.table_lineage(name)
+            .ok_or_else(|| FreshError::Custom(format!("Table '{}' was not created from a query", name)))?;
+        self.write_materialized_table(name, TableLineage::new(&lineage.sql, &lineage.parameters))
+    }
+
+    /// The query a table was materialized from, if it was
+    pub fn table_lineage(&self, name: &str) -> Option<TableLineage> {
+        read_lock(&self.lineage).get(name).cloned()
+    }
+
+    // CREATE TABLE ... AS: materialize the query, replacing the table only with OR REPLACE
+    fn create_table_as(&self, statement: &CreateTableAs) -> Result<StatementOutcome> {
+        let name = statement.name.as_str();
+        if !self.check_create_target(name, statement.if_not_exists, statement.or_replace)? {
+            return Ok(StatementOutcome::Done(format!("Table '{}' already exists", name)));
+        }
+        self.write_materialized_table(name, TableLineage::new(&statement.query, &[]))?;
+        Ok(StatementOutcome::Done(format!("Table '{}' created", name)))
+    }
+
+    // Whether CREATE TABLE should go ahead: false to skip it (IF NOT EXISTS on an
+    // existing table), an error if the name is taken and may not be replaced
+    fn check_create_target(&self, name: &str, if_not_exists: bool, or_replace: bool) -> Result<bool> {
+        if !self.table_exists(name)? {
+            return Ok(true);
+        }
+        if if_not_exists {
+            return Ok(false);
+        }
+        if !or_replace {
+            return Err(FreshError::Custom(format!("A table or view named '{}' already exists", name)));
+        }
+        if read_lock(&self.views).contains_key(name) {
+            return Err(FreshError::Custom(format!("'{}' is a view, not a table", name)));
+        }
+        Ok(true)
+    }
+
+    // Collect every row of the lineage's query into the table and save it in the project
+    fn write_materialized_table(&self, name: &str, lineage: TableLineage) -> Result<()> {
+        let batches = self.execute_query_batches_with_params(&lineage.sql, &lineage.parameters)?;
+        self.register_or_replace_table(name, batches)?;
+        self.save_table_to_project(name)?;
+        write_lock(&self.lineage).insert(name.to_string(), lineage);
+        self.save_lineage()
+    }
+
+    fn remove_lineage(&self, table: &str) -> Result<()> {
+        if write_lock(&self.lineage).remove(table).is_none() {
+            return Ok(());
+        }
+        self.save_lineage()
+    }
+
+    fn move_lineage(&self, table: &str, new_name: &str) -> Result<()> {
+        let Some(lineage) = write_lock(&self.lineage).remove(table) else {
+            return Ok(());
+        };
+        write_lock(&self.lineage).insert(new_name.to_string(), lineage);
+        self.save_lineage()
+    }
+
+    // Write the lineage of materialized tables to the project folder
+    fn save_lineage(&self) -> Result<()> {
+        let lineage = read_lock(&self.lineage).clone();
+        self.save_project_file(LINEAGE_FILE, &lineage, lineage.is_empty(), "table lineage")
+    }
+
+    /// Load the lineage of materialized tables saved in a project folder
+    pub fn load_lineage_from_directory(&self, directory: &Path) -> Result<()> {
+        let path = directory.join(LINEAGE_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| FreshError::Custom(format!("Failed to read table lineage file: {}", e)))?;
+        let lineage: BTreeMap<String, TableLineage> = serde_json::from_str(&json)
+            .map_err(|e| FreshError::Custom(format!("Failed to parse table lineage file: {}", e)))?;
+        *write_lock(&self.lineage) = lineage;
+        Ok(())
+    }
+
+    /// Delete every row of a table, keeping its columns, and save it in the project
+    pub fn truncate_table(&self, name: &str) -> Result<()> {
+        self.ensure_writable("truncate a table")?;
+        if read_lock(&self.external_tables).contains_key(name) {
+            return Err(FreshError::Custom(format!(
+                "Table '{}' is attached from files and cannot be modified",
+                name
+            )));
+        }
+        self.ensure_in_memory_table(name)?;
+        
+        let schema = read_lock(&self.registered_tables).get(name)
+            .and_then(|batches| batches.first())
+            .map(|batch| batch.schema())
+            .ok_or_else(|| FreshError::Custom(format!("Table '{}' has no schema", name)))?;
+        self.register_or_replace_table(name, vec![RecordBatch::new_empty(schema)])?;
+        self.save_table_to_project(name)
+    }
+
+    // Fail unless `name` is a table held in memory (not a view or an attached table)
+    fn ensure_in_memory_table(&self, name: &str) -> Result<()> {
+        let is_view = self.views.read()
+            .map_err(|_| FreshError::Database("View registry lock poisoned".to_string()))?
+            .contains_key(name);
+        if is_view {
+            return Err(FreshError::Custom(format!("'{}' is a view, not a table", name)));
+        }
+        if !read_lock(&self.registered_tables).contains_key(name) {
+            return Err(FreshError::Custom(format!("Table '{}' does not exist", name)));
+        }
+        Ok(())
+    }
+
+    fn ensure_new_table_name(&self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(FreshError::Custom("Table name cannot be empty".to_string()));
+        }
+        if self.table_exists(name)? {
+            return Err(FreshError::Custom(format!("A table or view named '{}' already exists", name)));
+        }
+        Ok(())
+    }
+
+    // Save a table in the project folder, if the database has one
+    fn save_table_to_project(&self, name: &str) -> Result<()> {
+        match &self.project_path {
+            Some(project_path) => self.save_table_dual(name, project_path),
+            None => Ok(()),
+        }
+    }
+
+    // Delete the files of a table from the project folder (on commit inside a transaction)
+    fn remove_table_files(&self, name: &str) -> Result<()> {
+        let Some(project_path) = &self.project_path else {
+            return Ok(());
+        };
+        if let Some(transaction) = write_lock(&self.transaction).as_mut() {
+            transaction.pending_saves.remove(name);
+            transaction.pending_removals.insert(name.to_string(), project_path.clone());
+            return Ok(());
+        }
+        Self::delete_table_files(name, project_path)
+    }
+
+    // Rename the files of a table in the project folder. Inside a transaction the
+    // table is saved under its new name on commit and the old files are deleted.
+    fn rename_table_files(&self, name: &str, new_name: &str) -> Result<()> {
+        let Some(project_path) = &self.project_path else {
+            return Ok(());
+        };
+        if self.in_transaction() {
+            self.remove_table_files(name)?;
+            return self.save_table_dual(new_name, project_path);
+        }
+        
+        for extension in ["parquet", "arrow"] {
+            let from = project_path.join(format!("{}.{}", name, extension));
+            if from.exists() {
+                let to = project_path.join(format!("{}.{}", new_name, extension));
+                std::fs::rename(&from, &to)
+                    .map_err(|e| FreshError::Custom(format!("Failed to rename {}: {}", from.display(), e)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_table_files(name: &str, directory: &Path) -> Result<()> {
+        for extension in ["parquet", "arrow"] {
+            let path = directory.join(format!("{}.{}", name, extension));
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .map_err(|e| FreshError::Custom(format!("Failed to delete {}: {}", path.display(), e)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Data-quality rules of a table, in the order they were added
+    pub fn quality_rules(&self, table: &str) -> Vec<QualityRule> {
+        read_lock(&self.quality_rules).get(table).cloned().unwrap_or_default()
+    }
+
+    /// Add a rule to a table and save the rules in the project
+    pub fn add_quality_rule(&self, table: &str, rule: QualityRule) -> Result<()> {
+        self.ensure_writable("add a data-quality rule")?;
+        if !self.table_exists(table)? {
+            return Err(FreshError::Custom(format!("Table '{}' does not exist", table)));
+        }
+        write_lock(&self.quality_rules).entry(table.to_string()).or_default().push(rule);
+        write_lock(&self.quality_reports).remove(table);
+        self.save_quality_rules()
+    }
+
+    pub fn remove_quality_rule(&self, table: &str, index: usize) -> Result<()> {
+        self.ensure_writable("remove a data-quality rule")?;
+        {
+            let mut rules = write_lock(&self.quality_rules);
+            let table_rules = rules.get_mut(table)
+                .filter(|rules| index < rules.len())
+                .ok_or_else(|| FreshError::Custom(format!("Table '{}' has no rule {}", table, index + 1)))?;
+            table_rules.remove(index);
+            if table_rules.is_empty() {
+                rules.remove(table);
+            }
+        }
+        write_lock(&self.quality_reports).remove(table);
+        self.save_quality_rules()
+    }
+
+    /// Check every rule of a table and keep the report until the table changes
+    pub fn validate_table(&self, table: &str) -> Result<QualityReport> {
+        if !self.table_exists(table)? {
+            return Err(FreshError::Custom(format!("Table '{}' does not exist", table)));
+        }
+        let report = crate::core::quality::validate(self, table, &self.quality_rules(table));
+        write_lock(&self.quality_reports).insert(table.to_string(), report.clone());
+        Ok(report)
+    }
+
+    /// Report of the last validation of a table, if it has not changed since
+    pub fn quality_report(&self, table: &str) -> Option<QualityReport> {
+        read_lock(&self.quality_reports).get(table).cloned()
+    }
+
+    /// Create `target` from the rows of `table` that break one of its rules, with
+    /// their position in `table` as the first column, and save it in the project
+    pub fn create_violations_table(&self, table: &str, rule_index: usize, target: &str) -> Result<()> {
+        self.ensure_writable("create a table")?;
+        let target = target.trim();
+        self.ensure_new_table_name(target)?;
+        let rule = self.quality_rules(table).into_iter().nth(rule_index)
+            .ok_or_else(|| FreshError::Custom(format!("Table '{}' has no rule {}", table, rule_index + 1)))?;
+        
+        let batches = self.execute_query_batches(&rule.violations_query(table))?;
+        self.register_or_replace_table(target, batches)?;
+        self.save_table_to_project(target)
+    }
+
+    fn remove_quality_rules(&self, table: &str) -> Result<()> {
+        if write_lock(&self.quality_rules).remove(table).is_none() {
+            return Ok(());
+        }
+        self.save_quality_rules()
+    }
+
+    fn move_quality_rules(&self, table: &str, new_name: &str) -> Result<()> {
+        let Some(rules) = write_lock(&self.quality_rules).remove(table) else {
+            return Ok(());
+        };
+        write_lock(&self.quality_rules).insert(new_name.to_string(), rules);
+        self.save_quality_rules()
+    }
+
+    // Write the data-quality rules to the project folder
+    fn save_quality_rules(&self) -> Result<()> {
+        let rules = read_lock(&self.quality_rules).clone();
+        self.save_project_file(QUALITY_RULES_FILE, &rules, rules.is_empty(), "quality rules")
+    }
+
+    /// Load the data-quality rules saved in a project folder
+    pub fn load_quality_rules_from_directory(&self, directory: &Path) -> Result<()> {
+        let path = directory.join(QUALITY_RULES_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| FreshError::Custom(format!("Failed to read quality rules file: {}", e)))?;
+        let rules: BTreeMap<String, Vec<QualityRule>> = serde_json::from_str(&json)
+            .map_err(|e| FreshError::Custom(format!("Failed to parse quality rules file: {}", e)))?;
+        *write_lock(&self.quality_rules) = rules;
+        Ok(())
+    }
+
+    /// Primary key and unique constraints declared on a table
+    pub fn table_keys(&self, table: &str) -> TableKeys {
+        read_lock(&self.table_keys).get(table).cloned().unwrap_or_default()
+    }
+
+    /// Columns that identify a row of a table (its primary key, or else its first
+    /// unique constraint); the default key when matching rows of a table
+    pub fn match_key(&self, table: &str) -> Vec<String> {
+        self.table_keys(table).match_key().to_vec()
+    }
+
+    /// Declare the primary key of a table (an empty list removes it). Fails if the
+    /// rows already hold duplicated or NULL key values.
+    pub fn set_primary_key(&self, table: &str, columns: &[String]) -> Result<()> {
+        let mut keys = self.table_keys(table);
+        keys.primary_key = columns.to_vec();
+        self.set_table_keys(table, keys)
+    }
+
+    /// Declare that the values of `columns` may not repeat in a table
+    pub fn add_unique_constraint(&self, table: &str, columns: &[String]) -> Result<()> {
+        if columns.is_empty() {
+            return Err(FreshError::Custom("A unique constraint needs at least one column".to_string()));
+        }
+        let mut keys = self.table_keys(table);
+        if keys.unique.iter().any(|unique| unique.as_slice() == columns) {
+            return Ok(());
+        }
+        keys.unique.push(columns.to_vec());
+        self.set_table_keys(table, keys)
+    }
+
+    /// Replace every key declared on a table. Fails, keeping the previous keys, if the
+    /// rows break one of the new ones.
+    pub fn set_table_keys(&self, table: &str, keys: TableKeys) -> Result<()> {
+        self.ensure_writable("declare a key")?;
+        let schema = match read_lock(&self.registered_tables).get(table).and_then(|batches| batches.first()) {
+            Some(batch) => Some(batch.schema()),
+            None => read_lock(&self.external_tables).get(table).map(|(_, schema)| schema.clone()),
+        };
+        let schema = schema
+            .ok_or_else(|| FreshError::Custom(format!("Table '{}' does not exist", table)))?;
+        if let Some(missing) = keys.columns().find(|column| schema.field_with_name(column).is_err()) {
+            return Err(FreshError::Custom(format!("Table '{}' has no column '{}'", table, missing)));
+        }
+        
+        crate::core::keys::check_keys(self, table, &keys)?;
+        if keys.is_empty() {
+            write_lock(&self.table_keys).remove(table);
+        } else {
+            write_lock(&self.table_keys).insert(table.to_string(), keys);
+        }
+        self.save_table_keys()
+    }
+
+    fn remove_table_keys(&self, table: &str) -> Result<()> {
+        if write_lock(&self.table_keys).remove(table).is_none() {
+            return Ok(());
+        }
+        self.save_table_keys()
+    }
+
+    fn move_table_keys(&self, table: &str, new_name: &str) -> Result<()> {
+        let Some(keys) = write_lock(&self.table_keys).remove(table) else {
+            return Ok(());
+        };
+        write_lock(&self.table_keys).insert(new_name.to_string(), keys);
+        self.save_table_keys()
+    }
+
+    // Write the declared keys to the project folder
+    fn save_table_keys(&self) -> Result<()> {
+        let keys = read_lock(&self.table_keys).clone();
+        self.save_project_file(TABLE_KEYS_FILE, &keys, keys.is_empty(), "table keys")
+    }
+
+    /// Load the table keys saved in a project folder
+    pub fn load_table_keys_from_directory(&self, directory: &Path) -> Result<()> {
+        let path = directory.join(TABLE_KEYS_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| FreshError::Custom(format!("Failed to read table keys file: {}", e)))?;
+        let keys: BTreeMap<String, TableKeys> = serde_json::from_str(&json)
+            .map_err(|e| FreshError::Custom(format!("Failed to parse table keys file: {}", e)))?;
+        *write_lock(&self.table_keys) = keys;
+        Ok(())
+    }
+
+    // Write a JSON project file, or remove it when there is nothing to store.
+    // Skipped inside a transaction, which writes every project file on commit.
+    fn save_project_file<T: Serialize>(&self, file_name: &str, value: &T, is_empty: bool, description: &str) -> Result<()> {
+        if self.in_transaction() {
+            return Ok(());
+        }
+        let Some(project_path) = &self.project_path else {
+            return Ok(());
+        };
+        
+        let path = project_path.join(file_name);
+        if is_empty {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .map_err(|e| FreshError::Custom(format!("Failed to remove {} file: {}", description, e)))?;
+            }
+            return Ok(());
+        }
+        
+        std::fs::create_dir_all(project_path)
+            .map_err(|e| FreshError::Custom(format!("Failed to create directory: {}", e)))?;
+        let json = serde_json::to_string_pretty(value)
+            .map_err(|e| FreshError::Custom(format!("Failed to serialize {}: {}", description, e)))?;
+        std::fs::write(&path, json)
+            .map_err(|e| FreshError::Custom(format!("Failed to write {} file: {}", description, e)))?;
+        
+        Ok(())
     }
 
     pub fn table_exists(&self, table_name: &str) -> Result<bool> {
-        Ok(self.registered_tables.contains_key(table_name))
+        if read_lock(&self.registered_tables).contains_key(table_name) || read_lock(&self.external_tables).contains_key(table_name) {
+            return Ok(true);
+        }
+        let views = self.views.read()
+            .map_err(|_| FreshError::Database("View registry lock poisoned".to_string()))?;
+        Ok(views.contains_key(table_name))
+    }
+
+    pub fn get_column_names(&self, query: &str) -> Result<Vec<String>> {
+        let ctx = &self.ctx;
+        let options = self.sql_options();
+        let rt = Runtime::new()?;
+        
+        rt.block_on(async {
+            let df = ctx.sql_with_options(query, options).await.map_err(|e| FreshError::Database(e.to_string()))?;
+            let schema = df.schema();
+            Ok(schema.fields().iter().map(|f| f.name().clone()).collect())
+        })
+    }
+    
+    pub fn get_column_types(&self, query: &str) -> Result<Vec<DataType>> {
+        let ctx = &self.ctx;
+        let options = self.sql_options();
+        let rt = Runtime::new()?;
+        
+        rt.block_on(async {
+            let df = ctx.sql_with_options(query, options).await.map_err(|e| FreshError::Database(e.to_string()))?;
+            let schema = df.schema();
+            Ok(schema.fields().iter().map(|f| f.data_type().clone()).collect())
+        })
+    }
+
+    /// Execute every statement of a script in order, stopping at the first that fails
+    pub fn execute_sql(&self, sql: &str) -> Result<()> {
+        self.ensure_writable("execute SQL statements")?;
+        
+        for statement in script::split_statements(sql) {
+            self.execute_statement(&statement.sql, &[])?;
+        }
+        Ok(())
+    }
+
+    /// Execute one SQL statement. Queries return their rows; INSERT, UPDATE and DELETE
+    /// change in-memory tables, CREATE and DROP change the catalog, and BEGIN, COMMIT
+    /// and ROLLBACK control a transaction. Changed tables are saved in the project.
+    pub fn execute_statement(&self, sql: &str, parameters: &[QueryParameter]) -> Result<StatementOutcome> {
+        use datafusion::logical_expr::{DdlStatement, LogicalPlan};
+        
+        if self.readonly {
+            let kind = self.classify_statement(&params::prepare_sql(sql))?;
+            if !kind.is_read_only() {
+                return Err(FreshError::Custom(format!("{} is not allowed in read-only mode", kind.description())));
+            }
+        }
+        
+        // Views are tracked so they can be listed and persisted
+        if let Some((name, query)) = Self::parse_create_view(sql) {
+            self.create_view(&name, &query)?;
+            return Ok(StatementOutcome::Done(format!("View '{}' created", name)));
+        }
+        // Tables created from queries are kept with the others and saved in the project
+        if let Some(statement) = Self::parse_create_table_as(sql) {
+            return self.create_table_as(&statement);
+        }
+        if let Some(outcome) = self.execute_transaction_statement(sql) {
+            return outcome;
+        }
+        
+        // Planning executes nothing, so each kind of statement can be applied here
+        let plan = self.statement_plan(sql, parameters)?;
+        match plan {
+            LogicalPlan::Ddl(DdlStatement::CreateMemoryTable(create)) => self.create_memory_table(create),
+            LogicalPlan::Ddl(DdlStatement::DropTable(drop)) => {
+                let name = drop.name.table();
+                if drop.if_exists && !self.table_exists(name)? {
+                    return Ok(StatementOutcome::Done(format!("Table '{}' does not exist", name)));
+                }
+                self.drop_table(name)?;
+                Ok(StatementOutcome::Done(format!("Table '{}' dropped", name)))
+            }
+            LogicalPlan::Ddl(DdlStatement::DropView(drop)) => {
+                let name = drop.name.table();
+                if drop.if_exists && !read_lock(&self.views).contains_key(name) {
+                    return Ok(StatementOutcome::Done(format!("View '{}' does not exist", name)));
+                }
+                self.drop_view(name)?;
+                Ok(StatementOutcome::Done(format!("View '{}' dropped", name)))
+            }
+            LogicalPlan::Dml(dml) => self.apply_dml(dml),
+            LogicalPlan::Ddl(_) | LogicalPlan::Statement(_) | LogicalPlan::Copy(_) => {
+                let kind = self.classify_statement(&params::prepare_sql(sql))?;
+                self.collect_plan(plan)?;
+                Ok(StatementOutcome::Done(format!("{} executed", kind.description())))
+            }
+            _ => Ok(StatementOutcome::Rows(self.execute_query_batches_with_params(sql, parameters)?)),
+        }
+    }
+
+    // BEGIN / START TRANSACTION, COMMIT and ROLLBACK control this database's transaction
+    // (DataFusion only plans START TRANSACTION and has no transactions of its own)
+    fn execute_transaction_statement(&self, sql: &str) -> Option<Result<StatementOutcome>> {
+        use datafusion::sql::parser::{DFParser, Statement as DFStatement};
+        use datafusion::sql::sqlparser::ast::Statement;
+        
+        let mut statements = DFParser::parse_sql(sql).ok()?;
+        let DFStatement::Statement(statement) = statements.pop_front()? else {
+            return None;
+        };
+        let outcome = match *statement {
+            Statement::StartTransaction { .. } => self.begin_transaction().map(|_| "Transaction started"),
+            Statement::Commit { .. } => self.commit_transaction().map(|_| "Transaction committed"),
+            Statement::Rollback { savepoint: None, .. } => self.rollback_transaction().map(|_| "Transaction rolled back"),
+            _ => return None,
+        };
+        Some(outcome.map(|message| StatementOutcome::Done(message.to_string())))
+    }
+
+    // Logical plan of a statement with its parameters bound; nothing is executed
+    fn statement_plan(&self, sql: &str, parameters: &[QueryParameter]) -> Result<datafusion::logical_expr::LogicalPlan> {
+        let state = self.ctx.state();
+        let plan = self.runtime.block_on(async {
+            state.create_logical_plan(&params::prepare_sql(sql)).await
+        }).map_err(|e| FreshError::Database(e.to_string()))?;
+        self.sql_options().verify_plan(&plan)
+            .map_err(|e| FreshError::Database(e.to_string()))?;
+        if parameters.is_empty() {
+            return Ok(plan);
+        }
+        
+        let types = plan.get_parameter_types()
+            .map_err(|e| FreshError::Custom(format!("Failed to read query parameters: {}", e)))?;
+        plan.with_param_values(params::param_values(parameters, &types)?)
+            .map_err(|e| FreshError::Custom(format!("Failed to bind query parameters: {}", e)))
+    }
+
+    // Run a logical plan and collect its output
+    fn collect_plan(&self, plan: datafusion::logical_expr::LogicalPlan) -> Result<Vec<RecordBatch>> {
+        let ctx = self.ctx.clone();
+        self.runtime.block_on(async {
+            ctx.execute_logical_plan(plan).await?.collect().await
+        }).map_err(|e| FreshError::Custom(format!("Failed to execute statement: {}", e)))
+    }
+
+    // CREATE TABLE with a column list (and optionally VALUES); declared keys are recorded
+    fn create_memory_table(&self, create: datafusion::logical_expr::CreateMemoryTable) -> Result<StatementOutcome> {
+        use datafusion::common::Constraint;
+        
+        let name = create.name.table().to_string();
+        if !self.check_create_target(&name, create.if_not_exists, create.or_replace)? {
+            return Ok(StatementOutcome::Done(format!("Table '{}' already exists", name)));
+        }
+        
+        let schema: SchemaRef = Arc::new(create.input.schema().as_ref().into());
+        let mut batches = self.collect_plan(create.input.as_ref().clone())?;
+        if batches.is_empty() {
+            batches.push(RecordBatch::new_empty(schema.clone()));
+        }
+        
+        let column_names = |indices: &[usize]| indices.iter().map(|i| schema.field(*i).name().clone()).collect::<Vec<_>>();
+        let mut keys = TableKeys::default();
+        for constraint in create.constraints.iter() {
+            match constraint {
+                Constraint::PrimaryKey(indices) => keys.primary_key = column_names(indices),
+                Constraint::Unique(indices) => keys.unique.push(column_names(indices)),
+            }
+        }
+        
+        self.register_or_replace_table(&name, batches)?;
+        if !keys.is_empty() {
+            self.set_table_keys(&name, keys)?;
+        }
+        self.save_table_to_project(&name)?;
+        self.remove_lineage(&name)?;
+        Ok(StatementOutcome::Done(format!("Table '{}' created", name)))
+    }
+
+    // INSERT, UPDATE and DELETE are applied by computing the table's new rows and
+    // registering them in place of the old ones
+    fn apply_dml(&self, dml: datafusion::logical_expr::DmlStatement) -> Result<StatementOutcome> {
+        use datafusion::logical_expr::WriteOp;
+        
+        let name = dml.table_name.table().to_string();
+        if read_lock(&self.external_tables).contains_key(&name) {
+            return Err(FreshError::Custom(format!(
+                "Table '{}' is attached from files and cannot be modified",
+                name
+            )));
+        }
+        self.ensure_in_memory_table(&name)?;
+        let existing = read_lock(&self.registered_tables).get(&name).cloned().unwrap_or_default();
+        let schema = existing.first()
+            .map(|batch| batch.schema())
+            .ok_or_else(|| FreshError::Custom(format!("Table '{}' does not exist", name)))?;
+        
+        let (batches, message) = match dml.op {
+            WriteOp::InsertInto | WriteOp::InsertOverwrite => {
+                let inserted = Self::conform_batches(self.collect_plan(dml.input.as_ref().clone())?, &schema)?;
+                let count: usize = inserted.iter().map(|b| b.num_rows()).sum();
+                let batches = match dml.op {
+                    WriteOp::InsertInto => existing.into_iter().chain(inserted).collect(),
+                    _ => inserted,
+                };
+                (batches, format!("Inserted {} rows into '{}'", count, name))
+            }
+            WriteOp::Delete => {
+                let before: usize = existing.iter().map(|b| b.num_rows()).sum();
+                let kept = Self::rows_kept_by_delete(dml.input.as_ref())?;
+                let batches = match kept {
+                    Some(plan) => Self::conform_batches(self.collect_plan(plan)?, &schema)?,
+                    None => Vec::new(),
+                };
+                let after: usize = batches.iter().map(|b| b.num_rows()).sum();
+                (batches, format!("Deleted {} rows from '{}'", before - after, name))
+            }
+            WriteOp::Update => {
+                let (plan, matched_column) = Self::rows_after_update(dml.input.as_ref())?;
+                let mut updated = 0;
+                let mut batches = Vec::new();
+                for batch in self.collect_plan(plan)? {
+                    let matched = batch.column(matched_column).as_any()
+                        .downcast_ref::<datafusion::arrow::array::BooleanArray>()
+                        .map(|flags| flags.true_count())
+                        .unwrap_or_default();
+                    updated += matched;
+                    batches.push(batch.project(&(0..matched_column).collect::<Vec<_>>())?);
+                }
+                (Self::conform_batches(batches, &schema)?, format!("Updated {} rows in '{}'", updated, name))
+            }
+            WriteOp::Ctas => {
+                return Err(FreshError::Custom("CREATE TABLE AS could not be parsed".to_string()));
+            }
+        };
+        
+        let batches = if batches.is_empty() { vec![RecordBatch::new_empty(schema)] } else { batches };
+        self.register_or_replace_table(&name, batches)?;
+        self.save_table_to_project(&name)?;
+        Ok(StatementOutcome::Done(message))
+    }
+
+    // Plan of the rows a DELETE leaves behind: those its WHERE clause is not true for.
+    // `None` when there is no WHERE clause and every row goes.
+    fn rows_kept_by_delete(input: &datafusion::logical_expr::LogicalPlan) -> Result<Option<datafusion::logical_expr::LogicalPlan>> {
+        use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder};
+        
+        let LogicalPlan::Filter(filter) = input else {
+            return Ok(None);
+        };
+        let plan = LogicalPlanBuilder::from(filter.input.as_ref().clone())
+            .filter(filter.predicate.clone().is_not_true())
+            .and_then(|builder| builder.build())
+            .map_err(|e| FreshError::Custom(format!("Failed to plan DELETE: {}", e)))?;
+        Ok(Some(plan))
+    }
+
+    // Plan of every row of the table after an UPDATE, followed by a boolean column that
+    // is true for the rows the WHERE clause matched. Returns the index of that column.
+    fn rows_after_update(input: &datafusion::logical_expr::LogicalPlan) -> Result<(datafusion::logical_expr::LogicalPlan, usize)> {
+        use datafusion::logical_expr::{lit, when, Expr, LogicalPlan, LogicalPlanBuilder};
+        
+        // The planner builds SET values over the matched rows: Projection(Filter(scan))
+        let LogicalPlan::Projection(projection) = input else {
+            return Err(FreshError::Custom("Unsupported UPDATE statement".to_string()));
+        };
+        let (predicate, source) = match projection.input.as_ref() {
+            LogicalPlan::Filter(filter) => (Some(filter.predicate.clone()), filter.input.clone()),
+            _ => (None, projection.input.clone()),
+        };
+        if matches!(source.as_ref(), LogicalPlan::Join(_) | LogicalPlan::CrossJoin(_)) {
+            return Err(FreshError::Custom("UPDATE ... FROM is not supported".to_string()));
+        }
+        
+        let matched = predicate.map(|p| p.is_true()).unwrap_or_else(|| lit(true));
+        let mut exprs = Vec::with_capacity(projection.expr.len() + 1);
+        for (expr, field) in projection.expr.iter().zip(projection.schema.fields()) {
+            let value = expr.clone().unalias();
+            if matches!(&value, Expr::Column(column) if column.name == *field.name()) {
+                exprs.push(expr.clone());
+                continue;
+            }
+            // Rows the WHERE clause did not match keep their value
+            let (qualifier, original) = source.schema().qualified_field_with_unqualified_name(field.name())
+                .map_err(|e| FreshError::Custom(format!("Failed to plan UPDATE: {}", e)))?;
+            let original = Expr::Column(datafusion::common::Column::from((qualifier, original)));
+            let updated = when(matched.clone(), value).otherwise(original)
+                .map_err(|e| FreshError::Custom(format!("Failed to plan UPDATE: {}", e)))?;
+            exprs.push(updated.alias(field.name()));
+        }
+        let matched_column = exprs.len();
+        exprs.push(matched.alias("__fresh_matched"));
+        
+        let plan = LogicalPlanBuilder::from(source.as_ref().clone())
+            .project(exprs)
+            .and_then(|builder| builder.build())
+            .map_err(|e| FreshError::Custom(format!("Failed to plan UPDATE: {}", e)))?;
+        Ok((plan, matched_column))
+    }
+
+    // Give batches the table's schema, casting columns whose type differs
+    fn conform_batches(batches: Vec<RecordBatch>, schema: &SchemaRef) -> Result<Vec<RecordBatch>> {
+        use datafusion::arrow::compute::cast;
+        
+        batches.into_iter()
+            .map(|batch| {
+                let columns = batch.columns().iter()
+                    .zip(schema.fields())
+                    .map(|(column, field)| cast(column, field.data_type()))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(RecordBatch::try_new(schema.clone(), columns)?)
+            })
+            .collect()
     }
 
-    pub fn get_column_names(&self, query: &str) -> Result<Vec<String>> {
-        let ctx = &self.ctx;
-        let rt = Runtime::new()?;
-        
-        rt.block_on(async {
-            let df = ctx.sql(query).await.map_err(|e| FreshError::Database(e.to_string()))?;
-            let schema = df.schema();
-            Ok(schema.fields().iter().map(|f| f.name().clone()).collect())
-        })
-    }
-    
-    pub fn get_column_types(&self, query: &str) -> Result<Vec<DataType>> {
-        let ctx = &self.ctx;
-        let rt = Runtime::new()?;
-        
-        rt.block_on(async {
-            let df = ctx.sql(query).await.map_err(|e| FreshError::Database(e.to_string()))?;
-            let schema = df.schema();
-            Ok(schema.fields().iter().map(|f| f.data_type().clone()).collect())
-        })
+    pub fn create_table_with_types(&self, table_name: &str, columns: &[(&str, &str)]) -> Result<()> {
+        self.create_table(table_name, columns)
     }
 
-    pub fn execute_sql(&mut self, sql: &str) -> Result<()> {
-        let ctx = self.ctx.clone();
-        self.runtime.block_on(async {
-            ctx.sql(sql).await
-        }).map_err(|e| FreshError::Custom(format!("Failed to execute SQL: {}", e)))?;
+    pub fn stream_insert_csv(&self, table_name: &str, csv_path: &Path, delimiter: char, has_header: bool) -> Result<()> {
+        let options = CsvIngestOptions {
+            delimiter,
+            header_row: if has_header { Some(0) } else { None },
+            ..CsvIngestOptions::default()
+        };
+        self.ingest_csv(table_name, csv_path, &options, &mut |_| {})?;
         Ok(())
     }
 
-    pub fn create_table_with_types(&mut self, table_name: &str, columns: &[(&str, &str)]) -> Result<()> {
-        self.create_table(table_name, columns)
+    /// Enhanced CSV import that can skip lines and select a specific row as header
+    pub fn stream_insert_csv_with_header_row(&self, table_name: &str, csv_path: &Path, delimiter: char, header_row: usize) -> Result<char> {
+        // A comma is the UI default, so treat it as "not specified" and detect the delimiter
+        let options = CsvIngestOptions {
+            delimiter,
+            infer_delimiter: delimiter == ',',
+            header_row: Some(header_row),
+            ..CsvIngestOptions::default()
+        };
+        self.ingest_csv(table_name, csv_path, &options, &mut |_| {})
     }
 
-    pub fn stream_insert_csv(&mut self, table_name: &str, csv_path: &Path, delimiter: char, has_header: bool) -> Result<()> {
-        // Read CSV with proper header handling
-        let mut rdr = csv::ReaderBuilder::new()
-            .delimiter(delimiter as u8)
-            .has_headers(has_header)
-            .from_path(csv_path)
-            .map_err(|e| FreshError::Custom(format!("Failed to read CSV: {}", e)))?;
-        
-        let mut rows = Vec::new();
-        let mut headers = Vec::new();
-        
-        if has_header {
-            // Get headers from the first row
-            match rdr.headers() {
-                Ok(header_result) => {
-                    headers = header_result.iter().map(|s| s.to_string()).collect();
-                }
-                Err(e) => {
-                    return Err(FreshError::Custom(format!("Failed to read CSV headers: {}", e)));
-                }
+    /// Stream a CSV file into a table in batches of `options.batch_size` rows.
+    /// `progress` is called after every batch; returns the delimiter that was used.
+    pub fn ingest_csv(&self, table_name: &str, csv_path: &Path, options: &CsvIngestOptions, progress: &mut dyn FnMut(IngestProgress)) -> Result<char> {
+        self.ensure_writable("import data")?;
+
+        let mut stream = CsvBatchStream::open(csv_path, options)?;
+        let schema = stream.schema();
+        
+        // If the table already exists with a schema, the data must match it
+        let expected_columns = read_lock(&self.registered_tables).get(table_name)
+            .and_then(|b| b.first())
+            .map(|b| b.schema().fields().len());
+        if let Some(expected_columns) = expected_columns {
+            if schema.fields().len() != expected_columns {
+                return Err(FreshError::Custom(format!(
+                    "CSV has {} columns, but table schema expects {} columns",
+                    schema.fields().len(), expected_columns
+                )));
             }
         }
         
-        // Read data rows
-        for result in rdr.records() {
-            let record = result.map_err(|e| FreshError::Custom(format!("Failed to read CSV record: {}", e)))?;
-            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
-            rows.push(row);
-        }
-        
-        // If no headers were found, create default column names
-        if headers.is_empty() {
-            headers = (0..rows.first().map(|r| r.len()).unwrap_or(0))
-                .map(|i| format!("col_{}", i))
-                .collect();
-        }
-        
-        // If the table already exists with a schema, we need to ensure the data matches
-        if let Some(existing_batch) = self.registered_tables.get(table_name) {
-            let existing_schema = existing_batch.schema();
-            let expected_columns = existing_schema.fields().len();
-            
-            // Ensure all rows have the expected number of columns
-            for (row_idx, row) in rows.iter().enumerate() {
-                if row.len() != expected_columns {
+        progress(stream.progress());
+        let mut batches = Vec::new();
+        let mut memory_used = 0usize;
+        while let Some(batch) = stream.next() {
+            let batch = batch?;
+            memory_used += batch.get_array_memory_size();
+            if let Some(limit) = options.memory_limit_bytes {
+                if memory_used > limit {
                     return Err(FreshError::Custom(format!(
-                        "Row {} has {} columns, but table schema expects {} columns",
-                        row_idx + 1, row.len(), expected_columns
+                        "Import of '{}' exceeded the memory limit of {} MB",
+                        table_name, limit / (1024 * 1024)
                     )));
                 }
             }
+            batches.push(batch);
+            progress(stream.progress());
         }
         
-        // Create schema with proper column names and type inference
-        let fields: Vec<Field> = headers.iter().enumerate()
-            .map(|(col_idx, name)| {
-                // Try to infer the data type from the first few rows
-                let data_type = if !rows.is_empty() {
-                    let mut is_numeric = true;
-                    let mut is_integer = true;
-                    
-                    // Check first 10 rows to infer type
-                    for row in rows.iter().take(10) {
-                        if col_idx < row.len() {
-                            let value: &str = &row[col_idx];
-                            if !value.is_empty() {
-                                // Check if it's numeric
-                                if !value.chars().all(|c| c.is_numeric() || c == '.' || c == '-') {
-                                    is_numeric = false;
-                                    break;
-                                }
-                                // Check if it's integer
-                                if value.contains('.') {
-                                    is_integer = false;
-                                }
-                            }
-                        }
-                    }
-                    
-                    if is_numeric {
-                        if is_integer {
-                            DataType::Int64
-                        } else {
-                            DataType::Float64
-                        }
-                    } else {
-                        DataType::Utf8
-                    }
-                } else {
-                    DataType::Utf8
-                };
-                
-                Field::new(name, data_type, true)
-            })
-            .collect();
-        
-        let schema = Schema::new(fields);
-        
-        // Convert rows to Arrow arrays
-        let arrays = self.string_rows_to_arrow_arrays(&headers, &rows)?;
-        
-        let batch = RecordBatch::try_new(Arc::new(schema), arrays)
-            .map_err(|e| FreshError::Custom(format!("Failed to create record batch: {}", e)))?;
+        if batches.is_empty() {
+            batches.push(RecordBatch::new_empty(schema));
+        }
         
         // Register the table with proper schema, handling replacement if it already exists
-        self.register_or_replace_table(table_name, batch.clone())?;
+        self.register_or_replace_table(table_name, batches)?;
         
-        // Store in our cache
-        self.registered_tables.insert(table_name.to_string(), Arc::new(batch));
+        // Check the imported rows against the table's data-quality rules
+        if !self.quality_rules(table_name).is_empty() {
+            if let Err(e) = self.validate_table(table_name) {
+                eprintln!("[Database] Failed to validate '{}': {}", table_name, e);
+            }
+        }
         
-        Ok(())
+        Ok(stream.delimiter())
     }
 
-    /// Enhanced CSV import that can skip lines and select a specific row as header
-    pub fn stream_insert_csv_with_header_row(&mut self, table_name: &str, csv_path: &Path, delimiter: char, header_row: usize) -> Result<char> {
-        use std::io::{BufRead, BufReader};
-        use std::fs::File;
-        
-        let file = File::open(csv_path)
-            .map_err(|e| FreshError::Custom(format!("Failed to open CSV file: {}", e)))?;
-        
-        let reader = BufReader::new(file);
-        let lines: Vec<String> = reader.lines()
-            .map(|line| line.unwrap_or_default())
-            .collect();
-        
-        // Validate header row (using original lines to match UI)
-        if header_row >= lines.len() {
-            return Err(FreshError::Custom("Header row exceeds file length".to_string()));
+    /// Snapshot the tables, views and catalog so that `rollback_transaction` can
+    /// restore them. Until the transaction commits, saving tables to the project
+    /// only records them, so files on disk never reflect a half-finished change.
+    pub fn begin_transaction(&self) -> Result<()> {
+        let mut transaction = write_lock(&self.transaction);
+        if transaction.is_some() {
+            return Err(FreshError::Custom("A transaction is already in progress".to_string()));
         }
         
-        // Start reading from the header row (using original lines to match UI)
-        let data_lines = lines.into_iter().skip(header_row).collect::<Vec<String>>();
-        
-        if data_lines.is_empty() {
-            return Err(FreshError::Custom("No data lines found after header row".to_string()));
+        let views = self.views.read()
+            .map_err(|_| FreshError::Database("View registry lock poisoned".to_string()))?
+            .clone();
+        *transaction = Some(Transaction {
+            registered_tables: read_lock(&self.registered_tables).clone(),
+            views,
+            external_tables: read_lock(&self.external_tables).clone(),
+            quality_rules: read_lock(&self.quality_rules).clone(),
+            table_keys: read_lock(&self.table_keys).clone(),
+            lineage: read_lock(&self.lineage).clone(),
+            catalog: self.catalog_providers()?,
+            pending_saves: BTreeMap::new(),
+            pending_removals: BTreeMap::new(),
+        });
+        Ok(())
+    }
+
+    /// Write every table saved during the transaction, delete the files of dropped
+    /// tables, then write the view, attached table and other project files. If any of
+    /// it fails, the project folder is put back as it was and the in-memory state is
+    /// rolled back.
+    pub fn commit_transaction(&self) -> Result<()> {
+        let transaction = write_lock(&self.transaction).take()
+            .ok_or_else(|| FreshError::Custom("No transaction in progress".to_string()))?;
+        
+        if let Err(e) = self.write_transaction(&transaction) {
+            self.restore_snapshot(transaction)?;
+            return Err(e);
         }
-        
-        // Extract headers from the first row (which is now the header)
-        let headers: Vec<String> = if !data_lines.is_empty() {
-            let header_line = &data_lines[0];
-            Self::parse_csv_line(header_line, delimiter)
-        } else {
-            return Err(FreshError::Custom("No header row found".to_string()));
-        };
-        
-        // Infer delimiter from header if not already specified
-        let inferred_delimiter = if delimiter == ',' {
-            Self::infer_delimiter_from_header(&data_lines[0])
-        } else {
-            delimiter
+        Ok(())
+    }
+
+    /// Restore the tables, views and catalog captured by `begin_transaction`
+    pub fn rollback_transaction(&self) -> Result<()> {
+        let transaction = write_lock(&self.transaction).take()
+            .ok_or_else(|| FreshError::Custom("No transaction in progress".to_string()))?;
+        self.restore_snapshot(transaction)
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        read_lock(&self.transaction).is_some()
+    }
+
+    // Every table provider registered in the default schema
+    fn catalog_providers(&self) -> Result<HashMap<String, Arc<dyn TableProvider>>> {
+        let options = self.ctx.state().config().options().catalog.clone();
+        let Some(schema) = self.ctx.catalog(&options.default_catalog)
+            .and_then(|catalog| catalog.schema(&options.default_schema)) else {
+            return Ok(HashMap::new());
         };
         
-        // Parse data rows (skip the header row) and filter using delimiter-based validation
-        let mut rows = Vec::new();
-        let expected_columns = headers.len();
-        
-        for line in data_lines.iter().skip(1) {
-            // Use delimiter-based validation instead of hardcoded string checks
-            if Self::is_valid_csv_line(line, inferred_delimiter, expected_columns) {
-                let row = Self::parse_csv_line(line, inferred_delimiter);
-                rows.push(row);
+        let mut providers = HashMap::new();
+        for name in schema.table_names() {
+            let provider = self.runtime.block_on(schema.table(&name))
+                .map_err(|e| FreshError::Custom(format!("Failed to snapshot table {}: {}", name, e)))?;
+            if let Some(provider) = provider {
+                providers.insert(name, provider);
             }
         }
-        
-        // If no headers were found, create default column names
-        let mut final_headers = if headers.is_empty() {
-            (0..rows.first().map(|r| r.len()).unwrap_or(0))
-                .map(|i| format!("col_{}", i))
-                .collect()
-        } else {
-            headers
-        };
-        
-        // Deduplicate column names to avoid DataFusion errors
-        let mut seen_names = std::collections::HashSet::new();
-        let mut deduplicated_headers = Vec::new();
-        
-        for (i, header) in final_headers.iter().enumerate() {
-            let mut unique_name = header.clone();
-            let mut counter = 1;
-            
-            while seen_names.contains(&unique_name) {
-                unique_name = format!("{}_{}", header, counter);
-                counter += 1;
-            }
-            
-            seen_names.insert(unique_name.clone());
-            deduplicated_headers.push(unique_name);
+        Ok(providers)
+    }
+
+    fn restore_snapshot(&self, transaction: Transaction) -> Result<()> {
+        // Swap the whole catalog back: drop everything registered now, then re-add the snapshot
+        for name in self.catalog_providers()?.into_keys() {
+            self.ctx.deregister_table(name.as_str())
+                .map_err(|e| FreshError::Custom(format!("Failed to roll back table {}: {}", name, e)))?;
+        }
+        for (name, provider) in transaction.catalog {
+            self.ctx.register_table(name.as_str(), provider)
+                .map_err(|e| FreshError::Custom(format!("Failed to restore table {}: {}", name, e)))?;
         }
         
-        final_headers = deduplicated_headers;
-        
-        // If the table already exists with a schema, we need to ensure the data matches
-        if let Some(existing_batch) = self.registered_tables.get(table_name) {
-            let existing_schema = existing_batch.schema();
-            let expected_columns = existing_schema.fields().len();
-            
-            // Ensure all rows have the expected number of columns
-            for (row_idx, row) in rows.iter().enumerate() {
-                if row.len() != expected_columns {
-                    return Err(FreshError::Custom(format!(
-                        "Row {} has {} columns, but table schema expects {} columns",
-                        row_idx + 1, row.len(), expected_columns
-                    )));
+        *write_lock(&self.registered_tables) = transaction.registered_tables;
+        *write_lock(&self.external_tables) = transaction.external_tables;
+        *write_lock(&self.quality_rules) = transaction.quality_rules;
+        *write_lock(&self.table_keys) = transaction.table_keys;
+        *write_lock(&self.lineage) = transaction.lineage;
+        write_lock(&self.quality_reports).clear();
+        *self.views.write()
+            .map_err(|_| FreshError::Database("View registry lock poisoned".to_string()))? = transaction.views;
+        write_lock(&self.batch_cache).clear();
+        write_lock(&self.profiles).clear();
+        Ok(())
+    }
+
+    // Stage every pending table, then move every file the commit replaces or deletes
+    // into a backup folder before changing anything. On failure the files written so
+    // far are removed and the backups moved back.
+    fn write_transaction(&self, transaction: &Transaction) -> Result<()> {
+        let targets = self.commit_targets(transaction);
+        let absent: Vec<PathBuf> = targets.iter().filter(|target| !target.exists()).cloned().collect();
+        let mut staging_dirs = BTreeSet::new();
+        let mut backups = Vec::new();
+        
+        let result = self.stage_pending_saves(&transaction.pending_saves, &mut staging_dirs)
+            .and_then(|staged| {
+                Self::back_up_files(&targets, &mut backups)?;
+                for (from, to) in &staged {
+                    std::fs::rename(from, to)
+                        .map_err(|e| FreshError::Custom(format!("Failed to move {} into the project: {}", to.display(), e)))?;
+                }
+                for (table_name, base_path) in &transaction.pending_removals {
+                    Self::delete_table_files(table_name, base_path)?;
                 }
+                self.save_views()?;
+                self.save_external_tables()?;
+                self.save_quality_rules()?;
+                self.save_table_keys()?;
+                self.save_lineage()
+            });
+        
+        for staging_dir in staging_dirs {
+            let _ = std::fs::remove_dir_all(staging_dir);
+        }
+        // Backups are only kept when they could not all be moved back
+        if result.is_ok() || Self::restore_files(&absent, &backups) {
+            let backup_dirs: BTreeSet<PathBuf> = backups.iter()
+                .filter_map(|(_, backup)| backup.parent().map(Path::to_path_buf))
+                .collect();
+            for backup_dir in backup_dirs {
+                let _ = std::fs::remove_dir_all(backup_dir);
             }
         }
-        
-        // Use the same type inference system as the UI (with null awareness)
-        
-        // Convert rows to the format expected by TypeInferrer
-        let sample_data: Vec<Vec<String>> = rows.iter().take(20).cloned().collect();
-        
-        // Infer types using the null-aware system (same as UI)
-        // For now, use default null values since we don't have access to user config here
-        let default_null_values = vec!["null".to_string(), "NULL".to_string(), "N/A".to_string(), "".to_string()];
-        let inferred_types = TypeInferrer::infer_column_types_with_nulls(&final_headers, &sample_data, &default_null_values);
-        
-        // Convert to Arrow schema
-        let fields: Vec<Field> = inferred_types.iter()
-            .map(|(name, col_type)| {
-                let data_type = col_type.to_arrow_type();
-                Field::new(name, data_type, true)
+        result
+    }
+
+    // Every file a commit may replace or delete
+    fn commit_targets(&self, transaction: &Transaction) -> Vec<PathBuf> {
+        let mut targets: Vec<PathBuf> = transaction.pending_saves.iter()
+            .chain(&transaction.pending_removals)
+            .flat_map(|(table_name, base_path)| {
+                ["parquet", "arrow"].map(|extension| base_path.join(format!("{}.{}", table_name, extension)))
             })
             .collect();
-        
-        let schema = Schema::new(fields);
-        
-        // Apply time normalization to columns that are detected as time types
-        let mut normalized_rows = rows.clone();
-        for (col_idx, (name, col_type)) in inferred_types.iter().enumerate() {
-            if col_type.is_time_type() {
-                // Extract column values
-                let mut column_values = Vec::new();
-                for row in &normalized_rows {
-                    if col_idx < row.len() {
-                        column_values.push(row[col_idx].clone());
-                    } else {
-                        column_values.push(String::new());
-                    }
-                }
-                
-                // Normalize the time column
-                let normalized_values = Self::normalize_time_column(&column_values);
-                
-                // Update the rows with normalized values
-                for (row_idx, normalized_value) in normalized_values.iter().enumerate() {
-                    if row_idx < normalized_rows.len() && col_idx < normalized_rows[row_idx].len() {
-                        normalized_rows[row_idx][col_idx] = normalized_value.clone();
-                    }
-                }
-            }
+        if let Some(project_path) = &self.project_path {
+            let files = [VIEWS_FILE, EXTERNAL_TABLES_FILE, QUALITY_RULES_FILE, TABLE_KEYS_FILE, LINEAGE_FILE];
+            targets.extend(files.map(|file| project_path.join(file)));
         }
-        
-        // Convert rows to Arrow arrays using the same type inference
-        let arrays = self.string_rows_to_arrow_arrays_with_schema(&final_headers, &normalized_rows, &schema)?;
-        
-        let batch = RecordBatch::try_new(Arc::new(schema), arrays)
-            .map_err(|e| FreshError::Custom(format!("Failed to create record batch: {}", e)))?;
-        
-        // Register the table with proper schema, handling replacement if it already exists
-        self.register_or_replace_table(table_name, batch.clone())?;
-        
-        // Store in our cache
-        self.registered_tables.insert(table_name.to_string(), Arc::new(batch));
-        
-        Ok(inferred_delimiter)
+        targets
     }
 
-    pub fn begin_transaction(&mut self) -> Result<()> {
-        // DataFusion doesn't support transactions in the same way
-        Ok(())
+    // Write every pending table into a staging folder next to its destination and
+    // return where each file goes once all of them have been written
+    fn stage_pending_saves(&self, pending_saves: &BTreeMap<String, PathBuf>, staging_dirs: &mut BTreeSet<PathBuf>) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let mut staged = Vec::new();
+        for (table_name, base_path) in pending_saves {
+            let staging_dir = base_path.join(TRANSACTION_STAGING_DIR);
+            staging_dirs.insert(staging_dir.clone());
+            self.write_table_files(table_name, &staging_dir)
+                .map_err(|e| FreshError::Custom(format!("Failed to save table '{}': {}", table_name, e)))?;
+            for extension in ["parquet", "arrow"] {
+                let file_name = format!("{}.{}", table_name, extension);
+                staged.push((staging_dir.join(&file_name), base_path.join(&file_name)));
+            }
+        }
+        Ok(staged)
     }
 
-    pub fn commit_transaction(&mut self) -> Result<()> {
-        // DataFusion doesn't support transactions in the same way
+    // Move the existing files among `targets` into a backup folder next to them,
+    // recording each `(original, backup)` pair as soon as it is moved
+    fn back_up_files(targets: &[PathBuf], backups: &mut Vec<(PathBuf, PathBuf)>) -> Result<()> {
+        for target in targets.iter().filter(|target| target.is_file()) {
+            let (Some(directory), Some(file_name)) = (target.parent(), target.file_name()) else {
+                continue;
+            };
+            let backup_dir = directory.join(TRANSACTION_BACKUP_DIR);
+            std::fs::create_dir_all(&backup_dir)
+                .map_err(|e| FreshError::Custom(format!("Failed to create directory: {}", e)))?;
+            let backup = backup_dir.join(file_name);
+            std::fs::rename(target, &backup)
+                .map_err(|e| FreshError::Custom(format!("Failed to back up {}: {}", target.display(), e)))?;
+            backups.push((target.clone(), backup));
+        }
         Ok(())
     }
 
-    pub fn rollback_transaction(&mut self) -> Result<()> {
-        // DataFusion doesn't support transactions in the same way
-        Ok(())
+    // Undo a failed commit: remove the files it wrote where there were none before and
+    // move the backups back over the others. Returns false when a backup could not be
+    // restored.
+    fn restore_files(absent: &[PathBuf], backups: &[(PathBuf, PathBuf)]) -> bool {
+        let mut restored = true;
+        let written = absent.iter().chain(backups.iter().map(|(original, _)| original));
+        for path in written.filter(|path| path.is_file()) {
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("[Database] Failed to remove {} after a failed commit: {}", path.display(), e);
+            }
+        }
+        for (original, backup) in backups {
+            if let Err(e) = std::fs::rename(backup, original) {
+                eprintln!("[Database] Failed to restore {} from {}: {}", original.display(), backup.display(), e);
+                restored = false;
+            }
+        }
+        restored
     }
 
-    pub fn insert_record(&mut self, table_name: &str, values: &[String]) -> Result<()> {
+    pub fn insert_record(&self, table_name: &str, values: &[String]) -> Result<()> {
         self.insert_data(table_name, &[values.to_vec()])
     }
 
-    pub fn batch_insert(&mut self, table_name: &str, all_values: &[Vec<String>]) -> Result<()> {
+    pub fn batch_insert(&self, table_name: &str, all_values: &[Vec<String>]) -> Result<()> {
         self.insert_data(table_name, all_values)
     }
 
-    pub fn create_table_with_schema(&mut self, table_name: &str, columns: &[(&str, DataType)]) -> Result<()> {
+    pub fn create_table_with_schema(&self, table_name: &str, columns: &[(&str, DataType)]) -> Result<()> {
         let fields: Vec<Field> = columns.iter()
             .map(|(name, data_type)| Field::new(*name, data_type.clone(), true))
             .collect();
@@ -1268,55 +2566,51 @@ impl Database {
         Ok(())
     }
 
-    pub fn insert_record_batch(&mut self, table_name: &str, batch: &RecordBatch) -> Result<()> {
-        // Clear cache for this table since data changed
-        self.batch_cache.remove(table_name);
-        
-        // Register the batch as a table
-        self.ctx.register_batch(table_name, batch.clone())
-            .map_err(|e| FreshError::Custom(format!("Failed to register record batch: {}", e)))?;
-        
-        // Store in our cache
-        self.registered_tables.insert(table_name.to_string(), Arc::new(batch.clone()));
-        
-        Ok(())
+    pub fn insert_record_batch(&self, table_name: &str, batch: &RecordBatch) -> Result<()> {
+        self.insert_record_batches(table_name, vec![batch.clone()])
     }
 
-    pub fn load_table_arrow_batch(&mut self, table_name: &str) -> Result<Arc<RecordBatch>> {
-        if let Some(batch) = self.registered_tables.get(table_name) {
-            return Ok(batch.clone());
-        }
-        
-        // Try to query the table to get its data
-        let query = format!("SELECT * FROM '{}'", table_name);
-        let ctx = self.ctx.clone();
-        
-        let result = self.runtime.block_on(async {
-            ctx.sql(&query).await
-        }).map_err(|e| FreshError::Custom(format!("Failed to load table: {}", e)))?;
-        
-        let record_batches = self.runtime.block_on(async {
-            result.collect().await
-        }).map_err(|e| FreshError::Custom(format!("Failed to collect table data: {}", e)))?;
-        
-        if record_batches.is_empty() {
-            return Err(FreshError::Custom("No data found in table".to_string()));
+    /// Register a table made of several record batches, replacing any existing table with that name
+    pub fn insert_record_batches(&self, table_name: &str, batches: Vec<RecordBatch>) -> Result<()> {
+        self.register_or_replace_table(table_name, batches)
+    }
+
+    pub fn load_table_arrow_batch(&self, table_name: &str) -> Result<Arc<RecordBatch>> {
+        let batches = self.load_table_arrow_batches(table_name)?;
+        Self::combine_batches(&batches).map(Arc::new)
+    }
+
+    /// Load every record batch of a table, caching query results in `registered_tables`
+    pub fn load_table_arrow_batches(&self, table_name: &str) -> Result<Vec<RecordBatch>> {
+        if let Some(batches) = read_lock(&self.registered_tables).get(table_name) {
+            return Ok(batches.clone());
         }
         
-        let batch = Arc::new(record_batches[0].clone());
-        self.registered_tables.insert(table_name.to_string(), batch.clone());
+        let batches = self.collect_table_batches(table_name)?;
+        write_lock(&self.registered_tables).insert(table_name.to_string(), batches.clone());
         
-        Ok(batch)
+        Ok(batches)
     }
 
-    /// Non-mutable version of load_table_arrow_batch for use with Arc<Database>
+    /// Non-mutable version of load_table_arrow_batch for use with Arc<Database>.
+    /// Multi-batch tables are concatenated so row indices are global to the table.
     pub fn get_table_arrow_batch(&self, table_name: &str) -> Result<Arc<RecordBatch>> {
-        if let Some(batch) = self.registered_tables.get(table_name) {
-            return Ok(batch.clone());
+        let batches = self.get_table_arrow_batches(table_name)?;
+        Self::combine_batches(&batches).map(Arc::new)
+    }
+
+    /// Like load_table_arrow_batches, but query results are not cached
+    pub fn get_table_arrow_batches(&self, table_name: &str) -> Result<Vec<RecordBatch>> {
+        if let Some(batches) = read_lock(&self.registered_tables).get(table_name) {
+            return Ok(batches.clone());
         }
         
-        // Try to query the table to get its data
-        let query = format!("SELECT * FROM '{}'", table_name);
+        self.collect_table_batches(table_name)
+    }
+
+    // Query a table through DataFusion and collect all of its batches
+    fn collect_table_batches(&self, table_name: &str) -> Result<Vec<RecordBatch>> {
+        let query = format!("SELECT * FROM {}", quote_identifier(table_name));
         let ctx = self.ctx.clone();
         
         let result = self.runtime.block_on(async {
@@ -1331,12 +2625,17 @@ impl Database {
             return Err(FreshError::Custom("No data found in table".to_string()));
         }
         
-        let batch = Arc::new(record_batches[0].clone());
-        
-        // Note: We can't insert into registered_tables here since we don't have mutable access
-        // This is a limitation when working with Arc<Database>
-        
-        Ok(batch)
+        Ok(record_batches)
+    }
+
+    // Concatenate batches sharing one schema into a single batch
+    fn combine_batches(batches: &[RecordBatch]) -> Result<RecordBatch> {
+        match batches {
+            [] => Err(FreshError::Custom("No data found in table".to_string())),
+            [single] => Ok(single.clone()),
+            _ => concat_batches(&batches[0].schema(), batches)
+                .map_err(|e| FreshError::Custom(format!("Failed to combine record batches: {}", e))),
+        }
     }
 
     fn warn_if_cloud_folder(_path: &Path) {
@@ -1346,40 +2645,119 @@ impl Database {
     // === HYBRID PERSISTENCE METHODS ===
 
     /// Save a table in both Arrow IPC (fast cache) and Parquet (persistent) formats
-    pub fn save_table_dual(&mut self, table_name: &str, base_path: &Path) -> Result<()> {
-        // Get the table data as RecordBatch
-        let batch = self.load_table_arrow_batch(table_name)?;
+    /// Inside a transaction the table is written when the transaction commits.
+    pub fn save_table_dual(&self, table_name: &str, base_path: &Path) -> Result<()> {
+        self.ensure_writable("save tables")?;
+        
+        // Make sure the table data is available before touching the filesystem
+        self.load_table_arrow_batches(table_name)?;
         
+        if let Some(transaction) = write_lock(&self.transaction).as_mut() {
+            transaction.pending_removals.remove(table_name);
+            transaction.pending_saves.insert(table_name.to_string(), base_path.to_path_buf());
+            return Ok(());
+        }
+        self.write_table_files(table_name, base_path)
+    }
+
+    // Write the Parquet file and Arrow cache of a table into a folder
+    fn write_table_files(&self, table_name: &str, base_path: &Path) -> Result<()> {
         // Create directory if it doesn't exist
         std::fs::create_dir_all(base_path)
             .map_err(|e| FreshError::Custom(format!("Failed to create directory: {}", e)))?;
         
+        // Save as Parquet first so the Arrow cache is never older than the durable copy
+        let parquet_path = base_path.join(format!("{}.parquet", table_name));
+        self.save_table_parquet(table_name, &parquet_path)?;
+        
         // Save as Arrow IPC (fast cache)
         let arrow_path = base_path.join(format!("{}.arrow", table_name));
         self.save_table_arrow_ipc(table_name, &arrow_path)?;
         
-        // For now, skip Parquet due to version conflicts
-        // TODO: Add Parquet support when Arrow versions are compatible
-                // Table saved successfully
+        Ok(())
+    }
+
+    /// Save a table as a Parquet file (compressed persistent format)
+    pub fn save_table_parquet(&self, table_name: &str, path: &Path) -> Result<()> {
+        use datafusion::parquet::arrow::ArrowWriter;
+        use datafusion::parquet::file::properties::WriterProperties;
+        use std::fs::File;
+        
+        self.ensure_writable("save tables")?;
+        
+        let batches = with_format_version(self.load_table_arrow_batches(table_name)?)?;
+        
+        let file = File::create(path)
+            .map_err(|e| FreshError::Custom(format!("Failed to create Parquet file: {}", e)))?;
+        
+        let props = WriterProperties::builder()
+            .set_compression(self.parquet_compression().to_parquet())
+            .build();
+        
+        let mut writer = ArrowWriter::try_new(file, batches[0].schema(), Some(props))
+            .map_err(|e| FreshError::Custom(format!("Failed to create Parquet writer: {}", e)))?;
+        
+        for batch in &batches {
+            writer.write(batch)
+                .map_err(|e| FreshError::Custom(format!("Failed to write Parquet data: {}", e)))?;
+        }
+        
+        writer.close()
+            .map_err(|e| FreshError::Custom(format!("Failed to finish Parquet file: {}", e)))?;
+        
+        Ok(())
+    }
+
+    /// Load a table from a Parquet file (compressed persistent format)
+    pub fn load_table_parquet(&self, table_name: &str, path: &Path) -> Result<()> {
+        use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        use std::fs::File;
+        
+        let file = File::open(path)
+            .map_err(|e| FreshError::Custom(format!("Failed to open Parquet file: {}", e)))?;
+        
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+            .map_err(|e| FreshError::Custom(format!("Failed to create Parquet reader: {}", e)))?;
+        let schema = builder.schema().clone();
+        let reader = builder.build()
+            .map_err(|e| FreshError::Custom(format!("Failed to create Parquet reader: {}", e)))?;
+        
+        let mut batches = Vec::new();
+        for batch_result in reader {
+            let batch = batch_result
+                .map_err(|e| FreshError::Custom(format!("Failed to read Parquet batch: {}", e)))?;
+            batches.push(batch);
+        }
+        
+        // A table saved without rows still carries its schema
+        if batches.is_empty() {
+            batches.push(RecordBatch::new_empty(schema));
+        }
+        
+        self.insert_record_batches(table_name, from_table_file(batches)?)?;
         
         Ok(())
     }
 
     /// Save a table as Arrow IPC file (fast cache format)
-    pub fn save_table_arrow_ipc(&mut self, table_name: &str, path: &Path) -> Result<()> {
+    pub fn save_table_arrow_ipc(&self, table_name: &str, path: &Path) -> Result<()> {
         use datafusion::arrow::ipc::writer::FileWriter;
         use std::fs::File;
         
-        let batch = self.load_table_arrow_batch(table_name)?;
+        self.ensure_writable("save tables")?;
+        
+        let batches = with_format_version(self.load_table_arrow_batches(table_name)?)?;
         
         let file = File::create(path)
             .map_err(|e| FreshError::Custom(format!("Failed to create Arrow IPC file: {}", e)))?;
         
-        let mut writer = FileWriter::try_new(file, &batch.schema())
+        let mut writer = FileWriter::try_new(file, &batches[0].schema())
             .map_err(|e| FreshError::Custom(format!("Failed to create Arrow IPC writer: {}", e)))?;
         
-        writer.write(&batch)
-            .map_err(|e| FreshError::Custom(format!("Failed to write Arrow IPC data: {}", e)))?;
+        for batch in &batches {
+            writer.write(batch)
+                .map_err(|e| FreshError::Custom(format!("Failed to write Arrow IPC data: {}", e)))?;
+        }
         
         writer.finish()
             .map_err(|e| FreshError::Custom(format!("Failed to finish Arrow IPC file: {}", e)))?;
@@ -1388,7 +2766,7 @@ impl Database {
     }
 
     /// Load a table from Arrow IPC file (fast cache format)
-    pub fn load_table_arrow_ipc(&mut self, table_name: &str, path: &Path) -> Result<()> {
+    pub fn load_table_arrow_ipc(&self, table_name: &str, path: &Path) -> Result<()> {
         use datafusion::arrow::ipc::reader::FileReader;
         use std::fs::File;
         
@@ -1397,6 +2775,7 @@ impl Database {
         
         let reader = FileReader::try_new(file, None)
             .map_err(|e| FreshError::Custom(format!("Failed to create Arrow IPC reader: {}", e)))?;
+        let schema = reader.schema();
         
         let mut batches = Vec::new();
         for batch_result in reader {
@@ -1405,33 +2784,29 @@ impl Database {
             batches.push(batch);
         }
         
+        // A table saved without rows still carries its schema
         if batches.is_empty() {
-            return Err(FreshError::Custom("No data found in Arrow IPC file".to_string()));
+            batches.push(RecordBatch::new_empty(schema));
         }
         
-        // Combine all batches into one (for simplicity)
-        let combined_batch = if batches.len() == 1 {
-            batches.remove(0)
-        } else {
-            // For multiple batches, we'd need to concatenate them
-            // For now, just use the first batch
-            batches.remove(0)
-        };
-        
-        // Register the table
-        self.insert_record_batch(table_name, &combined_batch)?;
+        // Register the table with every batch from the file
+        self.insert_record_batches(table_name, from_table_file(batches)?)?;
         
         Ok(())
     }
 
-    /// Load all tables from a directory (Arrow IPC format only for now)
-    pub fn load_all_tables_from_directory(&mut self, directory: &Path) -> Result<Vec<String>> {
+    /// Load all tables from a directory.
+    /// The Arrow IPC cache is used when it is at least as new as the Parquet file;
+    /// otherwise the table is loaded from Parquet and the cache is rewritten.
+    pub fn load_all_tables_from_directory(&self, directory: &Path) -> Result<Vec<String>> {
         let mut loaded_tables = Vec::new();
         
         if !directory.exists() {
             return Ok(loaded_tables);
         }
         
+        // Collect table names from both formats first
+        let mut table_names = std::collections::BTreeSet::new();
         for entry in std::fs::read_dir(directory)
             .map_err(|e| FreshError::Custom(format!("Failed to read directory: {}", e)))? {
             
@@ -1443,29 +2818,89 @@ impl Database {
                 .and_then(|n| n.to_str())
                 .ok_or_else(|| FreshError::Custom("Invalid file name".to_string()))?;
             
-            if let Some(table_name) = file_name.strip_suffix(".arrow") {
-                // Load Arrow IPC files
-                match self.load_table_arrow_ipc(table_name, &path) {
+            if let Some(table_name) = file_name.strip_suffix(".arrow")
+                .or_else(|| file_name.strip_suffix(".parquet")) {
+                table_names.insert(table_name.to_string());
+            }
+        }
+        
+        for table_name in table_names {
+            let arrow_path = directory.join(format!("{}.arrow", table_name));
+            let parquet_path = directory.join(format!("{}.parquet", table_name));
+            
+            if Self::is_arrow_cache_fresh(&arrow_path, &parquet_path) {
+                match self.load_table_arrow_ipc(&table_name, &arrow_path) {
                     Ok(_) => {
-                        loaded_tables.push(table_name.to_string());
-                        // Table loaded successfully
+                        loaded_tables.push(table_name);
+                        continue;
                     }
                     Err(e) => {
                         eprintln!("[Database] Failed to load table '{}' from Arrow IPC: {}", table_name, e);
                     }
                 }
             }
+            
+            if !parquet_path.exists() {
+                continue;
+            }
+            
+            match self.load_table_parquet(&table_name, &parquet_path) {
+                Ok(_) => {
+                    // Refresh the fast cache for the next open (never touch read-only projects)
+                    if self.readonly {
+                        loaded_tables.push(table_name);
+                        continue;
+                    }
+                    if let Err(e) = self.save_table_arrow_ipc(&table_name, &arrow_path) {
+                        eprintln!("[Database] Failed to refresh Arrow cache for '{}': {}", table_name, e);
+                    }
+                    loaded_tables.push(table_name);
+                }
+                Err(e) => {
+                    eprintln!("[Database] Failed to load table '{}' from Parquet: {}", table_name, e);
+                }
+            }
+        }
+        
+        match self.load_external_tables_from_directory(directory) {
+            Ok(attached) => loaded_tables.extend(attached),
+            Err(e) => eprintln!("[Database] Failed to load external tables: {}", e),
+        }
+        
+        // Views depend on tables, so register them last
+        if let Err(e) = self.load_views_from_directory(directory) {
+            eprintln!("[Database] Failed to load views: {}", e);
+        }
+        if let Err(e) = self.load_quality_rules_from_directory(directory) {
+            eprintln!("[Database] Failed to load quality rules: {}", e);
+        }
+        if let Err(e) = self.load_table_keys_from_directory(directory) {
+            eprintln!("[Database] Failed to load table keys: {}", e);
+        }
+        if let Err(e) = self.load_lineage_from_directory(directory) {
+            eprintln!("[Database] Failed to load table lineage: {}", e);
         }
         
         Ok(loaded_tables)
     }
 
-    /// Save all current tables in Arrow IPC format
-    pub fn save_all_tables(&mut self, base_path: &Path) -> Result<Vec<String>> {
+    // The Arrow cache is usable when it exists and is not older than the Parquet file
+    fn is_arrow_cache_fresh(arrow_path: &Path, parquet_path: &Path) -> bool {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        
+        match (modified(arrow_path), modified(parquet_path)) {
+            (Some(arrow_time), Some(parquet_time)) => arrow_time >= parquet_time,
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+
+    /// Save all current tables in Arrow IPC and Parquet formats
+    pub fn save_all_tables(&self, base_path: &Path) -> Result<Vec<String>> {
         let mut saved_tables = Vec::new();
         
         // Collect table names first to avoid borrowing issues
-        let table_names: Vec<String> = self.registered_tables.keys().cloned().collect();
+        let table_names: Vec<String> = read_lock(&self.registered_tables).keys().cloned().collect();
         
         for table_name in table_names {
             match self.save_table_dual(&table_name, base_path) {
@@ -1480,4 +2915,48 @@ impl Database {
         
         Ok(saved_tables)
     }
-} 
\ No newline at end of file
+} 
+
+// The registries are only changed by whole inserts, removes and replacements, so the
+// data behind a lock poisoned by a panic elsewhere is still consistent
+fn read_lock<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_lock<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Batches as written to table files, with the format version in their schema
+fn with_format_version(batches: Vec<RecordBatch>) -> Result<Vec<RecordBatch>> {
+    let Some(schema) = batches.first().map(|b| b.schema()) else {
+        return Ok(batches);
+    };
+    let mut metadata = schema.metadata().clone();
+    metadata.insert(FORMAT_VERSION_KEY.to_string(), FORMAT_VERSION.to_string());
+    let schema = Arc::new(schema.as_ref().clone().with_metadata(metadata));
+    Ok(batches.into_iter()
+        .map(|batch| batch.with_schema(schema.clone()))
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+// Batches read from a table file, without the format version. Files written before
+// versions were recorded are migrated; saving the table again records the version.
+fn from_table_file(batches: Vec<RecordBatch>) -> Result<Vec<RecordBatch>> {
+    let Some(schema) = batches.first().map(|b| b.schema()) else {
+        return Ok(batches);
+    };
+    let mut metadata = schema.metadata().clone();
+    if metadata.remove(FORMAT_VERSION_KEY).is_none() {
+        return datetime::upgrade_time_of_day_columns(batches);
+    }
+    let schema = Arc::new(Schema::new(schema.fields().clone()).with_metadata(metadata));
+    Ok(batches.into_iter()
+        .map(|batch| RecordBatch::try_new(schema.clone(), batch.columns().to_vec()))
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+/// Quote a table or column name for use in SQL
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -0,0 +1,257 @@
+//! Date-time parsing, time zones and display formatting.
+//!
+//! Absolute date-times are stored as `Timestamp(unit, Some(zone))` holding UTC instants.
+//! The zone decides how values without an explicit offset are read on import and which
+//! wall-clock time is shown. Times of day without a date are stored as `Time64`.
+
+use std::sync::Arc;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use datafusion::arrow::array::timezone::Tz;
+use datafusion::arrow::array::{ArrayRef, AsArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Int64Type, Schema, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+use crate::core::error::{FreshError, Result};
+
+/// Zone used for imported date-times when none is chosen or detected
+pub const DEFAULT_TIME_ZONE: &str = "UTC";
+
+// Formats of date-times without an offset, tried in order; every display preset is
+// among them, so shown values can be read back
+const NAIVE_FORMATS: [&str; 9] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%d/%m/%Y %H:%M:%S%.f",
+    "%m/%d/%Y %H:%M:%S%.f",
+    "%m/%d/%Y %I:%M:%S%.f %p",
+    "%d.%m.%Y %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M",
+];
+
+// Formats of date-times that carry their own offset (RFC 3339 is tried first)
+const OFFSET_FORMATS: [&str; 3] = [
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M:%S%.f %:z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+];
+
+/// How date-times are shown in result grids, exports and chart tooltips
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum DateTimeFormat {
+    /// 2024-03-01 14:30:00
+    #[default]
+    Iso,
+    /// 2024-03-01 14:30:00 +01:00
+    IsoWithOffset,
+    /// 03/01/2024 02:30:00 PM
+    Us,
+    /// 01.03.2024 14:30:00
+    European,
+    /// Any chrono `strftime` pattern
+    Custom(String),
+}
+
+impl DateTimeFormat {
+    pub const PRESETS: [DateTimeFormat; 4] = [
+        DateTimeFormat::Iso,
+        DateTimeFormat::IsoWithOffset,
+        DateTimeFormat::Us,
+        DateTimeFormat::European,
+    ];
+
+    /// `strftime` pattern; `%.f` prints a fraction only when the value has one
+    pub fn pattern(&self) -> &str {
+        match self {
+            DateTimeFormat::Iso => "%Y-%m-%d %H:%M:%S%.f",
+            DateTimeFormat::IsoWithOffset => "%Y-%m-%d %H:%M:%S%.f %:z",
+            DateTimeFormat::Us => "%m/%d/%Y %I:%M:%S%.f %p",
+            DateTimeFormat::European => "%d.%m.%Y %H:%M:%S%.f",
+            DateTimeFormat::Custom(pattern) => pattern,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            DateTimeFormat::Iso => "ISO (2024-03-01 14:30:00)",
+            DateTimeFormat::IsoWithOffset => "ISO with offset (2024-03-01 14:30:00 +01:00)",
+            DateTimeFormat::Us => "US (03/01/2024 02:30:00 PM)",
+            DateTimeFormat::European => "European (01.03.2024 14:30:00)",
+            DateTimeFormat::Custom(_) => "Custom",
+        }
+    }
+
+    /// Check that chrono can render the pattern; formatting with a broken one panics
+    pub fn validate(&self) -> Result<()> {
+        if let DateTimeFormat::Custom(pattern) = self {
+            if chrono::format::StrftimeItems::new(pattern).any(|item| item == chrono::format::Item::Error) {
+                return Err(FreshError::Custom(format!("Invalid date-time format '{}'", pattern)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse an IANA zone name ("Europe/Berlin") or a fixed offset ("+02:00")
+pub fn parse_time_zone(name: &str) -> Result<Tz> {
+    name.trim().parse::<Tz>()
+        .map_err(|_| FreshError::Custom(format!("Unknown time zone '{}'", name.trim())))
+}
+
+/// Read a date-time, with the offset it carries if it has one
+pub(crate) fn parse_datetime(value: &str) -> Option<(NaiveDateTime, Option<FixedOffset>)> {
+    let value = value.trim();
+    let with_offset = |dt: DateTime<FixedOffset>| (dt.naive_local(), Some(*dt.offset()));
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(with_offset(dt));
+    }
+    if let Some(dt) = OFFSET_FORMATS.iter().find_map(|f| DateTime::parse_from_str(value, f).ok()) {
+        return Some(with_offset(dt));
+    }
+    NAIVE_FORMATS.iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        .map(|naive| (naive, None))
+}
+
+/// The zone implied by the offsets written in `values`: their common offset, or `None`
+/// when no value has one or they disagree
+pub fn detect_time_zone<'a>(values: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut offsets = values.into_iter()
+        .filter_map(parse_datetime)
+        .filter_map(|(_, offset)| offset);
+    let first = offsets.next()?;
+    if offsets.any(|offset| offset != first) {
+        return None;
+    }
+    if first.local_minus_utc() == 0 {
+        Some(DEFAULT_TIME_ZONE.to_string())
+    } else {
+        Some(first.to_string())
+    }
+}
+
+/// Convert a date-time string to a timestamp in `unit`. Values without an offset are
+/// wall-clock times in `zone`; plain integers are Unix seconds, or milliseconds when
+/// too large to be seconds.
+pub(crate) fn parse_timestamp(value: &str, unit: &TimeUnit, zone: &Tz) -> Option<i64> {
+    let instant = match parse_datetime(value) {
+        Some((naive, Some(offset))) => offset.from_local_datetime(&naive).single()?.with_timezone(&Utc),
+        Some((naive, None)) => zone.from_local_datetime(&naive).earliest()?.with_timezone(&Utc),
+        None => {
+            let number = value.trim().parse::<i64>().ok()?;
+            if number.unsigned_abs() < 100_000_000_000 {
+                DateTime::from_timestamp(number, 0)?
+            } else {
+                DateTime::from_timestamp_millis(number)?
+            }
+        }
+    };
+
+    let seconds = instant.timestamp();
+    let nanos = instant.timestamp_subsec_nanos() as i64;
+    match unit {
+        TimeUnit::Second => Some(seconds),
+        TimeUnit::Millisecond => seconds.checked_mul(1_000)?.checked_add(nanos / 1_000_000),
+        TimeUnit::Microsecond => seconds.checked_mul(1_000_000)?.checked_add(nanos / 1_000),
+        TimeUnit::Nanosecond => seconds.checked_mul(1_000_000_000)?.checked_add(nanos),
+    }
+}
+
+/// Split a value counted in `unit` into seconds and nanoseconds
+fn split_seconds(value: i64, unit: &TimeUnit) -> (i64, u32) {
+    let per_second = units_per_second(unit);
+    let nanos = value.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    (value.div_euclid(per_second), nanos as u32)
+}
+
+pub(crate) fn units_per_second(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    }
+}
+
+/// Format a timestamp in `format`. Zoned timestamps are shown as wall-clock time in
+/// their zone; timestamps without a zone are shown as stored.
+pub(crate) fn format_timestamp(value: i64, unit: &TimeUnit, zone: Option<&str>, format: &DateTimeFormat) -> String {
+    let (seconds, nanos) = split_seconds(value, unit);
+    let Some(instant) = DateTime::from_timestamp(seconds, nanos) else {
+        return value.to_string();
+    };
+    match zone.map(parse_time_zone) {
+        Some(Ok(zone)) => instant.with_timezone(&zone).format(format.pattern()).to_string(),
+        // An offset directive needs a zone, so naive values are shown as UTC
+        _ => instant.format(format.pattern()).to_string(),
+    }
+}
+
+/// Format a time of day counted in `unit` since midnight as HH:MM:SS[.fraction]
+pub(crate) fn format_time_of_day(value: i64, unit: &TimeUnit) -> String {
+    let (seconds, nanos) = split_seconds(value, unit);
+    NaiveTime::from_num_seconds_from_midnight_opt(seconds.rem_euclid(86_400) as u32, nanos)
+        .map(|time| time.format("%H:%M:%S%.f").to_string())
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// Table files saved before `Time64` columns existed (before format versions were
+/// recorded) hold times of day as zone-less timestamps on 1970-01-01. Such columns
+/// are turned into `Time64` when those files are loaded so they are not shown as dates.
+pub(crate) fn upgrade_time_of_day_columns(batches: Vec<RecordBatch>) -> Result<Vec<RecordBatch>> {
+    let Some(schema) = batches.first().map(|b| b.schema()) else {
+        return Ok(batches);
+    };
+
+    let within_first_day = |index: usize, unit: &TimeUnit| {
+        let day = 86_400 * units_per_second(unit);
+        let mut any_value = false;
+        let all_within = batches.iter().all(|batch| {
+            let Ok(values) = cast(batch.column(index), &DataType::Int64) else {
+                return false;
+            };
+            values.as_primitive::<Int64Type>().iter().flatten().all(|v| {
+                any_value = true;
+                (0..day).contains(&v)
+            })
+        });
+        any_value && all_within
+    };
+    let upgraded: Vec<Option<TimeUnit>> = schema.fields().iter().enumerate()
+        .map(|(i, field)| match field.data_type() {
+            DataType::Timestamp(unit, None) if within_first_day(i, unit) => Some(match unit {
+                TimeUnit::Nanosecond => TimeUnit::Nanosecond,
+                _ => TimeUnit::Microsecond,
+            }),
+            _ => None,
+        })
+        .collect();
+    if upgraded.iter().all(Option::is_none) {
+        return Ok(batches);
+    }
+
+    let fields: Vec<Field> = schema.fields().iter().zip(&upgraded)
+        .map(|(field, unit)| match unit {
+            Some(unit) => field.as_ref().clone().with_data_type(DataType::Time64(*unit)),
+            None => field.as_ref().clone(),
+        })
+        .collect();
+    let new_schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    batches.iter()
+        .map(|batch| {
+            let columns = batch.columns().iter().zip(&upgraded)
+                .map(|(column, unit)| match unit {
+                    // Counted from midnight already, so only the unit and the type change
+                    Some(unit) => cast(column, &DataType::Timestamp(*unit, None))
+                        .and_then(|c| cast(&c, &DataType::Int64))
+                        .and_then(|c| cast(&c, &DataType::Time64(*unit))),
+                    None => Ok(column.clone()),
+                })
+                .collect::<std::result::Result<Vec<ArrayRef>, _>>()?;
+            Ok(RecordBatch::try_new(new_schema.clone(), columns)?)
+        })
+        .collect()
+}
@@ -5,10 +5,12 @@ use datafusion::arrow::array::{StringArray, Int64Array, Float64Array, BooleanArr
 use datafusion::arrow::datatypes::{DataType, TimeUnit};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::arrow::compute;
+use serde::{Deserialize, Serialize};
 use crate::core::error::Result;
 
 /// Configuration for duplicate group detection
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DuplicateDetectionConfig {
     /// Column name to group by
     pub group_column: String,
@@ -28,6 +30,17 @@ impl Default for DuplicateDetectionConfig {
     }
 }
 
+impl DuplicateDetectionConfig {
+    /// Defaults for a table whose rows are identified by `key` (see `Database::match_key`).
+    /// Copies of the same content get different key values, so the key columns are ignored.
+    pub fn for_key(key: &[String]) -> Self {
+        Self {
+            ignore_columns: key.iter().cloned().collect(),
+            ..Self::default()
+        }
+    }
+}
+
 /// Represents a group of rows that are duplicates
 #[derive(Debug, Clone)]
 pub struct DuplicateGroup {
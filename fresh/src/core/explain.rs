@@ -0,0 +1,108 @@
+//! Physical plans of executed queries with the metrics DataFusion collected for each
+//! operator, as EXPLAIN ANALYZE reports them.
+
+use std::time::Duration;
+use datafusion::physical_plan::display::DisplayableExecutionPlan;
+use datafusion::physical_plan::{displayable, ExecutionPlan};
+
+/// Number of operators highlighted as the slowest of a plan
+pub const SLOWEST_OPERATORS: usize = 3;
+
+/// Physical plan of a query after it ran to completion
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlan {
+    pub root: PlanNode,
+    /// Wall-clock time from planning to the last batch
+    pub elapsed: Duration,
+    /// The plan as EXPLAIN ANALYZE prints it, one operator per line with its metrics
+    pub text: String,
+}
+
+/// One operator of a physical plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    /// Position of the node in a depth-first walk of the plan, starting at 0 for the root
+    pub id: usize,
+    /// Operator name, e.g. `FilterExec`
+    pub operator: String,
+    /// The operator's one-line description without metrics, e.g. `FilterExec: id@0 < 10`
+    pub details: String,
+    /// Rows produced over all partitions; `None` for operators that don't count them
+    pub output_rows: Option<usize>,
+    /// CPU time spent in the operator itself, summed over partitions
+    pub elapsed_compute: Option<Duration>,
+    /// Output partitions the operator runs as
+    pub partitions: usize,
+    pub children: Vec<PlanNode>,
+}
+
+impl QueryPlan {
+    /// Collect the metrics of an execution plan that has been run
+    pub fn from_execution_plan(plan: &dyn ExecutionPlan, elapsed: Duration) -> Self {
+        let mut next_id = 0;
+        Self {
+            root: PlanNode::from_execution_plan(plan, &mut next_id),
+            elapsed,
+            text: DisplayableExecutionPlan::with_metrics(plan).indent(true).to_string(),
+        }
+    }
+
+    /// All operators, parents before their children
+    pub fn nodes(&self) -> Vec<&PlanNode> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+        nodes
+    }
+
+    /// Compute time of all operators together
+    pub fn total_compute(&self) -> Duration {
+        self.nodes().iter().filter_map(|n| n.elapsed_compute).sum()
+    }
+
+    /// Ids of the `count` operators with the most compute time, slowest first.
+    /// Operators that recorded no time are never among them.
+    pub fn slowest(&self, count: usize) -> Vec<usize> {
+        let mut timed: Vec<(Duration, usize)> = self.nodes().iter()
+            .filter_map(|n| n.elapsed_compute.filter(|t| !t.is_zero()).map(|t| (t, n.id)))
+            .collect();
+        timed.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        timed.into_iter().take(count).map(|(_, id)| id).collect()
+    }
+}
+
+impl PlanNode {
+    fn from_execution_plan(plan: &dyn ExecutionPlan, next_id: &mut usize) -> Self {
+        let id = *next_id;
+        *next_id += 1;
+
+        // Per-partition metrics summed per name, as EXPLAIN ANALYZE shows them
+        let metrics = plan.metrics().map(|m| m.aggregate_by_name());
+        let details = displayable(plan).one_line().to_string().trim_end().to_string();
+        Self {
+            id,
+            // `ExecutionPlan::name` needs a sized type; the description starts with it
+            operator: details.split(':').next().unwrap_or_default().trim().to_string(),
+            details,
+            output_rows: metrics.as_ref().and_then(|m| m.output_rows()),
+            elapsed_compute: metrics.as_ref()
+                .and_then(|m| m.elapsed_compute())
+                .map(|nanos| Duration::from_nanos(nanos as u64)),
+            partitions: plan.properties().output_partitioning().partition_count(),
+            children: plan.children().into_iter()
+                .map(|child| Self::from_execution_plan(child.as_ref(), next_id))
+                .collect(),
+        }
+    }
+
+    /// Share of the plan's compute time spent in this operator, between 0 and 1
+    pub fn compute_fraction(&self, total: Duration) -> f64 {
+        match self.elapsed_compute {
+            Some(time) if !total.is_zero() => time.as_secs_f64() / total.as_secs_f64(),
+            _ => 0.0,
+        }
+    }
+}
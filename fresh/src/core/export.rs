@@ -0,0 +1,260 @@
+//! Writing query output to files batch by batch, so exports never hold more than
+//! one batch in memory. Values keep their Arrow types: JSON numbers and booleans
+//! are written as such, and Parquet and Arrow IPC files carry the schema.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use datafusion::arrow::csv;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::ipc::writer::{FileWriter, StreamWriter};
+use datafusion::arrow::json::{self, writer::{JsonArray, LineDelimited}};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::parquet::file::properties::WriterProperties;
+use crate::core::database::ParquetCompression;
+use crate::core::error::{FreshError, Result};
+
+/// File formats query output can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Parquet,
+    /// Arrow IPC file, with a footer for random access
+    ArrowFile,
+    /// Arrow IPC stream, readable as it is written
+    ArrowStream,
+    /// One JSON object per line
+    NdJson,
+    /// A JSON array of objects
+    Json,
+}
+
+impl ExportFormat {
+    pub fn all() -> [ExportFormat; 6] {
+        [
+            ExportFormat::Csv,
+            ExportFormat::Parquet,
+            ExportFormat::ArrowFile,
+            ExportFormat::ArrowStream,
+            ExportFormat::NdJson,
+            ExportFormat::Json,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Parquet => "Parquet",
+            ExportFormat::ArrowFile => "Arrow IPC file",
+            ExportFormat::ArrowStream => "Arrow IPC stream",
+            ExportFormat::NdJson => "NDJSON",
+            ExportFormat::Json => "JSON",
+        }
+    }
+
+    /// File extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::ArrowFile => "arrow",
+            ExportFormat::ArrowStream => "arrows",
+            ExportFormat::NdJson => "ndjson",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    /// Format written to files with this extension, ignoring case
+    pub fn from_extension(extension: &str) -> Option<ExportFormat> {
+        Self::all().into_iter().find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+/// How CSV exports are written
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvExportOptions {
+    pub delimiter: u8,
+    /// Character that encloses values containing the delimiter, quotes or line breaks
+    pub quote: u8,
+    pub header: bool,
+    /// Written for NULL values
+    pub null_value: String,
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            header: true,
+            null_value: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub csv: CsvExportOptions,
+    pub compression: ParquetCompression,
+}
+
+/// Writes record batches to a file in one of the export formats as they arrive
+pub struct BatchExporter {
+    writer: Box<dyn FormatWriter>,
+    format: ExportFormat,
+    rows: usize,
+}
+
+// The writer of one format
+trait FormatWriter {
+    fn write(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError>;
+
+    /// Write what follows the last batch, such as a footer, and flush the file
+    fn finish(self: Box<Self>, rows: usize) -> std::result::Result<(), ArrowError>;
+}
+
+impl BatchExporter {
+    /// Create the file at `path` for batches with the given schema
+    pub fn create(path: &Path, schema: &SchemaRef, options: &ExportOptions) -> Result<Self> {
+        let format = options.format;
+        let file = File::create(path)
+            .map_err(|e| FreshError::Custom(format!("Failed to create {} file: {}", format.display_name(), e)))?;
+
+        let writer: std::result::Result<Box<dyn FormatWriter>, ArrowError> = match format {
+            ExportFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .with_delimiter(options.csv.delimiter)
+                    .with_quote(options.csv.quote)
+                    .with_header(options.csv.header)
+                    .with_null(options.csv.null_value.clone())
+                    .build(BufWriter::new(file));
+                // The header is written with the first batch, so results without rows get one too
+                writer.write(&RecordBatch::new_empty(schema.clone()))
+                    .map(|_| Box::new(writer) as Box<dyn FormatWriter>)
+            }
+            ExportFormat::Parquet => {
+                let props = WriterProperties::builder()
+                    .set_compression(options.compression.to_parquet())
+                    .build();
+                ArrowWriter::try_new(file, schema.clone(), Some(props))
+                    .map(|writer| Box::new(writer) as Box<dyn FormatWriter>)
+                    .map_err(Into::into)
+            }
+            ExportFormat::ArrowFile => FileWriter::try_new(BufWriter::new(file), schema)
+                .map(|writer| Box::new(writer) as Box<dyn FormatWriter>),
+            ExportFormat::ArrowStream => StreamWriter::try_new(BufWriter::new(file), schema)
+                .map(|writer| Box::new(writer) as Box<dyn FormatWriter>),
+            ExportFormat::NdJson => Ok(Box::new(json_writer::<LineDelimited>(file))),
+            ExportFormat::Json => Ok(Box::new(json_writer::<JsonArray>(file))),
+        };
+        let writer = writer
+            .map_err(|e| FreshError::Custom(format!("Failed to create {} writer: {}", format.display_name(), e)))?;
+
+        Ok(Self { writer, format, rows: 0 })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.writer.write(batch)
+            .map_err(|e| FreshError::Custom(format!("Failed to write {} data: {}", self.format.display_name(), e)))?;
+        self.rows += batch.num_rows();
+        Ok(())
+    }
+
+    /// Complete the file and return the number of rows written
+    pub fn finish(self) -> Result<usize> {
+        self.writer.finish(self.rows)
+            .map_err(|e| FreshError::Custom(format!("Failed to finish {} file: {}", self.format.display_name(), e)))?;
+        Ok(self.rows)
+    }
+}
+
+impl FormatWriter for csv::Writer<BufWriter<File>> {
+    fn write(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError> {
+        csv::Writer::write(self, batch)
+    }
+
+    fn finish(self: Box<Self>, _rows: usize) -> std::result::Result<(), ArrowError> {
+        Ok(self.into_inner().flush()?)
+    }
+}
+
+impl FormatWriter for ArrowWriter<File> {
+    fn write(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError> {
+        Ok(ArrowWriter::write(self, batch)?)
+    }
+
+    fn finish(self: Box<Self>, _rows: usize) -> std::result::Result<(), ArrowError> {
+        self.close()?;
+        Ok(())
+    }
+}
+
+impl FormatWriter for FileWriter<BufWriter<File>> {
+    fn write(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError> {
+        FileWriter::write(self, batch)
+    }
+
+    fn finish(self: Box<Self>, _rows: usize) -> std::result::Result<(), ArrowError> {
+        Ok(self.into_inner()?.flush()?)
+    }
+}
+
+impl FormatWriter for StreamWriter<BufWriter<File>> {
+    fn write(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError> {
+        StreamWriter::write(self, batch)
+    }
+
+    fn finish(self: Box<Self>, _rows: usize) -> std::result::Result<(), ArrowError> {
+        Ok(self.into_inner()?.flush()?)
+    }
+}
+
+impl FormatWriter for json::Writer<BufWriter<File>, LineDelimited> {
+    fn write(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError> {
+        json::Writer::write(self, batch)
+    }
+
+    fn finish(mut self: Box<Self>, _rows: usize) -> std::result::Result<(), ArrowError> {
+        json::Writer::finish(&mut self)?;
+        Ok(self.into_inner().flush()?)
+    }
+}
+
+impl FormatWriter for json::Writer<BufWriter<File>, JsonArray> {
+    fn write(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError> {
+        json::Writer::write(self, batch)
+    }
+
+    fn finish(mut self: Box<Self>, rows: usize) -> std::result::Result<(), ArrowError> {
+        json::Writer::finish(&mut self)?;
+        let mut file = self.into_inner();
+        // The writer only opens the array once it sees a row
+        if rows == 0 {
+            file.write_all(b"[]")?;
+        }
+        Ok(file.flush()?)
+    }
+}
+
+// NULLs are written as `null` instead of leaving the key out
+fn json_writer<F: json::writer::JsonFormat>(file: File) -> json::Writer<BufWriter<File>, F> {
+    json::WriterBuilder::new()
+        .with_explicit_nulls(true)
+        .build(BufWriter::new(file))
+}
+
+/// Write batches already in memory, such as one page of a result, to `path`
+pub fn export_batches(batches: &[RecordBatch], path: &Path, options: &ExportOptions) -> Result<usize> {
+    let Some(first) = batches.first() else {
+        return Err(FreshError::Custom("Nothing to export".to_string()));
+    };
+    let mut exporter = BatchExporter::create(path, &first.schema(), options)?;
+    for batch in batches {
+        exporter.write(batch)?;
+    }
+    exporter.finish()
+}
@@ -0,0 +1,261 @@
+//! Tables that stay in their source files and are scanned lazily.
+//!
+//! An external table is a DataFusion listing table over one file or a folder of
+//! files, so queries read only the columns they project and Parquet scans can skip
+//! row groups using the query's predicates. Nothing is copied into memory up front.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use datafusion::arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::json::JsonFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl};
+use datafusion::prelude::SessionContext;
+use serde::{Deserialize, Serialize};
+use crate::core::csv_ingest::infer_delimiter;
+use crate::core::error::{Result, FreshError};
+use crate::infer::{ColumnType, TypeInferrer};
+
+/// File format of an external table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExternalFormat {
+    Csv,
+    Parquet,
+    NdJson,
+}
+
+impl ExternalFormat {
+    /// Guess the format from a file extension (`.csv`, `.tsv`, `.parquet`, `.json`, `.ndjson`, `.jsonl`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" | "tsv" | "txt" => Some(ExternalFormat::Csv),
+            "parquet" | "pq" => Some(ExternalFormat::Parquet),
+            "json" | "ndjson" | "jsonl" => Some(ExternalFormat::NdJson),
+            _ => None,
+        }
+    }
+
+    /// Extension of the files picked up when a folder is attached
+    pub fn default_extension(&self) -> &'static str {
+        match self {
+            ExternalFormat::Csv => ".csv",
+            ExternalFormat::Parquet => ".parquet",
+            ExternalFormat::NdJson => ".json",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ExternalFormat::Csv => "CSV",
+            ExternalFormat::Parquet => "Parquet",
+            ExternalFormat::NdJson => "NDJSON",
+        }
+    }
+}
+
+/// Settings for attaching a file or folder as a table
+#[derive(Debug, Clone)]
+pub struct ExternalTableOptions {
+    /// File format; detected from the extension when `None`
+    pub format: Option<ExternalFormat>,
+    /// Only files ending with this are read from a folder; defaults to the format's extension
+    pub file_extension: Option<String>,
+    /// CSV field delimiter; detected from the header line when `None`
+    pub delimiter: Option<char>,
+    /// Whether CSV files start with a header row
+    pub has_header: bool,
+    /// Rows sampled by `TypeInferrer` (CSV) or DataFusion (NDJSON) to pick column types
+    pub sample_rows: usize,
+}
+
+impl Default for ExternalTableOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            file_extension: None,
+            delimiter: None,
+            has_header: true,
+            sample_rows: 1000,
+        }
+    }
+}
+
+/// Definition of an attached table, persisted in the project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalTable {
+    pub name: String,
+    /// File or folder the table reads from
+    pub location: PathBuf,
+    pub format: ExternalFormat,
+    pub file_extension: String,
+    pub delimiter: char,
+    pub has_header: bool,
+    pub sample_rows: usize,
+}
+
+impl ExternalTable {
+    /// Resolve the options against the file system
+    pub fn new(name: &str, location: &Path, options: &ExternalTableOptions) -> Result<Self> {
+        if !location.exists() {
+            return Err(FreshError::Custom(format!("'{}' does not exist", location.display())));
+        }
+        let location = location.canonicalize()
+            .map_err(|e| FreshError::Custom(format!("Failed to resolve '{}': {}", location.display(), e)))?;
+
+        let format = match options.format {
+            Some(format) => format,
+            None if location.is_file() => ExternalFormat::from_path(&location)
+                .ok_or_else(|| FreshError::Custom(format!(
+                    "Cannot tell the format of '{}'; choose CSV, Parquet or NDJSON",
+                    location.display()
+                )))?,
+            None => return Err(FreshError::Custom("Choose a file format to attach a folder".to_string())),
+        };
+
+        // A single file is read whatever its extension is
+        let file_extension = match (&options.file_extension, location.is_file()) {
+            (Some(extension), _) => extension.clone(),
+            (None, true) => String::new(),
+            (None, false) => format.default_extension().to_string(),
+        };
+
+        let mut table = Self {
+            name: name.to_string(),
+            location,
+            format,
+            file_extension,
+            delimiter: options.delimiter.unwrap_or(','),
+            has_header: options.has_header,
+            sample_rows: options.sample_rows.max(1),
+        };
+        if format == ExternalFormat::Csv && options.delimiter.is_none() {
+            let mut first_line = String::new();
+            BufReader::new(File::open(table.sample_file()?)?).read_line(&mut first_line)?;
+            table.delimiter = infer_delimiter(&first_line);
+        }
+        Ok(table)
+    }
+
+    /// Build the listing table provider; the schema is inferred from the files
+    pub(crate) async fn provider(&self, ctx: &SessionContext) -> Result<Arc<ListingTable>> {
+        let url = ListingTableUrl::parse(self.location.to_string_lossy())
+            .map_err(|e| FreshError::Custom(format!("Invalid table location: {}", e)))?;
+
+        let format: Arc<dyn FileFormat> = match self.format {
+            ExternalFormat::Csv => Arc::new(CsvFormat::default()
+                .with_has_header(self.has_header)
+                .with_delimiter(self.delimiter as u8)),
+            ExternalFormat::Parquet => Arc::new(ParquetFormat::default()),
+            ExternalFormat::NdJson => Arc::new(JsonFormat::default()
+                .with_schema_infer_max_rec(self.sample_rows)),
+        };
+        let state = ctx.state();
+        let listing_options = ListingOptions::new(format)
+            .with_file_extension(self.file_extension.clone())
+            .with_target_partitions(state.config().target_partitions());
+
+        let schema = match self.format {
+            // CSV columns use the same type inference as imports
+            ExternalFormat::Csv => self.infer_csv_schema()?,
+            _ => listing_options.infer_schema(&state, &url).await
+                .map_err(|e| FreshError::Custom(format!("Failed to infer schema of '{}': {}", self.location.display(), e)))?,
+        };
+
+        let config = ListingTableConfig::new(url)
+            .with_listing_options(listing_options)
+            .with_schema(schema);
+        let table = ListingTable::try_new(config)
+            .map_err(|e| FreshError::Custom(format!("Failed to create external table {}: {}", self.name, e)))?;
+        Ok(Arc::new(table))
+    }
+
+    // The file used for delimiter detection and type inference
+    fn sample_file(&self) -> Result<PathBuf> {
+        if self.location.is_file() {
+            return Ok(self.location.clone());
+        }
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&self.location)
+            .map_err(|e| FreshError::Custom(format!("Failed to read directory: {}", e)))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && path.to_string_lossy().ends_with(&self.file_extension))
+            .collect();
+        files.sort();
+        files.into_iter().next().ok_or_else(|| FreshError::Custom(format!(
+            "No '{}' files found in '{}'",
+            self.file_extension,
+            self.location.display()
+        )))
+    }
+
+    // Sample the first file with the csv crate and map the inferred types to ones
+    // DataFusion's CSV reader can parse; anything else stays text
+    fn infer_csv_schema(&self) -> Result<SchemaRef> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter as u8)
+            .has_headers(false)
+            .flexible(true)
+            .from_path(self.sample_file()?)?;
+        let mut records = reader.records();
+
+        let header: Option<Vec<String>> = if self.has_header {
+            let record = records.next()
+                .ok_or_else(|| FreshError::Custom("No header row found".to_string()))??;
+            Some(record.iter().map(|s| s.trim().to_string()).collect())
+        } else {
+            None
+        };
+        let mut samples = Vec::new();
+        for record in records.take(self.sample_rows) {
+            samples.push(record?.iter().map(|s| s.trim().to_string()).collect::<Vec<String>>());
+        }
+
+        let width = header.as_ref().map(|h| h.len())
+            .unwrap_or_else(|| samples.first().map(|r| r.len()).unwrap_or(0));
+        if width == 0 {
+            return Err(FreshError::Custom("CSV file has no columns".to_string()));
+        }
+        let headers: Vec<String> = (0..width)
+            .map(|i| match header.as_ref().and_then(|h| h.get(i)) {
+                Some(name) if !name.is_empty() => name.clone(),
+                _ => format!("column_{}", i + 1),
+            })
+            .collect();
+
+        let inferred = TypeInferrer::infer_column_types_with_nulls(&headers, &samples, &[]);
+        let mut seen = std::collections::HashSet::new();
+        let fields: Vec<Field> = inferred.iter().enumerate()
+            .map(|(i, (name, column_type))| {
+                let name = if seen.insert(name.clone()) { name.clone() } else { format!("{}_{}", name, i + 1) };
+                let values: Vec<&str> = samples.iter()
+                    .filter_map(|row| row.get(i).map(|v| v.as_str()))
+                    .filter(|v| !v.is_empty())
+                    .collect();
+                Field::new(name, csv_reader_type(column_type, &values), true)
+            })
+            .collect();
+        Ok(Arc::new(Schema::new(fields)))
+    }
+}
+
+// Arrow's CSV reader only understands a subset of what `TypeInferrer` recognizes
+// (e.g. booleans must be true/false and dates ISO formatted)
+fn csv_reader_type(column_type: &ColumnType, values: &[&str]) -> DataType {
+    let all = |check: fn(&str) -> bool| values.iter().all(|v| check(v));
+    match column_type {
+        ColumnType::Integer if all(|v| v.parse::<i64>().is_ok()) => DataType::Int64,
+        ColumnType::Real => DataType::Float64,
+        ColumnType::Boolean if all(|v| v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("false")) => DataType::Boolean,
+        ColumnType::Date if all(|v| chrono::NaiveDate::parse_from_str(v, "%Y-%m-%d").is_ok()) => DataType::Date32,
+        ColumnType::DateTime if all(|v| string_to_timestamp_nanos(v).is_ok()) => column_type.to_arrow_type(),
+        _ if !values.is_empty() && all(|v| v.parse::<i64>().is_ok()) => DataType::Int64,
+        _ if !values.is_empty() && all(|v| v.parse::<f64>().is_ok()) => DataType::Float64,
+        _ => DataType::Utf8,
+    }
+}
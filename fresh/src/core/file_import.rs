@@ -0,0 +1,305 @@
+//! CSV files to import as tables, and the import itself: the settings of each file
+//! (table name, header row, delimiter, NULL values, key columns), a preview of its first
+//! lines, validation of the settings and the transactional import into a project.
+//! Shared by the import dialog and `fresh import`.
+
+use std::path::{Path, PathBuf};
+use crate::core::{CsvIngestOptions, Database, IngestProgress};
+use crate::core::csv_ingest::infer_delimiter;
+use crate::core::datetime;
+use crate::infer::{ColumnType, TypeInferrer};
+
+#[derive(Clone)]
+pub struct FileConfig {
+    pub path: PathBuf,
+    pub table_name: String,
+    pub header_row: usize,
+    /// Field delimiter; `None` detects it from the header line
+    pub delimiter: Option<char>,
+    /// Delimiter detected from the header line of the preview
+    pub detected_delimiter: Option<char>,
+    pub sample_size: usize,
+    pub columns: Vec<ColumnConfig>,
+    pub null_values: Vec<String>,
+    /// Zone of date-times written without an offset; `None` detects it on import
+    pub time_zone: Option<String>,
+    /// Zone implied by the offsets in the sampled date-times
+    pub detected_time_zone: Option<String>,
+    pub preview_data: Option<PreviewData>,
+}
+
+#[derive(Clone)]
+pub struct ColumnConfig {
+    pub name: String,
+    pub data_type: ColumnType,
+    pub included: bool,
+    /// Part of the table's primary key
+    pub primary_key: bool,
+}
+
+#[derive(Clone)]
+pub struct PreviewData {
+    pub rows: Vec<Vec<String>>,
+}
+
+impl FileConfig {
+    pub fn new(path: PathBuf) -> Self {
+        let table_name = path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("table")
+            .to_string();
+        
+        Self {
+            path,
+            table_name,
+            header_row: 1, // Default to row 1 (1-indexed) instead of 0
+            delimiter: None,
+            detected_delimiter: None,
+            sample_size: 1000,
+            columns: Vec::new(),
+            null_values: vec!["", "NULL", "null", "N/A", "-"].into_iter().map(String::from).collect(),
+            time_zone: None,
+            detected_time_zone: None,
+            preview_data: None,
+        }
+    }
+    
+    pub fn file_name(&self) -> String {
+        self.path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string()
+    }
+    
+    /// Read the first lines of the file for the preview, detect the delimiter from the
+    /// header line and infer the column types from the rows after the header
+    pub fn load_preview(&mut self) -> Result<(), String> {
+        use std::io::BufRead;
+        
+        let file = std::fs::File::open(&self.path)
+            .map_err(|e| format!("Failed to load preview: {}", e))?;
+        // Only the start of the file is read, however large it is
+        let lines: Vec<String> = std::io::BufReader::new(file).lines()
+            .take(50)
+            .map_while(|line| line.ok())
+            .collect();
+        if lines.is_empty() {
+            return Err("No data found in file".to_string());
+        }
+        
+        // Get the header row from the selected header row position (1-indexed to 0-indexed)
+        let header_idx = self.header_row.saturating_sub(1);
+        self.detected_delimiter = lines.get(header_idx).map(|line| infer_delimiter(line));
+        let delimiter = self.delimiter();
+        let preview_rows: Vec<Vec<String>> = lines.iter()
+            .map(|line| parse_csv_line(line, delimiter))
+            .collect();
+        
+        if let Some(headers) = preview_rows.get(header_idx) {
+            // Use data rows after the header row for type inference, skipping comment lines
+            let sample_data: Vec<Vec<String>> = preview_rows.iter()
+                .skip(header_idx + 1)
+                .filter(|row| !row.is_empty() && !row[0].starts_with('#'))
+                .take(self.sample_size)
+                .cloned()
+                .collect();
+            
+            // Infer types using the sample data with null value awareness
+            let inferred_types = TypeInferrer::infer_column_types_with_nulls(headers, &sample_data, &self.null_values);
+            
+            let datetime_values = inferred_types.iter().enumerate()
+                .filter(|(_, (_, data_type))| *data_type == ColumnType::DateTime)
+                .flat_map(|(idx, _)| sample_data.iter().filter_map(move |row| row.get(idx)))
+                .map(|value| value.as_str());
+            self.detected_time_zone = datetime::detect_time_zone(datetime_values);
+            
+            self.columns = headers.iter().zip(inferred_types)
+                .map(|(header, (_name, data_type))| ColumnConfig {
+                    name: header.clone(),
+                    data_type,
+                    included: true,
+                    primary_key: false,
+                })
+                .collect();
+        }
+        
+        self.preview_data = Some(PreviewData { rows: preview_rows });
+        Ok(())
+    }
+    
+    /// The delimiter chosen, else the one detected in the preview, else a comma
+    pub fn delimiter(&self) -> char {
+        self.delimiter.or(self.detected_delimiter).unwrap_or(',')
+    }
+}
+
+// Split a CSV line into trimmed fields, handling quoted strings
+fn parse_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut row = Vec::new();
+    let mut current_field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                if in_quotes {
+                    // End of quoted field
+                    in_quotes = false;
+                } else {
+                    // Start of quoted field
+                    in_quotes = true;
+                }
+            },
+            c if c == delimiter && !in_quotes => {
+                // End of field
+                row.push(current_field.trim().to_string());
+                current_field.clear();
+            },
+            _ => {
+                current_field.push(ch);
+            }
+        }
+    }
+    
+    // Add the last field
+    row.push(current_field.trim().to_string());
+    row
+}
+
+/// Problem with the import settings that must be fixed before importing, if any
+pub fn validate_files(files: &[FileConfig]) -> Option<String> {
+    for config in files {
+        // Validate table name
+        if config.table_name.is_empty() {
+            return Some(format!("File '{}' has an empty table name", config.file_name()));
+        }
+        
+        // Check for invalid characters in table name
+        if !config.table_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Some(format!(
+                "Table name '{}' contains invalid characters. Use only letters, numbers, and underscores.",
+                config.table_name
+            ));
+        }
+        
+        // Check that at least one column is included; a file that was never previewed
+        // has no column settings and imports every column
+        if !config.columns.is_empty() && !config.columns.iter().any(|c| c.included) {
+            return Some(format!(
+                "Table '{}' has no columns selected. Select at least one column to include.",
+                config.table_name
+            ));
+        }
+        
+        if let Some(zone) = &config.time_zone {
+            if let Err(e) = datetime::parse_time_zone(zone) {
+                return Some(format!("{} (table '{}')", e, config.table_name));
+            }
+        }
+        
+        if let Some(column) = config.columns.iter().find(|c| c.primary_key && !c.included) {
+            return Some(format!(
+                "Column '{}' of table '{}' is part of the primary key and must be included.",
+                column.name, config.table_name
+            ));
+        }
+    }
+    
+    // Check for duplicate table names
+    let mut table_names = std::collections::HashSet::new();
+    for config in files {
+        if !table_names.insert(&config.table_name) {
+            return Some(format!(
+                "Duplicate table name '{}'. Each table must have a unique name.",
+                config.table_name
+            ));
+        }
+    }
+    
+    None
+}
+
+/// Import CSV files into the project at `project_path` as the import dialog does: each
+/// file becomes a table with the types `TypeInferrer` picks from the first
+/// `CsvIngestOptions::sample_rows` rows and its declared primary key. Of `columns`, only
+/// `primary_key` is read.
+/// Nothing is written to the project folder unless every file imports. `report` gets a
+/// progress message and the fraction done.
+pub fn import_files(
+    db: &Database,
+    project_path: &Path,
+    files: &mut [FileConfig],
+    ingest_options: &CsvIngestOptions,
+    report: &mut dyn FnMut(String, f32),
+) -> Result<(), String> {
+    db.begin_transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    
+    let total_files = files.len();
+    for (file_idx, config) in files.iter_mut().enumerate() {
+        report(
+            format!("Importing {} ({}/{})", config.file_name(), file_idx + 1, total_files),
+            0.2 + (0.7 * file_idx as f32 / total_files as f32)
+        );
+        
+        // Stream the file in batches, reporting bytes and rows as they are decoded
+        let options = CsvIngestOptions {
+            delimiter: config.delimiter(),
+            // Files that were not previewed are detected while importing
+            infer_delimiter: config.delimiter.is_none() && config.detected_delimiter.is_none(),
+            header_row: Some(config.header_row.saturating_sub(1)),
+            null_values: config.null_values.clone(),
+            time_zone: config.time_zone.clone(),
+            ..ingest_options.clone()
+        };
+        let file_name = config.file_name();
+        let mut report_progress = |progress: IngestProgress| {
+            report(
+                format!(
+                    "Importing {} ({}/{}) - {} rows, {:.1} of {:.1} MB",
+                    file_name, file_idx + 1, total_files, progress.rows_read,
+                    progress.bytes_read as f64 / (1024.0 * 1024.0),
+                    progress.total_bytes as f64 / (1024.0 * 1024.0)
+                ),
+                0.2 + (0.7 * (file_idx as f32 + progress.fraction()) / total_files as f32)
+            );
+        };
+        let primary_key: Vec<String> = config.columns.iter()
+            .filter(|c| c.primary_key)
+            .map(|c| c.name.clone())
+            .collect();
+        let imported = db.ingest_csv(&config.table_name, &config.path, &options, &mut report_progress)
+            .and_then(|delimiter| {
+                // Declaring the key checks the imported rows for duplicated or missing key values
+                if !primary_key.is_empty() {
+                    db.set_primary_key(&config.table_name, &primary_key)?;
+                }
+                // Saved in the project folder; the files are staged and moved into place
+                // when the transaction commits
+                db.save_table_dual(&config.table_name, project_path)?;
+                Ok(delimiter)
+            });
+        match imported {
+            Ok(inferred_delimiter) => {
+                // Update the config with the inferred delimiter if it was auto-detected
+                config.delimiter.get_or_insert(inferred_delimiter);
+            }
+            Err(e) => {
+                if let Err(rollback_error) = db.rollback_transaction() {
+                    eprintln!("[FileImport] Failed to roll back import: {}", rollback_error);
+                }
+                return Err(format!("Failed to import {}: {}", config.file_name(), e));
+            }
+        }
+        
+        report(format!("Completed {}", config.file_name()), 0.2 + (0.7 * (file_idx + 1) as f32 / total_files as f32));
+    }
+    
+    report("Saving tables to persistence...".to_string(), 0.9);
+    db.commit_transaction().map_err(|e| {
+        let _ = db.rollback_transaction();
+        format!("Failed to save tables to persistence: {}", e)
+    })
+}
+ 
\ No newline at end of file
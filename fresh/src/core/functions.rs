@@ -0,0 +1,551 @@
+//! SQL functions every `Database` registers on top of DataFusion's built-ins, and the
+//! type embedding applications use to register their own.
+//!
+//! The time functions accept timestamps (using the wall-clock time of their zone),
+//! `Time32`/`Time64` values and "HH:MM:SS" strings alike.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, Float64Array, Int64Array, StringArray, Time64NanosecondArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Float64Type, Int64Type, TimeUnit};
+use datafusion::common::utils::array_into_list_array;
+use datafusion::common::{exec_err, plan_err, ScalarValue};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::function::{AccumulatorArgs, StateFieldsArgs};
+use datafusion::logical_expr::{
+    create_udaf, create_udf, Accumulator, AggregateUDF, AggregateUDFImpl, ColumnarValue,
+    PartitionEvaluator, ScalarUDF, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+    WindowUDF, WindowUDFImpl,
+};
+use datafusion::prelude::SessionContext;
+use regex::Regex;
+use chrono::{Offset, TimeZone};
+use crate::core::database::Database;
+use crate::core::datetime;
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const NANOS_PER_DAY: i64 = 86_400 * NANOS_PER_SECOND;
+/// Mean Earth radius used by `haversine`, in kilometres
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// A user-defined function that can be added to a database with `Database::register_function`
+#[derive(Debug, Clone)]
+pub enum SqlFunction {
+    Scalar(ScalarUDF),
+    Aggregate(AggregateUDF),
+    Window(WindowUDF),
+}
+
+impl SqlFunction {
+    pub fn name(&self) -> &str {
+        match self {
+            SqlFunction::Scalar(function) => function.name(),
+            SqlFunction::Aggregate(function) => function.name(),
+            SqlFunction::Window(function) => function.name(),
+        }
+    }
+
+    pub(crate) fn register(&self, ctx: &SessionContext) {
+        match self {
+            SqlFunction::Scalar(function) => ctx.register_udf(function.clone()),
+            SqlFunction::Aggregate(function) => ctx.register_udaf(function.clone()),
+            SqlFunction::Window(function) => ctx.register_udwf(function.clone()),
+        }
+    }
+}
+
+impl From<ScalarUDF> for SqlFunction {
+    fn from(function: ScalarUDF) -> Self {
+        SqlFunction::Scalar(function)
+    }
+}
+
+impl From<AggregateUDF> for SqlFunction {
+    fn from(function: AggregateUDF) -> Self {
+        SqlFunction::Aggregate(function)
+    }
+}
+
+impl From<WindowUDF> for SqlFunction {
+    fn from(function: WindowUDF) -> Self {
+        SqlFunction::Window(function)
+    }
+}
+
+/// The function library registered on every database:
+///
+/// - `time_of_day(t)`: the time of day of a timestamp or time, as `Time64`
+/// - `seconds_since_midnight(t)`: the same as fractional seconds
+/// - `unwrap_midnight(t) OVER (...)`: seconds since the first midnight, adding a day
+///   every time the time of day jumps back by more than twelve hours
+/// - `haversine(lat1, lon1, lat2, lon2)`: great-circle distance in kilometres
+/// - `regexp_extract(text, pattern[, group])`: a capture group of the first match
+/// - `percentile_cont(value, fraction)`: exact percentile, interpolating between values
+/// - `mode(value)`: the most frequent value (the smallest one on ties)
+pub fn builtin_functions() -> Vec<SqlFunction> {
+    vec![
+        ScalarUDF::from(TimeFunction::time_of_day()).into(),
+        ScalarUDF::from(TimeFunction::seconds_since_midnight()).into(),
+        WindowUDF::from(UnwrapMidnight::new()).into(),
+        haversine().into(),
+        ScalarUDF::from(RegexpExtract::new()).into(),
+        percentile_cont().into(),
+        AggregateUDF::from(Mode::new()).into(),
+    ]
+}
+
+// Run `f` over the arguments as arrays; when every argument is a scalar the result is too
+fn invoke_on_arrays(args: &[ColumnarValue], f: impl Fn(&[ArrayRef]) -> Result<ArrayRef>) -> Result<ColumnarValue> {
+    let all_scalars = args.iter().all(|arg| matches!(arg, ColumnarValue::Scalar(_)));
+    let result = f(&ColumnarValue::values_to_arrays(args)?)?;
+    if all_scalars {
+        Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(&result, 0)?))
+    } else {
+        Ok(ColumnarValue::Array(result))
+    }
+}
+
+fn is_time_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Timestamp(_, _) | DataType::Time32(_) | DataType::Time64(_) | DataType::Utf8 | DataType::LargeUtf8 | DataType::Null
+    )
+}
+
+// Nanoseconds since midnight of every value; NULL where the value is NULL or not a valid time
+fn nanos_since_midnight(array: &ArrayRef) -> Result<Vec<Option<i64>>> {
+    let nanos = match array.data_type() {
+        DataType::Utf8 | DataType::LargeUtf8 => {
+            let strings = cast(array, &DataType::Utf8)?;
+            return Ok(strings.as_string::<i32>().iter()
+                .map(|s| s.and_then(|s| Database::parse_time_string_to_timestamp(s.trim(), &TimeUnit::Nanosecond)))
+                .collect());
+        }
+        DataType::Null => return Ok(vec![None; array.len()]),
+        DataType::Time32(_) | DataType::Time64(_) => cast(array, &DataType::Time64(TimeUnit::Nanosecond))?,
+        DataType::Timestamp(_, Some(zone)) => {
+            // Zoned timestamps hold UTC instants; shift each to the wall-clock time of its zone
+            let zone = datetime::parse_time_zone(zone)
+                .map_err(|e| DataFusionError::Execution(e.to_string()))?;
+            let utc = cast(array, &DataType::Timestamp(TimeUnit::Nanosecond, None))?;
+            let utc = cast(&utc, &DataType::Int64)?;
+            return Ok(utc.as_primitive::<Int64Type>().iter()
+                .map(|n| n.map(|n| {
+                    let instant = chrono::DateTime::from_timestamp_nanos(n).naive_utc();
+                    let offset = zone.offset_from_utc_datetime(&instant).fix().local_minus_utc() as i64;
+                    (n + offset * NANOS_PER_SECOND).rem_euclid(NANOS_PER_DAY)
+                }))
+                .collect());
+        }
+        DataType::Timestamp(_, None) => cast(array, &DataType::Timestamp(TimeUnit::Nanosecond, None))?,
+        other => return exec_err!("Expected a timestamp, time or string, got {}", other),
+    };
+    let nanos = cast(&nanos, &DataType::Int64)?;
+    Ok(nanos.as_primitive::<Int64Type>().iter()
+        .map(|n| n.map(|n| n.rem_euclid(NANOS_PER_DAY)))
+        .collect())
+}
+
+/// `time_of_day` and `seconds_since_midnight`, which differ only in their result type
+#[derive(Debug)]
+struct TimeFunction {
+    name: &'static str,
+    return_type: DataType,
+    signature: Signature,
+}
+
+impl TimeFunction {
+    fn time_of_day() -> Self {
+        Self {
+            name: "time_of_day",
+            return_type: DataType::Time64(TimeUnit::Nanosecond),
+            signature: Signature::any(1, Volatility::Immutable),
+        }
+    }
+
+    fn seconds_since_midnight() -> Self {
+        Self {
+            name: "seconds_since_midnight",
+            return_type: DataType::Float64,
+            signature: Signature::any(1, Volatility::Immutable),
+        }
+    }
+}
+
+impl ScalarUDFImpl for TimeFunction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        if !is_time_type(&arg_types[0]) {
+            return plan_err!("{} expects a timestamp, time or string, got {}", self.name, arg_types[0]);
+        }
+        Ok(self.return_type.clone())
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        invoke_on_arrays(args, |arrays| {
+            let nanos = nanos_since_midnight(&arrays[0])?;
+            Ok(match self.return_type {
+                DataType::Float64 => Arc::new(nanos.into_iter()
+                    .map(|n| n.map(|n| n as f64 / NANOS_PER_SECOND as f64))
+                    .collect::<Float64Array>()),
+                _ => Arc::new(nanos.into_iter().collect::<Time64NanosecondArray>()),
+            })
+        })
+    }
+}
+
+/// Window function turning times of day into a monotonic elapsed time
+#[derive(Debug)]
+struct UnwrapMidnight {
+    signature: Signature,
+}
+
+impl UnwrapMidnight {
+    fn new() -> Self {
+        Self { signature: Signature::any(1, Volatility::Immutable) }
+    }
+}
+
+impl WindowUDFImpl for UnwrapMidnight {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "unwrap_midnight"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        if !is_time_type(&arg_types[0]) {
+            return plan_err!("unwrap_midnight expects a timestamp, time or string, got {}", arg_types[0]);
+        }
+        Ok(DataType::Float64)
+    }
+
+    fn partition_evaluator(&self) -> Result<Box<dyn PartitionEvaluator>> {
+        Ok(Box::new(UnwrapMidnightEvaluator))
+    }
+}
+
+#[derive(Debug)]
+struct UnwrapMidnightEvaluator;
+
+impl PartitionEvaluator for UnwrapMidnightEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], _num_rows: usize) -> Result<ArrayRef> {
+        let mut days = 0;
+        let mut previous = None;
+        let elapsed: Float64Array = nanos_since_midnight(&values[0])?.into_iter()
+            .map(|nanos| {
+                let nanos = nanos?;
+                // A small step back is out-of-order data, not a new day
+                if previous.is_some_and(|previous| previous - nanos > NANOS_PER_DAY / 2) {
+                    days += 1;
+                }
+                previous = Some(nanos);
+                Some((days * NANOS_PER_DAY + nanos) as f64 / NANOS_PER_SECOND as f64)
+            })
+            .collect();
+        Ok(Arc::new(elapsed))
+    }
+}
+
+fn haversine() -> ScalarUDF {
+    create_udf(
+        "haversine",
+        vec![DataType::Float64; 4],
+        Arc::new(DataType::Float64),
+        Volatility::Immutable,
+        Arc::new(|args: &[ColumnarValue]| {
+            invoke_on_arrays(args, |arrays| {
+                let [lat1, lon1, lat2, lon2] = [0, 1, 2, 3].map(|i| arrays[i].as_primitive::<Float64Type>());
+                let distances: Float64Array = (0..lat1.len())
+                    .map(|i| {
+                        if [lat1, lon1, lat2, lon2].iter().any(|a| a.is_null(i)) {
+                            return None;
+                        }
+                        let (phi1, phi2) = (lat1.value(i).to_radians(), lat2.value(i).to_radians());
+                        let d_phi = phi2 - phi1;
+                        let d_lambda = (lon2.value(i) - lon1.value(i)).to_radians();
+                        let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+                        Some(2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin())
+                    })
+                    .collect();
+                Ok(Arc::new(distances))
+            })
+        }),
+    )
+}
+
+/// `regexp_extract(text, pattern[, group])`; the group defaults to 1 when the pattern
+/// has capture groups and to the whole match otherwise
+#[derive(Debug)]
+struct RegexpExtract {
+    signature: Signature,
+}
+
+impl RegexpExtract {
+    fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                ],
+                Volatility::Immutable,
+            ),
+        }
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtract {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "regexp_extract"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        invoke_on_arrays(args, |arrays| {
+            let texts = arrays[0].as_string::<i32>();
+            let patterns = arrays[1].as_string::<i32>();
+            let groups = arrays.get(2).map(|groups| groups.as_primitive::<Int64Type>());
+
+            // The pattern is nearly always a literal, so compile each distinct one once
+            let mut compiled: HashMap<&str, Regex> = HashMap::new();
+            let mut extracted = Vec::with_capacity(texts.len());
+            for i in 0..texts.len() {
+                if texts.is_null(i) || patterns.is_null(i) || groups.is_some_and(|g| g.is_null(i)) {
+                    extracted.push(None);
+                    continue;
+                }
+                let pattern = patterns.value(i);
+                if !compiled.contains_key(pattern) {
+                    let regex = Regex::new(pattern)
+                        .map_err(|e| datafusion::error::DataFusionError::Execution(format!("Invalid regexp_extract pattern: {}", e)))?;
+                    compiled.insert(pattern, regex);
+                }
+                let regex = &compiled[pattern];
+                let group = match groups {
+                    Some(groups) => groups.value(i),
+                    None => if regex.captures_len() > 1 { 1 } else { 0 },
+                };
+                if group < 0 || group as usize >= regex.captures_len() {
+                    return exec_err!("regexp_extract pattern '{}' has no group {}", pattern, group);
+                }
+                extracted.push(regex.captures(texts.value(i))
+                    .and_then(|captures| captures.get(group as usize))
+                    .map(|m| m.as_str().to_string()));
+            }
+            Ok(Arc::new(StringArray::from(extracted)))
+        })
+    }
+}
+
+fn percentile_cont() -> AggregateUDF {
+    create_udaf(
+        "percentile_cont",
+        vec![DataType::Float64, DataType::Float64],
+        Arc::new(DataType::Float64),
+        Volatility::Immutable,
+        Arc::new(|_| Ok(Box::<PercentileAccumulator>::default())),
+        Arc::new(vec![
+            DataType::List(Arc::new(Field::new("item", DataType::Float64, true))),
+            DataType::Float64,
+        ]),
+    )
+}
+
+/// Keeps every value, so the percentile is exact
+#[derive(Debug, Default)]
+struct PercentileAccumulator {
+    values: Vec<f64>,
+    fraction: Option<f64>,
+}
+
+impl PercentileAccumulator {
+    fn set_fraction(&mut self, fractions: &ArrayRef) -> Result<()> {
+        if self.fraction.is_some() {
+            return Ok(());
+        }
+        self.fraction = fractions.as_primitive::<Float64Type>().iter().flatten().next();
+        match self.fraction {
+            Some(fraction) if !(0.0..=1.0).contains(&fraction) => {
+                exec_err!("percentile_cont fraction must be between 0 and 1, got {}", fraction)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Accumulator for PercentileAccumulator {
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        self.values.extend(values[0].as_primitive::<Float64Type>().iter().flatten());
+        self.set_fraction(&values[1])
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        for list in states[0].as_list::<i32>().iter().flatten() {
+            self.values.extend(list.as_primitive::<Float64Type>().iter().flatten());
+        }
+        self.set_fraction(&states[1])
+    }
+
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let values = Float64Array::from(self.values.clone());
+        Ok(vec![
+            ScalarValue::List(Arc::new(array_into_list_array(Arc::new(values)))),
+            ScalarValue::Float64(self.fraction),
+        ])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let Some(fraction) = self.fraction.filter(|_| !self.values.is_empty()) else {
+            return Ok(ScalarValue::Float64(None));
+        };
+        self.values.sort_by(|a, b| a.total_cmp(b));
+        let rank = fraction * (self.values.len() - 1) as f64;
+        let (lower, upper) = (self.values[rank.floor() as usize], self.values[rank.ceil() as usize]);
+        Ok(ScalarValue::Float64(Some(lower + (upper - lower) * rank.fract())))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.values.capacity() * std::mem::size_of::<f64>()
+    }
+}
+
+/// `mode(value)` for a value of any type
+#[derive(Debug)]
+struct Mode {
+    signature: Signature,
+}
+
+impl Mode {
+    fn new() -> Self {
+        Self { signature: Signature::any(1, Volatility::Immutable) }
+    }
+}
+
+impl AggregateUDFImpl for Mode {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "mode"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        Ok(arg_types[0].clone())
+    }
+
+    fn accumulator(&self, acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ModeAccumulator {
+            data_type: acc_args.input_type.clone(),
+            counts: HashMap::new(),
+        }))
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(vec![
+            Field::new_list("values", Field::new("item", args.input_type.clone(), true), true),
+            Field::new_list("counts", Field::new("item", DataType::Int64, true), true),
+        ])
+    }
+}
+
+/// Counts every distinct non-NULL value
+#[derive(Debug)]
+struct ModeAccumulator {
+    data_type: DataType,
+    counts: HashMap<ScalarValue, i64>,
+}
+
+impl ModeAccumulator {
+    fn add(&mut self, values: &ArrayRef, counts: Option<&Int64Array>) -> Result<()> {
+        for i in 0..values.len() {
+            if values.is_null(i) {
+                continue;
+            }
+            let count = counts.map_or(1, |counts| counts.value(i));
+            *self.counts.entry(ScalarValue::try_from_array(values, i)?).or_default() += count;
+        }
+        Ok(())
+    }
+}
+
+impl Accumulator for ModeAccumulator {
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        self.add(&values[0], None)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        let counts = states[1].as_list::<i32>();
+        for (i, values) in states[0].as_list::<i32>().iter().enumerate() {
+            if let Some(values) = values {
+                let counts = counts.value(i);
+                self.add(&values, Some(counts.as_primitive::<Int64Type>()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let (values, counts): (Vec<ScalarValue>, Vec<i64>) = self.counts.iter()
+            .map(|(value, count)| (value.clone(), *count))
+            .unzip();
+        Ok(vec![
+            ScalarValue::List(ScalarValue::new_list(&values, &self.data_type)),
+            ScalarValue::List(Arc::new(array_into_list_array(Arc::new(Int64Array::from(counts))))),
+        ])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let mode = self.counts.iter()
+            .max_by(|(a, a_count), (b, b_count)| {
+                // Higher count wins; on a tie the smaller value wins
+                a_count.cmp(b_count).then_with(|| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal))
+            })
+            .map(|(value, _)| value.clone());
+        match mode {
+            Some(value) => Ok(value),
+            None => ScalarValue::try_from(&self.data_type),
+        }
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.counts.keys().map(|value| value.size()).sum::<usize>()
+            + self.counts.len() * std::mem::size_of::<i64>()
+    }
+}
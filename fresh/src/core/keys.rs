@@ -0,0 +1,79 @@
+//! Primary key and unique constraint declarations.
+//!
+//! DataFusion does not enforce constraints, so declared keys are checked with
+//! queries whenever a table that has them is registered with new rows.
+
+use serde::{Deserialize, Serialize};
+use crate::core::database::{quote_identifier, Database};
+use crate::core::error::{FreshError, Result};
+
+/// Keys declared on one table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableKeys {
+    /// Columns whose values identify each row; none of them may be NULL
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub primary_key: Vec<String>,
+    /// Column sets whose values may not repeat (rows with a NULL in the set are ignored)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unique: Vec<Vec<String>>,
+}
+
+impl TableKeys {
+    pub fn is_empty(&self) -> bool {
+        self.primary_key.is_empty() && self.unique.is_empty()
+    }
+
+    /// Columns that identify a row: the primary key, or else the first unique constraint
+    pub fn match_key(&self) -> &[String] {
+        if !self.primary_key.is_empty() {
+            return &self.primary_key;
+        }
+        self.unique.first().map(Vec::as_slice).unwrap_or_default()
+    }
+
+    // Every declared column set with its label for errors, and whether NULLs are rejected
+    fn constraints(&self) -> impl Iterator<Item = (&'static str, &[String], bool)> {
+        let primary_key = (!self.primary_key.is_empty())
+            .then_some(("Primary key", self.primary_key.as_slice(), true));
+        primary_key.into_iter()
+            .chain(self.unique.iter().map(|columns| ("Unique constraint", columns.as_slice(), false)))
+    }
+
+    /// Every column a key refers to
+    pub fn columns(&self) -> impl Iterator<Item = &String> {
+        self.primary_key.iter().chain(self.unique.iter().flatten())
+    }
+}
+
+/// Fail unless the rows of `table` satisfy every key: no duplicated key values, and
+/// no NULL in a primary key column
+pub(crate) fn check_keys(db: &Database, table: &str, keys: &TableKeys) -> Result<()> {
+    let source = quote_identifier(table);
+    for (label, columns, not_null) in keys.constraints() {
+        let list: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+        let list = list.join(", ");
+        let any_null: Vec<String> = columns.iter().map(|c| format!("{} IS NULL", quote_identifier(c))).collect();
+        let any_null = any_null.join(" OR ");
+
+        if not_null {
+            let nulls = db.execute_count_query(&format!("SELECT COUNT(*) FROM {} WHERE {}", source, any_null))?;
+            if nulls > 0 {
+                return Err(FreshError::Custom(format!(
+                    "{} ({}) of '{}' is NULL in {} rows",
+                    label, columns.join(", "), table, nulls
+                )));
+            }
+        }
+
+        let duplicated = db.execute_count_query(&format!(
+            "SELECT COUNT(*) FROM (SELECT {list} FROM {source} WHERE NOT ({any_null}) GROUP BY {list} HAVING COUNT(*) > 1)"
+        ))?;
+        if duplicated > 0 {
+            return Err(FreshError::Custom(format!(
+                "{} ({}) of '{}' has {} duplicated values",
+                label, columns.join(", "), table, duplicated
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Lineage of tables materialized from queries: the SQL and parameter values each
+//! was created from, so it can be run again to refresh the table.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::core::params::QueryParameter;
+
+/// The query a materialized table was last built from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableLineage {
+    pub sql: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<QueryParameter>,
+    /// Seconds since the Unix epoch (UTC) when the query last ran
+    pub materialized_at: i64,
+}
+
+impl TableLineage {
+    pub fn new(sql: &str, parameters: &[QueryParameter]) -> Self {
+        let materialized_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Self {
+            sql: sql.trim().trim_end_matches(';').trim().to_string(),
+            parameters: parameters.to_vec(),
+            materialized_at,
+        }
+    }
+
+    /// Time of the last materialization formatted for display, e.g. "2024-03-01 14:05:09 UTC"
+    pub fn materialized_at_display(&self) -> String {
+        chrono::DateTime::from_timestamp(self.materialized_at, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| self.materialized_at.to_string())
+    }
+}
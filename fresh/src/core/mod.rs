@@ -1,12 +1,42 @@
 pub mod database;
 pub mod csv_handler;
+pub mod csv_ingest;
+pub mod datetime;
 pub mod duplicate_detector;
 pub mod error;
+pub mod explain;
+pub mod export;
+pub mod external_table;
+pub mod file_import;
+pub mod functions;
+pub mod keys;
+pub mod lineage;
+pub mod params;
+pub mod profile;
+pub mod quality;
 pub mod query;
+pub mod query_library;
+pub mod script;
+pub mod sql_assist;
 pub mod transformations;
 
-pub use database::{Database, TableInfo};
+pub use database::{CreateTableAs, Database, TableInfo, ViewInfo, ParquetCompression, StatementKind, StatementOutcome};
 pub use csv_handler::{CsvReader, CsvWriter};
+pub use csv_ingest::{CsvBatchStream, CsvIngestOptions, IngestProgress};
+pub use datetime::DateTimeFormat;
 pub use duplicate_detector::{DuplicateDetector, DuplicateDetectionConfig, DuplicateDetectionResult, DuplicateGroup};
-pub use query::{QueryResult, QueryExecutor};
+pub use explain::{PlanNode, QueryPlan};
+pub use export::{BatchExporter, CsvExportOptions, ExportFormat, ExportOptions};
+pub use external_table::{ExternalFormat, ExternalTable, ExternalTableOptions};
+pub use file_import::FileConfig;
+pub use functions::SqlFunction;
+pub use keys::TableKeys;
+pub use lineage::TableLineage;
+pub use params::{ParameterType, QueryParameter};
+pub use profile::{ColumnProfile, HistogramBin, LengthStats, TableProfile, PROFILE_QUANTILES};
+pub use quality::{QualityReport, QualityRule, RuleResult};
+pub use query::{QueryResult, QueryExecutor, RunningQuery};
+pub use query_library::{QueryHistoryEntry, QueryLibrary, SavedQuery};
+pub use script::{ScriptResult, StatementReport, StatementStatus};
+pub use sql_assist::{Completion, CompletionKind, Completions, SqlSchema};
 pub use transformations::{DataTransformer, TransformationType, TransformationConfig}; 
\ No newline at end of file
@@ -0,0 +1,318 @@
+//! Named and positional query parameters.
+//!
+//! Queries may contain `:name`, `$name` or `$1` placeholders. `:name` is rewritten to
+//! `$name` before planning so DataFusion sees only `$` placeholders, and values are bound
+//! as typed scalars through DataFusion's parameter support, never spliced into the SQL.
+//! A parameter's type comes from a `-- @param name type [= default]` comment line, or is
+//! inferred from how the query uses it.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::common::ParamValues;
+use datafusion::scalar::ScalarValue;
+use serde::{Deserialize, Serialize};
+use crate::core::csv_ingest::parse_time_of_day;
+use crate::core::datetime::{self, DEFAULT_TIME_ZONE};
+use crate::core::error::{FreshError, Result};
+use crate::core::Database;
+
+/// Kind of value a parameter holds, which decides its input widget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ParameterType {
+    #[default]
+    Text,
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    DateTime,
+    Time,
+}
+
+impl ParameterType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParameterType::Text => "Text",
+            ParameterType::Integer => "Integer",
+            ParameterType::Float => "Float",
+            ParameterType::Boolean => "Boolean",
+            ParameterType::Date => "Date",
+            ParameterType::DateTime => "Date & Time",
+            ParameterType::Time => "Time",
+        }
+    }
+
+    /// Example input shown in an empty field
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ParameterType::Text => "text",
+            ParameterType::Integer => "42",
+            ParameterType::Float => "3.14",
+            ParameterType::Boolean => "true",
+            ParameterType::Date => "2024-03-01",
+            ParameterType::DateTime => "2024-03-01 14:30:00",
+            ParameterType::Time => "14:30:00",
+        }
+    }
+
+    /// Type named in a `@param` comment, accepting the usual SQL spellings
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "text" | "string" | "varchar" | "char" => Some(ParameterType::Text),
+            "int" | "integer" | "bigint" | "smallint" => Some(ParameterType::Integer),
+            "float" | "double" | "real" | "decimal" | "numeric" => Some(ParameterType::Float),
+            "bool" | "boolean" => Some(ParameterType::Boolean),
+            "date" => Some(ParameterType::Date),
+            "datetime" | "timestamp" | "timestamptz" => Some(ParameterType::DateTime),
+            "time" => Some(ParameterType::Time),
+            _ => None,
+        }
+    }
+
+    /// Parameter type for a value compared with a column of `data_type`
+    pub fn from_data_type(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Boolean => ParameterType::Boolean,
+            DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64
+            | DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => ParameterType::Integer,
+            DataType::Float16 | DataType::Float32 | DataType::Float64
+            | DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => ParameterType::Float,
+            DataType::Date32 | DataType::Date64 => ParameterType::Date,
+            DataType::Timestamp(_, _) => ParameterType::DateTime,
+            DataType::Time32(_) | DataType::Time64(_) => ParameterType::Time,
+            _ => ParameterType::Text,
+        }
+    }
+}
+
+/// A parameter of a query and the value entered for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryParameter {
+    /// Name without the leading `:` or `$`; positional parameters are named "1", "2", ...
+    pub name: String,
+    pub param_type: ParameterType,
+    pub value: String,
+}
+
+impl QueryParameter {
+    pub fn new(name: &str, param_type: ParameterType) -> Self {
+        Self {
+            name: name.to_string(),
+            param_type,
+            value: String::new(),
+        }
+    }
+
+    /// How the parameter is written in SQL, e.g. ":device" or "$1"
+    pub fn label(&self) -> String {
+        if self.name.chars().all(|c| c.is_ascii_digit()) {
+            format!("${}", self.name)
+        } else {
+            format!(":{}", self.name)
+        }
+    }
+
+    /// Check the entered value without binding it
+    pub fn validate(&self) -> Result<()> {
+        self.to_scalar(None).map(|_| ())
+    }
+
+    /// The value as a typed scalar. `inferred` is the type DataFusion expects at the
+    /// placeholder; date-times without an offset are read in its time zone.
+    pub fn to_scalar(&self, inferred: Option<&DataType>) -> Result<ScalarValue> {
+        let value = self.value.trim();
+        if value.is_empty() && self.param_type != ParameterType::Text {
+            return Err(FreshError::Custom(format!("Parameter {} has no value", self.label())));
+        }
+        let invalid = || FreshError::Custom(format!(
+            "Parameter {} is not a valid {}: '{}'",
+            self.label(),
+            self.param_type.name().to_lowercase(),
+            value
+        ));
+
+        let scalar = match self.param_type {
+            ParameterType::Text => ScalarValue::Utf8(Some(self.value.clone())),
+            ParameterType::Integer => ScalarValue::Int64(Some(value.parse().map_err(|_| invalid())?)),
+            ParameterType::Float => ScalarValue::Float64(Some(value.parse().map_err(|_| invalid())?)),
+            ParameterType::Boolean => match value.to_lowercase().as_str() {
+                "true" | "t" | "yes" | "1" => ScalarValue::Boolean(Some(true)),
+                "false" | "f" | "no" | "0" => ScalarValue::Boolean(Some(false)),
+                _ => return Err(invalid()),
+            },
+            ParameterType::Date => ScalarValue::Date32(Some(Database::parse_date_string(value).ok_or_else(invalid)?)),
+            ParameterType::Time => ScalarValue::Time64Microsecond(Some(
+                parse_time_of_day(value, &TimeUnit::Microsecond).ok_or_else(invalid)?,
+            )),
+            ParameterType::DateTime => {
+                let (unit, zone) = match inferred {
+                    Some(DataType::Timestamp(unit, zone)) => (*unit, zone.clone()),
+                    _ => (TimeUnit::Microsecond, Some(Arc::from(DEFAULT_TIME_ZONE))),
+                };
+                let tz = datetime::parse_time_zone(zone.as_deref().unwrap_or(DEFAULT_TIME_ZONE))?;
+                let timestamp = datetime::parse_timestamp(value, &unit, &tz).ok_or_else(invalid)?;
+                match unit {
+                    TimeUnit::Second => ScalarValue::TimestampSecond(Some(timestamp), zone),
+                    TimeUnit::Millisecond => ScalarValue::TimestampMillisecond(Some(timestamp), zone),
+                    TimeUnit::Microsecond => ScalarValue::TimestampMicrosecond(Some(timestamp), zone),
+                    TimeUnit::Nanosecond => ScalarValue::TimestampNanosecond(Some(timestamp), zone),
+                }
+            }
+        };
+
+        // Match the placeholder's type exactly when the value allows it
+        match inferred {
+            Some(target) if *target != scalar.data_type() => Ok(scalar.cast_to(target).unwrap_or(scalar)),
+            _ => Ok(scalar),
+        }
+    }
+}
+
+/// Names of the parameters in `sql`, in order of first use
+pub fn parameter_names(sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in scan_parameters(sql) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Parameters declared in `-- @param name type [= default]` comment lines
+pub fn declared_parameters(sql: &str) -> Vec<QueryParameter> {
+    sql.lines()
+        .filter_map(|line| line.trim().strip_prefix("--")?.trim().strip_prefix("@param"))
+        .filter_map(|declaration| {
+            let (declaration, default) = match declaration.split_once('=') {
+                Some((declaration, default)) => (declaration, Some(default.trim().trim_matches('\''))),
+                None => (declaration, None),
+            };
+            let mut words = declaration.split_whitespace();
+            let name = words.next()?.trim_start_matches([':', '$']).trim_end_matches(':');
+            let param_type = ParameterType::parse(words.next()?.trim_end_matches(','))?;
+            if name.is_empty() {
+                return None;
+            }
+            let mut parameter = QueryParameter::new(name, param_type);
+            parameter.value = default.unwrap_or_default().to_string();
+            Some(parameter)
+        })
+        .collect()
+}
+
+/// The parameters of `sql` with their types: declared in a comment, else inferred by
+/// DataFusion from the query (`inferred` is keyed by placeholder id, e.g. "$device"),
+/// else kept from `previous`. Values already entered in `previous` are kept.
+pub fn resolve_parameters(
+    sql: &str,
+    inferred: &HashMap<String, Option<DataType>>,
+    previous: &[QueryParameter],
+) -> Vec<QueryParameter> {
+    let declared = declared_parameters(sql);
+    parameter_names(sql).iter()
+        .map(|name| {
+            let declaration = declared.iter().find(|p| &p.name == name);
+            let earlier = previous.iter().find(|p| &p.name == name);
+            let param_type = declaration.map(|p| p.param_type)
+                .or_else(|| inferred.get(&format!("${}", name)).cloned().flatten()
+                    .map(|data_type| ParameterType::from_data_type(&data_type)))
+                .or_else(|| earlier.map(|p| p.param_type))
+                .unwrap_or_default();
+            let mut parameter = QueryParameter::new(name, param_type);
+            parameter.value = earlier.map(|p| p.value.clone())
+                .filter(|value| !value.is_empty())
+                .or_else(|| declaration.map(|p| p.value.clone()))
+                .unwrap_or_default();
+            parameter
+        })
+        .collect()
+}
+
+/// `sql` with every `:name` parameter written as `$name`, the form DataFusion plans
+pub fn prepare_sql(sql: &str) -> String {
+    let mut prepared = String::with_capacity(sql.len());
+    let mut copied = 0;
+    for (range, _) in scan_parameters(sql) {
+        if sql.as_bytes()[range.start] == b':' {
+            prepared.push_str(&sql[copied..range.start]);
+            prepared.push('$');
+            copied = range.start + 1;
+        }
+    }
+    prepared.push_str(&sql[copied..]);
+    prepared
+}
+
+/// Values keyed the way DataFusion looks them up: "$device" → "device", "$1" → "1"
+pub(crate) fn param_values(
+    parameters: &[QueryParameter],
+    inferred: &HashMap<String, Option<DataType>>,
+) -> Result<ParamValues> {
+    let values = parameters.iter()
+        .map(|p| {
+            let target = inferred.get(&format!("${}", p.name)).and_then(|t| t.as_ref());
+            Ok((p.name.clone(), p.to_scalar(target)?))
+        })
+        .collect::<Result<HashMap<String, ScalarValue>>>()?;
+    Ok(ParamValues::Map(values))
+}
+
+/// Byte ranges and names of the parameters in `sql`, skipping string literals,
+/// quoted identifiers, comments and `::` casts
+fn scan_parameters(sql: &str) -> Vec<(Range<usize>, String)> {
+    let bytes = sql.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut found = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == quote {
+                        // A doubled quote is an escaped quote
+                        if bytes.get(i + 1) == Some(&quote) {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'-' if next == Some(b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if next == Some(b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b':' if next == Some(b':') => i += 2,
+            sigil @ (b':' | b'$') => {
+                let start = i;
+                let mut end = i + 1;
+                let positional = sigil == b'$' && next.is_some_and(|b| b.is_ascii_digit());
+                if positional {
+                    while end < bytes.len() && bytes[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                } else if next.is_some_and(|b| b.is_ascii_alphabetic() || b == b'_') {
+                    while end < bytes.len() && is_word(bytes[end]) {
+                        end += 1;
+                    }
+                }
+                if end > start + 1 && (start == 0 || !is_word(bytes[start - 1])) {
+                    found.push((start..end, sql[start + 1..end].to_string()));
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    found
+}
@@ -0,0 +1,336 @@
+//! Column profiles: null and distinct counts, ranges, moments, quantiles, frequent
+//! values and histograms for every column of a table.
+//!
+//! The summary statistics and quantiles of all columns come from one aggregate query.
+//! The top values and histogram bins of all columns come from a second query that
+//! groups by every column at once with GROUPING SETS, so the table is scanned twice
+//! however many columns it has.
+
+use std::time::{Duration, Instant};
+use datafusion::arrow::array::{Array, ArrayRef, AsArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Float64Type, Int64Type, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use crate::core::database::{quote_identifier, Database};
+use crate::core::datetime::DateTimeFormat;
+use crate::core::error::{FreshError, Result};
+
+/// Quantiles reported for numeric columns (estimated with a t-digest)
+pub const PROFILE_QUANTILES: [f64; 5] = [0.05, 0.25, 0.5, 0.75, 0.95];
+/// Number of most frequent values kept per column
+const TOP_VALUES: usize = 10;
+const HISTOGRAM_BINS: usize = 20;
+/// Up to this many rows distinct values are counted exactly; larger tables use HyperLogLog
+const EXACT_DISTINCT_MAX_ROWS: usize = 1_000_000;
+
+/// Profile of every column of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableProfile {
+    pub table: String,
+    pub row_count: usize,
+    pub columns: Vec<ColumnProfile>,
+    /// Time taken to compute the profile
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub data_type: DataType,
+    pub null_count: usize,
+    /// Distinct non-null values; `None` for types that cannot be compared (lists, structs)
+    pub distinct_count: Option<usize>,
+    /// False when `distinct_count` is a HyperLogLog estimate
+    pub distinct_exact: bool,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Numeric columns only
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+    /// `(fraction, value)` for each of `PROFILE_QUANTILES`; numeric columns only
+    pub quantiles: Vec<(f64, f64)>,
+    /// Most frequent non-null values with their counts, most frequent first
+    pub top_values: Vec<(String, usize)>,
+    /// Character lengths of text columns
+    pub length: Option<LengthStats>,
+    /// Equal-width bins between `min` and `max`; numeric columns only
+    pub histogram: Vec<HistogramBin>,
+}
+
+impl ColumnProfile {
+    /// Share of rows that are NULL, between 0 and 1
+    pub fn null_fraction(&self, row_count: usize) -> f64 {
+        if row_count == 0 {
+            0.0
+        } else {
+            self.null_count as f64 / row_count as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthStats {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistogramBin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+// Positions of one column's statistics in the aggregate query
+#[derive(Default)]
+struct StatColumns {
+    non_null: usize,
+    distinct: Option<usize>,
+    min: Option<usize>,
+    max: Option<usize>,
+    mean: Option<usize>,
+    stddev: Option<usize>,
+    quantiles: Option<usize>,
+    length: Option<usize>,
+}
+
+// A grouping set of the frequency query: the values of a column, or the histogram bins
+// of a numeric one
+enum Grouping {
+    Values(usize),
+    Bins { column: usize, min: f64, width: f64 },
+}
+
+/// Compute the profile of a table, view or attached table
+pub(crate) fn profile_table(db: &Database, table: &str) -> Result<TableProfile> {
+    let started = Instant::now();
+    let source = quote_identifier(table);
+    let schema = db.execute_query_batches(&format!("SELECT * FROM {} LIMIT 0", source))?[0].schema();
+    let row_count = db.execute_count_query(&format!("SELECT COUNT(*) FROM {}", source))?.max(0) as usize;
+    let distinct_exact = row_count <= EXACT_DISTINCT_MAX_ROWS;
+
+    let mut select = Vec::new();
+    let mut push = |expression: String| {
+        select.push(expression);
+        select.len() - 1
+    };
+    let plans: Vec<StatColumns> = schema.fields().iter()
+        .map(|field| {
+            let column = quote_identifier(field.name());
+            let data_type = field.data_type();
+            let mut plan = StatColumns {
+                non_null: push(format!("COUNT({})", column)),
+                ..StatColumns::default()
+            };
+            if is_orderable(data_type) {
+                plan.distinct = Some(push(if distinct_exact {
+                    format!("COUNT(DISTINCT {})", column)
+                } else {
+                    format!("APPROX_DISTINCT({})", column)
+                }));
+                plan.min = Some(push(format!("MIN({})", column)));
+                plan.max = Some(push(format!("MAX({})", column)));
+            }
+            if data_type.is_numeric() {
+                let value = format!("CAST({} AS DOUBLE)", column);
+                plan.mean = Some(push(format!("AVG({})", value)));
+                plan.stddev = Some(push(format!("STDDEV({})", value)));
+                // APPROX_PERCENTILE_CONT fails on a table without rows
+                if row_count > 0 {
+                    let positions: Vec<usize> = PROFILE_QUANTILES.iter()
+                        .map(|quantile| push(format!("APPROX_PERCENTILE_CONT({}, {})", value, quantile)))
+                        .collect();
+                    plan.quantiles = positions.first().copied();
+                }
+            }
+            if is_text(data_type) {
+                let length = format!("CHARACTER_LENGTH({})", column);
+                plan.length = Some(push(format!("MIN({})", length)));
+                push(format!("MAX({})", length));
+                push(format!("AVG(CAST({} AS DOUBLE))", length));
+            }
+            plan
+        })
+        .collect();
+
+    let mut columns = Vec::with_capacity(plans.len());
+    if plans.is_empty() {
+        return Ok(TableProfile { table: table.to_string(), row_count, columns, elapsed: started.elapsed() });
+    }
+
+    let stats = db.execute_query_batches(&format!("SELECT {} FROM {}", select.join(", "), source))?;
+    let stats = stats.iter().find(|batch| batch.num_rows() > 0)
+        .ok_or_else(|| FreshError::Custom(format!("Profiling '{}' returned no statistics", table)))?;
+
+    let format = db.datetime_format();
+    let mut groupings = Vec::new();
+    for (field, plan) in schema.fields().iter().zip(&plans) {
+        let non_null = int_value(stats, plan.non_null)?.unwrap_or(0);
+        let mut profile = ColumnProfile {
+            name: field.name().clone(),
+            data_type: field.data_type().clone(),
+            null_count: row_count.saturating_sub(non_null),
+            distinct_count: plan.distinct.map(|i| int_value(stats, i)).transpose()?.flatten(),
+            distinct_exact,
+            min: plan.min.map(|i| string_value(stats, i, &format)).transpose()?.flatten(),
+            max: plan.max.map(|i| string_value(stats, i, &format)).transpose()?.flatten(),
+            mean: plan.mean.map(|i| float_value(stats, i)).transpose()?.flatten(),
+            stddev: plan.stddev.map(|i| float_value(stats, i)).transpose()?.flatten(),
+            quantiles: Vec::new(),
+            top_values: Vec::new(),
+            length: None,
+            histogram: Vec::new(),
+        };
+
+        // Without values the quantiles are meaningless zeros
+        if let Some(first) = plan.quantiles.filter(|_| non_null > 0) {
+            for (i, quantile) in PROFILE_QUANTILES.iter().enumerate() {
+                if let Some(value) = float_value(stats, first + i)? {
+                    profile.quantiles.push((*quantile, value));
+                }
+            }
+        }
+        if let Some(first) = plan.length {
+            if let (Some(min), Some(max), Some(mean)) = (
+                int_value(stats, first)?,
+                int_value(stats, first + 1)?,
+                float_value(stats, first + 2)?,
+            ) {
+                profile.length = Some(LengthStats { min, max, mean });
+            }
+        }
+        if non_null > 0 && is_orderable(field.data_type()) {
+            groupings.push(Grouping::Values(columns.len()));
+        }
+        if let (Some(min), Some(max)) = (
+            plan.min.filter(|_| field.data_type().is_numeric()).map(|i| float_value(stats, i)).transpose()?.flatten(),
+            plan.max.filter(|_| field.data_type().is_numeric()).map(|i| float_value(stats, i)).transpose()?.flatten(),
+        ) {
+            if min == max {
+                profile.histogram = vec![HistogramBin { start: min, end: max, count: non_null }];
+            } else if min.is_finite() && max.is_finite() {
+                let width = (max - min) / HISTOGRAM_BINS as f64;
+                profile.histogram = (0..HISTOGRAM_BINS)
+                    .map(|i| HistogramBin {
+                        start: min + width * i as f64,
+                        end: if i == HISTOGRAM_BINS - 1 { max } else { min + width * (i + 1) as f64 },
+                        count: 0,
+                    })
+                    .collect();
+                groupings.push(Grouping::Bins { column: columns.len(), min, width });
+            }
+        }
+        columns.push(profile);
+    }
+
+    if !groupings.is_empty() {
+        count_frequencies(db, &source, &schema, &groupings, &mut columns)?;
+    }
+
+    Ok(TableProfile { table: table.to_string(), row_count, columns, elapsed: started.elapsed() })
+}
+
+// Fill in the top values and histogram counts of every grouping with one query. Each
+// grouping set is its own partition of ranked groups; in its rows only that set's
+// column is not NULL.
+fn count_frequencies(db: &Database, source: &str, schema: &SchemaRef, groupings: &[Grouping], columns: &mut [ColumnProfile]) -> Result<()> {
+    let last = HISTOGRAM_BINS - 1;
+    let groups: Vec<String> = (0..groupings.len()).map(|i| format!("g{}", i)).collect();
+    let expressions: Vec<String> = groupings.iter().zip(&groups)
+        .map(|(grouping, group)| match grouping {
+            Grouping::Values(column) => format!("{} AS {}", quote_identifier(schema.field(*column).name()), group),
+            // Bin index of every value; the maximum falls into the last bin
+            Grouping::Bins { column, min, width } => {
+                let column = quote_identifier(schema.field(*column).name());
+                let bin = format!(
+                    "CAST(FLOOR((CAST({column} AS DOUBLE) - CAST('{min}' AS DOUBLE)) / CAST('{width}' AS DOUBLE)) AS BIGINT)"
+                );
+                format!("CASE WHEN {bin} > {last} THEN {last} ELSE {bin} END AS {group}")
+            }
+        })
+        .collect();
+    let partition: Vec<String> = groups.iter().map(|group| format!("{} IS NULL", group)).collect();
+    let sets: Vec<String> = groups.iter().map(|group| format!("({})", group)).collect();
+    // Enough ranks for every histogram bin; top values are cut to TOP_VALUES below
+    let ranks = TOP_VALUES.max(HISTOGRAM_BINS);
+    let query = format!(
+        "SELECT * FROM (SELECT {groups}, COUNT(*) AS frequency, \
+         ROW_NUMBER() OVER (PARTITION BY {partition} ORDER BY COUNT(*) DESC, {groups}) AS position \
+         FROM (SELECT {expressions} FROM {source}) GROUP BY GROUPING SETS ({sets})) \
+         WHERE position <= {ranks}",
+        groups = groups.join(", "),
+        partition = partition.join(", "),
+        expressions = expressions.join(", "),
+        sets = sets.join(", "),
+    );
+
+    let format = db.datetime_format();
+    let mut top_values = Vec::new();
+    for batch in db.execute_query_batches(&query)? {
+        for row in 0..batch.num_rows() {
+            // Rows counting NULLs have no grouping column set
+            let Some(group) = (0..groupings.len()).find(|&group| batch.column(group).is_valid(row)) else {
+                continue;
+            };
+            let count = int_value_at(&batch, groupings.len(), row)?.unwrap_or(0);
+            let position = int_value_at(&batch, groupings.len() + 1, row)?.unwrap_or(0);
+            match groupings[group] {
+                Grouping::Values(column) if position <= TOP_VALUES => {
+                    let value = Database::array_value_to_string(batch.column(group), row, &format)?;
+                    top_values.push((column, position, value, count));
+                }
+                Grouping::Values(_) => {}
+                Grouping::Bins { column, .. } => {
+                    if let Some(bin) = int_value_at(&batch, group, row)? {
+                        columns[column].histogram[bin.min(last)].count += count;
+                    }
+                }
+            }
+        }
+    }
+
+    top_values.sort_by_key(|(column, position, _, _)| (*column, *position));
+    for (column, _, value, count) in top_values {
+        columns[column].top_values.push((value, count));
+    }
+    Ok(())
+}
+
+// Types MIN, MAX, COUNT(DISTINCT) and GROUP BY work on
+fn is_orderable(data_type: &DataType) -> bool {
+    data_type.is_numeric() || data_type.is_temporal() || is_text(data_type) || matches!(data_type, DataType::Boolean)
+}
+
+fn is_text(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Utf8 | DataType::LargeUtf8)
+}
+
+fn cast_column(batch: &RecordBatch, column: usize, to: &DataType) -> Result<ArrayRef> {
+    cast(batch.column(column), to)
+        .map_err(|e| FreshError::Custom(format!("Failed to read profile statistic: {}", e)))
+}
+
+fn int_value_at(batch: &RecordBatch, column: usize, row: usize) -> Result<Option<usize>> {
+    let values = cast_column(batch, column, &DataType::Int64)?;
+    let values = values.as_primitive::<Int64Type>();
+    Ok((!values.is_null(row)).then(|| values.value(row).max(0) as usize))
+}
+
+fn int_value(batch: &RecordBatch, column: usize) -> Result<Option<usize>> {
+    int_value_at(batch, column, 0)
+}
+
+fn float_value(batch: &RecordBatch, column: usize) -> Result<Option<f64>> {
+    let values = cast_column(batch, column, &DataType::Float64)?;
+    let values = values.as_primitive::<Float64Type>();
+    Ok((!values.is_null(0)).then(|| values.value(0)))
+}
+
+fn string_value(batch: &RecordBatch, column: usize, format: &DateTimeFormat) -> Result<Option<String>> {
+    let values: &ArrayRef = batch.column(column);
+    if values.is_null(0) {
+        return Ok(None);
+    }
+    Database::array_value_to_string(values, 0, format).map(Some)
+}
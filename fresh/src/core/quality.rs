@@ -0,0 +1,200 @@
+//! Declarative data-quality rules and the validator that checks them.
+//!
+//! Every rule becomes a DataFusion query that selects the violating rows, numbered by
+//! their position in the table, so the same query backs the report and the
+//! "table of violations" that can be created from it.
+
+use std::time::{Duration, Instant};
+use datafusion::arrow::array::AsArray;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Int64Type};
+use serde::{Deserialize, Serialize};
+use crate::core::database::{quote_identifier, Database};
+use crate::core::error::{FreshError, Result};
+
+/// Name of the column holding the position of a row in its table
+pub const ROW_INDEX_COLUMN: &str = "row_index";
+
+/// A condition every row of a table must satisfy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QualityRule {
+    NotNull { column: String },
+    /// No value appears in more than one row (NULLs are ignored)
+    Unique { column: String },
+    /// Values lie between `min` and `max` (inclusive); either bound may be open
+    Range { column: String, min: Option<f64>, max: Option<f64> },
+    /// Values, as text, match a regular expression
+    Regex { column: String, pattern: String },
+    /// Values, as text, are one of `values`
+    AllowedValues { column: String, values: Vec<String> },
+    /// Values exist in `referenced_column` of `referenced_table`
+    ForeignKey { column: String, referenced_table: String, referenced_column: String },
+    /// Values never decrease from one row to the next (never stay equal when `strict`)
+    Monotonic { column: String, strict: bool },
+}
+
+impl QualityRule {
+    pub fn column(&self) -> &str {
+        match self {
+            QualityRule::NotNull { column }
+            | QualityRule::Unique { column }
+            | QualityRule::Range { column, .. }
+            | QualityRule::Regex { column, .. }
+            | QualityRule::AllowedValues { column, .. }
+            | QualityRule::ForeignKey { column, .. }
+            | QualityRule::Monotonic { column, .. } => column,
+        }
+    }
+
+    /// Short description for reports, e.g. "amount between 0 and 100"
+    pub fn describe(&self) -> String {
+        match self {
+            QualityRule::NotNull { column } => format!("{} is not null", column),
+            QualityRule::Unique { column } => format!("{} is unique", column),
+            QualityRule::Range { column, min, max } => match (min, max) {
+                (Some(min), Some(max)) => format!("{} between {} and {}", column, min, max),
+                (Some(min), None) => format!("{} >= {}", column, min),
+                (None, Some(max)) => format!("{} <= {}", column, max),
+                (None, None) => format!("{} in any range", column),
+            },
+            QualityRule::Regex { column, pattern } => format!("{} matches /{}/", column, pattern),
+            QualityRule::AllowedValues { column, values } => format!("{} in {{{}}}", column, values.join(", ")),
+            QualityRule::ForeignKey { column, referenced_table, referenced_column } => {
+                format!("{} references {}.{}", column, referenced_table, referenced_column)
+            }
+            QualityRule::Monotonic { column, strict: true } => format!("{} strictly increasing", column),
+            QualityRule::Monotonic { column, strict: false } => format!("{} never decreasing", column),
+        }
+    }
+
+    /// Query returning the row index and every column of the rows of `table` that break the rule
+    pub fn violations_query(&self, table: &str) -> String {
+        let source = quote_identifier(table);
+        let column = quote_identifier(self.column());
+        let text = format!("CAST({} AS VARCHAR)", column);
+
+        let condition = match self {
+            QualityRule::NotNull { .. } => format!("{} IS NULL", column),
+            QualityRule::Unique { .. } => format!(
+                "{column} IN (SELECT {column} FROM {source} WHERE {column} IS NOT NULL GROUP BY {column} HAVING COUNT(*) > 1)"
+            ),
+            QualityRule::Range { min, max, .. } => {
+                let mut outside = Vec::new();
+                if let Some(min) = min {
+                    outside.push(format!("CAST({} AS DOUBLE) < CAST('{}' AS DOUBLE)", column, min));
+                }
+                if let Some(max) = max {
+                    outside.push(format!("CAST({} AS DOUBLE) > CAST('{}' AS DOUBLE)", column, max));
+                }
+                if outside.is_empty() {
+                    "FALSE".to_string()
+                } else {
+                    format!("({})", outside.join(" OR "))
+                }
+            }
+            QualityRule::Regex { pattern, .. } => format!("{} !~ {}", text, quote_literal(pattern)),
+            QualityRule::AllowedValues { values, .. } if values.is_empty() => format!("{} IS NOT NULL", column),
+            QualityRule::AllowedValues { values, .. } => {
+                let values: Vec<String> = values.iter().map(|v| quote_literal(v)).collect();
+                format!("{} NOT IN ({})", text, values.join(", "))
+            }
+            QualityRule::ForeignKey { referenced_table, referenced_column, .. } => {
+                let referenced_column = quote_identifier(referenced_column);
+                format!(
+                    "{column} IS NOT NULL AND {column} NOT IN (SELECT {referenced_column} FROM {} WHERE {referenced_column} IS NOT NULL)",
+                    quote_identifier(referenced_table)
+                )
+            }
+            QualityRule::Monotonic { strict, .. } => {
+                let operator = if *strict { "<=" } else { "<" };
+                format!("{} {} __fresh_previous", column, operator)
+            }
+        };
+
+        // Rows are numbered in scan order, which is insertion order for in-memory tables
+        let previous = match self {
+            QualityRule::Monotonic { .. } => format!(", LAG({}) OVER () AS __fresh_previous", column),
+            _ => String::new(),
+        };
+        let columns = match self {
+            QualityRule::Monotonic { .. } => "* EXCLUDE (__fresh_previous)".to_string(),
+            _ => "*".to_string(),
+        };
+        format!(
+            "SELECT {columns} FROM (SELECT ROW_NUMBER() OVER () - 1 AS {ROW_INDEX_COLUMN}, *{previous} FROM {source}) \
+             WHERE {condition} ORDER BY {ROW_INDEX_COLUMN}"
+        )
+    }
+}
+
+/// Outcome of one rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleResult {
+    pub rule: QualityRule,
+    /// Positions (0-based) of the rows breaking the rule, in table order
+    pub violating_rows: Vec<usize>,
+    /// Set when the rule could not be checked, e.g. because its column no longer exists
+    pub error: Option<String>,
+}
+
+impl RuleResult {
+    pub fn violation_count(&self) -> usize {
+        self.violating_rows.len()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.violating_rows.is_empty()
+    }
+}
+
+/// Outcome of every rule of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityReport {
+    pub table: String,
+    pub results: Vec<RuleResult>,
+    pub elapsed: Duration,
+}
+
+impl QualityReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.passed())
+    }
+
+    /// Total violations over all rules (a row breaking two rules counts twice)
+    pub fn violation_count(&self) -> usize {
+        self.results.iter().map(|result| result.violation_count()).sum()
+    }
+}
+
+/// Check every rule against a table; a rule that fails to run is reported, not returned as an error
+pub(crate) fn validate(db: &Database, table: &str, rules: &[QualityRule]) -> QualityReport {
+    let started = Instant::now();
+    let results = rules.iter()
+        .map(|rule| match violating_rows(db, table, rule) {
+            Ok(violating_rows) => RuleResult { rule: rule.clone(), violating_rows, error: None },
+            Err(e) => RuleResult { rule: rule.clone(), violating_rows: Vec::new(), error: Some(e.to_string()) },
+        })
+        .collect();
+    QualityReport { table: table.to_string(), results, elapsed: started.elapsed() }
+}
+
+fn violating_rows(db: &Database, table: &str, rule: &QualityRule) -> Result<Vec<usize>> {
+    let query = format!(
+        "SELECT {} FROM ({})",
+        ROW_INDEX_COLUMN,
+        rule.violations_query(table)
+    );
+    let mut rows = Vec::new();
+    for batch in db.execute_query_batches(&query)? {
+        let indices = cast(batch.column(0), &DataType::Int64)
+            .map_err(|e| FreshError::Custom(format!("Failed to read violating rows: {}", e)))?;
+        rows.extend(indices.as_primitive::<Int64Type>().iter().flatten().map(|i| i.max(0) as usize));
+    }
+    rows.sort_unstable();
+    Ok(rows)
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
@@ -1,27 +1,38 @@
 use crate::core::{Database, error::{Result, FreshError}};
-use std::sync::Arc;
-use datafusion::arrow::datatypes::DataType;
+use crate::core::csv_ingest::convert_string_column;
+use crate::core::datetime::DateTimeFormat;
+use crate::core::params::{self, QueryParameter};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array, Int64Array, StringArray, UInt32Array};
+use datafusion::arrow::compute::{cast, concat, concat_batches, take_record_batch};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use tokio::task::AbortHandle;
 
 pub struct QueryExecutor;
 
 impl QueryExecutor {
     pub fn execute(db: &Arc<Database>, query: &str) -> Result<QueryResult> {
+        Self::execute_with_params(db, query, &[])
+    }
+    
+    /// Execute a query with values bound to its `:name`, `$name` or `$1` parameters
+    pub fn execute_with_params(db: &Arc<Database>, query: &str, parameters: &[QueryParameter]) -> Result<QueryResult> {
+        let query = Self::prepare(query, parameters);
+        
         // Validate query in read-only mode
         if db.is_readonly() {
-            Self::validate_read_only(query)?;
+            Self::validate_read_only(db, &query)?;
+        }
+        if let Some(statement) = Database::parse_create_table_as(&query) {
+            db.execute_sql(&query)?;
+            return Ok(Self::table_created(&statement.name));
         }
         
-        // Execute the query
-        let rows = db.execute_query(query)?;
-        let columns = db.get_column_names(query)?;
-        let column_types = db.get_column_types(query)?;
-        
-        Ok(QueryResult {
-            columns,
-            column_types,
-            rows,
-            total_rows: None,
-        })
+        // Execute the query once and keep the Arrow batches
+        let batches = db.execute_query_batches_with_params(&query, parameters)?;
+        Ok(QueryResult::from_batches(batches).with_datetime_format(db.datetime_format()))
     }
     
     pub fn execute_with_pagination(
@@ -32,53 +43,34 @@ impl QueryExecutor {
     ) -> Result<QueryResult> {
         // Validate query in read-only mode
         if db.is_readonly() {
-            Self::validate_read_only(query)?;
+            Self::validate_read_only(db, query)?;
+        }
+
+        // CREATE VIEW statements register the view instead of returning rows
+        if let Some((view_name, view_sql)) = Database::parse_create_view(query) {
+            db.create_view(&view_name, &view_sql)?;
+            return Ok(Self::view_created(&view_name));
+        }
+        if let Some(statement) = Database::parse_create_table_as(query) {
+            db.execute_sql(query)?;
+            return Ok(Self::table_created(&statement.name));
         }
 
         // Special case: if the query is a SELECT COUNT(*) query, use only execute_count_query
         let query_trimmed = query.trim().to_uppercase();
         if query_trimmed.starts_with("SELECT COUNT(*)") {
             let count = db.execute_count_query(query)?;
-            return Ok(QueryResult {
-                columns: vec!["COUNT(*)".to_string()],
-                column_types: vec![DataType::Int64],
-                rows: vec![vec![count.to_string()]],
-                total_rows: Some(1),
-            });
+            let mut result = QueryResult::from_rows(
+                vec!["COUNT(*)".to_string()],
+                vec![DataType::Int64],
+                vec![vec![count.to_string()]],
+            );
+            result.total_rows = Some(1);
+            return Ok(result);
         }
         
         // First, get the total count for pagination
-        // For simple SELECT * FROM table queries, we can get count directly
-        let count_query = if query_trimmed.starts_with("SELECT * FROM") {
-            // Extract table name from SELECT * FROM "table_name"
-            if let Some(table_name_part) = query.split("FROM").nth(1) {
-                // Extract just the table name, ignoring any WHERE clause
-                let table_name = if let Some(where_pos) = table_name_part.to_uppercase().find(" WHERE ") {
-                    &table_name_part[..where_pos]
-                } else {
-                    table_name_part
-                };
-                let table_name = table_name.trim().trim_matches('"').trim_matches('\'');
-
-                // Check if table exists
-                match db.table_exists(table_name) {
-                    Ok(exists) => {
-                        if !exists {
-                            return Err(FreshError::Custom(format!("Table '{}' does not exist", table_name)));
-                        }
-                    }
-                    Err(e) => {
-                        return Err(FreshError::Custom(format!("Error checking if table exists: {}", e)));
-                    }
-                }
-
-                format!("SELECT COUNT(*) FROM \"{}\"", table_name)
-            } else {
-                format!("SELECT COUNT(*) FROM ({})", query)
-            }
-        } else {
-            format!("SELECT COUNT(*) FROM ({})", query)
-        };
+        let count_query = Self::count_query(db, query)?;
         let total_rows = db.execute_count_query(&count_query)? as usize;
 
         // Then get the paginated results
@@ -89,29 +81,529 @@ impl QueryExecutor {
         Ok(result)
     }
     
-    pub fn validate_read_only(query: &str) -> Result<()> {
-        let query_upper = query.to_uppercase();
-        let forbidden_keywords = [
-            "INSERT", "UPDATE", "DELETE", "DROP", "CREATE", "ALTER", 
-            "TRUNCATE", "REPLACE", "ATTACH", "DETACH", "PRAGMA"
-        ];
+    /// Start a paginated query in the background. The page's rows and the total
+    /// row count are computed on the database runtime; poll the returned handle.
+    pub fn spawn_with_pagination(
+        db: &Arc<Database>,
+        query: &str,
+        page: usize,
+        page_size: usize,
+    ) -> Result<RunningQuery> {
+        Self::spawn_with_params(db, query, &[], page, page_size)
+    }
+    
+    /// Like [`QueryExecutor::spawn_with_pagination`], binding `parameters` to the
+    /// query's placeholders for both the page and the row count
+    pub fn spawn_with_params(
+        db: &Arc<Database>,
+        query: &str,
+        parameters: &[QueryParameter],
+        page: usize,
+        page_size: usize,
+    ) -> Result<RunningQuery> {
+        let prepared = Self::prepare(query, parameters);
+        let query = prepared.as_str();
         
-        for keyword in &forbidden_keywords {
-            if query_upper.contains(keyword) {
-                return Err(FreshError::Custom(
-                    format!("Query contains forbidden keyword '{}' in read-only mode", keyword)
-                ));
-            }
+        // Validate query in read-only mode
+        if db.is_readonly() {
+            Self::validate_read_only(db, query)?;
+        }
+
+        // CREATE VIEW statements register the view instead of returning rows
+        if let Some((view_name, view_sql)) = Database::parse_create_view(query) {
+            db.create_view(&view_name, &view_sql)?;
+            return Ok(RunningQuery::finished(Self::view_created(&view_name)));
+        }
+        // CREATE TABLE ... AS runs the query to completion and saves the table
+        if let Some(statement) = Database::parse_create_table_as(query) {
+            db.execute_sql(query)?;
+            return Ok(RunningQuery::finished(Self::table_created(&statement.name)));
+        }
+
+        let running = RunningQuery::new(db.datetime_format());
+
+        // A COUNT(*) query is its own total; everything else is counted separately
+        let query_trimmed = query.trim().to_uppercase();
+        let page_query = if query_trimmed.starts_with("SELECT COUNT(*)") {
+            running.progress.lock().unwrap().count_done = true;
+            query.to_string()
+        } else {
+            let count_query = Self::count_query(db, query)?;
+            let progress = running.progress.clone();
+            let done_progress = running.progress.clone();
+            let task = db.spawn_query_with_params(
+                &count_query,
+                parameters,
+                move |batch| {
+                    let count = batch.columns().first()
+                        .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+                        .filter(|c| !c.is_empty() && !c.is_null(0))
+                        .map(|c| c.value(0) as usize);
+                    if let Some(count) = count {
+                        progress.lock().unwrap().total_rows = Some(count);
+                    }
+                },
+                move |result| done_progress.lock().unwrap().finish_count(result.err()),
+            );
+            running.tasks.lock().unwrap().push(task);
+            format!("{} LIMIT {} OFFSET {}", query, page_size, page * page_size)
+        };
+
+        let progress = running.progress.clone();
+        let done_progress = running.progress.clone();
+        let task = db.spawn_query_with_params(
+            &page_query,
+            parameters,
+            move |batch| {
+                let mut progress = progress.lock().unwrap();
+                progress.rows += batch.num_rows();
+                progress.batches.push(batch);
+            },
+            move |result| done_progress.lock().unwrap().finish_page(result),
+        );
+        running.tasks.lock().unwrap().push(task);
+
+        Ok(running)
+    }
+
+    // Parameterized queries are planned with `$name` placeholders only
+    fn prepare(query: &str, parameters: &[QueryParameter]) -> String {
+        if parameters.is_empty() {
+            query.to_string()
+        } else {
+            params::prepare_sql(query)
+        }
+    }
+
+    fn view_created(view_name: &str) -> QueryResult {
+        Self::status_result(format!("View '{}' created", view_name))
+    }
+
+    fn table_created(table_name: &str) -> QueryResult {
+        Self::status_result(format!("Table '{}' created", table_name))
+    }
+
+    // One-cell result reporting what a statement did
+    fn status_result(message: String) -> QueryResult {
+        let mut result = QueryResult::from_rows(
+            vec!["Result".to_string()],
+            vec![DataType::Utf8],
+            vec![vec![message]],
+        );
+        result.total_rows = Some(1);
+        result
+    }
+
+    /// Query that counts the rows `query` returns
+    fn count_query(db: &Database, query: &str) -> Result<String> {
+        let query_trimmed = query.trim().to_uppercase();
+        
+        // For simple SELECT * FROM table queries, we can get count directly
+        if !query_trimmed.starts_with("SELECT * FROM") {
+            return Ok(format!("SELECT COUNT(*) FROM ({})", query));
+        }
+        
+        // Extract table name from SELECT * FROM "table_name"
+        let Some(table_name_part) = query.split("FROM").nth(1) else {
+            return Ok(format!("SELECT COUNT(*) FROM ({})", query));
+        };
+        
+        // Extract just the table name, ignoring any WHERE clause
+        let table_name = if let Some(where_pos) = table_name_part.to_uppercase().find(" WHERE ") {
+            &table_name_part[..where_pos]
+        } else {
+            table_name_part
+        };
+        let table_name = table_name.trim().trim_matches('"').trim_matches('\'');
+
+        // Check if table exists
+        match db.table_exists(table_name) {
+            Ok(true) => Ok(format!("SELECT COUNT(*) FROM \"{}\"", table_name)),
+            Ok(false) => Err(FreshError::Custom(format!("Table '{}' does not exist", table_name))),
+            Err(e) => Err(FreshError::Custom(format!("Error checking if table exists: {}", e))),
+        }
+    }
+    
+    /// Reject anything but plain queries, based on the statement's logical plan
+    /// rather than keywords, so identifiers such as `created_date` are allowed
+    pub fn validate_read_only(db: &Database, query: &str) -> Result<()> {
+        let kind = db.classify_statement(query)?;
+        if !kind.is_read_only() {
+            return Err(FreshError::Custom(
+                format!("{} is not allowed in read-only mode", kind.description())
+            ));
         }
         
         Ok(())
     }
 }
 
+/// Result of a query, backed by the Arrow batches DataFusion produced.
+/// Values are only formatted as strings when something displays them.
 #[derive(Debug, Clone)]
 pub struct QueryResult {
     pub columns: Vec<String>,
-    pub column_types: Vec<DataType>,  // Add column type information
-    pub rows: Vec<Vec<String>>, 
+    pub column_types: Vec<DataType>,
+    pub batches: Vec<RecordBatch>,
     pub total_rows: Option<usize>,
-} 
\ No newline at end of file
+    // Display strings, formatted on first use
+    display_rows: OnceLock<Vec<Vec<String>>>,
+    // How date-times are formatted into the display strings
+    datetime_format: DateTimeFormat,
+}
+
+impl QueryResult {
+    /// Build a result from query output; columns and types come from the batch schema
+    pub fn from_batches(batches: Vec<RecordBatch>) -> Self {
+        let (columns, column_types) = match batches.first() {
+            Some(batch) => batch.schema().fields().iter()
+                .map(|field| (field.name().clone(), field.data_type().clone()))
+                .unzip(),
+            None => (Vec::new(), Vec::new()),
+        };
+
+        Self {
+            columns,
+            column_types,
+            batches,
+            total_rows: None,
+            display_rows: OnceLock::new(),
+            datetime_format: DateTimeFormat::default(),
+        }
+    }
+
+    /// Build a result from display strings, parsing each column into its declared type
+    pub fn from_rows(columns: Vec<String>, column_types: Vec<DataType>, rows: Vec<Vec<String>>) -> Self {
+        let fields: Vec<Field> = columns.iter()
+            .zip(column_types.iter())
+            .map(|(name, data_type)| Field::new(name, data_type.clone(), true))
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+
+        let arrays: Option<Vec<ArrayRef>> = column_types.iter().enumerate()
+            .map(|(col_idx, data_type)| {
+                let values: StringArray = rows.iter()
+                    .map(|row| row.get(col_idx).map(|v| v.as_str()))
+                    .collect();
+                convert_string_column(&values, data_type, &[]).ok()
+            })
+            .collect();
+        let batches = arrays
+            .and_then(|arrays| RecordBatch::try_new(schema.clone(), arrays).ok())
+            .map(|batch| vec![batch])
+            .unwrap_or_else(|| vec![RecordBatch::new_empty(schema)]);
+
+        Self {
+            columns,
+            column_types,
+            batches,
+            total_rows: None,
+            display_rows: OnceLock::from(rows),
+            datetime_format: DateTimeFormat::default(),
+        }
+    }
+
+    /// Show date-times in `format`, usually the format of the database queried
+    pub fn with_datetime_format(mut self, format: DateTimeFormat) -> Self {
+        self.datetime_format = format;
+        // Rows formatted before are formatted again
+        if self.batches.iter().any(|b| b.num_rows() > 0) {
+            self.display_rows = OnceLock::new();
+        }
+        self
+    }
+
+    /// Number of rows held in this result (not the total across pages)
+    pub fn num_rows(&self) -> usize {
+        match self.display_rows.get() {
+            Some(rows) if self.batches.iter().all(|b| b.num_rows() == 0) => rows.len(),
+            _ => self.batches.iter().map(|b| b.num_rows()).sum(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_rows() == 0
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c == name)
+    }
+
+    /// Whole column as one Arrow array
+    pub fn column(&self, index: usize) -> Option<ArrayRef> {
+        let arrays: Vec<&dyn Array> = self.batches.iter()
+            .filter_map(|b| b.columns().get(index).map(|a| a.as_ref()))
+            .collect();
+        match arrays.len() {
+            0 => None,
+            1 => Some(self.batches[0].column(index).clone()),
+            _ => concat(&arrays).ok(),
+        }
+    }
+
+    /// Column values as numbers. Temporal columns yield their raw value in the
+    /// column's own unit (days for dates, the time unit for timestamps);
+    /// NULLs and unparsable text are `None`.
+    pub fn f64_column(&self, index: usize) -> Vec<Option<f64>> {
+        let mut values = Vec::with_capacity(self.num_rows());
+        for batch in &self.batches {
+            let Some(array) = batch.columns().get(index) else {
+                continue;
+            };
+            match array_to_f64(array) {
+                Some(numbers) => values.extend(numbers.iter()),
+                None => {
+                    for row in 0..array.len() {
+                        values.push(if array.is_null(row) {
+                            None
+                        } else {
+                            Database::array_value_to_string(array, row, &self.datetime_format).ok()
+                                .and_then(|v| v.trim().parse::<f64>().ok())
+                        });
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    /// Column values formatted for display; NULLs are `None`
+    pub fn string_column(&self, index: usize) -> Vec<Option<String>> {
+        let mut values = Vec::with_capacity(self.num_rows());
+        for batch in &self.batches {
+            let Some(array) = batch.columns().get(index) else {
+                continue;
+            };
+            for row in 0..array.len() {
+                values.push(if array.is_null(row) {
+                    None
+                } else {
+                    Database::array_value_to_string(array, row, &self.datetime_format).ok()
+                });
+            }
+        }
+        values
+    }
+
+    /// Display string for a single cell
+    pub fn display_value(&self, row: usize, column: usize) -> String {
+        if let Some(rows) = self.display_rows.get() {
+            return rows.get(row).and_then(|r| r.get(column)).cloned().unwrap_or_default();
+        }
+
+        let mut offset = row;
+        for batch in &self.batches {
+            if offset < batch.num_rows() {
+                return batch.columns().get(column)
+                    .and_then(|array| Database::array_value_to_string(array, offset, &self.datetime_format).ok())
+                    .unwrap_or_default();
+            }
+            offset -= batch.num_rows();
+        }
+        String::new()
+    }
+
+    /// Whether a cell is NULL (display strings render NULL as empty text)
+    pub fn is_null(&self, row: usize, column: usize) -> bool {
+        let mut offset = row;
+        for batch in &self.batches {
+            if offset < batch.num_rows() {
+                return batch.columns().get(column).is_some_and(|array| array.is_null(offset));
+            }
+            offset -= batch.num_rows();
+        }
+        false
+    }
+
+    /// Every row formatted as display strings (computed once, on first use)
+    pub fn rows(&self) -> &[Vec<String>] {
+        self.display_rows.get_or_init(|| {
+            let mut rows = Vec::with_capacity(self.num_rows());
+            for batch in &self.batches {
+                match Database::record_batch_to_rows(batch, &self.datetime_format) {
+                    Ok(batch_rows) => rows.extend(batch_rows),
+                    Err(e) => eprintln!("[QueryResult] Failed to format rows: {}", e),
+                }
+            }
+            rows
+        })
+    }
+
+    /// Every `step`-th row as a new result
+    pub fn sample_every(&self, step: usize) -> Result<QueryResult> {
+        let batch = self.to_record_batch()?;
+        let indices: UInt32Array = (0..batch.num_rows())
+            .step_by(step.max(1))
+            .map(|i| i as u32)
+            .collect();
+        let mut result = QueryResult::from_batches(vec![take_record_batch(&batch, &indices)?])
+            .with_datetime_format(self.datetime_format.clone());
+        result.total_rows = Some(result.num_rows());
+        Ok(result)
+    }
+
+    /// Rows `page * page_size` up to the next page as a new result; `total_rows` keeps
+    /// the size of the whole result
+    pub fn page(&self, page: usize, page_size: usize) -> Result<QueryResult> {
+        let batch = self.to_record_batch()?;
+        let offset = (page * page_size).min(batch.num_rows());
+        let length = page_size.min(batch.num_rows() - offset);
+        let mut result = QueryResult::from_batches(vec![batch.slice(offset, length)])
+            .with_datetime_format(self.datetime_format.clone());
+        result.total_rows = Some(self.total_rows.unwrap_or(batch.num_rows()));
+        Ok(result)
+    }
+
+    /// All batches combined into one
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        match self.batches.first() {
+            Some(first) => Ok(concat_batches(&first.schema(), &self.batches)?),
+            None => Ok(RecordBatch::new_empty(Arc::new(Schema::empty()))),
+        }
+    }
+}
+
+/// A query running on the database runtime. Dropping it cancels the query.
+pub struct RunningQuery {
+    progress: Arc<Mutex<QueryProgress>>,
+    tasks: Mutex<Vec<AbortHandle>>,
+    started: Instant,
+    datetime_format: DateTimeFormat,
+}
+
+#[derive(Default)]
+struct QueryProgress {
+    batches: Vec<RecordBatch>,
+    rows: usize,
+    schema: Option<SchemaRef>,
+    total_rows: Option<usize>,
+    page_done: bool,
+    count_done: bool,
+    error: Option<String>,
+    elapsed: Option<Duration>,
+    result: Option<QueryResult>,
+}
+
+impl QueryProgress {
+    fn finish_page(&mut self, result: Result<SchemaRef>) {
+        match result {
+            Ok(schema) => self.schema = Some(schema),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.page_done = true;
+    }
+
+    fn finish_count(&mut self, error: Option<FreshError>) {
+        if let Some(e) = error {
+            self.error = Some(e.to_string());
+        }
+        self.count_done = true;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.error.is_some() || (self.page_done && self.count_done)
+    }
+}
+
+impl RunningQuery {
+    fn new(datetime_format: DateTimeFormat) -> Self {
+        Self {
+            progress: Arc::new(Mutex::new(QueryProgress::default())),
+            tasks: Mutex::new(Vec::new()),
+            started: Instant::now(),
+            datetime_format,
+        }
+    }
+
+    /// A query that completed without running anything in the background
+    pub fn finished(result: QueryResult) -> Self {
+        let running = Self::new(result.datetime_format.clone());
+        {
+            let mut progress = running.progress.lock().unwrap();
+            progress.rows = result.num_rows();
+            progress.page_done = true;
+            progress.count_done = true;
+            progress.elapsed = Some(Duration::ZERO);
+            progress.result = Some(result);
+        }
+        running
+    }
+
+    /// Rows received so far for the current page
+    pub fn rows_received(&self) -> usize {
+        self.progress.lock().unwrap().rows
+    }
+
+    /// Time since the query started, frozen once it finishes
+    pub fn elapsed(&self) -> Duration {
+        self.progress.lock().unwrap().elapsed.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress.lock().unwrap().is_finished()
+    }
+
+    /// Abort the DataFusion streams backing this query
+    pub fn cancel(&self) {
+        self.abort_tasks();
+        let mut progress = self.progress.lock().unwrap();
+        if !progress.is_finished() {
+            progress.error = Some("Query cancelled".to_string());
+        }
+    }
+
+    fn abort_tasks(&self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+
+    /// The outcome once the query has finished; `None` while it is still running
+    pub fn result(&self) -> Option<Result<QueryResult>> {
+        let mut progress = self.progress.lock().unwrap();
+        if !progress.is_finished() {
+            return None;
+        }
+        if progress.elapsed.is_none() {
+            progress.elapsed = Some(self.started.elapsed());
+        }
+        if let Some(error) = &progress.error {
+            self.abort_tasks();
+            return Some(Err(FreshError::Custom(error.clone())));
+        }
+
+        if progress.result.is_none() {
+            let mut batches = std::mem::take(&mut progress.batches);
+            if batches.is_empty() {
+                let schema = progress.schema.clone().unwrap_or_else(|| Arc::new(Schema::empty()));
+                batches.push(RecordBatch::new_empty(schema));
+            }
+            let mut result = QueryResult::from_batches(batches).with_datetime_format(self.datetime_format.clone());
+            result.total_rows = Some(progress.total_rows.unwrap_or(progress.rows));
+            progress.result = Some(result);
+        }
+        progress.result.clone().map(Ok)
+    }
+}
+
+impl Drop for RunningQuery {
+    fn drop(&mut self) {
+        self.abort_tasks();
+    }
+}
+
+// Numeric view of an array; None when the type has no numeric meaning
+fn array_to_f64(array: &ArrayRef) -> Option<Float64Array> {
+    let data_type = array.data_type();
+    let numeric = match data_type {
+        DataType::Timestamp(_, _) | DataType::Date64 | DataType::Time64(_) | DataType::Duration(_) => {
+            cast(array, &DataType::Int64).ok()?
+        }
+        DataType::Date32 | DataType::Time32(_) => cast(array, &DataType::Int32).ok()?,
+        DataType::Boolean => array.clone(),
+        t if t.is_numeric() => array.clone(),
+        _ => return None,
+    };
+    let floats = cast(&numeric, &DataType::Float64).ok()?;
+    floats.as_any().downcast_ref::<Float64Array>().cloned()
+}
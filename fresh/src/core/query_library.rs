@@ -0,0 +1,272 @@
+use crate::core::error::{FreshError, Result};
+use crate::core::params::QueryParameter;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// File inside the project folder that stores one executed statement per line
+const HISTORY_FILE: &str = "query_history.jsonl";
+/// File inside the project folder that stores the saved query library
+const SAVED_QUERIES_FILE: &str = "saved_queries.json";
+/// Older entries are dropped from the history file when the project is opened
+const MAX_HISTORY_ENTRIES: usize = 5000;
+
+/// A statement executed from a query window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryHistoryEntry {
+    pub sql: String,
+    /// Seconds since the Unix epoch (UTC)
+    pub executed_at: i64,
+    pub duration_ms: u64,
+    pub row_count: Option<usize>,
+    pub error: Option<String>,
+}
+
+impl QueryHistoryEntry {
+    /// Entry for a statement that finished just now
+    pub fn new(sql: &str, duration: Duration, row_count: Option<usize>, error: Option<String>) -> Self {
+        let executed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Self {
+            sql: sql.trim().to_string(),
+            executed_at,
+            duration_ms: duration.as_millis() as u64,
+            row_count,
+            error,
+        }
+    }
+
+    /// Execution time formatted for display, e.g. "2024-03-01 14:05:09 UTC"
+    pub fn executed_at_display(&self) -> String {
+        chrono::DateTime::from_timestamp(self.executed_at, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| self.executed_at.to_string())
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A named query promoted from the history or a query window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedQuery {
+    pub name: String,
+    pub sql: String,
+    /// Values last used for the query's parameters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<QueryParameter>,
+}
+
+/// Query history and saved queries of one project folder
+pub struct QueryLibrary {
+    project_path: Option<PathBuf>,
+    readonly: bool,
+    history: Vec<QueryHistoryEntry>,
+    saved: Vec<SavedQuery>,
+}
+
+impl QueryLibrary {
+    /// Library that lives only in memory
+    pub fn in_memory() -> Self {
+        Self {
+            project_path: None,
+            readonly: false,
+            history: Vec::new(),
+            saved: Vec::new(),
+        }
+    }
+
+    /// Load the history and saved queries of a project folder.
+    /// Read-only projects keep the history for the session without writing it.
+    pub fn open(project_path: &Path, readonly: bool) -> Result<Self> {
+        let mut library = Self {
+            project_path: Some(project_path.to_path_buf()),
+            readonly,
+            history: Vec::new(),
+            saved: Vec::new(),
+        };
+
+        let history_path = project_path.join(HISTORY_FILE);
+        if history_path.exists() {
+            let contents = std::fs::read_to_string(&history_path)
+                .map_err(|e| FreshError::Custom(format!("Failed to read query history: {}", e)))?;
+            for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<QueryHistoryEntry>(line) {
+                    Ok(entry) => library.history.push(entry),
+                    Err(e) => eprintln!("[QueryLibrary] Skipping malformed history entry: {}", e),
+                }
+            }
+            if library.history.len() > MAX_HISTORY_ENTRIES {
+                let excess = library.history.len() - MAX_HISTORY_ENTRIES;
+                library.history.drain(..excess);
+                if !readonly {
+                    library.rewrite_history()?;
+                }
+            }
+        }
+
+        let saved_path = project_path.join(SAVED_QUERIES_FILE);
+        if saved_path.exists() {
+            let json = std::fs::read_to_string(&saved_path)
+                .map_err(|e| FreshError::Custom(format!("Failed to read saved queries: {}", e)))?;
+            library.saved = serde_json::from_str(&json)
+                .map_err(|e| FreshError::Custom(format!("Failed to parse saved queries: {}", e)))?;
+        }
+
+        Ok(library)
+    }
+
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// All history entries, oldest first
+    pub fn history(&self) -> &[QueryHistoryEntry] {
+        &self.history
+    }
+
+    /// Append an executed statement to the history
+    pub fn record(&mut self, entry: QueryHistoryEntry) -> Result<()> {
+        if entry.sql.is_empty() {
+            return Ok(());
+        }
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| FreshError::Custom(format!("Failed to serialize history entry: {}", e)))?;
+        self.history.push(entry);
+
+        let Some(project_path) = self.writable_path() else {
+            return Ok(());
+        };
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(project_path.join(HISTORY_FILE))
+            .map_err(|e| FreshError::Custom(format!("Failed to open query history: {}", e)))?;
+        writeln!(file, "{}", line)
+            .map_err(|e| FreshError::Custom(format!("Failed to write query history: {}", e)))?;
+        Ok(())
+    }
+
+    /// History entries whose SQL or error contains `needle` (case-insensitive), newest first
+    pub fn search_history(&self, needle: &str) -> Vec<&QueryHistoryEntry> {
+        let needle = needle.trim().to_lowercase();
+        self.history.iter()
+            .rev()
+            .filter(|entry| {
+                needle.is_empty()
+                    || entry.sql.to_lowercase().contains(&needle)
+                    || entry.error.as_ref().is_some_and(|e| e.to_lowercase().contains(&needle))
+            })
+            .collect()
+    }
+
+    /// Saved queries sorted by name
+    pub fn saved_queries(&self) -> &[SavedQuery] {
+        &self.saved
+    }
+
+    pub fn saved_query(&self, name: &str) -> Option<&SavedQuery> {
+        self.saved.iter().find(|q| q.name == name)
+    }
+
+    /// Add a saved query, replacing one with the same name
+    pub fn save_query(&mut self, name: &str, sql: &str) -> Result<()> {
+        self.save_query_with_parameters(name, sql, &[])
+    }
+
+    /// Add a saved query together with the values of its parameters
+    pub fn save_query_with_parameters(&mut self, name: &str, sql: &str, parameters: &[QueryParameter]) -> Result<()> {
+        self.ensure_writable("save a query")?;
+        let name = name.trim();
+        let sql = sql.trim();
+        if name.is_empty() {
+            return Err(FreshError::Custom("Query name cannot be empty".to_string()));
+        }
+        if sql.is_empty() {
+            return Err(FreshError::Custom("Query cannot be empty".to_string()));
+        }
+
+        match self.saved.iter_mut().find(|q| q.name == name) {
+            Some(existing) => {
+                existing.sql = sql.to_string();
+                existing.parameters = parameters.to_vec();
+            }
+            None => {
+                self.saved.push(SavedQuery {
+                    name: name.to_string(),
+                    sql: sql.to_string(),
+                    parameters: parameters.to_vec(),
+                });
+                self.saved.sort_by_key(|q| q.name.to_lowercase());
+            }
+        }
+        self.write_saved_queries()
+    }
+
+    pub fn delete_saved_query(&mut self, name: &str) -> Result<()> {
+        self.ensure_writable("delete a saved query")?;
+        let before = self.saved.len();
+        self.saved.retain(|q| q.name != name);
+        if self.saved.len() == before {
+            return Err(FreshError::Custom(format!("Saved query '{}' does not exist", name)));
+        }
+        self.write_saved_queries()
+    }
+
+    fn ensure_writable(&self, action: &str) -> Result<()> {
+        if self.readonly {
+            return Err(FreshError::Custom(format!(
+                "Cannot {} in a read-only project",
+                action
+            )));
+        }
+        Ok(())
+    }
+
+    fn writable_path(&self) -> Option<&Path> {
+        if self.readonly {
+            return None;
+        }
+        self.project_path.as_deref()
+    }
+
+    fn rewrite_history(&self) -> Result<()> {
+        let Some(project_path) = self.writable_path() else {
+            return Ok(());
+        };
+        let mut contents = String::new();
+        for entry in &self.history {
+            let line = serde_json::to_string(entry)
+                .map_err(|e| FreshError::Custom(format!("Failed to serialize history entry: {}", e)))?;
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+        std::fs::write(project_path.join(HISTORY_FILE), contents)
+            .map_err(|e| FreshError::Custom(format!("Failed to write query history: {}", e)))?;
+        Ok(())
+    }
+
+    fn write_saved_queries(&self) -> Result<()> {
+        let Some(project_path) = self.writable_path() else {
+            return Ok(());
+        };
+        let path = project_path.join(SAVED_QUERIES_FILE);
+        if self.saved.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .map_err(|e| FreshError::Custom(format!("Failed to remove saved queries: {}", e)))?;
+            }
+            return Ok(());
+        }
+
+        let json = serde_json::to_string_pretty(&self.saved)
+            .map_err(|e| FreshError::Custom(format!("Failed to serialize saved queries: {}", e)))?;
+        std::fs::write(&path, json)
+            .map_err(|e| FreshError::Custom(format!("Failed to write saved queries: {}", e)))?;
+        Ok(())
+    }
+}
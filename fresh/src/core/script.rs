@@ -0,0 +1,161 @@
+//! SQL scripts: several statements separated by semicolons, executed in order with
+//! a status reported for each.
+
+use std::time::{Duration, Instant};
+use crate::core::database::{Database, StatementOutcome};
+use crate::core::params::{self, QueryParameter};
+use crate::core::query::QueryResult;
+use crate::core::sql_assist::{self, SqlTokenKind};
+
+/// One statement of a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptStatement {
+    /// The statement without its terminating semicolon
+    pub sql: String,
+    /// Line of the script the statement starts on, from 1
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementStatus {
+    /// Ran, with a summary such as "Inserted 3 rows into 'sales'"
+    Succeeded(String),
+    Failed(String),
+    /// Not run because an earlier statement failed
+    Skipped,
+}
+
+/// How one statement of a script went
+#[derive(Debug, Clone)]
+pub struct StatementReport {
+    pub sql: String,
+    pub line: usize,
+    pub status: StatementStatus,
+    /// Rows returned, for queries
+    pub row_count: Option<usize>,
+    pub elapsed: Duration,
+}
+
+/// Outcome of running a script
+#[derive(Debug, Clone, Default)]
+pub struct ScriptResult {
+    pub statements: Vec<StatementReport>,
+    /// Rows of the last query in the script that ran
+    pub result: Option<QueryResult>,
+    pub elapsed: Duration,
+}
+
+impl ScriptResult {
+    /// Message of the statement that stopped the script, if one failed
+    pub fn error(&self) -> Option<String> {
+        self.statements.iter().find_map(|statement| match &statement.status {
+            StatementStatus::Failed(e) => Some(format!("Line {}: {}", statement.line, e)),
+            _ => None,
+        })
+    }
+
+    /// Whether a statement other than a query ran, so tables or views may have changed
+    pub fn changed_catalog(&self) -> bool {
+        self.statements.iter()
+            .any(|s| matches!(s.status, StatementStatus::Succeeded(_)) && s.row_count.is_none())
+    }
+}
+
+/// Split a script at the semicolons between statements; semicolons in strings, quoted
+/// names and comments don't count. Comments before a statement are not part of it, and
+/// statements with nothing but comments are dropped.
+pub fn split_statements(script: &str) -> Vec<ScriptStatement> {
+    let mut statements = Vec::new();
+    // Start of the current statement's first token that is not whitespace or a comment
+    let mut code_start = None;
+    let mut push = |start: Option<usize>, end: usize| {
+        if let Some(start) = start {
+            statements.push(ScriptStatement {
+                sql: script[start..end].trim_end().to_string(),
+                line: script[..start].matches('\n').count() + 1,
+            });
+        }
+    };
+
+    for token in sql_assist::tokenize(script) {
+        match token.kind {
+            SqlTokenKind::Punctuation if &script[token.range.clone()] == ";" => {
+                push(code_start.take(), token.range.start);
+            }
+            SqlTokenKind::Whitespace | SqlTokenKind::Comment => {}
+            _ => {
+                code_start.get_or_insert(token.range.start);
+            }
+        }
+    }
+    push(code_start, script.len());
+    statements
+}
+
+/// Whether `sql` holds more than one statement
+pub fn is_script(sql: &str) -> bool {
+    split_statements(sql).len() > 1
+}
+
+/// Execute the statements of a script in order, stopping at the first that fails.
+/// Each statement is given the parameters it uses. When a transaction the script
+/// started is still open after a failure, it is rolled back.
+pub fn run_script(db: &Database, script: &str, parameters: &[QueryParameter]) -> ScriptResult {
+    let started = Instant::now();
+    let was_in_transaction = db.in_transaction();
+    let mut result = ScriptResult::default();
+    let mut failed = false;
+
+    for statement in split_statements(script) {
+        if failed {
+            result.statements.push(StatementReport {
+                sql: statement.sql,
+                line: statement.line,
+                status: StatementStatus::Skipped,
+                row_count: None,
+                elapsed: Duration::ZERO,
+            });
+            continue;
+        }
+
+        let names = params::parameter_names(&statement.sql);
+        let used: Vec<QueryParameter> = parameters.iter()
+            .filter(|p| names.contains(&p.name))
+            .cloned()
+            .collect();
+        let statement_started = Instant::now();
+        let (status, row_count) = match db.execute_statement(&statement.sql, &used) {
+            Ok(StatementOutcome::Rows(batches)) => {
+                let query_result = QueryResult::from_batches(batches).with_datetime_format(db.datetime_format());
+                let rows = query_result.num_rows();
+                result.result = Some(query_result);
+                (StatementStatus::Succeeded(format!("{} rows", rows)), Some(rows))
+            }
+            Ok(StatementOutcome::Done(message)) => (StatementStatus::Succeeded(message), None),
+            Err(e) => {
+                failed = true;
+                let mut message = e.to_string();
+                if !was_in_transaction && db.in_transaction() {
+                    match db.rollback_transaction() {
+                        Ok(()) => message.push_str(" (transaction rolled back)"),
+                        Err(e) => eprintln!("[Script] Failed to roll back transaction: {}", e),
+                    }
+                }
+                (StatementStatus::Failed(message), None)
+            }
+        };
+        result.statements.push(StatementReport {
+            sql: statement.sql,
+            line: statement.line,
+            status,
+            row_count,
+            elapsed: statement_started.elapsed(),
+        });
+    }
+
+    if let Some(query_result) = &mut result.result {
+        query_result.total_rows = Some(query_result.num_rows());
+    }
+    result.elapsed = started.elapsed();
+    result
+}
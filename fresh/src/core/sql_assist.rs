@@ -0,0 +1,460 @@
+//! Lexing, completion and error positions for the SQL editor.
+//!
+//! The lexer never fails: half-typed SQL (an unterminated string, a dangling comment)
+//! still yields tokens up to the end of the text, so highlighting keeps working while
+//! the user types.
+
+use std::ops::Range;
+use std::sync::LazyLock;
+use regex::Regex;
+use crate::core::database::TableInfo;
+
+/// Keywords highlighted and offered by completion
+pub const SQL_KEYWORDS: &[&str] = &[
+    "ALL", "AND", "ANY", "AS", "ASC", "BETWEEN", "BY", "CASE", "CAST", "CREATE", "CROSS",
+    "DELETE", "DESC", "DISTINCT", "DROP", "ELSE", "END", "EXCEPT", "EXISTS", "EXPLAIN",
+    "EXTERNAL", "FALSE", "FILTER", "FIRST", "FOLLOWING", "FROM", "FULL", "GROUP", "HAVING",
+    "ILIKE", "IN", "INNER", "INSERT", "INTERSECT", "INTERVAL", "INTO", "IS", "JOIN", "LAST",
+    "LEFT", "LIKE", "LIMIT", "NATURAL", "NOT", "NULL", "NULLS", "OFFSET", "ON", "OR", "ORDER",
+    "OUTER", "OVER", "PARTITION", "PRECEDING", "RANGE", "RECURSIVE", "REPLACE", "RIGHT",
+    "ROWS", "SELECT", "SET", "TABLE", "THEN", "TRUE", "UNBOUNDED", "UNION", "UPDATE", "USING",
+    "VALUES", "VIEW", "WHEN", "WHERE", "WINDOW", "WITH",
+];
+
+/// Keywords after which a table name is expected
+const TABLE_KEYWORDS: [&str; 5] = ["FROM", "JOIN", "INTO", "UPDATE", "TABLE"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlTokenKind {
+    Keyword,
+    Identifier,
+    /// An identifier followed by an opening parenthesis
+    Function,
+    QuotedIdentifier,
+    String,
+    Number,
+    Comment,
+    Parameter,
+    Operator,
+    Bracket,
+    Punctuation,
+    Whitespace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlToken {
+    pub kind: SqlTokenKind,
+    /// Byte range in the SQL text
+    pub range: Range<usize>,
+}
+
+/// Split SQL into tokens covering the whole text
+pub fn tokenize(sql: &str) -> Vec<SqlToken> {
+    let bytes = sql.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80;
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let next = bytes.get(i + 1).copied();
+        let kind = match bytes[i] {
+            b if b.is_ascii_whitespace() => {
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                SqlTokenKind::Whitespace
+            }
+            b'-' if next == Some(b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                SqlTokenKind::Comment
+            }
+            b'/' if next == Some(b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+                SqlTokenKind::Comment
+            }
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == quote {
+                        // A doubled quote is an escaped quote
+                        if bytes.get(i + 1) == Some(&quote) {
+                            i += 2;
+                            continue;
+                        }
+                        i += 1;
+                        break;
+                    }
+                    i += 1;
+                }
+                if quote == b'\'' { SqlTokenKind::String } else { SqlTokenKind::QuotedIdentifier }
+            }
+            b if b.is_ascii_digit() || (b == b'.' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                SqlTokenKind::Number
+            }
+            // `::name` is a cast, not a parameter
+            b':' | b'$' if next.is_some_and(|n| n.is_ascii_alphanumeric() || n == b'_')
+                && (start == 0 || bytes[start - 1] != b':') => {
+                i += 1;
+                while i < bytes.len() && is_word(bytes[i]) {
+                    i += 1;
+                }
+                SqlTokenKind::Parameter
+            }
+            b if is_word(b) => {
+                while i < bytes.len() && is_word(bytes[i]) {
+                    i += 1;
+                }
+                let word = &sql[start..i];
+                if SQL_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word)) {
+                    SqlTokenKind::Keyword
+                } else if sql[i..].trim_start().starts_with('(') {
+                    SqlTokenKind::Function
+                } else {
+                    SqlTokenKind::Identifier
+                }
+            }
+            b'(' | b')' | b'[' | b']' => {
+                i += 1;
+                SqlTokenKind::Bracket
+            }
+            b',' | b';' | b'.' => {
+                i += 1;
+                SqlTokenKind::Punctuation
+            }
+            _ => {
+                // Operators, and any other character, one UTF-8 character at a time
+                i += sql[i..].chars().next().map_or(1, char::len_utf8);
+                SqlTokenKind::Operator
+            }
+        };
+        tokens.push(SqlToken { kind, range: start..i });
+    }
+    tokens
+}
+
+/// Positions of the bracket next to `cursor` and its partner, if both exist
+pub fn matching_bracket(sql: &str, cursor: usize) -> Option<(usize, usize)> {
+    let brackets: Vec<usize> = tokenize(sql).into_iter()
+        .filter(|t| t.kind == SqlTokenKind::Bracket)
+        .map(|t| t.range.start)
+        .collect();
+    // The bracket just before the cursor wins over the one just after it
+    let at = brackets.iter().copied()
+        .find(|&p| p + 1 == cursor)
+        .or_else(|| brackets.iter().copied().find(|&p| p == cursor))?;
+
+    let bytes = sql.as_bytes();
+    let (open, close) = match bytes[at] {
+        b'(' | b')' => (b'(', b')'),
+        _ => (b'[', b']'),
+    };
+    let mut depth = 0i32;
+    if bytes[at] == open {
+        for &p in brackets.iter().filter(|&&p| p >= at) {
+            depth += if bytes[p] == open { 1 } else if bytes[p] == close { -1 } else { 0 };
+            if depth == 0 {
+                return Some((at, p));
+            }
+        }
+    } else {
+        for &p in brackets.iter().rev().filter(|&&p| p <= at) {
+            depth += if bytes[p] == close { 1 } else if bytes[p] == open { -1 } else { 0 };
+            if depth == 0 {
+                return Some((p, at));
+            }
+        }
+    }
+    None
+}
+
+/// Tables, views and functions known to the editor
+#[derive(Debug, Clone, Default)]
+pub struct SqlSchema {
+    pub tables: Vec<TableInfo>,
+    pub functions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+    Column,
+    Table,
+    Function,
+    Keyword,
+}
+
+impl CompletionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompletionKind::Column => "column",
+            CompletionKind::Table => "table",
+            CompletionKind::Function => "function",
+            CompletionKind::Keyword => "keyword",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// Column type or owning table, shown next to the label
+    pub detail: String,
+    /// Text that replaces the word being typed
+    pub insert: String,
+}
+
+/// Candidates for the word ending at `cursor` and the byte range they replace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completions {
+    pub replace: Range<usize>,
+    pub items: Vec<Completion>,
+}
+
+/// Complete the word ending at byte `cursor`. After `alias.` only that table's
+/// columns are offered; after FROM or JOIN only tables; elsewhere columns of the
+/// tables the query reads, then functions, tables and keywords.
+pub fn complete(sql: &str, cursor: usize, schema: &SqlSchema) -> Option<Completions> {
+    if cursor > sql.len() || !sql.is_char_boundary(cursor) {
+        return None;
+    }
+    let tokens = tokenize(sql);
+    // No completion inside strings, comments or quoted names, including ones the
+    // cursor is still typing
+    let inside = |t: &SqlToken| {
+        let text = &sql[t.range.clone()];
+        let open = match t.kind {
+            SqlTokenKind::String => text.len() == 1 || !text.ends_with('\''),
+            SqlTokenKind::QuotedIdentifier => text.len() == 1 || !text.ends_with('"'),
+            SqlTokenKind::Comment => text.starts_with("--") || !text.ends_with("*/"),
+            _ => return false,
+        };
+        t.range.start < cursor && (cursor < t.range.end || (open && cursor == t.range.end))
+    };
+    if tokens.iter().any(inside) {
+        return None;
+    }
+
+    let word_start = sql[..cursor]
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |p| p + 1);
+    let prefix = &sql[word_start..cursor];
+    let before: Vec<&SqlToken> = tokens.iter()
+        .filter(|t| t.range.end <= word_start && !matches!(t.kind, SqlTokenKind::Whitespace | SqlTokenKind::Comment))
+        .collect();
+    let matches_prefix = |label: &str| label.to_lowercase().starts_with(&prefix.to_lowercase())
+        && !label.eq_ignore_ascii_case(prefix);
+
+    let mut items = Vec::new();
+    let qualified = before.last().is_some_and(|t| t.range.end == word_start && &sql[t.range.clone()] == ".");
+    if qualified {
+        let qualifier = before.len().checked_sub(2)
+            .map(|i| unquote(&sql[before[i].range.clone()]))
+            .unwrap_or_default();
+        let table_name = resolve_alias(sql, &tokens, &qualifier);
+        if let Some(table) = find_table(schema, &table_name) {
+            items.extend(column_completions(table).filter(|c| matches_prefix(&c.label)));
+        }
+    } else {
+        let previous_keyword = before.last()
+            .filter(|t| t.kind == SqlTokenKind::Keyword)
+            .map(|t| sql[t.range.clone()].to_uppercase());
+        let wants_table = previous_keyword.as_deref().is_some_and(|k| TABLE_KEYWORDS.contains(&k));
+
+        if !wants_table {
+            for name in referenced_tables(sql, &tokens) {
+                if let Some(table) = find_table(schema, &name) {
+                    items.extend(column_completions(table).filter(|c| matches_prefix(&c.label)));
+                }
+            }
+            items.extend(schema.functions.iter()
+                .filter(|f| matches_prefix(f))
+                .map(|f| Completion {
+                    label: f.clone(),
+                    kind: CompletionKind::Function,
+                    detail: String::new(),
+                    insert: format!("{}(", f),
+                }));
+        }
+        items.extend(schema.tables.iter()
+            .filter(|t| matches_prefix(&t.name))
+            .map(|t| Completion {
+                label: t.name.clone(),
+                kind: CompletionKind::Table,
+                detail: format!("{} columns", t.columns.len()),
+                insert: quote_if_needed(&t.name),
+            }));
+        if !wants_table && !prefix.is_empty() {
+            items.extend(SQL_KEYWORDS.iter()
+                .filter(|k| matches_prefix(k))
+                .map(|k| Completion {
+                    label: k.to_string(),
+                    kind: CompletionKind::Keyword,
+                    detail: String::new(),
+                    insert: k.to_string(),
+                }));
+        }
+    }
+
+    // Columns of several tables may share a name; keep the first
+    let mut seen = std::collections::HashSet::new();
+    items.retain(|c| seen.insert((c.kind, c.label.clone())));
+    if items.is_empty() {
+        return None;
+    }
+    Some(Completions { replace: word_start..cursor, items })
+}
+
+fn column_completions(table: &TableInfo) -> impl Iterator<Item = Completion> + '_ {
+    table.columns.iter().map(move |c| Completion {
+        label: c.name.clone(),
+        kind: CompletionKind::Column,
+        detail: format!("{} · {}", c.data_type, table.name),
+        insert: quote_if_needed(&c.name),
+    })
+}
+
+fn find_table<'a>(schema: &'a SqlSchema, name: &str) -> Option<&'a TableInfo> {
+    schema.tables.iter()
+        .find(|t| t.name == name)
+        .or_else(|| schema.tables.iter().find(|t| t.name.eq_ignore_ascii_case(name)))
+}
+
+/// Names of the tables after FROM and JOIN, in order
+fn referenced_tables(sql: &str, tokens: &[SqlToken]) -> Vec<String> {
+    table_references(sql, tokens).into_iter().map(|(table, _)| table).collect()
+}
+
+/// The table an alias (or a table name used as qualifier) refers to
+fn resolve_alias(sql: &str, tokens: &[SqlToken], qualifier: &str) -> String {
+    table_references(sql, tokens).into_iter()
+        .find(|(_, alias)| alias.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(qualifier)))
+        .map_or_else(|| qualifier.to_string(), |(table, _)| table)
+}
+
+/// `(table, alias)` pairs written as `FROM table [AS] alias` or `JOIN table [AS] alias`
+fn table_references(sql: &str, tokens: &[SqlToken]) -> Vec<(String, Option<String>)> {
+    let words: Vec<&SqlToken> = tokens.iter()
+        .filter(|t| !matches!(t.kind, SqlTokenKind::Whitespace | SqlTokenKind::Comment))
+        .collect();
+    let text = |t: &SqlToken| sql[t.range.clone()].to_string();
+    let is_name = |t: &&&SqlToken| matches!(t.kind, SqlTokenKind::Identifier | SqlTokenKind::QuotedIdentifier);
+
+    let mut references = Vec::new();
+    for (i, token) in words.iter().enumerate() {
+        let keyword = text(token).to_uppercase();
+        if token.kind != SqlTokenKind::Keyword || !(keyword == "FROM" || keyword == "JOIN") {
+            continue;
+        }
+        let Some(table) = words.get(i + 1).filter(is_name) else {
+            continue;
+        };
+        let mut alias_at = i + 2;
+        if words.get(alias_at).is_some_and(|t| text(t).eq_ignore_ascii_case("AS")) {
+            alias_at += 1;
+        }
+        let alias = words.get(alias_at).filter(is_name).map(|t| unquote(&text(t)));
+        references.push((unquote(&text(table)), alias));
+    }
+    references
+}
+
+fn unquote(name: &str) -> String {
+    name.strip_prefix('"')
+        .and_then(|n| n.strip_suffix('"'))
+        .map_or_else(|| name.to_string(), |n| n.replace("\"\"", "\""))
+}
+
+/// Names that are not plain lower-case identifiers have to be quoted
+fn quote_if_needed(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !SQL_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(name));
+    if plain {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+// Position reported by tokenizer errors
+static LOCATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"Line: (\d+), Column:? (\d+)").expect("valid location pattern")
+});
+
+// Planning errors naming something that does not exist
+static UNKNOWN_NAME_PATTERNS: LazyLock<[Regex; 3]> = LazyLock::new(|| {
+    [
+        r"No field named ([^\s,]+?)\.?(?:\s|$)",
+        r"table '([^']+)' not found",
+        r"Invalid function '([^']+)'",
+    ].map(|pattern| Regex::new(pattern).expect("valid unknown name pattern"))
+});
+
+/// Byte range of the text a DataFusion error points at: the reported line and column
+/// for tokenizer errors, the token the parser stopped at for syntax errors, or the
+/// unknown column, table or function named in planning errors
+pub fn error_span(sql: &str, message: &str) -> Option<Range<usize>> {
+    if let Some(captures) = LOCATION.captures(message) {
+        let start = offset_of(sql, captures[1].parse().ok()?, captures[2].parse().ok()?)?;
+        return token_span_at(sql, start);
+    }
+    if message.contains("ParserError") {
+        return parser_error_span(sql);
+    }
+
+    let name = UNKNOWN_NAME_PATTERNS.iter()
+        .filter_map(|pattern| pattern.captures(message).map(|c| c[1].to_string()))
+        .next()?;
+    // Qualified names are reported in full; the last part is what was typed
+    let name = unquote(name.rsplit('.').next().unwrap_or(&name));
+    tokenize(sql).into_iter()
+        .filter(|t| matches!(t.kind, SqlTokenKind::Identifier | SqlTokenKind::QuotedIdentifier | SqlTokenKind::Function))
+        .find(|t| unquote(&sql[t.range.clone()]).eq_ignore_ascii_case(&name))
+        .map(|t| t.range)
+}
+
+// Syntax errors carry no position, so parse again and see where the parser stopped.
+// At the end of the text the last token is marked, since something should follow it.
+fn parser_error_span(sql: &str) -> Option<Range<usize>> {
+    use datafusion::sql::sqlparser::dialect::GenericDialect;
+    use datafusion::sql::sqlparser::parser::Parser;
+    use datafusion::sql::sqlparser::tokenizer::{Token, Tokenizer};
+
+    let dialect = GenericDialect {};
+    let tokens = Tokenizer::new(&dialect, sql).tokenize_with_location().ok()?;
+    let mut parser = Parser::new(&dialect).with_tokens_with_locations(tokens.clone());
+    if parser.parse_statements().is_ok() {
+        return None;
+    }
+
+    // The parser has either just consumed the token it rejects or is about to
+    let stopped_at = tokens.iter()
+        .skip(parser.index().saturating_sub(1))
+        .find(|t| !matches!(t.token, Token::Whitespace(_)))
+        .and_then(|t| offset_of(sql, t.location.line as usize, t.location.column as usize));
+    match stopped_at {
+        Some(start) => token_span_at(sql, start),
+        None => tokenize(sql).into_iter()
+            .rfind(|t| !matches!(t.kind, SqlTokenKind::Whitespace | SqlTokenKind::Comment))
+            .map(|t| t.range),
+    }
+}
+
+// Byte offset of a 1-based line and column, counting columns in characters
+fn offset_of(sql: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start: usize = sql.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+    sql.get(line_start..)?
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map(|(offset, _)| line_start + offset)
+}
+
+// From `start` to the end of the token there
+fn token_span_at(sql: &str, start: usize) -> Option<Range<usize>> {
+    let token = tokenize(sql).into_iter().find(|t| t.range.contains(&start))?;
+    Some(start..token.range.end)
+}
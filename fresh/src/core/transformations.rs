@@ -7,8 +7,10 @@ use anyhow::{Result, anyhow};
 use std::sync::Arc;
 use std::path::PathBuf;
 use chrono::{DateTime, Utc, NaiveDateTime};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TransformationType {
     Delta,
     DeltaMultiple,
@@ -16,13 +18,19 @@ pub enum TransformationType {
     RowId,
 }
 
-#[derive(Debug, Clone)]
+/// One transformation step, e.g. read from a pipeline file
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformationConfig {
     pub transformation_type: TransformationType,
+    #[serde(default)]
     pub selected_columns: Vec<String>,
     pub output_column_name: String,
+    /// Seconds per bin, for time binning
+    #[serde(default)]
     pub bin_size: Option<String>,
+    #[serde(default)]
     pub time_column: Option<String>,
+    #[serde(default)]
     pub grouping_columns: Option<Vec<String>>,
 }
 
@@ -33,6 +41,34 @@ impl DataTransformer {
         Self
     }
 
+    /// Apply one configured transformation, adding its output column(s) to the batch
+    pub fn apply(&self, batch: &RecordBatch, config: &TransformationConfig) -> Result<RecordBatch> {
+        match config.transformation_type {
+            TransformationType::Delta => {
+                if config.selected_columns.len() != 1 {
+                    return Err(anyhow!("Delta transformation requires exactly one column"));
+                }
+                self.apply_delta(batch, &config.selected_columns[0], &config.output_column_name)
+            }
+            TransformationType::DeltaMultiple => {
+                if config.selected_columns.is_empty() {
+                    return Err(anyhow!("Delta multiple transformation requires at least one column"));
+                }
+                self.apply_delta_multiple(batch, &config.selected_columns, &config.output_column_name)
+            }
+            TransformationType::TimeBin => {
+                let time_column = config.time_column.as_ref()
+                    .ok_or_else(|| anyhow!("Time column is required for time binning"))?;
+                let bin_size: f64 = config.bin_size.as_deref().unwrap_or_default().parse()
+                    .map_err(|_| anyhow!("Invalid bin size"))?;
+                self.apply_time_bin(batch, time_column, bin_size, &config.output_column_name)
+            }
+            TransformationType::RowId => {
+                self.apply_row_id(batch, &config.output_column_name, config.grouping_columns.as_deref())
+            }
+        }
+    }
+
     /// Apply delta transformation to compute differences between consecutive rows
     pub fn apply_delta(&self, batch: &RecordBatch, column_name: &str, output_name: &str) -> Result<RecordBatch> {
         let schema = batch.schema();
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use crate::core::datetime::{self, DEFAULT_TIME_ZONE};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ColumnType {
@@ -25,10 +26,10 @@ impl ColumnType {
             ColumnType::Boolean => "BOOLEAN",
             ColumnType::Date => "DATE",
             ColumnType::DateTime => "TIMESTAMP",
-            ColumnType::TimeSeconds => "TIMESTAMP",
-            ColumnType::TimeMilliseconds => "TIMESTAMP",
-            ColumnType::TimeMicroseconds => "TIMESTAMP",
-            ColumnType::TimeNanoseconds => "TIMESTAMP",
+            ColumnType::TimeSeconds => "TIME",
+            ColumnType::TimeMilliseconds => "TIME",
+            ColumnType::TimeMicroseconds => "TIME",
+            ColumnType::TimeNanoseconds => "TIME",
             ColumnType::Blob => "BLOB",
         }
     }
@@ -40,11 +41,13 @@ impl ColumnType {
             ColumnType::Text => DataType::Utf8,
             ColumnType::Boolean => DataType::Boolean,
             ColumnType::Date => DataType::Date32,
-            ColumnType::DateTime => DataType::Timestamp(TimeUnit::Second, None),
-            ColumnType::TimeSeconds => DataType::Timestamp(TimeUnit::Second, None),
-            ColumnType::TimeMilliseconds => DataType::Timestamp(TimeUnit::Millisecond, None),
-            ColumnType::TimeMicroseconds => DataType::Timestamp(TimeUnit::Microsecond, None),
-            ColumnType::TimeNanoseconds => DataType::Timestamp(TimeUnit::Nanosecond, None),
+            // Absolute instants in UTC; importers replace the zone with the one chosen for the file
+            ColumnType::DateTime => DataType::Timestamp(TimeUnit::Microsecond, Some(DEFAULT_TIME_ZONE.into())),
+            // Arrow's Time64 only counts micro- or nanoseconds since midnight
+            ColumnType::TimeSeconds => DataType::Time64(TimeUnit::Microsecond),
+            ColumnType::TimeMilliseconds => DataType::Time64(TimeUnit::Microsecond),
+            ColumnType::TimeMicroseconds => DataType::Time64(TimeUnit::Microsecond),
+            ColumnType::TimeNanoseconds => DataType::Time64(TimeUnit::Nanosecond),
             ColumnType::Blob => DataType::Binary,
         }
     }
@@ -56,7 +59,7 @@ impl ColumnType {
             ColumnType::Text => "Text",
             ColumnType::Boolean => "Boolean",
             ColumnType::Date => "Date",
-            ColumnType::DateTime => "Time",
+            ColumnType::DateTime => "Date & Time",
             ColumnType::TimeSeconds => "Time (seconds)",
             ColumnType::TimeMilliseconds => "Time (milliseconds)",
             ColumnType::TimeMicroseconds => "Time (microseconds)",
@@ -132,11 +135,6 @@ impl TypeInferrer {
     }
     
     fn infer_column_type(header: &str, samples: &[Vec<String>], col_idx: usize) -> ColumnType {
-        // First check header for time unit hints
-        if let Some(time_type) = Self::detect_time_unit_from_header(header) {
-            return time_type;
-        }
-
         let mut is_int = true;
         let mut is_float = true;
         let mut is_bool = true;
@@ -217,19 +215,15 @@ impl TypeInferrer {
         } else if is_date {
             ColumnType::Date
         } else if is_time {
-            // Determine the appropriate time unit based on the data
-            Self::detect_time_unit_from_data(samples, col_idx)
+            // A unit named in the header wins over the precision seen in the data
+            Self::detect_time_unit_from_header(header)
+                .unwrap_or_else(|| Self::detect_time_unit_from_data(samples, col_idx))
         } else {
             ColumnType::Text
         }
     }
 
     fn infer_column_type_with_nulls(header: &str, samples: &[Vec<String>], col_idx: usize, null_values: &[String]) -> ColumnType {
-        // First check header for time unit hints
-        if let Some(time_type) = Self::detect_time_unit_from_header(header) {
-            return time_type;
-        }
-
         let mut is_int = true;
         let mut is_float = true;
         let mut is_bool = true;
@@ -314,8 +308,9 @@ impl TypeInferrer {
         } else if is_date {
             ColumnType::Date
         } else if is_time {
-            // Determine the appropriate time unit based on the data
-            Self::detect_time_unit_from_data(samples, col_idx)
+            // A unit named in the header wins over the precision seen in the data
+            Self::detect_time_unit_from_header(header)
+                .unwrap_or_else(|| Self::detect_time_unit_from_data(samples, col_idx))
         } else {
             ColumnType::Text
         }
@@ -397,24 +392,8 @@ impl TypeInferrer {
     }
 
     fn is_datetime(value: &str) -> bool {
-        // Common datetime formats
-        let formats = [
-            "%Y-%m-%d %H:%M:%S",
-            "%Y-%m-%d %H:%M:%S%.f",
-            "%Y-%m-%dT%H:%M:%S",
-            "%Y-%m-%dT%H:%M:%S%.f",
-            "%Y-%m-%dT%H:%M:%SZ",
-            "%Y-%m-%dT%H:%M:%S%.fZ",
-            "%Y/%m/%d %H:%M:%S",
-            "%d/%m/%Y %H:%M:%S",
-            "%m/%d/%Y %H:%M:%S",
-            "%Y-%m-%d %H:%M",
-            "%Y/%m/%d %H:%M",
-        ];
-        for format in &formats {
-            if parse_datetime(value, format) {
-                return true;
-            }
+        if datetime::parse_datetime(value).is_some() {
+            return true;
         }
         // Check for Unix timestamp (seconds or milliseconds)
         if let Ok(ts) = value.parse::<i64>() {
@@ -556,7 +535,7 @@ impl TypeInferrer {
     }
 }
 
-// Minimal date/datetime parser for common formats (no chrono)
+// Minimal date parser for common formats (no chrono)
 fn parse_date(value: &str, format: &str) -> bool {
     // Only check length and digit/sep pattern for now
     let cleaned = value.replace(['-', '/', ' '], "");
@@ -566,12 +545,3 @@ fn parse_date(value: &str, format: &str) -> bool {
         _ => false,
     }
 }
-
-fn parse_datetime(value: &str, format: &str) -> bool {
-    // Only check length and digit/sep pattern for now
-    let cleaned = value.replace(['-', '/', ' ', ':', 'T', 'Z', '.'], "");
-    match format {
-        "%Y-%m-%d %H:%M:%S" | "%Y-%m-%dT%H:%M:%S" | "%Y/%m/%d %H:%M:%S" | "%d/%m/%Y %H:%M:%S" | "%m/%d/%Y %H:%M:%S" => cleaned.len() >= 14 && cleaned.chars().all(|c| c.is_ascii_digit()),
-        _ => false,
-    }
-} 
\ No newline at end of file
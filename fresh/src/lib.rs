@@ -1,4 +1,5 @@
 pub mod app;
+pub mod cli;
 pub mod core;
 pub mod infer;
 pub mod ui;
@@ -26,12 +27,9 @@ mod tests {
             vec!["10".to_string(), "55".to_string(), "B".to_string()],
         ];
         
-        QueryResult {
-            columns,
-            rows,
-            column_types: vec![DataType::Int64, DataType::Int64, DataType::Utf8],
-            total_rows: Some(10),
-        }
+        let mut result = QueryResult::from_rows(columns, vec![DataType::Int64, DataType::Int64, DataType::Utf8], rows);
+        result.total_rows = Some(10);
+        result
     }
 
     /// Create large test dataset for performance testing
@@ -44,12 +42,9 @@ mod tests {
             rows.push(vec![i.to_string(), (i * 2).to_string(), category.to_string()]);
         }
         
-        QueryResult {
-            columns,
-            rows,
-            column_types: vec![DataType::Int64, DataType::Int64, DataType::Utf8],
-            total_rows: Some(size),
-        }
+        let mut result = QueryResult::from_rows(columns, vec![DataType::Int64, DataType::Int64, DataType::Utf8], rows);
+        result.total_rows = Some(size);
+        result
     }
 
     #[test]
@@ -250,6 +245,38 @@ mod tests {
         assert!(true, "Column validation method exists and can be called");
     }
 
+    #[test]
+    fn test_non_numeric_y_column_is_an_error() {
+        let columns = vec!["X".to_string(), "Y".to_string()];
+        let rows = vec![
+            vec!["1".to_string(), "10".to_string()],
+            vec!["2".to_string(), "ten".to_string()],
+        ];
+        let data = QueryResult::from_rows(columns, vec![DataType::Int64, DataType::Utf8], rows);
+        let config = PlotConfiguration {
+            title: "Test".to_string(),
+            x_column: "X".to_string(),
+            y_column: "Y".to_string(),
+            color_column: None,
+            size_column: None,
+            group_column: None,
+            show_legend: true,
+            show_grid: true,
+            show_axes_labels: true,
+            color_scheme: crate::ui::plots::ColorScheme::Viridis,
+            marker_size: 3.0,
+            line_width: 2.0,
+            allow_zoom: true,
+            allow_pan: true,
+            allow_selection: true,
+            show_tooltips: true,
+            plot_specific: PlotSpecificConfig::ScatterPlot(ScatterPlotConfig::default()),
+        };
+
+        let error = crate::ui::plots::extract_plot_points(&data, &config).unwrap_err();
+        assert!(error.contains("'ten'"), "Error should name the value that isn't a number: {}", error);
+    }
+
     #[test]
     fn test_plot_type_support() {
         // Test that all plot types have proper implementations
@@ -305,12 +332,8 @@ mod tests {
             rows.push(vec![i.to_string(), (i * 2).to_string()]);
         }
         
-        let data = QueryResult {
-            columns,
-            rows,
-            column_types: vec![DataType::Int64, DataType::Int64],
-            total_rows: Some(1000),
-        };
+        let mut data = QueryResult::from_rows(columns, vec![DataType::Int64, DataType::Int64], rows);
+        data.total_rows = Some(1000);
 
         let config = PlotConfiguration {
             title: "Large Dataset Test".to_string(),
@@ -507,7 +530,7 @@ mod tests {
         let total_points = plot_data.series.iter().map(|s| s.points.len()).sum::<usize>();
         
         println!("📊 {}: {} points processed in {:?}, rendered {} points", 
-                test_name, data.rows.len(), duration, total_points);
+                test_name, data.num_rows(), duration, total_points);
         
         // Performance assertions
         assert!(duration.as_secs() < 10, "{} should process in under 10 seconds", test_name);
@@ -1,4 +1,5 @@
 mod app;
+mod cli;
 mod core;
 mod infer;
 mod ui;
@@ -8,6 +9,12 @@ use app::FreshApp;
 use ui::apply_theme;
 
 fn main() -> Result<(), eframe::Error> {
+    // With arguments, run the headless command instead of the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     // Load icon from fresh.png
     let icon_data = load_icon_from_png();
     
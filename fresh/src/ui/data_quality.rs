@@ -0,0 +1,316 @@
+use crate::core::{Database, QualityRule, TableInfo};
+use crate::core::error::{FreshError, Result};
+
+/// Row indices listed per rule before the list is cut short
+const LISTED_ROWS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum RuleKind {
+    #[default]
+    NotNull,
+    Unique,
+    Range,
+    Regex,
+    AllowedValues,
+    ForeignKey,
+    Monotonic,
+}
+
+impl RuleKind {
+    const ALL: [RuleKind; 7] = [
+        RuleKind::NotNull,
+        RuleKind::Unique,
+        RuleKind::Range,
+        RuleKind::Regex,
+        RuleKind::AllowedValues,
+        RuleKind::ForeignKey,
+        RuleKind::Monotonic,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            RuleKind::NotNull => "Not null",
+            RuleKind::Unique => "Unique",
+            RuleKind::Range => "Value range",
+            RuleKind::Regex => "Matches regex",
+            RuleKind::AllowedValues => "Allowed values",
+            RuleKind::ForeignKey => "Foreign key",
+            RuleKind::Monotonic => "Increasing",
+        }
+    }
+}
+
+/// Data-quality rules of one table, with the report of their last validation
+#[derive(Default)]
+pub struct DataQualityWindow {
+    pub visible: bool,
+    table: String,
+    // New rule being edited
+    kind: RuleKind,
+    column: String,
+    min: String,
+    max: String,
+    pattern: String,
+    allowed_values: String,
+    referenced_table: String,
+    referenced_column: String,
+    strict: bool,
+    violations_table: String,
+    error: Option<String>,
+}
+
+impl DataQualityWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn open(&mut self, table: &str) {
+        self.visible = true;
+        self.table = table.to_string();
+        self.column.clear();
+        self.violations_table = format!("{}_violations", table);
+        self.error = None;
+    }
+
+    /// Returns true when tables or their violation counts changed, so the table list needs a refresh
+    pub fn show(&mut self, ctx: &egui::Context, db: &Database, tables: &[TableInfo]) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        let mut tables_changed = false;
+        let mut visible = self.visible;
+        let readonly = db.is_readonly();
+        let rules = db.quality_rules(&self.table);
+        let report = db.quality_report(&self.table);
+
+        egui::Window::new(format!("Data Quality: {}", self.table))
+            .id(egui::Id::new("data_quality"))
+            .open(&mut visible)
+            .resizable(true)
+            .default_size([600.0, 500.0])
+            .show(ctx, |ui| {
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.heading("Rules");
+                if rules.is_empty() {
+                    ui.label("No rules yet.");
+                }
+                let mut remove = None;
+                for (i, rule) in rules.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!readonly, egui::Button::new("✖").small()).clicked() {
+                            remove = Some(i);
+                        }
+                        ui.label(rule.describe());
+                    });
+                }
+                if let Some(index) = remove {
+                    let removed = db.remove_quality_rule(&self.table, index);
+                    tables_changed |= removed.is_ok();
+                    self.set_result(removed);
+                }
+
+                if !readonly {
+                    ui.separator();
+                    tables_changed |= self.show_rule_editor(ui, db, tables);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.heading("Report");
+                    if ui.add_enabled(!rules.is_empty(), egui::Button::new("Validate")).clicked() {
+                        let result = db.validate_table(&self.table).map(|_| ());
+                        tables_changed |= result.is_ok();
+                        self.set_result(result);
+                    }
+                });
+
+                let Some(report) = &report else {
+                    ui.label("Not validated since the table last changed.");
+                    return;
+                };
+                if report.passed() {
+                    ui.colored_label(egui::Color32::from_rgb(100, 200, 100), format!(
+                        "All {} rules pass ({} ms)",
+                        report.results.len(),
+                        report.elapsed.as_millis()
+                    ));
+                } else {
+                    ui.label(format!(
+                        "{} violations over {} rules ({} ms)",
+                        report.violation_count(),
+                        report.results.len(),
+                        report.elapsed.as_millis()
+                    ));
+                }
+                if !readonly {
+                    ui.horizontal(|ui| {
+                        ui.label("Violations table name:");
+                        ui.text_edit_singleline(&mut self.violations_table);
+                    });
+                }
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for (i, result) in report.results.iter().enumerate() {
+                            ui.group(|ui| {
+                                ui.horizontal(|ui| {
+                                    if result.passed() {
+                                        ui.colored_label(egui::Color32::from_rgb(100, 200, 100), "✓");
+                                    } else {
+                                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "✗");
+                                    }
+                                    ui.label(result.rule.describe());
+                                    ui.label(format!("{} rows", result.violation_count()));
+
+                                    let can_create = !readonly && result.error.is_none() && result.violation_count() > 0;
+                                    if ui.add_enabled(can_create, egui::Button::new("Create table of violations")).clicked() {
+                                        let created = db.create_violations_table(&self.table, i, &self.violations_table);
+                                        tables_changed |= created.is_ok();
+                                        self.set_result(created);
+                                    }
+                                });
+                                if let Some(error) = &result.error {
+                                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                                } else if !result.violating_rows.is_empty() {
+                                    let listed: Vec<String> = result.violating_rows.iter()
+                                        .take(LISTED_ROWS)
+                                        .map(|row| row.to_string())
+                                        .collect();
+                                    let more = result.violating_rows.len().saturating_sub(LISTED_ROWS);
+                                    let suffix = if more > 0 { format!(" and {} more", more) } else { String::new() };
+                                    ui.label(format!("Rows: {}{}", listed.join(", "), suffix));
+                                }
+                            });
+                        }
+                    });
+            });
+
+        self.visible = visible;
+        tables_changed
+    }
+
+    /// Returns true when a rule was added
+    fn show_rule_editor(&mut self, ui: &mut egui::Ui, db: &Database, tables: &[TableInfo]) -> bool {
+        let columns: Vec<String> = tables.iter()
+            .find(|t| t.name == self.table)
+            .map(|t| t.columns.iter().map(|c| c.name.clone()).collect())
+            .unwrap_or_default();
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("quality_rule_kind")
+                .selected_text(self.kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in RuleKind::ALL {
+                        ui.selectable_value(&mut self.kind, kind, kind.label());
+                    }
+                });
+            egui::ComboBox::from_id_salt("quality_rule_column")
+                .selected_text(if self.column.is_empty() { "column" } else { self.column.as_str() })
+                .show_ui(ui, |ui| {
+                    for column in &columns {
+                        ui.selectable_value(&mut self.column, column.clone(), column);
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| match self.kind {
+            RuleKind::NotNull | RuleKind::Unique => {}
+            RuleKind::Range => {
+                ui.label("Min:");
+                ui.add(egui::TextEdit::singleline(&mut self.min).desired_width(80.0).hint_text("open"));
+                ui.label("Max:");
+                ui.add(egui::TextEdit::singleline(&mut self.max).desired_width(80.0).hint_text("open"));
+            }
+            RuleKind::Regex => {
+                ui.label("Pattern:");
+                ui.text_edit_singleline(&mut self.pattern);
+            }
+            RuleKind::AllowedValues => {
+                ui.label("Values:");
+                ui.add(egui::TextEdit::singleline(&mut self.allowed_values).hint_text("comma separated"));
+            }
+            RuleKind::ForeignKey => {
+                let referenced_columns: Vec<String> = tables.iter()
+                    .find(|t| t.name == self.referenced_table)
+                    .map(|t| t.columns.iter().map(|c| c.name.clone()).collect())
+                    .unwrap_or_default();
+                ui.label("References:");
+                egui::ComboBox::from_id_salt("quality_rule_table")
+                    .selected_text(self.referenced_table.as_str())
+                    .show_ui(ui, |ui| {
+                        for table in tables {
+                            ui.selectable_value(&mut self.referenced_table, table.name.clone(), &table.name);
+                        }
+                    });
+                egui::ComboBox::from_id_salt("quality_rule_referenced_column")
+                    .selected_text(self.referenced_column.as_str())
+                    .show_ui(ui, |ui| {
+                        for column in &referenced_columns {
+                            ui.selectable_value(&mut self.referenced_column, column.clone(), column);
+                        }
+                    });
+            }
+            RuleKind::Monotonic => {
+                ui.checkbox(&mut self.strict, "Strictly (no repeated values)");
+            }
+        });
+
+        if !ui.add_enabled(!self.column.is_empty(), egui::Button::new("Add Rule")).clicked() {
+            return false;
+        }
+        let result = self.build_rule()
+            .map_err(FreshError::Custom)
+            .and_then(|rule| db.add_quality_rule(&self.table, rule));
+        let added = result.is_ok();
+        self.set_result(result);
+        added
+    }
+
+    fn build_rule(&self) -> std::result::Result<QualityRule, String> {
+        let column = self.column.clone();
+        let bound = |text: &str, name: &str| -> std::result::Result<Option<f64>, String> {
+            let text = text.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            text.parse::<f64>().map(Some).map_err(|_| format!("{} must be a number", name))
+        };
+
+        Ok(match self.kind {
+            RuleKind::NotNull => QualityRule::NotNull { column },
+            RuleKind::Unique => QualityRule::Unique { column },
+            RuleKind::Range => QualityRule::Range {
+                column,
+                min: bound(&self.min, "Min")?,
+                max: bound(&self.max, "Max")?,
+            },
+            RuleKind::Regex if self.pattern.is_empty() => return Err("Enter a pattern".to_string()),
+            RuleKind::Regex => QualityRule::Regex { column, pattern: self.pattern.clone() },
+            RuleKind::AllowedValues => QualityRule::AllowedValues {
+                column,
+                values: self.allowed_values.split(',')
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect(),
+            },
+            RuleKind::ForeignKey if self.referenced_table.is_empty() || self.referenced_column.is_empty() => {
+                return Err("Choose the referenced table and column".to_string());
+            }
+            RuleKind::ForeignKey => QualityRule::ForeignKey {
+                column,
+                referenced_table: self.referenced_table.clone(),
+                referenced_column: self.referenced_column.clone(),
+            },
+            RuleKind::Monotonic => QualityRule::Monotonic { column, strict: self.strict },
+        })
+    }
+
+    fn set_result(&mut self, result: Result<()>) {
+        self.error = result.err().map(|e| e.to_string());
+    }
+}
@@ -384,30 +384,15 @@ impl TransformationManager {
         // Convert the rows to a RecordBatch
         let batch = self.convert_rows_to_batch(&rows)?;
         
-        let transformed_batch = match request.transformation_type {
-            TransformationType::Delta => {
-                if request.selected_columns.len() != 1 {
-                    return Err(anyhow!("Delta transformation requires exactly one column"));
-                }
-                self.transformer.apply_delta(&batch, &request.selected_columns[0], &request.output_column_name)?
-            }
-            TransformationType::DeltaMultiple => {
-                if request.selected_columns.is_empty() {
-                    return Err(anyhow!("Delta multiple transformation requires at least one column"));
-                }
-                self.transformer.apply_delta_multiple(&batch, &request.selected_columns, &request.output_column_name)?
-            }
-            TransformationType::TimeBin => {
-                let time_column = request.time_column.as_ref()
-                    .ok_or_else(|| anyhow!("Time column is required for time binning"))?;
-                let bin_size: f64 = request.bin_size.parse()
-                    .map_err(|_| anyhow!("Invalid bin size"))?;
-                self.transformer.apply_time_bin(&batch, time_column, bin_size, &request.output_column_name)?
-            }
-            TransformationType::RowId => {
-                self.transformer.apply_row_id(&batch, &request.output_column_name, request.grouping_columns.as_deref())?
-            }
+        let config = TransformationConfig {
+            transformation_type: request.transformation_type.clone(),
+            selected_columns: request.selected_columns.clone(),
+            output_column_name: request.output_column_name.clone(),
+            bin_size: Some(request.bin_size.clone()),
+            time_column: request.time_column.clone(),
+            grouping_columns: request.grouping_columns.clone(),
         };
+        let transformed_batch = self.transformer.apply(&batch, &config)?;
 
         // Save the transformed data
         let output_filename = format!("{}_{}.arrow", request.table_name, request.output_column_name);
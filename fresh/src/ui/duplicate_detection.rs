@@ -80,6 +80,7 @@ impl DuplicateDetectionDialog {
         ui.separator();
 
         // Table selection
+        let previous_table = self.selected_table.clone();
         ui.horizontal(|ui| {
             ui.label("Table:");
             ComboBox::from_id_source("table_selection")
@@ -90,6 +91,11 @@ impl DuplicateDetectionDialog {
                     }
                 });
         });
+        if self.selected_table != previous_table {
+            self.selected_group_column.clear();
+            self.ignore_columns.clear();
+            self.update_available_tables_and_columns(db);
+        }
 
         // Group column selection
         ui.horizontal(|ui| {
@@ -313,6 +319,11 @@ impl DuplicateDetectionDialog {
         } else {
             self.available_columns.clear();
         }
+        
+        // Start from the table's declared key, which differs between otherwise identical rows
+        if self.ignore_columns.is_empty() && !self.selected_table.is_empty() {
+            self.ignore_columns = DuplicateDetectionConfig::for_key(&db.match_key(&self.selected_table)).ignore_columns;
+        }
     }
 }
 
@@ -0,0 +1,142 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::core::explain::SLOWEST_OPERATORS;
+use crate::core::{Database, PlanNode, QueryParameter, QueryPlan};
+
+type PlanSlot = Arc<Mutex<Option<Result<QueryPlan, String>>>>;
+
+const SLOW_COLORS: [egui::Color32; SLOWEST_OPERATORS] = [
+    egui::Color32::from_rgb(255, 100, 100),
+    egui::Color32::from_rgb(255, 160, 80),
+    egui::Color32::from_rgb(230, 200, 90),
+];
+
+/// Physical plan of a query with per-operator metrics, from EXPLAIN ANALYZE run on
+/// a worker thread
+pub struct ExplainWindow {
+    id: egui::Id,
+    pub visible: bool,
+    title: String,
+    plan: PlanSlot,
+}
+
+impl ExplainWindow {
+    pub fn new(id: egui::Id) -> Self {
+        Self {
+            id,
+            visible: false,
+            title: String::new(),
+            plan: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Start analyzing `query` and show the window; an analysis still running is
+    /// left to finish and discarded
+    pub fn open(&mut self, db: Arc<Database>, title: &str, query: &str, parameters: &[QueryParameter]) {
+        self.visible = true;
+        self.title = title.to_string();
+        self.plan = Arc::new(Mutex::new(None));
+
+        let slot = self.plan.clone();
+        let query = query.to_string();
+        let parameters = parameters.to_vec();
+        std::thread::spawn(move || {
+            let result = db.explain_analyze(&query, &parameters).map_err(|e| e.to_string());
+            if let Ok(mut slot) = slot.lock() {
+                *slot = Some(result);
+            }
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.visible {
+            return;
+        }
+
+        let result = self.plan.lock().ok().and_then(|slot| slot.clone());
+        let mut visible = self.visible;
+
+        egui::Window::new(format!("Plan: {}", self.title))
+            .id(self.id)
+            .open(&mut visible)
+            .resizable(true)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| match &result {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Running EXPLAIN ANALYZE...");
+                    });
+                    ctx.request_repaint();
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("Failed to explain query: {}", e));
+                }
+                Some(Ok(plan)) => Self::show_plan(ui, plan),
+            });
+
+        self.visible = visible;
+    }
+
+    fn show_plan(ui: &mut egui::Ui, plan: &QueryPlan) {
+        let total = plan.total_compute();
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Ran in {}, {} operators, {} compute",
+                format_duration(plan.elapsed),
+                plan.nodes().len(),
+                format_duration(total)
+            ));
+            if ui.button("Copy").on_hover_text("Copy the plan as text").clicked() {
+                ui.ctx().copy_text(plan.text.clone());
+            }
+        });
+        ui.separator();
+
+        let slowest = plan.slowest(SLOWEST_OPERATORS);
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .show(ui, |ui| Self::show_node(ui, &plan.root, total, &slowest));
+    }
+
+    fn show_node(ui: &mut egui::Ui, node: &PlanNode, total: Duration, slowest: &[usize]) {
+        let mut header = egui::RichText::new(format!("{}  ({})", node.operator, metrics_summary(node, total)));
+        if let Some(rank) = slowest.iter().position(|id| *id == node.id) {
+            header = header.color(SLOW_COLORS[rank]).strong();
+        }
+
+        egui::CollapsingHeader::new(header)
+            .id_salt(("plan_node", node.id))
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new(&node.details).monospace().weak());
+                for child in &node.children {
+                    Self::show_node(ui, child, total, slowest);
+                }
+            });
+    }
+}
+
+// Rows, compute time with its share of the total, and partitions of an operator
+fn metrics_summary(node: &PlanNode, total: Duration) -> String {
+    let mut parts = Vec::new();
+    if let Some(rows) = node.output_rows {
+        parts.push(format!("{} rows", rows));
+    }
+    if let Some(time) = node.elapsed_compute {
+        parts.push(format!("{} ({:.0}%)", format_duration(time), node.compute_fraction(total) * 100.0));
+    }
+    let plural = if node.partitions == 1 { "" } else { "s" };
+    parts.push(format!("{} partition{}", node.partitions, plural));
+    parts.join(", ")
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.as_millis() >= 1000 {
+        format!("{:.2} s", duration.as_secs_f64())
+    } else if duration.as_micros() >= 1000 {
+        format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+    } else {
+        format!("{} µs", duration.as_micros())
+    }
+}
@@ -1,39 +1,33 @@
 use egui::{Context, Id};
 use rfd::FileDialog;
-use std::fs::File;
-use std::io::Write;
-use crate::core::{QueryResult, CsvWriter};
+use crate::core::{export, ExportFormat, ExportOptions, ParquetCompression, QueryResult};
 
 pub struct ExportDialog {
     id: Id,
     result: QueryResult,
-    export_format: ExportFormat,
+    options: ExportOptions,
+    error: Option<String>,
     show: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ExportFormat {
-    Csv,
-    Json,
-}
-
 impl ExportDialog {
     pub fn new(id: Id, result: QueryResult) -> Self {
         Self {
             id,
             result,
-            export_format: ExportFormat::Csv,
+            options: ExportOptions::default(),
+            error: None,
             show: true,
         }
     }
-    
+
     pub fn show(&mut self, ctx: &Context) -> bool {
         if !self.show {
             return false;
         }
-        
+
         let mut keep_open = true;
-        
+
         egui::Window::new("Export Results")
             .id(self.id)
             .default_size([300.0, 150.0])
@@ -42,76 +36,107 @@ impl ExportDialog {
             .open(&mut keep_open)
             .show(ctx, |ui| {
                 ui.vertical(|ui| {
-                    ui.label("Select export format:");
-                    
-                    ui.radio_value(&mut self.export_format, ExportFormat::Csv, "CSV");
-                    ui.radio_value(&mut self.export_format, ExportFormat::Json, "JSON");
-                    
+                    show_export_options(ui, self.id, &mut self.options);
+
+                    if let Some(error) = &self.error {
+                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                    }
+
                     ui.separator();
-                    
+
                     ui.horizontal(|ui| {
                         if ui.button("Export").clicked() {
                             self.export();
-                            self.show = false;
                         }
-                        
+
                         if ui.button("Cancel").clicked() {
                             self.show = false;
                         }
                     });
                 });
             });
-        
+
         keep_open && self.show
     }
-    
-    fn export(&self) {
-        let extension = match self.export_format {
-            ExportFormat::Csv => "csv",
-            ExportFormat::Json => "json",
+
+    fn export(&mut self) {
+        let Some(path) = save_file_dialog(self.options.format, "results") else {
+            return;
         };
-        
-        if let Some(path) = FileDialog::new()
-            .add_filter(extension, &[extension])
-            .save_file()
-        {
-            match self.export_format {
-                ExportFormat::Csv => self.export_csv(&path),
-                ExportFormat::Json => self.export_json(&path),
-            }
+        match export::export_batches(&self.result.batches, &path, &self.options) {
+            Ok(_) => self.show = false,
+            Err(e) => self.error = Some(e.to_string()),
         }
     }
-    
-    fn export_csv(&self, path: &std::path::Path) {
-        if let Ok(mut writer) = CsvWriter::from_path(path) {
-            // Write headers
-            let _ = writer.write_headers(&self.result.columns);
-            
-            // Write rows
-            for row in &self.result.rows {
-                let _ = writer.write_record(row);
-            }
-            
-            let _ = writer.flush();
+}
+
+/// Ask where to save an export, suggesting `file_stem` with the format's extension
+pub fn save_file_dialog(format: ExportFormat, file_stem: &str) -> Option<std::path::PathBuf> {
+    let extension = format.extension();
+    FileDialog::new()
+        .add_filter(format.display_name(), &[extension])
+        .set_file_name(format!("{}.{}", file_stem, extension))
+        .save_file()
+}
+
+/// Format picker with the settings of the chosen format
+pub fn show_export_options(ui: &mut egui::Ui, id: Id, options: &mut ExportOptions) {
+    ui.horizontal(|ui| {
+        ui.label("Format:");
+        egui::ComboBox::from_id_salt(id.with("export_format"))
+            .selected_text(options.format.display_name())
+            .show_ui(ui, |ui| {
+                for format in ExportFormat::all() {
+                    ui.selectable_value(&mut options.format, format, format.display_name());
+                }
+            });
+    });
+
+    match options.format {
+        ExportFormat::Csv => {
+            let csv = &mut options.csv;
+            ui.horizontal(|ui| {
+                ui.label("Delimiter:");
+                byte_edit(ui, &mut csv.delimiter);
+                ui.label("Quote:");
+                byte_edit(ui, &mut csv.quote);
+                ui.label("NULL as:");
+                ui.add(egui::TextEdit::singleline(&mut csv.null_value)
+                    .desired_width(60.0)
+                    .hint_text("empty"));
+                ui.checkbox(&mut csv.header, "Header");
+            });
+        }
+        ExportFormat::Parquet => {
+            ui.horizontal(|ui| {
+                ui.label("Compression:");
+                egui::ComboBox::from_id_salt(id.with("export_compression"))
+                    .selected_text(options.compression.display_name())
+                    .show_ui(ui, |ui| {
+                        for compression in ParquetCompression::all() {
+                            ui.selectable_value(&mut options.compression, compression, compression.display_name());
+                        }
+                    });
+            });
         }
+        ExportFormat::ArrowFile | ExportFormat::ArrowStream | ExportFormat::NdJson | ExportFormat::Json => {}
     }
-    
-    fn export_json(&self, path: &std::path::Path) {
-        let json_data: Vec<serde_json::Map<String, serde_json::Value>> = self.result.rows
-            .iter()
-            .map(|row| {
-                let mut map = serde_json::Map::new();
-                for (i, value) in row.iter().enumerate() {
-                    if let Some(column) = self.result.columns.get(i) {
-                        map.insert(column.clone(), serde_json::Value::String(value.clone()));
-                    }
+}
+
+// One ASCII character, e.g. a delimiter; "\t" stands for a tab
+fn byte_edit(ui: &mut egui::Ui, value: &mut u8) {
+    let mut text = match *value {
+        b'\t' => "\\t".to_string(),
+        byte => (byte as char).to_string(),
+    };
+    if ui.add(egui::TextEdit::singleline(&mut text).desired_width(24.0)).changed() {
+        match text.as_str() {
+            "\\t" => *value = b'\t',
+            text => {
+                if let Some(c) = text.chars().last().filter(char::is_ascii) {
+                    *value = c as u8;
                 }
-                map
-            })
-            .collect();
-        
-        if let Ok(json_string) = serde_json::to_string_pretty(&json_data) {
-            let _ = File::create(path).and_then(|mut file| file.write_all(json_string.as_bytes()));
+            }
         }
     }
-} 
\ No newline at end of file
+}
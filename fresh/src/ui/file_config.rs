@@ -1,67 +1,21 @@
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use egui::{Context, Id};
-use crate::core::{Database, CsvReader};
+use crate::core::{Database, CsvReader, ParquetCompression, CsvIngestOptions};
+use crate::core::file_import::{import_files, validate_files, FileConfig};
+use crate::core::datetime::{self, DEFAULT_TIME_ZONE};
 use crate::infer::{TypeInferrer, ColumnType};
 
-#[derive(Clone)]
-pub struct FileConfig {
-    pub path: PathBuf,
-    pub table_name: String,
-    pub header_row: usize,
-    pub delimiter: char,
-    pub sample_size: usize,
-    pub columns: Vec<ColumnConfig>,
-    pub null_values: Vec<String>,
-    pub preview_data: Option<PreviewData>,
-}
-
-#[derive(Clone)]
-pub struct ColumnConfig {
-    pub name: String,
-    pub data_type: ColumnType,
-    pub included: bool,
-}
-
-#[derive(Clone)]
-pub struct PreviewData {
-    pub rows: Vec<Vec<String>>,
-    pub original_row_numbers: Vec<usize>,
-}
-
-impl FileConfig {
-    pub fn new(path: PathBuf) -> Self {
-        let table_name = path.file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("table")
-            .to_string();
-        
-        Self {
-            path,
-            table_name,
-            header_row: 1, // Default to row 1 (1-indexed) instead of 0
-            delimiter: ',',
-            sample_size: 1000,
-            columns: Vec::new(),
-            null_values: vec!["", "NULL", "null", "N/A", "-"].into_iter().map(String::from).collect(),
-            preview_data: None,
-        }
-    }
-    
-    pub fn file_name(&self) -> String {
-        self.path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string()
-    }
-}
-
 pub struct FileConfigDialog {
     pub show: bool,
     pub database_path: Option<PathBuf>,
     pub files: Vec<FileConfig>,
     pub current_file_index: usize,
     pub create_database: bool,
+    pub parquet_compression: ParquetCompression,
+    pub import_batch_size: usize,
+    /// Memory ceiling for a single imported table in MB (0 = no limit)
+    pub import_memory_limit_mb: usize,
     
     // UI state
     null_value_input: String,
@@ -80,57 +34,6 @@ pub enum ProcessingState {
 }
 
 impl FileConfigDialog {
-    // Helper function to parse CSV line properly, handling quoted strings
-    fn parse_csv_line(line: &str, delimiter: char) -> Vec<String> {
-        let mut row = Vec::new();
-        let mut current_field = String::new();
-        let mut in_quotes = false;
-        let mut chars = line.chars().peekable();
-        
-        while let Some(ch) = chars.next() {
-            match ch {
-                '"' => {
-                    if in_quotes {
-                        // End of quoted field
-                        in_quotes = false;
-                    } else {
-                        // Start of quoted field
-                        in_quotes = true;
-                    }
-                },
-                c if c == delimiter && !in_quotes => {
-                    // End of field
-                    row.push(current_field.trim().to_string());
-                    current_field.clear();
-                },
-                _ => {
-                    current_field.push(ch);
-                }
-            }
-        }
-        
-        // Add the last field
-        row.push(current_field.trim().to_string());
-        row
-    }
-
-    /// Infer the most likely delimiter from a header line
-    fn infer_delimiter_from_header(header_line: &str) -> char {
-        let delimiters = [',', '\t', ';', '|'];
-        let mut best_delimiter = ',';
-        let mut max_fields = 0;
-        
-        for &delimiter in &delimiters {
-            let fields = Self::parse_csv_line(header_line, delimiter);
-            if fields.len() > max_fields && fields.len() > 1 {
-                max_fields = fields.len();
-                best_delimiter = delimiter;
-            }
-        }
-        
-        best_delimiter
-    }
-
     /// Get the display name for a delimiter
     fn delimiter_display_name(delimiter: char) -> &'static str {
         match delimiter {
@@ -149,6 +52,9 @@ impl FileConfigDialog {
             files: Vec::new(),
             current_file_index: 0,
             create_database: false,
+            parquet_compression: ParquetCompression::default(),
+            import_batch_size: CsvIngestOptions::default().batch_size,
+            import_memory_limit_mb: 0,
             null_value_input: String::new(),
             error: None,
             processing_state: Arc::new(Mutex::new(ProcessingState::Idle)),
@@ -387,6 +293,23 @@ impl FileConfigDialog {
                             }
                         }
                     });
+                    ui.horizontal(|ui| {
+                        ui.label("Parquet Compression:");
+                        egui::ComboBox::from_id_salt("parquet_compression")
+                            .selected_text(self.parquet_compression.display_name())
+                            .show_ui(ui, |ui| {
+                                for compression in ParquetCompression::all() {
+                                    ui.selectable_value(&mut self.parquet_compression, compression, compression.display_name());
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Import Batch Size:");
+                        ui.add(egui::DragValue::new(&mut self.import_batch_size).range(256..=1_048_576).suffix(" rows"));
+                        ui.label("Memory Limit:");
+                        ui.add(egui::DragValue::new(&mut self.import_memory_limit_mb).range(0..=1_048_576).suffix(" MB"))
+                            .on_hover_text("Abort an import whose table grows beyond this size (0 = no limit)");
+                    });
                 });
                 if let Some(error) = &self.error.clone() {
                     ui.horizontal(|ui| {
@@ -533,34 +456,27 @@ impl FileConfigDialog {
                             // Delimiter
                             ui.horizontal(|ui| {
                                 ui.label("Delimiter:");
-                                
-                                // Try to infer delimiter from header if we have preview data
-                                let inferred_delimiter = if let Some(ref preview) = config.preview_data {
-                                    if let Some(header_line) = preview.rows.get(config.header_row.saturating_sub(1)) {
-                                        if let Some(header_str) = header_line.get(0) {
-                                            Self::infer_delimiter_from_header(header_str)
-                                        } else {
-                                            ','
-                                        }
-                                    } else {
-                                        ','
-                                    }
-                                } else {
-                                    ','
-                                };
+                                let previous_delimiter = config.delimiter();
                                 
                                 // Show inferred delimiter if it's different from current selection
-                                if inferred_delimiter != config.delimiter {
+                                let inferred_delimiter = config.detected_delimiter.unwrap_or(',');
+                                if config.delimiter.is_some_and(|d| d != inferred_delimiter) {
                                     ui.label(format!("Inferred: {}", Self::delimiter_display_name(inferred_delimiter)));
                                     if ui.button("Use Inferred").clicked() {
-                                        config.delimiter = inferred_delimiter;
+                                        config.delimiter = Some(inferred_delimiter);
                                     }
                                 }
                                 
-                                ui.radio_value(&mut config.delimiter, ',', "Comma");
-                                ui.radio_value(&mut config.delimiter, '\t', "Tab");
-                                ui.radio_value(&mut config.delimiter, ';', "Semicolon");
-                                ui.radio_value(&mut config.delimiter, '|', "Pipe");
+                                ui.radio_value(&mut config.delimiter, None, "Detect");
+                                ui.radio_value(&mut config.delimiter, Some(','), "Comma");
+                                ui.radio_value(&mut config.delimiter, Some('\t'), "Tab");
+                                ui.radio_value(&mut config.delimiter, Some(';'), "Semicolon");
+                                ui.radio_value(&mut config.delimiter, Some('|'), "Pipe");
+                                
+                                // The preview and the columns are split with the delimiter
+                                if config.delimiter() != previous_delimiter {
+                                    self.needs_resampling = true;
+                                }
                             });
                             
                             ui.add_space(10.0);
@@ -600,6 +516,37 @@ impl FileConfigDialog {
                             
                             ui.add_space(10.0);
                             
+                            // Time zone of date-times written without an offset
+                            ui.horizontal(|ui| {
+                                ui.label("Time Zone:");
+                                let mut detect = config.time_zone.is_none();
+                                if ui.checkbox(&mut detect, "Detect").changed() {
+                                    config.time_zone = if detect {
+                                        None
+                                    } else {
+                                        Some(config.detected_time_zone.clone().unwrap_or_else(|| DEFAULT_TIME_ZONE.to_string()))
+                                    };
+                                }
+                                match &mut config.time_zone {
+                                    None => {
+                                        let detected = match &config.detected_time_zone {
+                                            Some(zone) => format!("{} (from the offsets in the data)", zone),
+                                            None => format!("{} (the data has no offsets)", DEFAULT_TIME_ZONE),
+                                        };
+                                        ui.label(detected);
+                                    }
+                                    Some(zone) => {
+                                        ui.text_edit_singleline(zone)
+                                            .on_hover_text("A zone name such as Europe/Berlin, or an offset such as +02:00");
+                                        if datetime::parse_time_zone(zone).is_err() {
+                                            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "Unknown time zone");
+                                        }
+                                    }
+                                }
+                            });
+                            
+                            ui.add_space(10.0);
+                            
                             // Column selection
                             ui.group(|ui| {
                                 ui.set_width(ui.available_width());
@@ -640,6 +587,7 @@ impl FileConfigDialog {
                                                 .column(Column::auto().at_least(60.0)) // Include
                                                 .column(Column::auto().at_least(100.0).resizable(true)) // Column
                                                 .column(Column::auto().at_least(100.0)) // Type
+                                                .column(Column::auto().at_least(40.0)) // Key
                                                 .header(20.0, |mut header| {
                                                     header.col(|ui| {
                                                         ui.label(egui::RichText::new("Include").strong());
@@ -650,6 +598,10 @@ impl FileConfigDialog {
                                                     header.col(|ui| {
                                                         ui.label(egui::RichText::new("Type").strong());
                                                     });
+                                                    header.col(|ui| {
+                                                        ui.label(egui::RichText::new("Key").strong())
+                                                            .on_hover_text("Primary key: the checked columns together identify each row");
+                                                    });
                                                 })
                                                 .body(|mut body| {
                                                     for (col_idx, col) in config.columns.iter_mut().enumerate() {
@@ -674,7 +626,7 @@ impl FileConfigDialog {
                                                                         ui.selectable_value(&mut new_type, ColumnType::Real, "Float (64-bit)");
                                                                         ui.selectable_value(&mut new_type, ColumnType::Boolean, "Boolean");
                                                                         ui.selectable_value(&mut new_type, ColumnType::Date, "Date");
-                                                                        ui.selectable_value(&mut new_type, ColumnType::DateTime, "Time");
+                                                                        ui.selectable_value(&mut new_type, ColumnType::DateTime, "Date & Time");
                                                                         ui.selectable_value(&mut new_type, ColumnType::TimeSeconds, "Time (seconds)");
                                                                         ui.selectable_value(&mut new_type, ColumnType::TimeMilliseconds, "Time (milliseconds)");
                                                                         ui.selectable_value(&mut new_type, ColumnType::TimeMicroseconds, "Time (microseconds)");
@@ -707,14 +659,15 @@ impl FileConfigDialog {
                                                                     }
                                                                 }
                                                             });
+                                                            row.col(|ui| {
+                                                                ui.checkbox(&mut col.primary_key, "");
+                                                            });
                                                         });
                                                     }
                                                 });
                                         });
                                 }
                             });
-                            
-                            // Note: Primary key handling removed since it's not relevant for Arrow/DataFusion
                         }
                     }); // End left column
                     // Right side - data preview
@@ -810,272 +763,73 @@ impl FileConfigDialog {
     
     pub fn load_preview_for_current_file(&mut self) {
         if let Some(config) = self.files.get_mut(self.current_file_index) {
-            let path = config.path.clone();
-            let delimiter = config.delimiter;
-            let sample_size = config.sample_size;
-            let header_row = config.header_row;
-            
-                                // Load preview data from the beginning of the file (first 50 rows)
-                    match std::fs::read_to_string(&path) {
-                        Ok(content) => {
-                            let lines: Vec<&str> = content.lines().collect();
-                            
-                            // Take more lines to ensure we get 50 valid rows for preview
-                            let data_lines = lines.into_iter().take(200).collect::<Vec<&str>>();
-                    
-                    if data_lines.is_empty() {
-                        self.error = Some("No data found in file".to_string());
-                        return;
-                    }
-                    
-                    // Show raw file content for preview (first 50 lines)
-                    let mut preview_rows: Vec<Vec<String>> = Vec::new();
-                    let mut valid_data_rows = Vec::new();
-                    
-                    // Collect raw lines for preview (up to 50 lines)
-                    for (line_idx, line) in data_lines.iter().enumerate() {
-                        // Parse the line to show as individual cells
-                        let row = Self::parse_csv_line(line, delimiter);
-                        preview_rows.push(row.clone());
-                        
-                        // Also collect valid data rows for type inference (filtered)
-                        if !line.trim().starts_with('#') {
-                            let is_garbage = row.iter().all(|cell| cell.is_empty()) ||
-                                           row.iter().any(|cell| {
-                                               let cell_lower = cell.to_lowercase();
-                                               cell_lower.contains("fake line") ||
-                                               cell_lower.contains("ignore this") ||
-                                               cell_lower.contains("data starts here") ||
-                                               cell_lower.contains("realistic patterns")
-                                           });
-                            
-                            if !is_garbage && valid_data_rows.len() < sample_size {
-                                valid_data_rows.push(row);
-                            }
-                        }
-                        
-                        // Stop after 50 lines for preview
-                        if preview_rows.len() >= 50 {
-                            break;
-                        }
-                    }
-                    
-                    // Get the header row from the selected header row position (1-indexed to 0-indexed)
-                    let header_idx = header_row.saturating_sub(1);
-                    if header_idx < preview_rows.len() {
-                        let headers = preview_rows[header_idx].clone();
-                        
-                        // Use data rows after the header row for type inference (not filtered)
-                        let mut sample_data = Vec::new();
-                        for (idx, row) in preview_rows.iter().enumerate() {
-                            if idx > header_idx && sample_data.len() < sample_size {
-                                // Skip comment lines (lines starting with #)
-                                if !row.is_empty() && !row[0].starts_with('#') {
-                                    sample_data.push(row.clone());
-                                }
-                            }
-                        }
-                        
-                        // Infer types using the sample data with null value awareness
-                        let inferred_types = TypeInferrer::infer_column_types_with_nulls(&headers, &sample_data, &config.null_values);
-                        
-                        // Update columns
-                        config.columns.clear();
-                        for (header, (_name, data_type)) in headers.iter().zip(inferred_types.iter()) {
-                            config.columns.push(ColumnConfig {
-                                name: header.clone(),
-                                data_type: data_type.clone(),
-                                included: true,
-                            });
-                        }
-                    }
-                    
-                    config.preview_data = Some(PreviewData { 
-                        rows: preview_rows,
-                        original_row_numbers: Vec::new(), // Not used anymore
-                    });
-                }
-                Err(e) => {
-                    self.error = Some(format!("Failed to load preview: {}", e));
-                }
+            if let Err(e) = config.load_preview() {
+                self.error = Some(e);
             }
         }
     }
     
     fn start_database_creation(&mut self) -> Option<PathBuf> {
         let db_path = self.database_path.clone()?;
-        let mut files = self.files.clone();
-        
-        // For DataFusion, we need to create the database synchronously
-        // since it's in-memory and we need to return the actual database instance
-        match Database::open_writable(&db_path) {
-            Ok(mut db) => {
-                let total_files = files.len();
-                
-                // Create all tables first
-                // Skip pre-creating tables - let stream_insert_csv handle schema creation from CSV headers
-                
-                // Import data for each file
-                for (file_idx, config) in files.iter_mut().enumerate() {
-                    // Use enhanced streaming import with custom header row
-                    match db.stream_insert_csv_with_header_row(&config.table_name, &config.path, config.delimiter, config.header_row.saturating_sub(1)) {
-                        Ok(inferred_delimiter) => {
-                            // Update the config with the inferred delimiter if it was auto-detected
-                            if config.delimiter == ',' {
-                                config.delimiter = inferred_delimiter;
-                            }
-                        }
-                        Err(e) => {
-                            self.error = Some(format!("Failed to import {}: {}", config.file_name(), e));
-                            return None;
-                        }
-                    }
-                }
-                
-                // Save all tables to persistence (Arrow IPC format) directly in the project folder
-                if let Err(e) = db.save_all_tables(&db_path) {
-                    self.error = Some(format!("Failed to save tables to persistence: {}", e));
-                    return None;
-                }
-                
-                // Return the path for the app to load
-                Some(db_path)
-            }
-            Err(e) => {
-                self.error = Some(format!("Failed to create database: {}", e));
+        let files = self.files.clone();
+        let parquet_compression = self.parquet_compression;
+        let ingest_options = CsvIngestOptions {
+            batch_size: self.import_batch_size,
+            memory_limit_bytes: if self.import_memory_limit_mb > 0 {
+                Some(self.import_memory_limit_mb * 1024 * 1024)
+            } else {
                 None
-            }
+            },
+            ..CsvIngestOptions::default()
+        };
+        let processing_state = self.processing_state.clone();
+        if let Ok(mut state) = processing_state.lock() {
+            *state = ProcessingState::Processing("Initializing DataFusion context...".to_string(), 0.0);
         }
+        
+        // Import on a worker thread; show() picks up progress and completion from the processing state
+        std::thread::spawn(move || {
+            Self::create_database_in_thread(db_path, files, parquet_compression, ingest_options, processing_state);
+        });
+        None
     }
     
     fn create_database_in_thread(
         db_path: PathBuf,
         mut files: Vec<FileConfig>,
+        parquet_compression: ParquetCompression,
+        ingest_options: CsvIngestOptions,
         processing_state: Arc<Mutex<ProcessingState>>
     ) {
-        // Update state to processing
-        if let Ok(mut state) = processing_state.lock() {
-            *state = ProcessingState::Processing("Initializing DataFusion context...".to_string(), 0.0);
-        }
-        
-        match Database::open_writable(&db_path) {
-            Ok(mut db) => {
-                let total_files = files.len();
-                
-                // DataFusion is in-memory, so no need for pragmas or transactions
-                // Just proceed with table creation and data import
-                
-                // Skip pre-creating tables - let stream_insert_csv handle schema creation from CSV headers
-                
-                // Now import data for each file
-                for (file_idx, config) in files.iter_mut().enumerate() {
-                    // Update initial progress for this file
-                    if let Ok(mut state) = processing_state.lock() {
-                        *state = ProcessingState::Processing(
-                            format!("Importing {} ({}/{})", config.file_name(), file_idx + 1, total_files),
-                            0.2 + (0.7 * file_idx as f32 / total_files as f32)
-                        );
-                    }
-                    
-                    // Use enhanced streaming import with custom header row
-                    match db.stream_insert_csv_with_header_row(&config.table_name, &config.path, config.delimiter, config.header_row.saturating_sub(1)) {
-                        Ok(inferred_delimiter) => {
-                            // Update the config with the inferred delimiter if it was auto-detected
-                            if config.delimiter == ',' {
-                                config.delimiter = inferred_delimiter;
-                            }
-                        }
-                        Err(e) => {
-                            if let Ok(mut state) = processing_state.lock() {
-                                *state = ProcessingState::Error(format!("Failed to import {}: {}", config.file_name(), e));
-                            }
-                            let _ = db.rollback_transaction();
-                            return;
-                        }
-                    }
-                    
-                    // Final progress update for this file
-                    if let Ok(mut state) = processing_state.lock() {
-                        let overall_progress = 0.2 + (0.7 * (file_idx + 1) as f32 / total_files as f32);
-                        *state = ProcessingState::Processing(
-                            format!("Completed {}", config.file_name()),
-                            overall_progress
-                        );
-                    }
-                }
-                
-                // eprintln!("All files imported, creating indexes...");
-                
-                // Note: DataFusion is in-memory, so no indexes or transactions needed
-                if let Ok(mut state) = processing_state.lock() {
-                    *state = ProcessingState::Processing("Saving tables to persistence...".to_string(), 0.9);
-                }
-                
-                // Save all tables to persistence (Arrow IPC format) directly in the project folder
-                if let Err(e) = db.save_all_tables(&db_path) {
-                    eprintln!("[FileConfig] Warning: Failed to save tables to persistence: {}", e);
-                    // Don't fail the entire operation, just log the warning
-                } else {
-                    eprintln!("[FileConfig] Successfully saved {} tables to persistence", 
-                             std::fs::read_dir(&db_path).map(|entries| entries.count()).unwrap_or(0));
-                }
-                
-                // eprintln!("DataFusion context finalized successfully");
-                
-                // Explicitly drop the database connection to ensure it's closed
-                drop(db);
-                
-                if let Ok(mut state) = processing_state.lock() {
-                    *state = ProcessingState::Complete;
-                    // eprintln!("State set to Complete");
-                }
+        let set_state = |state: ProcessingState| {
+            if let Ok(mut current) = processing_state.lock() {
+                *current = state;
             }
+        };
+        set_state(ProcessingState::Processing("Initializing DataFusion context...".to_string(), 0.0));
+        
+        let db = match Database::open_writable(&db_path) {
+            Ok(db) => db,
             Err(e) => {
-                // eprintln!("Failed to create database: {}", e);
-                if let Ok(mut state) = processing_state.lock() {
-                    *state = ProcessingState::Error(format!("Failed to create database: {}", e));
-                }
+                set_state(ProcessingState::Error(format!("Failed to create database: {}", e)));
+                return;
             }
-        }
+        };
+        db.set_parquet_compression(parquet_compression);
         
-        // eprintln!("create_database_in_thread finished");
+        let mut report_progress = |message: String, progress: f32| {
+            set_state(ProcessingState::Processing(message, progress));
+        };
+        match import_files(&db, &db_path, &mut files, &ingest_options, &mut report_progress) {
+            Ok(()) => {
+                eprintln!("[FileConfig] Successfully saved {} tables to persistence", files.len());
+                set_state(ProcessingState::Complete);
+            }
+            Err(e) => set_state(ProcessingState::Error(e)),
+        }
     }
 
     fn validate_constraints(&self) -> Option<String> {
-        for config in &self.files {
-            // Validate table name
-            if config.table_name.is_empty() {
-                return Some(format!("File '{}' has an empty table name", config.file_name()));
-            }
-            
-            // Check for invalid characters in table name
-            if !config.table_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
-                return Some(format!(
-                    "Table name '{}' contains invalid characters. Use only letters, numbers, and underscores.",
-                    config.table_name
-                ));
-            }
-            
-            // Check that at least one column is included
-            if !config.columns.iter().any(|c| c.included) {
-                return Some(format!(
-                    "Table '{}' has no columns selected. Select at least one column to include.",
-                    config.table_name
-                ));
-            }
-        }
-        
-        // Check for duplicate table names
-        let mut table_names = std::collections::HashSet::new();
-        for config in &self.files {
-            if !table_names.insert(&config.table_name) {
-                return Some(format!(
-                    "Duplicate table name '{}'. Each table must have a unique name.",
-                    config.table_name
-                ));
-            }
-        }
-        
-        None
+        validate_files(&self.files)
     }
-} 
\ No newline at end of file
+}
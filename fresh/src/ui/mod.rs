@@ -1,6 +1,12 @@
 mod sidebar;
 mod query_window;
+mod parameter_panel;
+mod sql_editor;
+mod explain_window;
+mod query_history;
+mod table_profile;
 mod csv_import;
+mod data_quality;
 mod duplicate_detection;
 mod data_transformation;
 pub mod file_config;
@@ -14,14 +20,17 @@ pub mod plots;
 
 pub use sidebar::{Sidebar, SidebarAction};
 pub use query_window::QueryWindow;
-pub use plot_window::{PlotWindow, PlotConfig};
+pub use query_history::{QueryHistoryWindow, QueryHistoryAction};
+pub use table_profile::TableProfileWindow;
+pub use plot_window::{PlotWindow, PlotConfig, PlotSource};
 pub use plots::PlotType;
 pub use csv_import::CsvImportDialog;
+pub use data_quality::DataQualityWindow;
 pub use duplicate_detection::{DuplicateDetectionDialog, DuplicateResultsViewer};
 pub use data_transformation::{TransformationDialog, TransformationRequest, TransformationManager};
-pub use file_config::{FileConfigDialog, FileConfig};
+pub use file_config::FileConfigDialog;
 pub use theme::apply_theme;
 pub use home_screen::HomeScreen;
 pub use multi_csv_import::MultiCsvImportDialog;
-pub use export_dialog::{ExportDialog, ExportFormat};
+pub use export_dialog::ExportDialog;
 pub use gpu_renderer::{GpuPlotRenderer, RenderMode, GpuRenderer, GpuCapabilities}; 
\ No newline at end of file
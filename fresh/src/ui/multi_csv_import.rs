@@ -280,7 +280,7 @@ impl MultiCsvImportDialog {
         let db_path = self.database_path.as_ref()?;
         
         match Database::open_writable(db_path) {
-            Ok(mut db) => {
+            Ok(db) => {
                 // Process each included CSV file
                 for config in &self.csv_files {
                     if !config.include {
@@ -0,0 +1,48 @@
+use std::hash::Hash;
+use crate::core::{ParameterType, QueryParameter};
+
+/// One typed input per query parameter, laid out as a grid. Returns true when Enter
+/// was pressed in one of the inputs, so the caller can run the query.
+pub fn show(ui: &mut egui::Ui, id_salt: impl Hash, parameters: &mut [QueryParameter]) -> bool {
+    let mut submitted = false;
+
+    egui::Grid::new(id_salt)
+        .num_columns(4)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            for parameter in parameters.iter_mut() {
+                ui.monospace(parameter.label());
+                ui.label(egui::RichText::new(parameter.param_type.name()).color(egui::Color32::from_gray(140)));
+
+                match parameter.param_type {
+                    ParameterType::Boolean => {
+                        let mut checked = parameter.value.eq_ignore_ascii_case("true");
+                        // A checkbox always holds a value
+                        if ui.checkbox(&mut checked, "").changed() || parameter.value.is_empty() {
+                            parameter.value = checked.to_string();
+                        }
+                    }
+                    param_type => {
+                        let width = match param_type {
+                            ParameterType::Integer | ParameterType::Float | ParameterType::Time => 100.0,
+                            ParameterType::Date => 110.0,
+                            _ => 180.0,
+                        };
+                        let response = ui.add(egui::TextEdit::singleline(&mut parameter.value)
+                            .desired_width(width)
+                            .hint_text(param_type.hint()));
+                        submitted |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    }
+                }
+
+                match parameter.validate() {
+                    Ok(()) => ui.label(""),
+                    Err(e) => ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "✗")
+                        .on_hover_text(e.to_string()),
+                };
+                ui.end_row();
+            }
+        });
+
+    submitted
+}
@@ -1,5 +1,6 @@
 use egui::{Color32, RichText, Ui, ScrollArea, CollapsingHeader, Grid, ComboBox};
-use crate::core::QueryResult;
+use crate::core::{QueryParameter, QueryResult};
+use crate::ui::parameter_panel;
 use crate::ui::plots::{self, PlotType, PlotData, PlotPoint, Plot as PlotTrait, PlotConfiguration, PlotSpecificConfig};
 use crate::ui::gpu_renderer::{GpuPlotRenderer, RenderMode};
 use datafusion::arrow::datatypes::DataType;
@@ -143,6 +144,13 @@ impl PlotConfig {
     }
 }
 
+/// The query a plot was made from and the parameter values it ran with
+#[derive(Debug, Clone, Default)]
+pub struct PlotSource {
+    pub sql: String,
+    pub parameters: Vec<QueryParameter>,
+}
+
 pub struct PlotWindow<'a> {
     pub id: String,
     pub title: String,
@@ -150,7 +158,8 @@ pub struct PlotWindow<'a> {
     pub is_config_open: bool,
     pub open: bool,
     data: Option<QueryResult>,
-    source_query_id: Option<String>,
+    source: Option<PlotSource>,
+    refresh_requested: bool,
     gpu_renderer: Option<GpuPlotRenderer<'a>>,
 }
 
@@ -163,7 +172,8 @@ impl<'a> PlotWindow<'a> {
             is_config_open: true,
             open: true,
             data: None,
-            source_query_id: None,
+            source: None,
+            refresh_requested: false,
             gpu_renderer: None,
         }
     }
@@ -172,8 +182,17 @@ impl<'a> PlotWindow<'a> {
         self.data = Some(data);
     }
 
-    pub fn set_source_query(&mut self, query_id: String) {
-        self.source_query_id = Some(query_id);
+    pub fn set_source(&mut self, source: PlotSource) {
+        self.source = Some(source);
+    }
+
+    /// The source query with the parameter values to re-run it with, once after
+    /// the user asked for a refresh
+    pub fn take_refresh_request(&mut self) -> Option<PlotSource> {
+        if !std::mem::take(&mut self.refresh_requested) {
+            return None;
+        }
+        self.source.clone()
     }
 
     /// Initialize GPU renderer if available
@@ -297,6 +316,19 @@ impl<'a> PlotWindow<'a> {
         }
     }
 
+    /// Get numeric and temporal columns for the X axis of line charts
+    fn get_valid_line_x_columns(&self) -> Vec<String> {
+        if let Some(data) = &self.data {
+            data.columns.iter()
+                .zip(data.column_types.iter())
+                .filter(|(_, column_type)| plots::is_numeric_type(column_type) || plots::is_temporal_type(column_type))
+                .map(|(col_name, _)| col_name.clone())
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
     /// Get valid categorical columns for X axis
     fn get_valid_categorical_columns(&self) -> Vec<String> {
         if let Some(data) = &self.data {
@@ -343,6 +375,19 @@ impl<'a> PlotWindow<'a> {
                 
                 ui.separator();
                 
+                // Parameters of the source query; changing them re-runs it
+                if let Some(source) = self.source.as_mut().filter(|s| !s.parameters.is_empty()) {
+                    CollapsingHeader::new("Query Parameters")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            let submitted = parameter_panel::show(ui, format!("{}_parameters", self.id), &mut source.parameters);
+                            if ui.button("Refresh").on_hover_text("Re-run the query with these values").clicked() || submitted {
+                                self.refresh_requested = true;
+                            }
+                        });
+                    ui.separator();
+                }
+                
                 // Plot Type Selection with categories
                 ui.horizontal(|ui| {
                     ui.label("Plot Type:");
@@ -476,6 +521,7 @@ impl<'a> PlotWindow<'a> {
         let valid_columns = self.get_valid_columns_for_plot(plot_type);
         let numeric_columns = self.get_valid_numeric_columns();
         let categorical_columns = self.get_valid_categorical_columns();
+        let line_x_columns = self.get_valid_line_x_columns();
         
         // Primary columns (required)
         ui.label(RichText::new("Required Columns:").strong());
@@ -505,9 +551,13 @@ impl<'a> PlotWindow<'a> {
                     PlotType::Histogram => {
                         &numeric_columns // Only numeric columns for histograms
                     },
-                    PlotType::ScatterPlot | PlotType::LineChart => {
+                    PlotType::ScatterPlot => {
                         &numeric_columns // Both X and Y should be numeric
                     },
+                    PlotType::LineChart => {
+                        // X may also be a date or time
+                        if i == 0 { &line_x_columns } else { &numeric_columns }
+                    },
                     PlotType::BoxPlot | PlotType::ViolinPlot => {
                         if i == 0 { &categorical_columns } else { &numeric_columns }
                     },
@@ -1080,20 +1130,12 @@ impl<'a> PlotWindow<'a> {
 
             let mut points = Vec::new();
             
-            for (row_idx, row) in data.rows.iter().enumerate() {
-                if row.len() > y_idx {
-                    let y_val = row[y_idx].parse::<f64>()
-                        .map_err(|_| format!("Failed to parse Y value '{}' as number", row[y_idx]))?;
-                    
-                    let x_val = if let Some(x_idx) = x_idx {
-                        if row.len() > x_idx {
-                            row[x_idx].parse::<f64>().unwrap_or(row_idx as f64)
-                        } else {
-                            row_idx as f64
-                        }
-                    } else {
-                        row_idx as f64
-                    };
+            let x_values = x_idx.map(|x_idx| data.f64_column(x_idx));
+            for (row_idx, y_val) in data.f64_column(y_idx).into_iter().enumerate() {
+                if let Some(y_val) = y_val {
+                    let x_val = x_values.as_ref()
+                        .and_then(|values| values[row_idx])
+                        .unwrap_or(row_idx as f64);
                     
                     // Create tooltip data
                     let mut tooltip_data = std::collections::HashMap::new();
@@ -1125,10 +1167,8 @@ impl<'a> PlotWindow<'a> {
             
             let mut points = Vec::new();
             
-            for (row_idx, row) in data.rows.iter().enumerate() {
-                if row.len() > col_idx {
-                    let val = row[col_idx].parse::<f64>()
-                        .map_err(|_| format!("Failed to parse value '{}' as number", row[col_idx]))?;
+            for (row_idx, val) in data.f64_column(col_idx).into_iter().enumerate() {
+                if let Some(val) = val {
                     
                     // Create tooltip data
                     let mut tooltip_data = std::collections::HashMap::new();
@@ -222,14 +222,7 @@ impl PlotTrait for AnomalyPlot {
             .ok_or_else(|| format!("Y column '{}' not found", config.y_column))?;
         
         // Extract numeric values
-        let mut values = Vec::new();
-        for row in &query_result.rows {
-            if row.len() > y_idx {
-                if let Ok(val) = row[y_idx].parse::<f64>() {
-                    values.push(val);
-                }
-            }
-        }
+        let values: Vec<f64> = query_result.f64_column(y_idx).into_iter().flatten().collect();
         
         if values.is_empty() {
             return Err("No valid numeric data found in Y column".to_string());
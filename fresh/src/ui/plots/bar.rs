@@ -39,6 +39,7 @@ impl BarChartPlot {
         
         // Get unique groups from group column
         let groups = self.get_unique_categories(query_result, group_col)?;
+        let sums = self.sum_by_category(query_result, config, Some(group_col))?;
         
         // Get bar chart specific config
         let default_config;
@@ -59,28 +60,8 @@ impl BarChartPlot {
             
             // For each category, get the value for this group
             for (cat_idx, category) in categories.iter().enumerate() {
-                // Filter data for this category and group
-                let filtered_rows: Vec<&Vec<String>> = query_result.rows.iter()
-                    .filter(|row| {
-                        let x_idx = query_result.columns.iter().position(|c| c == &config.x_column).unwrap_or(0);
-                        let group_idx = query_result.columns.iter().position(|c| c == group_col).unwrap_or(0);
-                        
-                        row.len() > x_idx && row.len() > group_idx && 
-                        row[x_idx] == *category && row[group_idx] == *group
-                    })
-                    .collect();
-                
-                // Aggregate values
-                let y_idx = query_result.columns.iter().position(|c| c == &config.y_column).unwrap_or(0);
-                let sum: f64 = filtered_rows.iter()
-                    .filter_map(|row| {
-                        if row.len() > y_idx {
-                            row[y_idx].parse::<f64>().ok()
-                        } else {
-                            None
-                        }
-                    })
-                    .sum();
+                // Total of this category and group
+                let sum = sums.get(&(category.clone(), Some(group.clone()))).copied().unwrap_or(0.0);
                 
                 // Calculate x position for grouped bars
                 // Each category gets its own x position, and groups are offset within that position
@@ -142,6 +123,7 @@ impl BarChartPlot {
         
         // Get unique groups from group column
         let groups = self.get_unique_categories(query_result, group_col)?;
+        let sums = self.sum_by_category(query_result, config, Some(group_col))?;
         
         // Get bar chart specific config
         let default_config;
@@ -169,28 +151,8 @@ impl BarChartPlot {
             
             // For each category, get the value for this group
             for (cat_idx, category) in categories.iter().enumerate() {
-                // Filter data for this category and group
-                let filtered_rows: Vec<&Vec<String>> = query_result.rows.iter()
-                    .filter(|row| {
-                        let x_idx = query_result.columns.iter().position(|c| c == &config.x_column).unwrap_or(0);
-                        let group_idx = query_result.columns.iter().position(|c| c == group_col).unwrap_or(0);
-                        
-                        row.len() > x_idx && row.len() > group_idx && 
-                        row[x_idx] == *category && row[group_idx] == *group
-                    })
-                    .collect();
-                
-                // Aggregate values
-                let y_idx = query_result.columns.iter().position(|c| c == &config.y_column).unwrap_or(0);
-                let sum: f64 = filtered_rows.iter()
-                    .filter_map(|row| {
-                        if row.len() > y_idx {
-                            row[y_idx].parse::<f64>().ok()
-                        } else {
-                            None
-                        }
-                    })
-                    .sum();
+                // Total of this category and group
+                let sum = sums.get(&(category.clone(), Some(group.clone()))).copied().unwrap_or(0.0);
                 
                 // Get current cumulative height for this category
                 let base_height = *cumulative_heights.get(category).unwrap_or(&0.0);
@@ -250,6 +212,7 @@ impl BarChartPlot {
         
         // Get unique groups from group column
         let groups = self.get_unique_categories(query_result, group_col)?;
+        let sums = self.sum_by_category(query_result, config, Some(group_col))?;
         
         // Get bar chart specific config
         let default_config;
@@ -261,25 +224,10 @@ impl BarChartPlot {
         };
         
         // First pass: calculate total for each category
+        let totals = self.sum_by_category(query_result, config, None)?;
         let mut category_totals: HashMap<String, f64> = HashMap::new();
         for category in &categories {
-            let filtered_rows: Vec<&Vec<String>> = query_result.rows.iter()
-                .filter(|row| {
-                    let x_idx = query_result.columns.iter().position(|c| c == &config.x_column).unwrap_or(0);
-                    row.len() > x_idx && row[x_idx] == *category
-                })
-                .collect();
-            
-            let y_idx = query_result.columns.iter().position(|c| c == &config.y_column).unwrap_or(0);
-            let total: f64 = filtered_rows.iter()
-                .filter_map(|row| {
-                    if row.len() > y_idx {
-                        row[y_idx].parse::<f64>().ok()
-                    } else {
-                        None
-                    }
-                })
-                .sum();
+            let total = totals.get(&(category.clone(), None)).copied().unwrap_or(0.0);
             
             category_totals.insert(category.clone(), total);
         }
@@ -301,28 +249,8 @@ impl BarChartPlot {
             
             // For each category, get the value for this group
             for (cat_idx, category) in categories.iter().enumerate() {
-                // Filter data for this category and group
-                let filtered_rows: Vec<&Vec<String>> = query_result.rows.iter()
-                    .filter(|row| {
-                        let x_idx = query_result.columns.iter().position(|c| c == &config.x_column).unwrap_or(0);
-                        let group_idx = query_result.columns.iter().position(|c| c == group_col).unwrap_or(0);
-                        
-                        row.len() > x_idx && row.len() > group_idx && 
-                        row[x_idx] == *category && row[group_idx] == *group
-                    })
-                    .collect();
-                
-                // Aggregate values
-                let y_idx = query_result.columns.iter().position(|c| c == &config.y_column).unwrap_or(0);
-                let sum: f64 = filtered_rows.iter()
-                    .filter_map(|row| {
-                        if row.len() > y_idx {
-                            row[y_idx].parse::<f64>().ok()
-                        } else {
-                            None
-                        }
-                    })
-                    .sum();
+                // Total of this category and group
+                let sum = sums.get(&(category.clone(), Some(group.clone()))).copied().unwrap_or(0.0);
                 
                 // Calculate percentage
                 let total = category_totals.get(category).unwrap_or(&1.0);
@@ -371,17 +299,47 @@ impl BarChartPlot {
         Ok(all_series)
     }
     
+    /// Sum of the Y column for each X category, and for each group too when `group_column`
+    /// is given. Rows with a NULL category or group are left out; NULL values add nothing.
+    fn sum_by_category(
+        &self,
+        query_result: &QueryResult,
+        config: &PlotConfiguration,
+        group_column: Option<&str>,
+    ) -> Result<HashMap<(String, Option<String>), f64>, String> {
+        let column_index = |name: &str| query_result.column_index(name)
+            .ok_or_else(|| format!("Column '{}' not found", name));
+        let categories = query_result.string_column(column_index(&config.x_column)?);
+        let values = query_result.f64_column(column_index(&config.y_column)?);
+        let groups = group_column.map(|name| column_index(name).map(|idx| query_result.string_column(idx))).transpose()?;
+        
+        let mut sums = HashMap::new();
+        for (row_idx, (category, value)) in categories.into_iter().zip(values).enumerate() {
+            let Some(category) = category else {
+                continue;
+            };
+            let group = match &groups {
+                Some(groups) => match &groups[row_idx] {
+                    Some(group) => Some(group.clone()),
+                    None => continue,
+                },
+                None => None,
+            };
+            *sums.entry((category, group)).or_insert(0.0) += value.unwrap_or(0.0);
+        }
+        Ok(sums)
+    }
+    
     /// Get unique categories from a column
     fn get_unique_categories(&self, query_result: &QueryResult, column: &str) -> Result<Vec<String>, String> {
         let col_idx = query_result.columns.iter().position(|c| c == column)
             .ok_or_else(|| format!("Column '{}' not found", column))?;
         
-        let mut categories = std::collections::HashSet::new();
-        for row in &query_result.rows {
-            if row.len() > col_idx {
-                categories.insert(row[col_idx].clone());
-            }
-        }
+        // NULL categories have no bar
+        let categories: std::collections::HashSet<String> = query_result.string_column(col_idx)
+            .into_iter()
+            .flatten()
+            .collect();
         
         let mut categories_vec: Vec<String> = categories.into_iter().collect();
         
@@ -493,6 +451,7 @@ impl BarChartPlot {
         
         // Get unique categories from X column
         let categories = self.get_unique_categories(query_result, &config.x_column)?;
+        let totals = self.sum_by_category(query_result, config, None)?;
         
         // Get bar chart specific config
         let default_config;
@@ -509,25 +468,7 @@ impl BarChartPlot {
         let series_color = colors[0]; // Use first color for the series
         
         for (cat_idx, category) in categories.iter().enumerate() {
-            // Filter data for this category
-            let filtered_rows: Vec<&Vec<String>> = query_result.rows.iter()
-                .filter(|row| {
-                    let x_idx = query_result.columns.iter().position(|c| c == &config.x_column).unwrap_or(0);
-                    row.len() > x_idx && row[x_idx] == *category
-                })
-                .collect();
-            
-            // Aggregate values
-            let y_idx = query_result.columns.iter().position(|c| c == &config.y_column).unwrap_or(0);
-            let sum: f64 = filtered_rows.iter()
-                .filter_map(|row| {
-                    if row.len() > y_idx {
-                        row[y_idx].parse::<f64>().ok()
-                    } else {
-                        None
-                    }
-                })
-                .sum();
+            let sum = totals.get(&(category.clone(), None)).copied().unwrap_or(0.0);
             
             // Create tooltip data
             let mut tooltip_data = HashMap::new();
@@ -291,25 +291,19 @@ impl BoxPlotImpl {
         let mut grouped_data: HashMap<String, Vec<f64>> = HashMap::new();
         
         // Collect data by group
-        for row in &query_result.rows {
-            if row.len() > y_idx {
-                if let Ok(y_val) = row[y_idx].parse::<f64>() {
-                    let group_name = if let Some(group_col) = group_by {
-                        if let Some(group_idx) = query_result.columns.iter().position(|c| c == group_col) {
-                            if row.len() > group_idx {
-                                row[group_idx].clone()
-                            } else {
-                                "Unknown".to_string()
-                            }
-                        } else {
-                            "Unknown".to_string()
-                        }
-                    } else {
-                        "All Data".to_string()
-                    };
-                    
-                    grouped_data.entry(group_name).or_insert_with(Vec::new).push(y_val);
-                }
+        let y_values = query_result.f64_column(y_idx);
+        let group_values = group_by.map(|group_col| {
+            query_result.column_index(group_col).map(|group_idx| query_result.string_column(group_idx))
+        });
+        for (row_idx, y_val) in y_values.into_iter().enumerate() {
+            if let Some(y_val) = y_val {
+                let group_name = match &group_values {
+                    Some(Some(groups)) => groups[row_idx].clone().unwrap_or_else(|| "Unknown".to_string()),
+                    Some(None) => "Unknown".to_string(),
+                    None => "All Data".to_string(),
+                };
+                
+                grouped_data.entry(group_name).or_default().push(y_val);
             }
         }
         
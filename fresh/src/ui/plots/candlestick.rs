@@ -1,4 +1,4 @@
-use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points};
+use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points, numeric_column};
 use egui::{Ui, Color32, RichText, Vec2, Pos2, Rect, Response, Stroke};
 use egui_plot::{Plot, PlotPoints, PlotBounds, Line, PlotUi};
 use datafusion::arrow::datatypes::DataType;
@@ -65,81 +65,73 @@ impl PlotTrait for CandlestickPlot {
         let mut points = Vec::new();
         let mut candlestick_data = Vec::new();
         
-        for (row_idx, row) in query_result.rows.iter().enumerate() {
-            if row.len() > x_idx && row.len() > y_idx {
-                // Parse time value
-                let time_val = if let Ok(timestamp) = row[x_idx].parse::<f64>() {
-                    timestamp
-                } else {
-                    // Try to parse as date string or use row index
-                    row_idx as f64
-                };
-                
-                let open_val = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse Open value '{}' as number", row[y_idx]))?;
-                
-                // Parse High, Low, Close values
-                let high_val = if let Some(high_idx) = high_idx {
-                    if row.len() > high_idx {
-                        row[high_idx].parse::<f64>().unwrap_or(open_val)
-                    } else {
-                        open_val
-                    }
-                } else {
-                    open_val * 1.02 // Default high
-                };
-                
-                let low_val = if let Some(low_idx) = low_idx {
-                    if row.len() > low_idx {
-                        row[low_idx].parse::<f64>().unwrap_or(open_val)
-                    } else {
-                        open_val
-                    }
-                } else {
-                    open_val * 0.98 // Default low
-                };
-                
-                // Use open as close if no close column
-                let close_val = open_val;
-                
-                // Determine if bullish or bearish
-                let is_bullish = close_val >= open_val;
-                let color = if is_bullish {
-                    Color32::from_rgb(0, 150, 0) // Green for bullish
-                } else {
-                    Color32::from_rgb(200, 0, 0) // Red for bearish
-                };
-                
-                // Create tooltip data
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("Time".to_string(), time_val.to_string());
-                tooltip_data.insert("Open".to_string(), open_val.to_string());
-                tooltip_data.insert("High".to_string(), high_val.to_string());
-                tooltip_data.insert("Low".to_string(), low_val.to_string());
-                tooltip_data.insert("Close".to_string(), close_val.to_string());
-                tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-                
-                points.push(PlotPoint {
-                    x: time_val,
-                    y: open_val,
-                    z: None,
-                    label: None,
-                    color: Some(color),
-                    size: None,
-                    series_id: None,
-                    tooltip_data,
-                });
-                
-                candlestick_data.push(CandlestickData {
-                    time: time_val,
-                    open: open_val,
-                    high: high_val,
-                    low: low_val,
-                    close: close_val,
-                    is_bullish,
-                });
-            }
+        let times = query_result.f64_column(x_idx);
+        let opens = numeric_column(query_result, y_idx, "Open")?;
+        let highs = high_idx.map(|idx| query_result.f64_column(idx));
+        let lows = low_idx.map(|idx| query_result.f64_column(idx));
+        
+        for row_idx in 0..query_result.num_rows() {
+            // Rows without an Open value have no candle
+            let Some(open_val) = opens[row_idx] else {
+                continue;
+            };
+            
+            // Time value, or the row index where there is none
+            let time_val = times[row_idx].unwrap_or(row_idx as f64);
+            
+            // Parse High, Low, Close values
+            let high_val = if let Some(highs) = &highs {
+                highs[row_idx].unwrap_or(open_val)
+            } else {
+                open_val * 1.02 // Default high
+            };
+            
+            let low_val = if let Some(lows) = &lows {
+                lows[row_idx].unwrap_or(open_val)
+            } else {
+                open_val * 0.98 // Default low
+            };
+            
+            // Use open as close if no close column
+            let close_val = open_val;
+            
+            // Determine if bullish or bearish
+            let is_bullish = close_val >= open_val;
+            let color = if is_bullish {
+                Color32::from_rgb(0, 150, 0) // Green for bullish
+            } else {
+                Color32::from_rgb(200, 0, 0) // Red for bearish
+            };
+            
+            // Create tooltip data
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("Time".to_string(), time_val.to_string());
+            tooltip_data.insert("Open".to_string(), open_val.to_string());
+            tooltip_data.insert("High".to_string(), high_val.to_string());
+            tooltip_data.insert("Low".to_string(), low_val.to_string());
+            tooltip_data.insert("Close".to_string(), close_val.to_string());
+            tooltip_data.insert(config.x_column.clone(), query_result.display_value(row_idx, x_idx));
+            tooltip_data.insert(config.y_column.clone(), query_result.display_value(row_idx, y_idx));
+            
+            points.push(PlotPoint {
+                x: time_val,
+                y: open_val,
+                z: None,
+                label: None,
+                color: Some(color),
+                size: None,
+                series_id: None,
+                tooltip_data,
+            });
+            
+            candlestick_data.push(CandlestickData {
+                time: time_val,
+                open: open_val,
+                high: high_val,
+                low: low_val,
+                close: close_val,
+                is_bullish,
+            });
         }
         
         // Sort by time
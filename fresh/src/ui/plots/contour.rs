@@ -1,4 +1,4 @@
-use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points};
+use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points, numeric_column};
 use egui::{Ui, Color32, RichText, Vec2, Pos2, Rect, Response};
 use egui_plot::{Plot, PlotPoints, PlotBounds, Line, PlotUi};
 use datafusion::arrow::datatypes::DataType;
@@ -55,46 +55,44 @@ impl PlotTrait for ContourPlot {
         let mut y_values = Vec::new();
         let mut z_values = Vec::new();
         
-        for (row_idx, row) in query_result.rows.iter().enumerate() {
-            if row.len() > x_idx && row.len() > y_idx {
-                let x_val = row[x_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse X value '{}' as number", row[x_idx]))?;
-                let y_val = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse Y value '{}' as number", row[y_idx]))?;
-                
-                let z_val = if let Some(z_idx) = z_idx {
-                    if row.len() > z_idx {
-                        row[z_idx].parse::<f64>().unwrap_or(0.0)
-                    } else {
-                        0.0
-                    }
-                } else {
-                    y_val // Use Y value as Z if no separate Z column
-                };
-                
-                x_values.push(x_val);
-                y_values.push(y_val);
-                z_values.push(z_val);
-                
-                // Create tooltip data
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("X".to_string(), x_val.to_string());
-                tooltip_data.insert("Y".to_string(), y_val.to_string());
-                tooltip_data.insert("Z".to_string(), z_val.to_string());
-                tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-                
-                points.push(PlotPoint {
-                    x: x_val,
-                    y: y_val,
-                    z: Some(z_val),
-                    label: None,
-                    color: None,
-                    size: None,
-                    series_id: None,
-                    tooltip_data,
-                });
-            }
+        let x_column = numeric_column(query_result, x_idx, "X")?;
+        let y_column = numeric_column(query_result, y_idx, "Y")?;
+        let z_column = z_idx.map(|idx| query_result.f64_column(idx));
+        
+        for row_idx in 0..query_result.num_rows() {
+            // Rows without both coordinates are not plotted
+            let (Some(x_val), Some(y_val)) = (x_column[row_idx], y_column[row_idx]) else {
+                continue;
+            };
+            
+            let z_val = if let Some(z_column) = &z_column {
+                z_column[row_idx].unwrap_or(0.0)
+            } else {
+                y_val // Use Y value as Z if no separate Z column
+            };
+            
+            x_values.push(x_val);
+            y_values.push(y_val);
+            z_values.push(z_val);
+            
+            // Create tooltip data
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("X".to_string(), x_val.to_string());
+            tooltip_data.insert("Y".to_string(), y_val.to_string());
+            tooltip_data.insert("Z".to_string(), z_val.to_string());
+            tooltip_data.insert(config.x_column.clone(), query_result.display_value(row_idx, x_idx));
+            tooltip_data.insert(config.y_column.clone(), query_result.display_value(row_idx, y_idx));
+            
+            points.push(PlotPoint {
+                x: x_val,
+                y: y_val,
+                z: Some(z_val),
+                label: None,
+                color: None,
+                size: None,
+                series_id: None,
+                tooltip_data,
+            });
         }
         
         // Create contour grid data
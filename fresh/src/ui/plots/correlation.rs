@@ -26,17 +26,9 @@ impl CorrelationPlot {
         }
         
         // Extract numeric data
-        let mut numeric_data = vec![Vec::new(); numeric_columns.len()];
-        
-        for row in &query_result.rows {
-            for (col_idx, &data_idx) in numeric_indices.iter().enumerate() {
-                if row.len() > data_idx {
-                    if let Ok(val) = row[data_idx].parse::<f64>() {
-                        numeric_data[col_idx].push(val);
-                    }
-                }
-            }
-        }
+        let mut numeric_data: Vec<Vec<f64>> = numeric_indices.iter()
+            .map(|&data_idx| query_result.f64_column(data_idx).into_iter().flatten().collect())
+            .collect();
         
         // Ensure all columns have the same number of valid values
         let min_length = numeric_data.iter().map(|v| v.len()).min().unwrap_or(0);
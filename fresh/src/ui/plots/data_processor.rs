@@ -6,8 +6,6 @@
 use datafusion::prelude::*;
 use datafusion::arrow::array::{Array, Float64Array, StringArray, Int64Array};
 use datafusion::arrow::record_batch::RecordBatch;
-use datafusion::arrow::datatypes::{Schema, Field, DataType};
-use std::sync::Arc;
 use crate::core::QueryResult;
 
 /// DataFusion-powered data processor for plot operations
@@ -22,97 +20,13 @@ impl DataProcessor {
         Self { context }
     }
 
-    /// Convert QueryResult to Arrow RecordBatch for DataFusion processing
+    /// Combine the QueryResult's Arrow batches for DataFusion processing
     fn query_result_to_record_batch(&self, data: &QueryResult) -> Result<RecordBatch, String> {
-        if data.rows.is_empty() {
+        if data.is_empty() {
             return Err("No data to process".to_string());
         }
 
-        let mut columns: Vec<Arc<dyn Array>> = Vec::new();
-        
-        for (col_idx, col_type) in data.column_types.iter().enumerate() {
-            match col_type {
-                DataType::Float64 | DataType::Float32 => {
-                    let values: Result<Vec<f64>, _> = data.rows.iter()
-                        .map(|row| {
-                            if col_idx < row.len() {
-                                row[col_idx].parse::<f64>()
-                                    .map_err(|_| format!("Failed to parse '{}' as float", row[col_idx]))
-                            } else {
-                                Ok(0.0)
-                            }
-                        })
-                        .collect();
-                    
-                    match values {
-                        Ok(vals) => {
-                            let array = Float64Array::from(vals);
-                            columns.push(Arc::new(array));
-                        }
-                        Err(e) => return Err(e),
-                    }
-                }
-                DataType::Int64 | DataType::Int32 | DataType::Int16 | DataType::Int8 => {
-                    let values: Result<Vec<i64>, _> = data.rows.iter()
-                        .map(|row| {
-                            if col_idx < row.len() {
-                                row[col_idx].parse::<i64>()
-                                    .map_err(|_| format!("Failed to parse '{}' as integer", row[col_idx]))
-                            } else {
-                                Ok(0)
-                            }
-                        })
-                        .collect();
-                    
-                    match values {
-                        Ok(vals) => {
-                            let array = Int64Array::from(vals);
-                            columns.push(Arc::new(array));
-                        }
-                        Err(e) => return Err(e),
-                    }
-                }
-                DataType::Utf8 | DataType::LargeUtf8 => {
-                    let values: Vec<String> = data.rows.iter()
-                        .map(|row| {
-                            if col_idx < row.len() {
-                                row[col_idx].clone()
-                            } else {
-                                String::new()
-                            }
-                        })
-                        .collect();
-                    
-                    let array = StringArray::from(values);
-                    columns.push(Arc::new(array));
-                }
-                _ => {
-                    // Default to string for unsupported types
-                    let values: Vec<String> = data.rows.iter()
-                        .map(|row| {
-                            if col_idx < row.len() {
-                                row[col_idx].clone()
-                            } else {
-                                String::new()
-                            }
-                        })
-                        .collect();
-                    
-                    let array = StringArray::from(values);
-                    columns.push(Arc::new(array));
-                }
-            }
-        }
-
-        // Create schema
-        let fields: Vec<Field> = data.columns.iter()
-            .zip(data.column_types.iter())
-            .map(|(name, dtype)| Field::new(name, dtype.clone(), true))
-            .collect();
-        
-        let schema = Arc::new(Schema::new(fields));
-        
-        RecordBatch::try_new(schema, columns)
+        data.to_record_batch()
             .map_err(|e| format!("Failed to create RecordBatch: {}", e))
     }
 
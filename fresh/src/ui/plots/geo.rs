@@ -1,4 +1,4 @@
-use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points};
+use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points, numeric_column};
 use egui::{Ui, Color32, RichText, Vec2, Pos2, Rect, Response, Stroke};
 use egui_plot::{Plot, PlotPoints, PlotBounds, Line, PlotUi};
 use datafusion::arrow::datatypes::DataType;
@@ -36,9 +36,9 @@ impl PlotTrait for GeoPlot {
         
         // For large datasets, sample the data
         let max_points = 5000; // Limit for performance
-        let sample_size = query_result.rows.len().min(max_points);
-        let step = if query_result.rows.len() > max_points {
-            query_result.rows.len() / max_points
+        let sample_size = query_result.num_rows().min(max_points);
+        let step = if query_result.num_rows() > max_points {
+            query_result.num_rows() / max_points
         } else {
             1
         };
@@ -62,75 +62,73 @@ impl PlotTrait for GeoPlot {
         let mut points = Vec::new();
         let mut geo_data = Vec::new();
         
-        for (row_idx, row) in query_result.rows.iter().enumerate().step_by(step) {
-            if row.len() > x_idx && row.len() > y_idx {
-                // Parse longitude (X) value
-                let lon_val = row[x_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse longitude value '{}' as number", row[x_idx]))?;
-                
-                // Parse latitude (Y) value
-                let lat_val = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse latitude value '{}' as number", row[y_idx]))?;
-                
-                // Validate coordinates
-                if lon_val < -180.0 || lon_val > 180.0 {
-                    return Err(format!("Invalid longitude value: {}", lon_val));
-                }
-                if lat_val < -90.0 || lat_val > 90.0 {
-                    return Err(format!("Invalid latitude value: {}", lat_val));
-                }
-                
-                // Create color mapping
-                let color = if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        let color_value = &row[color_idx];
-                        if let Ok(num_val) = color_value.parse::<f64>() {
-                            let normalized = (num_val - 0.0).max(0.0).min(1.0);
-                            Color32::from_rgb(
-                                (normalized * 255.0) as u8,
-                                ((1.0 - normalized) * 255.0) as u8,
-                                128
-                            )
-                        } else {
-                            // Categorical color
-                            let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
-                            Color32::from_rgb(
-                                (hash % 256) as u8,
-                                ((hash >> 8) % 256) as u8,
-                                ((hash >> 16) % 256) as u8,
-                            )
-                        }
+        let longitudes = numeric_column(query_result, x_idx, "longitude")?;
+        let latitudes = numeric_column(query_result, y_idx, "latitude")?;
+        let color_values = color_idx.map(|idx| (query_result.f64_column(idx), query_result.string_column(idx)));
+        
+        for row_idx in (0..query_result.num_rows()).step_by(step) {
+            // Rows without both coordinates have no place on the map
+            let (Some(lon_val), Some(lat_val)) = (longitudes[row_idx], latitudes[row_idx]) else {
+                continue;
+            };
+            
+            // Validate coordinates
+            if lon_val < -180.0 || lon_val > 180.0 {
+                return Err(format!("Invalid longitude value: {}", lon_val));
+            }
+            if lat_val < -90.0 || lat_val > 90.0 {
+                return Err(format!("Invalid latitude value: {}", lat_val));
+            }
+            
+            // Create color mapping
+            let color = if let Some((numbers, labels)) = &color_values {
+                if let Some(color_value) = &labels[row_idx] {
+                    if let Some(num_val) = numbers[row_idx] {
+                        let normalized = (num_val - 0.0).max(0.0).min(1.0);
+                        Color32::from_rgb(
+                            (normalized * 255.0) as u8,
+                            ((1.0 - normalized) * 255.0) as u8,
+                            128
+                        )
                     } else {
-                        Color32::BLUE
+                        // Categorical color
+                        let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
+                        Color32::from_rgb(
+                            (hash % 256) as u8,
+                            ((hash >> 8) % 256) as u8,
+                            ((hash >> 16) % 256) as u8,
+                        )
                     }
                 } else {
                     Color32::BLUE
-                };
-                
-                // Create tooltip data
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("Longitude".to_string(), lon_val.to_string());
-                tooltip_data.insert("Latitude".to_string(), lat_val.to_string());
-                tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-                
-                points.push(PlotPoint {
-                    x: lon_val,
-                    y: lat_val,
-                    z: None,
-                    label: None,
-                    color: Some(color),
-                    size: None,
-                    series_id: None,
-                    tooltip_data,
-                });
-                
-                geo_data.push(GeoDataPoint {
-                    longitude: lon_val,
-                    latitude: lat_val,
-                    color,
-                });
-            }
+                }
+            } else {
+                Color32::BLUE
+            };
+            
+            // Create tooltip data
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("Longitude".to_string(), lon_val.to_string());
+            tooltip_data.insert("Latitude".to_string(), lat_val.to_string());
+            tooltip_data.insert(config.x_column.clone(), query_result.display_value(row_idx, x_idx));
+            tooltip_data.insert(config.y_column.clone(), query_result.display_value(row_idx, y_idx));
+            
+            points.push(PlotPoint {
+                x: lon_val,
+                y: lat_val,
+                z: None,
+                label: None,
+                color: Some(color),
+                size: None,
+                series_id: None,
+                tooltip_data,
+            });
+            
+            geo_data.push(GeoDataPoint {
+                longitude: lon_val,
+                latitude: lat_val,
+                color,
+            });
         }
         
         // Calculate geographic statistics
@@ -78,21 +78,25 @@ impl HeatmapPlot {
         
         // Get value column index (if specified, otherwise use a constant value)
         let value_idx = if let Some(color_col) = &config.color_column {
-            query_result.columns.iter().position(|c| c == color_col)
-                .ok_or_else(|| format!("Value column '{}' not found", color_col))?
+            Some(query_result.columns.iter().position(|c| c == color_col)
+                .ok_or_else(|| format!("Value column '{}' not found", color_col))?)
         } else {
             // If no value column is specified, we'll just count occurrences
-            usize::MAX
+            None
         };
         
-        // Extract unique row and column labels
+        let x_values = query_result.string_column(x_idx);
+        let y_values = query_result.string_column(y_idx);
+        let values = value_idx.map(|idx| query_result.f64_column(idx));
+        
+        // Extract unique row and column labels; cells with a NULL label are left out
         let mut row_labels = HashSet::new();
         let mut col_labels = HashSet::new();
         
-        for row in &query_result.rows {
-            if row.len() > x_idx && row.len() > y_idx {
-                col_labels.insert(row[x_idx].clone());
-                row_labels.insert(row[y_idx].clone());
+        for (x, y) in x_values.iter().zip(&y_values) {
+            if let (Some(x), Some(y)) = (x, y) {
+                col_labels.insert(x.clone());
+                row_labels.insert(y.clone());
             }
         }
         
@@ -117,21 +121,18 @@ impl HeatmapPlot {
         let mut matrix = vec![vec![0.0; col_labels.len()]; row_labels.len()];
         
         // Fill matrix with values
-        for row in &query_result.rows {
-            if row.len() > x_idx && row.len() > y_idx {
-                let col_label = &row[x_idx];
-                let row_label = &row[y_idx];
-                
-                if let (Some(&row_idx), Some(&col_idx)) = (row_map.get(row_label), col_map.get(col_label)) {
-                    let value = if value_idx != usize::MAX && row.len() > value_idx {
-                        row[value_idx].parse::<f64>().unwrap_or(1.0)
-                    } else {
-                        // If no value column, increment count
-                        1.0
-                    };
-                    
-                    matrix[row_idx][col_idx] += value;
-                }
+        for (i, (col_label, row_label)) in x_values.iter().zip(&y_values).enumerate() {
+            let (Some(col_label), Some(row_label)) = (col_label, row_label) else {
+                continue;
+            };
+            // If no value column, increment count; NULL values add nothing
+            let value = match &values {
+                Some(values) => values[i].unwrap_or(0.0),
+                None => 1.0,
+            };
+            
+            if let (Some(&row_idx), Some(&col_idx)) = (row_map.get(row_label), col_map.get(col_label)) {
+                matrix[row_idx][col_idx] += value;
             }
         }
         
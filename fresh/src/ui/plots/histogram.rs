@@ -192,14 +192,7 @@ impl HistogramPlot {
             .position(|c| c == &config.y_column)
             .ok_or_else(|| format!("Y column '{}' not found", config.y_column))?;
 
-        let mut values = Vec::new();
-        for row in &query_result.rows {
-            if row.len() > y_idx {
-                if let Ok(val) = row[y_idx].parse::<f64>() {
-                    values.push(val);
-                }
-            }
-        }
+        let values: Vec<f64> = query_result.f64_column(y_idx).into_iter().flatten().collect();
 
         if values.is_empty() {
             return Err("No numeric data to create histogram".to_string());
@@ -462,14 +455,7 @@ impl PlotTrait for HistogramPlot {
             .position(|c| c == &config.y_column)
             .ok_or_else(|| format!("Value column '{}' not found", config.y_column))?;
 
-        let mut values = Vec::new();
-        for row in &query_result.rows {
-            if row.len() > value_idx {
-                if let Ok(val) = row[value_idx].parse::<f64>() {
-                    values.push(val);
-                }
-            }
-        }
+        let values: Vec<f64> = query_result.f64_column(value_idx).into_iter().flatten().collect();
 
         let mean = if !values.is_empty() {
             values.iter().sum::<f64>() / values.len() as f64
@@ -3,7 +3,10 @@ use egui_plot::{Line, Plot, PlotPoints, Legend, PlotUi, Points, MarkerShape as E
                 PlotBounds, Polygon, PlotPoint as EguiPlotPoint, LineStyle as EguiLineStyle};
 use datafusion::arrow::datatypes::{DataType, TimeUnit};
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use chrono::DateTime;
 use crate::core::QueryResult;
+use crate::core::datetime;
 
 use super::{
     Plot as PlotTrait, 
@@ -55,105 +58,70 @@ impl LineChartPlot {
             None
         };
 
-        let mut points = Vec::new();
-        let mut color_map = HashMap::new();
+        self.collect_points(query_result, config, x_idx, y_idx, color_idx)
+    }
+
+    /// Build points from typed columns; temporal X values are converted to milliseconds
+    fn collect_points(&self, query_result: &QueryResult, config: &PlotConfiguration, x_idx: usize, y_idx: usize, color_idx: Option<usize>) -> Result<Vec<super::PlotPoint>, String> {
+        let x_values = query_result.f64_column(x_idx);
+        let y_values = query_result.f64_column(y_idx);
+        let color_values = color_idx.map(|idx| query_result.string_column(idx));
+        let x_type = &query_result.column_types[x_idx];
+
+        // Pre-allocate vectors for better performance
+        let mut points = Vec::with_capacity(y_values.len());
+        let mut color_map = HashMap::with_capacity(100);
         let mut color_index = 0;
         
-        // Enhanced temporal data handling based on frog-viz patterns
-        for (_row_idx, row) in query_result.rows.iter().enumerate() {
-            if row.len() > y_idx && row.len() > x_idx {
-                let y_val = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse Y value '{}' as number", row[y_idx]))?;
-                
-                // Enhanced temporal value parsing with proper error handling
-                let x_val = match &query_result.column_types[x_idx] {
-                    DataType::Date32 => {
-                        let days = row[x_idx].parse::<i32>()
-                            .map_err(|_| format!("Failed to parse Date32 value '{}'", row[x_idx]))?;
-                        (days as f64) * 86400000.0 // days to ms
-                    },
-                    DataType::Date64 => {
-                        row[x_idx].parse::<f64>()
-                            .map_err(|_| format!("Failed to parse Date64 value '{}'", row[x_idx]))?
-                    },
-                    DataType::Timestamp(time_unit, _) => {
-                        let timestamp = row[x_idx].parse::<i64>()
-                            .map_err(|_| format!("Failed to parse Timestamp value '{}'", row[x_idx]))?;
-                        
-                        match time_unit {
-                            TimeUnit::Second => (timestamp as f64) * 1000.0,
-                            TimeUnit::Millisecond => timestamp as f64,
-                            TimeUnit::Microsecond => (timestamp as f64) / 1000.0,
-                            TimeUnit::Nanosecond => (timestamp as f64) / 1_000_000.0,
-                        }
-                    },
-                    DataType::Time32(time_unit) => {
-                        let time_val = row[x_idx].parse::<i32>()
-                            .map_err(|_| format!("Failed to parse Time32 value '{}'", row[x_idx]))?;
-                        
-                        match time_unit {
-                            TimeUnit::Second => (time_val as f64) * 1000.0,
-                            TimeUnit::Millisecond => time_val as f64,
-                            _ => time_val as f64,
-                        }
-                    },
-                    DataType::Time64(time_unit) => {
-                        let time_val = row[x_idx].parse::<i64>()
-                            .map_err(|_| format!("Failed to parse Time64 value '{}'", row[x_idx]))?;
-                        
-                        match time_unit {
-                            TimeUnit::Microsecond => (time_val as f64) / 1000.0,
-                            TimeUnit::Nanosecond => (time_val as f64) / 1_000_000.0,
-                            _ => time_val as f64,
-                        }
-                    },
-                    _ => {
-                        row[x_idx].parse::<f64>()
-                            .map_err(|_| format!("Failed to parse X value '{}' as number", row[x_idx]))?
-                    }
-                };
-
-                // Professional color mapping based on frog-viz patterns
-                let point_color = if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        let color_value = &row[color_idx];
-                        if let Some(&existing_color) = color_map.get(color_value) {
-                            Some(existing_color)
-                        } else {
-                            let new_color = categorical_color(color_index);
-                            color_map.insert(color_value.clone(), new_color);
-                            color_index += 1;
-                            Some(new_color)
-                        }
-                    } else {
-                        None
+        for (row_idx, (x_raw, y_val)) in x_values.iter().zip(y_values.iter()).enumerate() {
+            // NULLs leave a gap instead of failing the whole chart
+            let (Some(x_raw), Some(y_val)) = (*x_raw, *y_val) else {
+                continue;
+            };
+            
+            // Enhanced temporal value handling: everything is plotted in milliseconds
+            let x_val = match x_type {
+                DataType::Date32 => x_raw * 86400000.0, // days to ms
+                DataType::Timestamp(time_unit, _) | DataType::Time32(time_unit) | DataType::Time64(time_unit) => {
+                    match time_unit {
+                        TimeUnit::Second => x_raw * 1000.0,
+                        TimeUnit::Millisecond => x_raw,
+                        TimeUnit::Microsecond => x_raw / 1000.0,
+                        TimeUnit::Nanosecond => x_raw / 1_000_000.0,
                     }
-                } else {
-                    None
-                };
+                },
+                _ => x_raw,
+            };
 
-                // Enhanced tooltip data with rich information
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("X".to_string(), row[x_idx].clone());
-                tooltip_data.insert("Y".to_string(), row[y_idx].clone());
-                
-                if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        tooltip_data.insert(config.color_column.as_ref().unwrap().clone(), row[color_idx].clone());
-                    }
-                }
+            // Professional color mapping based on frog-viz patterns
+            let color_value = color_values.as_ref().and_then(|values| values[row_idx].as_ref());
+            let point_color = color_value.map(|color_value| {
+                *color_map.entry(color_value.clone()).or_insert_with(|| {
+                    let new_color = categorical_color(color_index);
+                    color_index += 1;
+                    new_color
+                })
+            });
 
-                points.push(super::PlotPoint {
-                    x: x_val,
-                    y: y_val,
-                    z: None,
-                    label: None,
-                    color: point_color,
-                    size: None,
-                    series_id: None,
-                    tooltip_data,
-                });
+            // Enhanced tooltip data with rich information
+            let mut tooltip_data = HashMap::with_capacity(3);
+            tooltip_data.insert("X".to_string(), query_result.display_value(row_idx, x_idx));
+            tooltip_data.insert("Y".to_string(), query_result.display_value(row_idx, y_idx));
+            
+            if let Some(color_value) = color_value {
+                tooltip_data.insert(config.color_column.as_ref().unwrap().clone(), color_value.clone());
             }
+
+            points.push(super::PlotPoint {
+                x: x_val,
+                y: y_val,
+                z: None,
+                label: None,
+                color: point_color,
+                size: None,
+                series_id: None,
+                tooltip_data,
+            });
         }
         
         Ok(points)
@@ -317,7 +285,7 @@ impl LineChartPlot {
         let data_processor = DataProcessor::new();
         
         // Performance optimization: Pre-allocate for large datasets
-        let total_rows = query_result.rows.len();
+        let total_rows = query_result.num_rows();
         let estimated_series_count = if config.color_column.is_some() { 10 } else { 1 };
         
         // Debug: Print input data info
@@ -325,12 +293,8 @@ impl LineChartPlot {
         println!("  Query result: {} columns, {} rows", query_result.columns.len(), total_rows);
         println!("  Config: X='{}', Y='{}'", config.x_column, config.y_column);
         
-        // Extract points with temporal support and performance optimizations
-        let points = if total_rows > 100_000 {
-            self.extract_temporal_points_optimized(query_result, config, total_rows)?
-        } else {
-            self.extract_temporal_points(query_result, config)?
-        };
+        // Extract points with temporal support straight from the typed columns
+        let points = self.extract_temporal_points(query_result, config)?;
         
         println!("  Extracted {} points", points.len());
         if !points.is_empty() {
@@ -492,117 +456,6 @@ impl LineChartPlot {
         }
     }
     
-    /// Optimized temporal points extraction for large datasets
-    fn extract_temporal_points_optimized(&self, query_result: &QueryResult, config: &PlotConfiguration, total_rows: usize) -> Result<Vec<super::PlotPoint>, String> {
-        if config.y_column.is_empty() {
-            return Err("Y column not selected".to_string());
-        }
-
-        let y_idx = query_result.columns.iter().position(|c| c == &config.y_column)
-            .ok_or("Y column not found")?;
-        
-        let x_idx = if !config.x_column.is_empty() {
-            query_result.columns.iter().position(|c| c == &config.x_column)
-                .ok_or_else(|| format!("X column '{}' not found", config.x_column))?
-        } else {
-            return Err("X column is required for temporal data".to_string());
-        };
-
-        let color_idx = if let Some(color_col) = &config.color_column {
-            if !color_col.is_empty() {
-                query_result.columns.iter().position(|c| c == color_col)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-
-        // Pre-allocate vectors for better performance
-        let mut points = Vec::with_capacity(total_rows);
-        let mut color_map = HashMap::with_capacity(100); // Pre-allocate color map
-        let mut color_index = 0;
-        
-        // Use iterator for better performance
-        for row in query_result.rows.iter() {
-            if row.len() > y_idx && row.len() > x_idx {
-                let y_val = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse Y value '{}' as number", row[y_idx]))?;
-                
-                // Optimized temporal value parsing
-                let x_val = match &query_result.column_types[x_idx] {
-                    DataType::Date32 => {
-                        let days = row[x_idx].parse::<i32>()
-                            .map_err(|_| format!("Failed to parse Date32 value '{}'", row[x_idx]))?;
-                        (days as f64) * 86400000.0
-                    },
-                    DataType::Date64 => {
-                        row[x_idx].parse::<f64>()
-                            .map_err(|_| format!("Failed to parse Date64 value '{}'", row[x_idx]))?
-                    },
-                    DataType::Timestamp(time_unit, _) => {
-                        let timestamp = row[x_idx].parse::<i64>()
-                            .map_err(|_| format!("Failed to parse Timestamp value '{}'", row[x_idx]))?;
-                        
-                        match time_unit {
-                            TimeUnit::Second => (timestamp as f64) * 1000.0,
-                            TimeUnit::Millisecond => timestamp as f64,
-                            TimeUnit::Microsecond => (timestamp as f64) / 1000.0,
-                            TimeUnit::Nanosecond => (timestamp as f64) / 1_000_000.0,
-                        }
-                    },
-                    _ => {
-                        row[x_idx].parse::<f64>()
-                            .map_err(|_| format!("Failed to parse X value '{}' as number", row[x_idx]))?
-                    }
-                };
-
-                // Optimized color mapping
-                let point_color = if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        let color_value = &row[color_idx];
-                        if let Some(&existing_color) = color_map.get(color_value) {
-                            Some(existing_color)
-                        } else {
-                            let new_color = categorical_color(color_index);
-                            color_map.insert(color_value.clone(), new_color);
-                            color_index += 1;
-                            Some(new_color)
-                        }
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-
-                // Optimized tooltip data creation
-                let mut tooltip_data = HashMap::with_capacity(3);
-                tooltip_data.insert("X".to_string(), row[x_idx].clone());
-                tooltip_data.insert("Y".to_string(), row[y_idx].clone());
-                
-                if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        tooltip_data.insert(config.color_column.as_ref().unwrap().clone(), row[color_idx].clone());
-                    }
-                }
-
-                points.push(super::PlotPoint {
-                    x: x_val,
-                    y: y_val,
-                    z: None,
-                    label: None,
-                    color: point_color,
-                    size: None,
-                    series_id: None,
-                    tooltip_data,
-                });
-            }
-        }
-        
-        Ok(points)
-    }
-    
     /// Sample points for performance with very large datasets
     fn sample_points_for_performance(&self, points: &[super::PlotPoint], total_rows: usize) -> Vec<super::PlotPoint> {
         if total_rows <= 100_000 {
@@ -647,6 +500,10 @@ impl PlotTrait for LineChartPlot {
             DataType::Timestamp(TimeUnit::Millisecond, None),
             DataType::Timestamp(TimeUnit::Microsecond, None),
             DataType::Timestamp(TimeUnit::Nanosecond, None),
+            DataType::Time32(TimeUnit::Second),
+            DataType::Time32(TimeUnit::Millisecond),
+            DataType::Time64(TimeUnit::Microsecond),
+            DataType::Time64(TimeUnit::Nanosecond),
         ])
     }
 
@@ -713,6 +570,15 @@ impl PlotTrait for LineChartPlot {
         let all_points: Vec<super::PlotPoint> = series.iter()
             .flat_map(|s| s.points.clone())
             .collect();
+
+        // Temporal X values are plotted as milliseconds; the axis needs to know how to label them
+        let x_axis = query_result.column_index(&config.x_column)
+            .and_then(|idx| match &query_result.column_types[idx] {
+                DataType::Timestamp(_, zone) => Some(serde_json::json!({ "x_axis": "datetime", "time_zone": zone.as_deref() })),
+                DataType::Date32 | DataType::Date64 => Some(serde_json::json!({ "x_axis": "date" })),
+                DataType::Time32(_) | DataType::Time64(_) => Some(serde_json::json!({ "x_axis": "time" })),
+                _ => None,
+            });
         
         Ok(PlotData {
             points: all_points,
@@ -724,7 +590,7 @@ impl PlotTrait for LineChartPlot {
                 show_legend: config.show_legend,
                 show_grid: config.show_grid,
                 color_scheme: config.color_scheme.clone(),
-                extra_data: None,
+                extra_data: x_axis,
             },
             statistics,
         })
@@ -783,6 +649,17 @@ impl PlotTrait for LineChartPlot {
                 .y_axis_label(config.y_column.clone());
         }
 
+        // Label temporal X values as dates and times instead of raw milliseconds
+        if let Some(extra_data) = &data.metadata.extra_data {
+            if let Some(axis) = extra_data.get("x_axis").and_then(|a| a.as_str()) {
+                let axis = axis.to_string();
+                let zone = extra_data.get("time_zone").and_then(|z| z.as_str()).map(String::from);
+                plot = plot.x_axis_formatter(move |mark, range| {
+                    format_temporal_axis_label(mark.value, range, &axis, zone.as_deref())
+                });
+            }
+        }
+
         // Performance optimization: Cache bounds to avoid recalculation
         let mut cached_bounds = None;
         
@@ -1107,4 +984,25 @@ impl PlotTrait for LineChartPlot {
         
         None
     }
-}
\ No newline at end of file
+}
+
+/// Label for a temporal X position in milliseconds. Date-times show their date once the
+/// visible range spans more than a day, so multi-day data is not read as one 24-hour cycle.
+fn format_temporal_axis_label(ms: f64, range: &RangeInclusive<f64>, axis: &str, zone: Option<&str>) -> String {
+    const MS_PER_DAY: f64 = 86_400_000.0;
+    let Some(instant) = DateTime::from_timestamp_millis(ms.round() as i64) else {
+        return String::new();
+    };
+    let span = range.end() - range.start();
+    let pattern = match axis {
+        "date" => "%Y-%m-%d",
+        "time" => "%H:%M:%S",
+        _ if span > 90.0 * MS_PER_DAY => "%Y-%m-%d",
+        _ if span > MS_PER_DAY => "%m-%d %H:%M",
+        _ => "%H:%M:%S",
+    };
+    match zone.map(datetime::parse_time_zone) {
+        Some(Ok(zone)) => instant.with_timezone(&zone).format(pattern).to_string(),
+        _ => instant.format(pattern).to_string(),
+    }
+}
@@ -111,7 +111,12 @@ pub trait Plot {
             if let Some(x_idx) = query_result.columns.iter().position(|c| c == &config.x_column) {
                 if x_idx < query_result.column_types.len() {
                     let actual_type = &query_result.column_types[x_idx];
-                    if !required_x_types.iter().any(|req_type| req_type == actual_type) {
+                    // Timestamps qualify whatever their time zone
+                    let matches = |req_type: &DataType| match (req_type, actual_type) {
+                        (DataType::Timestamp(required, _), DataType::Timestamp(actual, _)) => required == actual,
+                        _ => req_type == actual_type,
+                    };
+                    if !required_x_types.iter().any(matches) {
                         return Err(format!(
                             "X column '{}' has type {:?} which is not valid for {} plot. Required types: {:?}",
                             config.x_column, actual_type, self.name(), required_x_types
@@ -945,11 +950,16 @@ impl PlotType {
     pub fn supports_column_types(&self, x_type: Option<&DataType>, y_type: &DataType) -> bool {
         match self {
             // Numeric plots require numeric data
-            PlotType::ScatterPlot | PlotType::LineChart | PlotType::Scatter3D | 
+            PlotType::ScatterPlot | PlotType::Scatter3D | 
             PlotType::Surface3D | PlotType::ContourPlot => {
                 is_numeric_type(y_type) && x_type.map_or(true, is_numeric_type)
             }
             
+            // Line charts also run along a time axis
+            PlotType::LineChart => {
+                is_numeric_type(y_type) && x_type.map_or(true, |t| is_numeric_type(t) || is_temporal_type(t))
+            }
+            
             // Bar charts can have categorical X axis
             PlotType::BarChart => {
                 is_numeric_type(y_type) && x_type.map_or(true, |t| is_categorical_type(t) || is_numeric_type(t))
@@ -1036,91 +1046,87 @@ pub fn extract_plot_points(query_result: &QueryResult, config: &PlotConfiguratio
         None
     };
 
-    let mut points = Vec::new();
+    // Read typed columns straight from the Arrow batches
+    let y_values = numeric_column(query_result, y_idx, "Y")?;
+    let x_values = x_idx.map(|idx| query_result.f64_column(idx));
+    let x_labels = x_idx.map(|idx| query_result.string_column(idx));
+    let color_values = color_idx.map(|idx| query_result.string_column(idx));
+    let size_values = size_idx.map(|idx| query_result.f64_column(idx));
+
+    let mut points = Vec::with_capacity(y_values.len());
     let colors = get_categorical_colors(&config.color_scheme);
     let mut color_map: HashMap<String, Color32> = HashMap::new();
     let mut color_index = 0;
     
-    for (row_idx, row) in query_result.rows.iter().enumerate() {
-        if row.len() > y_idx {
-            let y_val = row[y_idx].parse::<f64>()
-                .map_err(|_| format!("Failed to parse Y value '{}' as number", row[y_idx]))?;
-            
-            let x_val = if let Some(x_idx) = x_idx {
-                if row.len() > x_idx {
-                    row[x_idx].parse::<f64>().unwrap_or(row_idx as f64)
-                } else {
-                    row_idx as f64
-                }
-            } else {
-                row_idx as f64
-            };
-
-            // Handle color mapping
-            let point_color = if let Some(color_idx) = color_idx {
-                if row.len() > color_idx {
-                    let color_value = &row[color_idx];
-                    if let Some(&existing_color) = color_map.get(color_value) {
-                        Some(existing_color)
-                    } else {
-                        let new_color = colors[color_index % colors.len()];
-                        color_map.insert(color_value.clone(), new_color);
-                        color_index += 1;
-                        Some(new_color)
-                    }
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-
-            // Handle size mapping
-            let point_size = if let Some(size_idx) = size_idx {
-                if row.len() > size_idx {
-                    row[size_idx].parse::<f32>().ok()
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-
-            // Create tooltip data
-            let mut tooltip_data = HashMap::new();
-            tooltip_data.insert("X".to_string(), x_val.to_string());
-            tooltip_data.insert("Y".to_string(), y_val.to_string());
-            
-            if let Some(x_idx) = x_idx {
-                if row.len() > x_idx {
-                    tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                }
-            }
-            tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-            
-            if let Some(color_idx) = color_idx {
-                if row.len() > color_idx {
-                    tooltip_data.insert(config.color_column.as_ref().unwrap().clone(), row[color_idx].clone());
-                }
-            }
-
-            points.push(PlotPoint {
-                x: x_val,
-                y: y_val,
-                z: None,
-                label: None,
-                color: point_color,
-                size: point_size,
-                series_id: None,
-                tooltip_data,
-            });
+    for (row_idx, y_val) in y_values.iter().enumerate() {
+        // Rows with a NULL Y value have nothing to plot
+        let Some(y_val) = *y_val else {
+            continue;
+        };
+        
+        let x_val = x_values.as_ref()
+            .and_then(|values| values[row_idx])
+            .unwrap_or(row_idx as f64);
+
+        // Handle color mapping
+        let color_value = color_values.as_ref().and_then(|values| values[row_idx].as_ref());
+        let point_color = color_value.map(|color_value| {
+            *color_map.entry(color_value.clone()).or_insert_with(|| {
+                let new_color = colors[color_index % colors.len()];
+                color_index += 1;
+                new_color
+            })
+        });
+
+        // Handle size mapping
+        let point_size = size_values.as_ref()
+            .and_then(|values| values[row_idx])
+            .map(|size| size as f32);
+
+        // Create tooltip data
+        let mut tooltip_data = HashMap::new();
+        tooltip_data.insert("X".to_string(), x_val.to_string());
+        tooltip_data.insert("Y".to_string(), y_val.to_string());
+        
+        if let Some(Some(x_label)) = x_labels.as_ref().map(|labels| &labels[row_idx]) {
+            tooltip_data.insert(config.x_column.clone(), x_label.clone());
+        }
+        tooltip_data.insert(config.y_column.clone(), query_result.display_value(row_idx, y_idx));
+        
+        if let Some(color_value) = color_value {
+            tooltip_data.insert(config.color_column.as_ref().unwrap().clone(), color_value.clone());
         }
+
+        points.push(PlotPoint {
+            x: x_val,
+            y: y_val,
+            z: None,
+            label: None,
+            color: point_color,
+            size: point_size,
+            series_id: None,
+            tooltip_data,
+        });
     }
     
     Ok(points)
 }
 
 /// Get categorical colors from color scheme
+/// Column values as numbers for plotting. NULLs are `None`; a value that is not a number
+/// is an error naming the column's role, `what`.
+pub fn numeric_column(query_result: &QueryResult, index: usize, what: &str) -> Result<Vec<Option<f64>>, String> {
+    let values = query_result.f64_column(index);
+    // The values are only formatted to report one that isn't a number
+    if values.iter().any(Option::is_none) {
+        let text = query_result.string_column(index);
+        if let Some(value) = values.iter().zip(&text).find_map(|(number, text)| text.as_ref().filter(|_| number.is_none())) {
+            return Err(format!("Failed to parse {} value '{}' as number", what, value));
+        }
+    }
+    Ok(values)
+}
+
 pub fn get_categorical_colors(scheme: &ColorScheme) -> Vec<Color32> {
     scheme.get_colors(10)
 }
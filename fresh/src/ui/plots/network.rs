@@ -36,9 +36,9 @@ impl PlotTrait for NetworkPlot {
         
         // For large datasets, sample the data
         let max_points = 1500; // Limit for performance
-        let sample_size = query_result.rows.len().min(max_points);
-        let step = if query_result.rows.len() > max_points {
-            query_result.rows.len() / max_points
+        let sample_size = query_result.num_rows().min(max_points);
+        let step = if query_result.num_rows() > max_points {
+            query_result.num_rows() / max_points
         } else {
             1
         };
@@ -63,82 +63,85 @@ impl PlotTrait for NetworkPlot {
         let mut nodes = HashMap::new();
         let mut edges = Vec::new();
         
-        for (row_idx, row) in query_result.rows.iter().enumerate().step_by(step) {
-            if row.len() > x_idx && row.len() > y_idx {
-                let source = row[x_idx].clone();
-                let target = row[y_idx].clone();
-                
-                // Create color mapping
-                let color = if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        let color_value = &row[color_idx];
-                        if let Ok(num_val) = color_value.parse::<f64>() {
-                            let normalized = (num_val - 0.0).max(0.0).min(1.0);
-                            Color32::from_rgb(
-                                (normalized * 255.0) as u8,
-                                ((1.0 - normalized) * 255.0) as u8,
-                                128
-                            )
-                        } else {
-                            // Categorical color
-                            let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
-                            Color32::from_rgb(
-                                (hash % 256) as u8,
-                                ((hash >> 8) % 256) as u8,
-                                ((hash >> 16) % 256) as u8,
-                            )
-                        }
+        let sources = query_result.string_column(x_idx);
+        let targets = query_result.string_column(y_idx);
+        let color_values = color_idx.map(|idx| (query_result.f64_column(idx), query_result.string_column(idx)));
+        
+        for row_idx in (0..query_result.num_rows()).step_by(step) {
+            // An edge needs both of its nodes
+            let (Some(source), Some(target)) = (sources[row_idx].clone(), targets[row_idx].clone()) else {
+                continue;
+            };
+            
+            // Create color mapping
+            let color = if let Some((numbers, labels)) = &color_values {
+                if let Some(color_value) = &labels[row_idx] {
+                    if let Some(num_val) = numbers[row_idx] {
+                        let normalized = (num_val - 0.0).max(0.0).min(1.0);
+                        Color32::from_rgb(
+                            (normalized * 255.0) as u8,
+                            ((1.0 - normalized) * 255.0) as u8,
+                            128
+                        )
                     } else {
-                        Color32::BLUE
+                        // Categorical color
+                        let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
+                        Color32::from_rgb(
+                            (hash % 256) as u8,
+                            ((hash >> 8) % 256) as u8,
+                            ((hash >> 16) % 256) as u8,
+                        )
                     }
                 } else {
                     Color32::BLUE
-                };
-                
-                // Add nodes if they don't exist
-                if !nodes.contains_key(&source) {
-                    nodes.insert(source.clone(), NodeData {
-                        id: source.clone(),
-                        x: (nodes.len() as f64 * 0.1) % 10.0,
-                        y: (nodes.len() as f64 * 0.2) % 10.0,
-                        color,
-                    });
                 }
-                
-                if !nodes.contains_key(&target) {
-                    nodes.insert(target.clone(), NodeData {
-                        id: target.clone(),
-                        x: (nodes.len() as f64 * 0.3) % 10.0,
-                        y: (nodes.len() as f64 * 0.4) % 10.0,
-                        color,
-                    });
-                }
-                
-                // Add edge
-                edges.push(EdgeData {
-                    source: source.clone(),
-                    target: target.clone(),
+            } else {
+                Color32::BLUE
+            };
+            
+            // Add nodes if they don't exist
+            if !nodes.contains_key(&source) {
+                nodes.insert(source.clone(), NodeData {
+                    id: source.clone(),
+                    x: (nodes.len() as f64 * 0.1) % 10.0,
+                    y: (nodes.len() as f64 * 0.2) % 10.0,
                     color,
                 });
-                
-                // Create tooltip data
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("Source".to_string(), source.clone());
-                tooltip_data.insert("Target".to_string(), target.clone());
-                tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-                
-                points.push(PlotPoint {
-                    x: nodes[&source].x,
-                    y: nodes[&source].y,
-                    z: None,
-                    label: None,
-                    color: Some(color),
-                    size: None,
-                    series_id: None,
-                    tooltip_data,
+            }
+            
+            if !nodes.contains_key(&target) {
+                nodes.insert(target.clone(), NodeData {
+                    id: target.clone(),
+                    x: (nodes.len() as f64 * 0.3) % 10.0,
+                    y: (nodes.len() as f64 * 0.4) % 10.0,
+                    color,
                 });
             }
+            
+            // Add edge
+            edges.push(EdgeData {
+                source: source.clone(),
+                target: target.clone(),
+                color,
+            });
+            
+            // Create tooltip data
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("Source".to_string(), source.clone());
+            tooltip_data.insert("Target".to_string(), target.clone());
+            tooltip_data.insert(config.x_column.clone(), source.clone());
+            tooltip_data.insert(config.y_column.clone(), target.clone());
+            
+            points.push(PlotPoint {
+                x: nodes[&source].x,
+                y: nodes[&source].y,
+                z: None,
+                label: None,
+                color: Some(color),
+                size: None,
+                series_id: None,
+                tooltip_data,
+            });
         }
         
         // Calculate network statistics
@@ -1,4 +1,4 @@
-use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points};
+use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points, numeric_column};
 use egui::{Ui, Color32, RichText, Vec2, Pos2, Rect, Response, Stroke};
 use egui_plot::{Plot, PlotPoints, PlotBounds, Line, PlotUi, Points};
 use datafusion::arrow::datatypes::DataType;
@@ -67,99 +67,92 @@ impl PlotTrait for PolarPlot {
         let mut points = Vec::new();
         let mut polar_data = Vec::new();
         
-        for (row_idx, row) in query_result.rows.iter().enumerate() {
-            if row.len() > x_idx && row.len() > y_idx {
-                // Parse angle (theta) value - convert to radians if needed
-                let angle_val = row[x_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse angle value '{}' as number", row[x_idx]))?;
-                
-                // Normalize angle to 0-2π range
-                let normalized_angle = if angle_val < 0.0 {
-                    angle_val + 2.0 * PI
-                } else if angle_val > 2.0 * PI {
-                    angle_val - 2.0 * PI
-                } else {
-                    angle_val
-                };
-                
-                // Parse radius value
-                let radius_val = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse radius value '{}' as number", row[y_idx]))?;
-                
-                // Ensure radius is positive
-                let radius_val = radius_val.abs();
-                
-                // Create color mapping
-                let color = if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        let color_value = &row[color_idx];
-                        if let Ok(num_val) = color_value.parse::<f64>() {
-                            // Use a color gradient based on the value
-                            let normalized = (num_val - 0.0).max(0.0).min(1.0);
-                            Color32::from_rgb(
-                                (normalized * 255.0) as u8,
-                                ((1.0 - normalized) * 255.0) as u8,
-                                128
-                            )
-                        } else {
-                            // Categorical color
-                            let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
-                            Color32::from_rgb(
-                                (hash % 256) as u8,
-                                ((hash >> 8) % 256) as u8,
-                                ((hash >> 16) % 256) as u8,
-                            )
-                        }
+        let angles = numeric_column(query_result, x_idx, "angle")?;
+        let radii = numeric_column(query_result, y_idx, "radius")?;
+        let color_values = color_idx.map(|idx| (query_result.f64_column(idx), query_result.string_column(idx)));
+        let sizes = size_idx.map(|idx| query_result.f64_column(idx));
+        
+        for row_idx in 0..query_result.num_rows() {
+            // Rows without both an angle and a radius are not plotted
+            let (Some(angle_val), Some(radius_val)) = (angles[row_idx], radii[row_idx]) else {
+                continue;
+            };
+            
+            // Normalize angle to 0-2π range
+            let normalized_angle = if angle_val < 0.0 {
+                angle_val + 2.0 * PI
+            } else if angle_val > 2.0 * PI {
+                angle_val - 2.0 * PI
+            } else {
+                angle_val
+            };
+            
+            // Ensure radius is positive
+            let radius_val = radius_val.abs();
+            
+            // Create color mapping
+            let color = if let Some((numbers, labels)) = &color_values {
+                if let Some(color_value) = &labels[row_idx] {
+                    if let Some(num_val) = numbers[row_idx] {
+                        // Use a color gradient based on the value
+                        let normalized = (num_val - 0.0).max(0.0).min(1.0);
+                        Color32::from_rgb(
+                            (normalized * 255.0) as u8,
+                            ((1.0 - normalized) * 255.0) as u8,
+                            128
+                        )
                     } else {
-                        Color32::BLUE
+                        // Categorical color
+                        let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
+                        Color32::from_rgb(
+                            (hash % 256) as u8,
+                            ((hash >> 8) % 256) as u8,
+                            ((hash >> 16) % 256) as u8,
+                        )
                     }
                 } else {
                     Color32::BLUE
-                };
-                
-                // Get size mapping
-                let size = if let Some(size_idx) = size_idx {
-                    if row.len() > size_idx {
-                        row[size_idx].parse::<f32>().unwrap_or(4.0)
-                    } else {
-                        4.0
-                    }
-                } else {
-                    4.0
-                };
-                
-                // Convert polar to Cartesian coordinates
-                let x = radius_val * normalized_angle.cos();
-                let y = radius_val * normalized_angle.sin();
-                
-                // Create tooltip data
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("Angle (rad)".to_string(), normalized_angle.to_string());
-                tooltip_data.insert("Angle (deg)".to_string(), (normalized_angle * 180.0 / PI).to_string());
-                tooltip_data.insert("Radius".to_string(), radius_val.to_string());
-                tooltip_data.insert("X".to_string(), x.to_string());
-                tooltip_data.insert("Y".to_string(), y.to_string());
-                tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-                
-                points.push(PlotPoint {
-                    x,
-                    y,
-                    z: None,
-                    label: None,
-                    color: Some(color),
-                    size: Some(size),
-                    series_id: None,
-                    tooltip_data,
-                });
-                
-                polar_data.push(PolarDataPoint {
-                    angle: normalized_angle,
-                    radius: radius_val,
-                    color,
-                    size,
-                });
-            }
+                }
+            } else {
+                Color32::BLUE
+            };
+            
+            // Get size mapping
+            let size = sizes.as_ref()
+                .and_then(|sizes| sizes[row_idx])
+                .map_or(4.0, |size| size as f32);
+            
+            // Convert polar to Cartesian coordinates
+            let x = radius_val * normalized_angle.cos();
+            let y = radius_val * normalized_angle.sin();
+            
+            // Create tooltip data
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("Angle (rad)".to_string(), normalized_angle.to_string());
+            tooltip_data.insert("Angle (deg)".to_string(), (normalized_angle * 180.0 / PI).to_string());
+            tooltip_data.insert("Radius".to_string(), radius_val.to_string());
+            tooltip_data.insert("X".to_string(), x.to_string());
+            tooltip_data.insert("Y".to_string(), y.to_string());
+            tooltip_data.insert(config.x_column.clone(), query_result.display_value(row_idx, x_idx));
+            tooltip_data.insert(config.y_column.clone(), query_result.display_value(row_idx, y_idx));
+            
+            points.push(PlotPoint {
+                x,
+                y,
+                z: None,
+                label: None,
+                color: Some(color),
+                size: Some(size),
+                series_id: None,
+                tooltip_data,
+            });
+            
+            polar_data.push(PolarDataPoint {
+                angle: normalized_angle,
+                radius: radius_val,
+                color,
+                size,
+            });
         }
         
         // Calculate polar statistics
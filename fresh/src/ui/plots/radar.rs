@@ -70,11 +70,13 @@ impl PlotTrait for RadarPlot {
         // Find all numeric columns for radar chart
         let mut numeric_columns = Vec::new();
         let mut column_indices = Vec::new();
+        let mut column_values = Vec::new();
         
         // Start with the Y column
         if let Some(idx) = query_result.columns.iter().position(|c| c == &config.y_column) {
             numeric_columns.push(&config.y_column);
             column_indices.push(idx);
+            column_values.push(query_result.f64_column(idx));
         } else {
             return Err(format!("Column '{}' not found", config.y_column));
         }
@@ -87,19 +89,14 @@ impl PlotTrait for RadarPlot {
             
             if col != &config.y_column && !numeric_columns.contains(&col) {
                 // Check if this column contains numeric data
-                let mut has_numeric_data = false;
-                for row in &query_result.rows {
-                    if row.len() > i {
-                        if row[i].parse::<f64>().is_ok() {
-                            has_numeric_data = true;
-                            break;
-                        }
-                    }
+                if !super::is_numeric_type(&query_result.column_types[i]) {
+                    continue;
                 }
-                
-                if has_numeric_data {
+                let values = query_result.f64_column(i);
+                if values.iter().any(Option::is_some) {
                     numeric_columns.push(col);
                     column_indices.push(i);
+                    column_values.push(values);
                 }
             }
         }
@@ -110,17 +107,13 @@ impl PlotTrait for RadarPlot {
         
         // Calculate min/max for each column for normalization
         let mut column_ranges = Vec::new();
-        for &col_idx in &column_indices {
+        for values in &column_values {
             let mut min_val = f64::INFINITY;
             let mut max_val = f64::NEG_INFINITY;
             
-            for row in &query_result.rows {
-                if row.len() > col_idx {
-                    if let Ok(val) = row[col_idx].parse::<f64>() {
-                        min_val = min_val.min(val);
-                        max_val = max_val.max(val);
-                    }
-                }
+            for &val in values.iter().flatten() {
+                min_val = min_val.min(val);
+                max_val = max_val.max(val);
             }
             
             column_ranges.push((min_val, max_val));
@@ -129,85 +122,81 @@ impl PlotTrait for RadarPlot {
         let mut points = Vec::new();
         let mut series_data = Vec::new();
         
-        for (row_idx, row) in query_result.rows.iter().enumerate() {
-            if row.len() >= column_indices.iter().max().unwrap_or(&0) + 1 {
-                let mut point_data = Vec::new();
-                let mut tooltip_data = HashMap::new();
-                
-                // Extract and normalize values for each column
-                for (i, &col_idx) in column_indices.iter().enumerate() {
-                    if row.len() > col_idx {
-                        let value = row[col_idx].parse::<f64>().unwrap_or(0.0);
-                        let (min_val, max_val) = column_ranges[i];
-                        let range = max_val - min_val;
-                        
-                        // Normalize to 0-1 range
-                        let normalized = if range > 0.0 {
-                            (value - min_val) / range
-                        } else {
-                            0.5
-                        };
-                        
-                        point_data.push(normalized);
-                        tooltip_data.insert(query_result.columns[col_idx].clone(), format!("{:.3}", value));
-                    } else {
-                        point_data.push(0.0);
-                    }
-                }
+        let color_column = config.color_column.as_ref().filter(|c| !c.is_empty());
+        let color_values = color_column
+            .and_then(|color_col| query_result.columns.iter().position(|c| c == color_col))
+            .map(|idx| (query_result.f64_column(idx), query_result.string_column(idx)));
+        
+        for row_idx in 0..query_result.num_rows() {
+            // Rows with a NULL in any dimension are not drawn
+            let Some(values) = column_values.iter().map(|values| values[row_idx]).collect::<Option<Vec<f64>>>() else {
+                continue;
+            };
+            
+            let mut point_data = Vec::new();
+            let mut tooltip_data = HashMap::new();
+            
+            // Normalize the values of each column
+            for (i, (&col_idx, value)) in column_indices.iter().zip(values).enumerate() {
+                let (min_val, max_val) = column_ranges[i];
+                let range = max_val - min_val;
                 
-                // Create color mapping
-                let color = if let Some(color_col) = &config.color_column {
-                    if !color_col.is_empty() {
-                        let color_idx = query_result.columns.iter().position(|c| c == color_col);
-                        if let Some(idx) = color_idx {
-                            if row.len() > idx {
-                                let color_value = &row[idx];
-                                // Create color based on value
-                                if let Ok(num_val) = color_value.parse::<f64>() {
-                                    let normalized = (num_val - 0.0).max(0.0).min(1.0);
-                                    Color32::from_rgb(
-                                        (normalized * 255.0) as u8,
-                                        ((1.0 - normalized) * 255.0) as u8,
-                                        128
-                                    )
-                                } else {
-                                    // Categorical color
-                                    let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
-                                    Color32::from_rgb(
-                                        (hash % 256) as u8,
-                                        ((hash >> 8) % 256) as u8,
-                                        ((hash >> 16) % 256) as u8,
-                                    )
-                                }
-                            } else {
-                                Color32::BLUE
-                            }
-                        } else {
-                            Color32::BLUE
-                        }
-                    } else {
-                        Color32::BLUE
-                    }
+                // Normalize to 0-1 range
+                let normalized = if range > 0.0 {
+                    (value - min_val) / range
                 } else {
-                    // Use a gradient based on row index
-                    let hue = (row_idx as f32 * 137.5) % 360.0; // Golden angle
-                    let (r, g, b) = self.hsv_to_rgb(hue, 0.8, 0.9);
-                    Color32::from_rgb(r, g, b)
+                    0.5
                 };
                 
-                points.push(PlotPoint {
-                    x: 0.0, // Will be set during rendering
-                    y: 0.0, // Will be set during rendering
-                    z: None,
-                    label: None,
-                    color: Some(color),
-                    size: None,
-                    series_id: Some(format!("row_{}", row_idx)),
-                    tooltip_data,
-                });
-                
-                series_data.push(point_data);
+                point_data.push(normalized);
+                tooltip_data.insert(query_result.columns[col_idx].clone(), format!("{:.3}", value));
             }
+            
+            // Create color mapping
+            let color = if color_column.is_some() {
+                match &color_values {
+                    Some((numbers, labels)) => match (numbers[row_idx], &labels[row_idx]) {
+                        // Create color based on value
+                        (Some(num_val), _) => {
+                            let normalized = (num_val - 0.0).max(0.0).min(1.0);
+                            Color32::from_rgb(
+                                (normalized * 255.0) as u8,
+                                ((1.0 - normalized) * 255.0) as u8,
+                                128
+                            )
+                        }
+                        // Categorical color
+                        (None, Some(color_value)) => {
+                            let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
+                            Color32::from_rgb(
+                                (hash % 256) as u8,
+                                ((hash >> 8) % 256) as u8,
+                                ((hash >> 16) % 256) as u8,
+                            )
+                        }
+                        (None, None) => Color32::BLUE,
+                    },
+                    None => Color32::BLUE,
+                }
+            } else {
+                // Use a gradient based on row index
+                let hue = (row_idx as f32 * 137.5) % 360.0; // Golden angle
+                let (r, g, b) = self.hsv_to_rgb(hue, 0.8, 0.9);
+                Color32::from_rgb(r, g, b)
+            };
+            
+            points.push(PlotPoint {
+                x: 0.0, // Will be set during rendering
+                y: 0.0, // Will be set during rendering
+                z: None,
+                label: None,
+                color: Some(color),
+                size: None,
+                series_id: Some(format!("row_{}", row_idx)),
+                tooltip_data,
+            });
+            
+            series_data.push(point_data);
         }
         
         // Store the column information in metadata for rendering
@@ -1,4 +1,4 @@
-use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points};
+use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points, numeric_column};
 use egui::{Ui, Color32, RichText, Vec2, Pos2, Rect, Response, Stroke};
 use egui_plot::{Plot, PlotPoints, PlotBounds, Line, PlotUi};
 use datafusion::arrow::datatypes::DataType;
@@ -52,9 +52,9 @@ impl PlotTrait for SankeyPlot {
         
         // For large datasets, sample the data
         let max_points = 1000; // Limit for performance
-        let sample_size = query_result.rows.len().min(max_points);
-        let step = if query_result.rows.len() > max_points {
-            query_result.rows.len() / max_points
+        let sample_size = query_result.num_rows().min(max_points);
+        let step = if query_result.num_rows() > max_points {
+            query_result.num_rows() / max_points
         } else {
             1
         };
@@ -64,77 +64,73 @@ impl PlotTrait for SankeyPlot {
         let mut nodes = HashMap::new();
         let mut node_counter = 0;
         
-        for (row_idx, row) in query_result.rows.iter().enumerate().step_by(step) {
-            if row.len() > x_idx && row.len() > y_idx {
-                let source = row[x_idx].clone();
-                let target = row[y_idx].clone();
-                
-                // Parse flow value
-                let value = if let Some(value_idx) = value_idx {
-                    if row.len() > value_idx {
-                        row[value_idx].parse::<f64>().unwrap_or(1.0)
-                    } else {
-                        1.0
-                    }
-                } else {
-                    1.0
-                };
-                
-                // Create color mapping
-                let color = Color32::from_rgb(
-                    ((row_idx * 30) % 256) as u8,
-                    ((row_idx * 50) % 256) as u8,
-                    ((row_idx * 70) % 256) as u8,
-                );
-                
-                // Add nodes if they don't exist
-                if !nodes.contains_key(&source) {
-                    nodes.insert(source.clone(), NodeData {
-                        id: source.clone(),
-                        x: 0.1, // Left column
-                        y: node_counter as f64 * 0.1,
-                        color,
-                    });
-                    node_counter += 1;
-                }
-                
-                if !nodes.contains_key(&target) {
-                    nodes.insert(target.clone(), NodeData {
-                        id: target.clone(),
-                        x: 0.9, // Right column
-                        y: node_counter as f64 * 0.1,
-                        color,
-                    });
-                    node_counter += 1;
-                }
-                
-                // Add flow
-                flows.push(FlowData {
-                    source: source.clone(),
-                    target: target.clone(),
-                    value,
+        let sources = query_result.string_column(x_idx);
+        let targets = query_result.string_column(y_idx);
+        let values = value_idx.map(|idx| numeric_column(query_result, idx, "flow")).transpose()?;
+        
+        for row_idx in (0..query_result.num_rows()).step_by(step) {
+            // A flow needs both of its nodes
+            let (Some(source), Some(target)) = (sources[row_idx].clone(), targets[row_idx].clone()) else {
+                continue;
+            };
+            
+            // Flow value; without a value column, or with a NULL value, each row counts once
+            let value = values.as_ref().and_then(|values| values[row_idx]).unwrap_or(1.0);
+            
+            // Create color mapping
+            let color = Color32::from_rgb(
+                ((row_idx * 30) % 256) as u8,
+                ((row_idx * 50) % 256) as u8,
+                ((row_idx * 70) % 256) as u8,
+            );
+            
+            // Add nodes if they don't exist
+            if !nodes.contains_key(&source) {
+                nodes.insert(source.clone(), NodeData {
+                    id: source.clone(),
+                    x: 0.1, // Left column
+                    y: node_counter as f64 * 0.1,
                     color,
                 });
-                
-                // Create tooltip data
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("Source".to_string(), source.clone());
-                tooltip_data.insert("Target".to_string(), target.clone());
-                tooltip_data.insert("Value".to_string(), value.to_string());
-                tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-                
-                points.push(PlotPoint {
-                    x: nodes[&source].x,
-                    y: nodes[&source].y,
-                    z: None,
-                    label: None,
-                    color: Some(color),
-                    size: Some(value as f32),
-                    series_id: None,
-                    tooltip_data,
+                node_counter += 1;
+            }
+            
+            if !nodes.contains_key(&target) {
+                nodes.insert(target.clone(), NodeData {
+                    id: target.clone(),
+                    x: 0.9, // Right column
+                    y: node_counter as f64 * 0.1,
+                    color,
                 });
+                node_counter += 1;
             }
+            
+            // Add flow
+            flows.push(FlowData {
+                source: source.clone(),
+                target: target.clone(),
+                value,
+                color,
+            });
+            
+            // Create tooltip data
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("Source".to_string(), source.clone());
+            tooltip_data.insert("Target".to_string(), target.clone());
+            tooltip_data.insert("Value".to_string(), value.to_string());
+            tooltip_data.insert(config.x_column.clone(), source.clone());
+            tooltip_data.insert(config.y_column.clone(), target.clone());
+            
+            points.push(PlotPoint {
+                x: nodes[&source].x,
+                y: nodes[&source].y,
+                z: None,
+                label: None,
+                color: Some(color),
+                size: Some(value as f32),
+                series_id: None,
+                tooltip_data,
+            });
         }
         
         // Calculate Sankey statistics
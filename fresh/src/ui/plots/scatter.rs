@@ -71,76 +71,60 @@ impl ScatterPlotImpl {
             None
         };
 
-        let mut points = Vec::new();
+        // Typed columns come straight from the Arrow batches
+        let x_values = query_result.f64_column(x_idx);
+        let y_values = query_result.f64_column(y_idx);
+        let color_values = color_idx.map(|idx| query_result.string_column(idx));
+        let size_values = size_idx.map(|idx| query_result.f64_column(idx));
+
+        let mut points = Vec::with_capacity(x_values.len());
         let mut color_map = HashMap::new();
         let mut color_index = 0;
 
         // Enhanced data processing with professional color mapping
-        for (_row_idx, row) in query_result.rows.iter().enumerate() {
-            if row.len() > x_idx && row.len() > y_idx {
-                let x_val = row[x_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse X value '{}' as number", row[x_idx]))?;
-                let y_val = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse Y value '{}' as number", row[y_idx]))?;
-
-                // Professional color mapping based on frog-viz patterns
-                let point_color = if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        let color_value = &row[color_idx];
-                        if let Some(&existing_color) = color_map.get(color_value) {
-                            Some(existing_color)
-                        } else {
-                            let new_color = categorical_color(color_index);
-                            color_map.insert(color_value.clone(), new_color);
-                            color_index += 1;
-                            Some(new_color)
-                        }
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-
-                // Size mapping with proper scaling
-                let point_size = if let Some(size_idx) = size_idx {
-                    if row.len() > size_idx {
-                        row[size_idx].parse::<f32>().unwrap_or(config.marker_size)
-                    } else {
-                        config.marker_size
-                    }
-                } else {
-                    config.marker_size
-                };
+        for (row_idx, (x_val, y_val)) in x_values.iter().zip(y_values.iter()).enumerate() {
+            // Points need both coordinates; NULLs are skipped rather than failing the plot
+            let (Some(x_val), Some(y_val)) = (*x_val, *y_val) else {
+                continue;
+            };
+
+            // Professional color mapping based on frog-viz patterns
+            let color_value = color_values.as_ref().and_then(|values| values[row_idx].as_ref());
+            let point_color = color_value.map(|color_value| {
+                *color_map.entry(color_value.clone()).or_insert_with(|| {
+                    let new_color = categorical_color(color_index);
+                    color_index += 1;
+                    new_color
+                })
+            });
 
-                // Enhanced tooltip data with rich information
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("X".to_string(), row[x_idx].clone());
-                tooltip_data.insert("Y".to_string(), row[y_idx].clone());
-                
-                if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        tooltip_data.insert(config.color_column.as_ref().unwrap().clone(), row[color_idx].clone());
-                    }
-                }
+            // Size mapping with proper scaling
+            let size_value = size_values.as_ref().and_then(|values| values[row_idx]);
+            let point_size = size_value.map(|size| size as f32).unwrap_or(config.marker_size);
 
-                if let Some(size_idx) = size_idx {
-                    if row.len() > size_idx {
-                        tooltip_data.insert(config.size_column.as_ref().unwrap().clone(), row[size_idx].clone());
-                    }
-                }
+            // Enhanced tooltip data with rich information
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("X".to_string(), query_result.display_value(row_idx, x_idx));
+            tooltip_data.insert("Y".to_string(), query_result.display_value(row_idx, y_idx));
+            
+            if let Some(color_value) = color_value {
+                tooltip_data.insert(config.color_column.as_ref().unwrap().clone(), color_value.clone());
+            }
 
-                points.push(super::PlotPoint {
-                    x: x_val,
-                    y: y_val,
-                    z: None,
-                    label: None,
-                    color: point_color,
-                    size: Some(point_size),
-                    series_id: None,
-                    tooltip_data,
-                });
+            if let (Some(size_idx), Some(_)) = (size_idx, size_value) {
+                tooltip_data.insert(config.size_column.as_ref().unwrap().clone(), query_result.display_value(row_idx, size_idx));
             }
+
+            points.push(super::PlotPoint {
+                x: x_val,
+                y: y_val,
+                z: None,
+                label: None,
+                color: point_color,
+                size: Some(point_size),
+                series_id: None,
+                tooltip_data,
+            });
         }
 
         // Create series based on color grouping
@@ -373,6 +373,16 @@ impl PlotTrait for Scatter3DPlot {
         let size_idx = config.size_column.as_ref()
             .and_then(|col| query_result.columns.iter().position(|c| c == col));
         
+        let x_values = query_result.f64_column(x_idx);
+        let y_values = query_result.f64_column(y_idx);
+        let z_values = query_result.f64_column(z_idx);
+        let color_values = color_idx.map(|idx| query_result.f64_column(idx));
+        let size_values = size_idx.map(|idx| query_result.f64_column(idx));
+        // Rows with a NULL coordinate are not plotted
+        let coordinates = || x_values.iter().zip(&y_values).zip(&z_values)
+            .enumerate()
+            .filter_map(|(i, ((x, y), z))| Some((i, (*x)?, (*y)?, (*z)?)));
+        
         // Find min/max for normalization
         let mut x_min = f64::INFINITY;
         let mut x_max = f64::NEG_INFINITY;
@@ -381,21 +391,13 @@ impl PlotTrait for Scatter3DPlot {
         let mut z_min = f64::INFINITY;
         let mut z_max = f64::NEG_INFINITY;
         
-        for row in &query_result.rows {
-            if row.len() > x_idx.max(y_idx).max(z_idx) {
-                if let (Ok(x), Ok(y), Ok(z)) = (
-                    row[x_idx].parse::<f64>(),
-                    row[y_idx].parse::<f64>(),
-                    row[z_idx].parse::<f64>()
-                ) {
-                    x_min = x_min.min(x);
-                    x_max = x_max.max(x);
-                    y_min = y_min.min(y);
-                    y_max = y_max.max(y);
-                    z_min = z_min.min(z);
-                    z_max = z_max.max(z);
-                }
-            }
+        for (_, x, y, z) in coordinates() {
+            x_min = x_min.min(x);
+            x_max = x_max.max(x);
+            y_min = y_min.min(y);
+            y_max = y_max.max(y);
+            z_min = z_min.min(z);
+            z_max = z_max.max(z);
         }
         
         // Normalize ranges to -1 to 1
@@ -403,101 +405,80 @@ impl PlotTrait for Scatter3DPlot {
         let y_range = y_max - y_min;
         let z_range = z_max - z_min;
         
-        for (i, row) in query_result.rows.iter().enumerate() {
-            if row.len() > x_idx.max(y_idx).max(z_idx) {
-                if let (Ok(x), Ok(y), Ok(z)) = (
-                    row[x_idx].parse::<f64>(),
-                    row[y_idx].parse::<f64>(),
-                    row[z_idx].parse::<f64>()
-                ) {
-                    // Normalize coordinates to -1 to 1
-                    let norm_x = if x_range > 0.0 { (x - x_min) / x_range * 2.0 - 1.0 } else { 0.0 };
-                    let norm_y = if y_range > 0.0 { (y - y_min) / y_range * 2.0 - 1.0 } else { 0.0 };
-                    let norm_z = if z_range > 0.0 { (z - z_min) / z_range * 2.0 - 1.0 } else { 0.0 };
-                    
-                    // Determine color with improved color mapping
-                    let color = if let Some(color_idx) = color_idx {
-                        if row.len() > color_idx {
-                            if let Ok(color_val) = row[color_idx].parse::<f64>() {
-                                // Use a more sophisticated color scheme
-                                let normalized = (color_val - x_min) / x_range.max(1.0);
-                                let normalized = normalized.clamp(0.0, 1.0);
-                                
-                                // Viridis-like color scheme
-                                if normalized < 0.25 {
-                                    let t = normalized / 0.25;
-                                    Color32::from_rgb(
-                                        (68.0 + (1.0 - t) * 187.0) as u8,
-                                        (1.0 + t * 198.0) as u8,
-                                        (84.0 + t * 112.0) as u8,
-                                    )
-                                } else if normalized < 0.5 {
-                                    let t = (normalized - 0.25) / 0.25;
-                                    Color32::from_rgb(
-                                        (255.0 - t * 187.0) as u8,
-                                        (199.0 + t * 56.0) as u8,
-                                        (196.0 - t * 112.0) as u8,
-                                    )
-                                } else if normalized < 0.75 {
-                                    let t = (normalized - 0.5) / 0.25;
-                                    Color32::from_rgb(
-                                        (68.0 + t * 187.0) as u8,
-                                        (255.0 - t * 56.0) as u8,
-                                        (84.0 + t * 112.0) as u8,
-                                    )
-                                } else {
-                                    let t = (normalized - 0.75) / 0.25;
-                                    Color32::from_rgb(
-                                        (255.0 - t * 187.0) as u8,
-                                        (199.0 + t * 56.0) as u8,
-                                        (196.0 + t * 59.0) as u8,
-                                    )
-                                }
-                            } else {
-                                Color32::from_rgb(100, 150, 255) // Default blue
-                            }
-                        } else {
-                            Color32::from_rgb(100, 150, 255) // Default blue
-                        }
-                    } else {
-                        // Use a gradient based on point index for better visual appeal
-                        let hue = (i as f32 * 137.5) % 360.0; // Golden angle for good distribution
-                        let (r, g, b) = self.hsv_to_rgb(hue, 0.8, 0.9);
-                        Color32::from_rgb(r, g, b)
-                    };
+        for (i, x, y, z) in coordinates() {
+            // Normalize coordinates to -1 to 1
+            let norm_x = if x_range > 0.0 { (x - x_min) / x_range * 2.0 - 1.0 } else { 0.0 };
+            let norm_y = if y_range > 0.0 { (y - y_min) / y_range * 2.0 - 1.0 } else { 0.0 };
+            let norm_z = if z_range > 0.0 { (z - z_min) / z_range * 2.0 - 1.0 } else { 0.0 };
+            
+            // Determine color with improved color mapping
+            let color = if let Some(color_values) = &color_values {
+                if let Some(color_val) = color_values[i] {
+                    // Use a more sophisticated color scheme
+                    let normalized = (color_val - x_min) / x_range.max(1.0);
+                    let normalized = normalized.clamp(0.0, 1.0);
                     
-                    // Determine size
-                    let size = if let Some(size_idx) = size_idx {
-                        if row.len() > size_idx {
-                            if let Ok(size_val) = row[size_idx].parse::<f64>() {
-                                (size_val / x_max.max(1.0) * 10.0).clamp(2.0, 15.0) as f32
-                            } else {
-                                5.0
-                            }
-                        } else {
-                            5.0
-                        }
+                    // Viridis-like color scheme
+                    if normalized < 0.25 {
+                        let t = normalized / 0.25;
+                        Color32::from_rgb(
+                            (68.0 + (1.0 - t) * 187.0) as u8,
+                            (1.0 + t * 198.0) as u8,
+                            (84.0 + t * 112.0) as u8,
+                        )
+                    } else if normalized < 0.5 {
+                        let t = (normalized - 0.25) / 0.25;
+                        Color32::from_rgb(
+                            (255.0 - t * 187.0) as u8,
+                            (199.0 + t * 56.0) as u8,
+                            (196.0 - t * 112.0) as u8,
+                        )
+                    } else if normalized < 0.75 {
+                        let t = (normalized - 0.5) / 0.25;
+                        Color32::from_rgb(
+                            (68.0 + t * 187.0) as u8,
+                            (255.0 - t * 56.0) as u8,
+                            (84.0 + t * 112.0) as u8,
+                        )
                     } else {
-                        5.0
-                    };
-                    
-                    let mut tooltip_data = HashMap::new();
-                    tooltip_data.insert("X".to_string(), format!("{:.3}", x));
-                    tooltip_data.insert("Y".to_string(), format!("{:.3}", y));
-                    tooltip_data.insert("Z".to_string(), format!("{:.3}", z));
-                    
-                    points.push(PlotPoint {
-                        x: norm_x,
-                        y: norm_y,
-                        z: Some(norm_z),
-                        label: Some(format!("Point {}", i)),
-                        color: Some(color),
-                        size: Some(size),
-                        series_id: Some("3d_points".to_string()),
-                        tooltip_data,
-                    });
+                        let t = (normalized - 0.75) / 0.25;
+                        Color32::from_rgb(
+                            (255.0 - t * 187.0) as u8,
+                            (199.0 + t * 56.0) as u8,
+                            (196.0 + t * 59.0) as u8,
+                        )
+                    }
+                } else {
+                    Color32::from_rgb(100, 150, 255) // Default blue
                 }
-            }
+            } else {
+                // Use a gradient based on point index for better visual appeal
+                let hue = (i as f32 * 137.5) % 360.0; // Golden angle for good distribution
+                let (r, g, b) = self.hsv_to_rgb(hue, 0.8, 0.9);
+                Color32::from_rgb(r, g, b)
+            };
+            
+            // Determine size
+            let size = match size_values.as_ref().and_then(|values| values[i]) {
+                Some(size_val) => (size_val / x_max.max(1.0) * 10.0).clamp(2.0, 15.0) as f32,
+                None => 5.0,
+            };
+            
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("X".to_string(), format!("{:.3}", x));
+            tooltip_data.insert("Y".to_string(), format!("{:.3}", y));
+            tooltip_data.insert("Z".to_string(), format!("{:.3}", z));
+            
+            points.push(PlotPoint {
+                x: norm_x,
+                y: norm_y,
+                z: Some(norm_z),
+                label: Some(format!("Point {}", i)),
+                color: Some(color),
+                size: Some(size),
+                series_id: Some("3d_points".to_string()),
+                tooltip_data,
+            });
         }
         
         // Create series
@@ -1,4 +1,4 @@
-use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points};
+use super::{Plot as PlotTrait, PlotData, PlotConfiguration, PlotPoint, extract_plot_points, numeric_column};
 use egui::{Ui, Color32, RichText, Vec2, Pos2, Rect, Response, Stroke};
 use egui_plot::{Plot, PlotPoints, PlotBounds, Line, PlotUi};
 use datafusion::arrow::datatypes::DataType;
@@ -54,70 +54,68 @@ impl PlotTrait for StreamPlot {
         let mut points = Vec::new();
         let mut stream_data = Vec::new();
         
-        for (row_idx, row) in query_result.rows.iter().enumerate() {
-            if row.len() > x_idx && row.len() > y_idx {
-                // Parse time value
-                let time_val = if let Ok(timestamp) = row[x_idx].parse::<f64>() {
-                    timestamp
-                } else {
-                    // Try to parse as date string or use row index
-                    row_idx as f64
-                };
-                
-                let value = row[y_idx].parse::<f64>()
-                    .map_err(|_| format!("Failed to parse Y value '{}' as number", row[y_idx]))?;
-                
-                // Create color mapping
-                let color = if let Some(color_idx) = color_idx {
-                    if row.len() > color_idx {
-                        let color_value = &row[color_idx];
-                        if let Ok(num_val) = color_value.parse::<f64>() {
-                            let normalized = (num_val - 0.0).max(0.0).min(1.0);
-                            Color32::from_rgb(
-                                (normalized * 255.0) as u8,
-                                ((1.0 - normalized) * 255.0) as u8,
-                                128
-                            )
-                        } else {
-                            // Categorical color
-                            let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
-                            Color32::from_rgb(
-                                (hash % 256) as u8,
-                                ((hash >> 8) % 256) as u8,
-                                ((hash >> 16) % 256) as u8,
-                            )
-                        }
+        let times = query_result.f64_column(x_idx);
+        let values = numeric_column(query_result, y_idx, "Y")?;
+        let color_values = color_idx.map(|idx| (query_result.f64_column(idx), query_result.string_column(idx)));
+        
+        for row_idx in 0..query_result.num_rows() {
+            // Rows without a value leave a gap
+            let Some(value) = values[row_idx] else {
+                continue;
+            };
+            
+            // Time value, or the row index where there is none
+            let time_val = times[row_idx].unwrap_or(row_idx as f64);
+            
+            // Create color mapping
+            let color = if let Some((numbers, labels)) = &color_values {
+                if let Some(color_value) = &labels[row_idx] {
+                    if let Some(num_val) = numbers[row_idx] {
+                        let normalized = (num_val - 0.0).max(0.0).min(1.0);
+                        Color32::from_rgb(
+                            (normalized * 255.0) as u8,
+                            ((1.0 - normalized) * 255.0) as u8,
+                            128
+                        )
                     } else {
-                        Color32::BLUE
+                        // Categorical color
+                        let hash = color_value.chars().map(|c| c as u32).sum::<u32>();
+                        Color32::from_rgb(
+                            (hash % 256) as u8,
+                            ((hash >> 8) % 256) as u8,
+                            ((hash >> 16) % 256) as u8,
+                        )
                     }
                 } else {
                     Color32::BLUE
-                };
-                
-                // Create tooltip data
-                let mut tooltip_data = HashMap::new();
-                tooltip_data.insert("Time".to_string(), time_val.to_string());
-                tooltip_data.insert("Value".to_string(), value.to_string());
-                tooltip_data.insert(config.x_column.clone(), row[x_idx].clone());
-                tooltip_data.insert(config.y_column.clone(), row[y_idx].clone());
-                
-                points.push(PlotPoint {
-                    x: time_val,
-                    y: value,
-                    z: None,
-                    label: None,
-                    color: Some(color),
-                    size: None,
-                    series_id: None,
-                    tooltip_data,
-                });
-                
-                stream_data.push(StreamDataPoint {
-                    time: time_val,
-                    value,
-                    color,
-                });
-            }
+                }
+            } else {
+                Color32::BLUE
+            };
+            
+            // Create tooltip data
+            let mut tooltip_data = HashMap::new();
+            tooltip_data.insert("Time".to_string(), time_val.to_string());
+            tooltip_data.insert("Value".to_string(), value.to_string());
+            tooltip_data.insert(config.x_column.clone(), query_result.display_value(row_idx, x_idx));
+            tooltip_data.insert(config.y_column.clone(), query_result.display_value(row_idx, y_idx));
+            
+            points.push(PlotPoint {
+                x: time_val,
+                y: value,
+                z: None,
+                label: None,
+                color: Some(color),
+                size: None,
+                series_id: None,
+                tooltip_data,
+            });
+            
+            stream_data.push(StreamDataPoint {
+                time: time_val,
+                value,
+                color,
+            });
         }
         
         // Sort by time
@@ -211,48 +211,47 @@ impl SunburstChart {
         // Build tree
         let mut root = SunburstNode::new("root".to_string(), 0, Vec::new());
         
-        for row in &query_result.rows {
-            if row.len() >= column_indices.iter().max().unwrap_or(&0) + 1 {
-                let mut current_node = &mut root;
-                let mut current_path = Vec::new();
+        let labels: Vec<Vec<Option<String>>> = column_indices.iter()
+            .map(|&col_idx| query_result.string_column(col_idx))
+            .collect();
+        let values = value_idx.map(|idx| query_result.f64_column(idx));
+        
+        for row_idx in 0..query_result.num_rows() {
+            // Rows with a NULL anywhere in the hierarchy have no place in the tree
+            let Some(path) = labels.iter().map(|column| column[row_idx].as_ref()).collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            
+            let mut current_node = &mut root;
+            let mut current_path = Vec::new();
+            
+            // Build path through hierarchy
+            for (depth, name) in path.into_iter().enumerate() {
+                current_path.push(name.clone());
                 
-                // Build path through hierarchy
-                for (depth, &col_idx) in column_indices.iter().enumerate() {
-                    if row.len() > col_idx {
-                        let name = &row[col_idx];
-                        current_path.push(name.clone());
-                        
-                        // Find or create child node
-                        let child_idx = current_node.children.iter()
-                            .position(|child| child.name == *name);
-                        
-                        if let Some(idx) = child_idx {
-                            current_node = &mut current_node.children[idx];
-                        } else {
-                            let mut new_node = SunburstNode::new(
-                                name.clone(),
-                                depth + 1,
-                                current_path.clone(),
-                            );
-                            
-                            // Set value if this is a leaf node
-                            if depth == column_indices.len() - 1 {
-                                if let Some(value_idx) = value_idx {
-                                    if row.len() > value_idx {
-                                        new_node.value = row[value_idx].parse::<f64>().unwrap_or(1.0);
-                                    } else {
-                                        new_node.value = 1.0;
-                                    }
-                                } else {
-                                    new_node.value = 1.0;
-                                }
-                            }
-                            
-                            current_node.children.push(new_node);
-                            let last_idx = current_node.children.len() - 1;
-                            current_node = &mut current_node.children[last_idx];
-                        }
+                // Find or create child node
+                let child_idx = current_node.children.iter()
+                    .position(|child| child.name == *name);
+                
+                if let Some(idx) = child_idx {
+                    current_node = &mut current_node.children[idx];
+                } else {
+                    let mut new_node = SunburstNode::new(
+                        name.clone(),
+                        depth + 1,
+                        current_path.clone(),
+                    );
+                    
+                    // Set value if this is a leaf node
+                    if depth == column_indices.len() - 1 {
+                        new_node.value = values.as_ref()
+                            .and_then(|values| values[row_idx])
+                            .unwrap_or(1.0);
                     }
+                    
+                    current_node.children.push(new_node);
+                    let last_idx = current_node.children.len() - 1;
+                    current_node = &mut current_node.children[last_idx];
                 }
             }
         }
@@ -482,9 +481,9 @@ impl PlotTrait for SunburstPlot {
         
         // For large datasets, sample the data
         let max_points = 3000; // Limit for performance
-        let sample_size = query_result.rows.len().min(max_points);
-        let step = if query_result.rows.len() > max_points {
-            query_result.rows.len() / max_points
+        let sample_size = query_result.num_rows().min(max_points);
+        let step = if query_result.num_rows() > max_points {
+            query_result.num_rows() / max_points
         } else {
             1
         };
@@ -497,20 +496,9 @@ impl PlotTrait for SunburstPlot {
             sunburst.config.value_column = Some(config.y_column.clone());
         }
         
-        // Build hierarchy with sampling
-        let mut sampled_rows = Vec::new();
-        for (i, row) in query_result.rows.iter().enumerate().step_by(step) {
-            sampled_rows.push(row.clone());
-        }
-        
-        // Create a modified query result with sampled data
-        let sampled_rows_len = sampled_rows.len();
-        let sampled_result = QueryResult {
-            columns: query_result.columns.clone(),
-            rows: sampled_rows,
-            column_types: query_result.column_types.clone(),
-            total_rows: Some(sampled_rows_len),
-        };
+        // Build hierarchy from a sample of the rows
+        let sampled_result = query_result.sample_every(step)
+            .map_err(|e| format!("Failed to sample data: {}", e))?;
         
         sunburst.build_hierarchy(&sampled_result);
         
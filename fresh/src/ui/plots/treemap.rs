@@ -176,58 +176,53 @@ impl Treemap {
         let mut root = TreeNode::new("root".to_string(), "Root".to_string());
         root.is_leaf = false;
         
-        for row in &query_result.rows {
-            if row.len() > path_idx.max(value_idx) {
-                let path = &row[path_idx];
-                let value = row[value_idx].parse::<f64>().unwrap_or(0.0);
-                
-                let color_value = color_idx.and_then(|idx| {
-                    if row.len() > idx {
-                        row[idx].parse::<f64>().ok()
-                    } else {
-                        None
-                    }
-                });
-                
-                let label = label_idx.and_then(|idx| {
-                    if row.len() > idx {
-                        Some(row[idx].clone())
-                    } else {
-                        None
-                    }
-                }).unwrap_or_else(|| path.split('/').last().unwrap_or(path).to_string());
+        let paths = query_result.string_column(path_idx);
+        let values = query_result.f64_column(value_idx);
+        let color_values = color_idx.map(|idx| query_result.f64_column(idx));
+        let labels = label_idx.map(|idx| query_result.string_column(idx));
+        
+        for (row_idx, path) in paths.iter().enumerate() {
+            // Rows without a path have no place in the tree
+            let Some(path) = path else {
+                continue;
+            };
+            
+            let value = values[row_idx].unwrap_or(0.0);
+            let color_value = color_values.as_ref().and_then(|values| values[row_idx]);
+            let label = labels.as_ref()
+                .and_then(|labels| labels[row_idx].clone())
+                .unwrap_or_else(|| path.split('/').last().unwrap_or(path).to_string());
+            
+            // Parse hierarchical path
+            let path_parts: Vec<&str> = path.split('/').collect();
+            let mut current_node = &mut root;
+            
+            for (depth, part) in path_parts.iter().enumerate() {
+                let node_id = path_parts[..=depth].join("/");
                 
-                // Parse hierarchical path
-                let path_parts: Vec<&str> = path.split('/').collect();
-                let mut current_node = &mut root;
+                // Find or create child node
+                let child_idx = current_node.children.iter()
+                    .position(|child| child.id == node_id);
                 
-                for (depth, part) in path_parts.iter().enumerate() {
-                    let node_id = path_parts[..=depth].join("/");
-                    
-                    // Find or create child node
-                    let child_idx = current_node.children.iter()
-                        .position(|child| child.id == node_id);
+                if let Some(idx) = child_idx {
+                    current_node = &mut current_node.children[idx];
+                } else {
+                    let mut new_node = TreeNode::new(node_id.clone(), part.to_string());
+                    new_node.depth = depth;
+                    new_node.parent = Some(current_node.id.clone());
                     
-                    if let Some(idx) = child_idx {
-                        current_node = &mut current_node.children[idx];
+                    if depth == path_parts.len() - 1 {
+                        // Leaf node
+                        new_node.value = value;
+                        new_node.color_value = color_value;
+                        new_node.label = label.clone();
                     } else {
-                        let mut new_node = TreeNode::new(node_id.clone(), part.to_string());
-                        new_node.depth = depth;
-                        new_node.parent = Some(current_node.id.clone());
-                        
-                        if depth == path_parts.len() - 1 {
-                            // Leaf node
-                            new_node.value = value;
-                            new_node.color_value = color_value;
-                            new_node.label = label.clone();
-                        } else {
-                            new_node.is_leaf = false;
-                        }
-                        
-                        current_node.children.push(new_node);
-                        let last_idx = current_node.children.len() - 1;
-                        current_node = &mut current_node.children[last_idx];
+                        new_node.is_leaf = false;
                     }
+                    
+                    current_node.children.push(new_node);
+                    let last_idx = current_node.children.len() - 1;
+                    current_node = &mut current_node.children[last_idx];
                 }
             }
         }
@@ -561,9 +556,9 @@ impl PlotTrait for TreemapPlot {
         
         // For large datasets, sample the data
         let max_points = 2000; // Limit for performance
-        let sample_size = query_result.rows.len().min(max_points);
-        let step = if query_result.rows.len() > max_points {
-            query_result.rows.len() / max_points
+        let sample_size = query_result.num_rows().min(max_points);
+        let step = if query_result.num_rows() > max_points {
+            query_result.num_rows() / max_points
         } else {
             1
         };
@@ -577,20 +572,9 @@ impl PlotTrait for TreemapPlot {
             treemap.config.color_column = Some(color_col.clone());
         }
         
-        // Build hierarchy with sampling
-        let mut sampled_rows = Vec::new();
-        for (i, row) in query_result.rows.iter().enumerate().step_by(step) {
-            sampled_rows.push(row.clone());
-        }
-        
-        // Create a modified query result with sampled data
-        let sampled_rows_len = sampled_rows.len();
-        let sampled_result = QueryResult {
-            columns: query_result.columns.clone(),
-            rows: sampled_rows,
-            column_types: query_result.column_types.clone(),
-            total_rows: Some(sampled_rows_len),
-        };
+        // Build hierarchy from a sample of the rows
+        let sampled_result = query_result.sample_every(step)
+            .map_err(|e| format!("Failed to sample data: {}", e))?;
         
         treemap.build_hierarchy(&sampled_result);
         
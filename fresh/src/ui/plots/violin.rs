@@ -143,12 +143,10 @@ impl ViolinPlot {
             let mut groups = std::collections::HashMap::new();
             
             // Collect data points for each group
-            for row in &query_result.rows {
-                if row.len() > y_idx && row.len() > group_idx {
-                    if let Ok(y_val) = row[y_idx].parse::<f64>() {
-                        let group = row[group_idx].clone();
-                        groups.entry(group).or_insert_with(Vec::new).push(y_val);
-                    }
+            let group_values = query_result.string_column(group_idx);
+            for (y_val, group) in query_result.f64_column(y_idx).into_iter().zip(group_values) {
+                if let Some(y_val) = y_val {
+                    groups.entry(group.unwrap_or_default()).or_insert_with(Vec::new).push(y_val);
                 }
             }
             
@@ -166,16 +164,9 @@ impl ViolinPlot {
             Ok(result)
         } else {
             // No grouping, process all data together
-            let mut values = Vec::new();
             
             // Collect all data points
-            for row in &query_result.rows {
-                if row.len() > y_idx {
-                    if let Ok(y_val) = row[y_idx].parse::<f64>() {
-                        values.push(y_val);
-                    }
-                }
-            }
+            let values: Vec<f64> = query_result.f64_column(y_idx).into_iter().flatten().collect();
             
             // Calculate box plot statistics manually
             let stats = self.calculate_box_plot_stats(&values, None);
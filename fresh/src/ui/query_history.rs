@@ -0,0 +1,117 @@
+use crate::core::QueryLibrary;
+
+/// What the user asked for in the query history window
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryHistoryAction {
+    /// Re-open a statement in a new query window
+    Open(String),
+    /// Promote a statement to a named saved query
+    SaveQuery { name: String, sql: String },
+}
+
+/// Searchable list of the statements executed in the current project
+#[derive(Default)]
+pub struct QueryHistoryWindow {
+    pub visible: bool,
+    search: String,
+    // Statement being promoted to a saved query, and the name typed for it
+    promote_sql: Option<String>,
+    promote_name: String,
+}
+
+impl QueryHistoryWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, library: &QueryLibrary) -> Option<QueryHistoryAction> {
+        if !self.visible {
+            return None;
+        }
+
+        let mut action = None;
+        let mut visible = self.visible;
+
+        egui::Window::new("Query History")
+            .open(&mut visible)
+            .resizable(true)
+            .default_size([600.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    ui.add(egui::TextEdit::singleline(&mut self.search)
+                        .desired_width(300.0)
+                        .hint_text("SQL text or error"));
+                    if ui.button("Clear").clicked() {
+                        self.search.clear();
+                    }
+                });
+
+                if let Some(sql) = self.promote_sql.clone() {
+                    ui.horizontal(|ui| {
+                        ui.label("Save as:");
+                        ui.add(egui::TextEdit::singleline(&mut self.promote_name)
+                            .desired_width(200.0)
+                            .hint_text("query name"));
+                        if ui.add_enabled(!self.promote_name.trim().is_empty(), egui::Button::new("Save")).clicked() {
+                            action = Some(QueryHistoryAction::SaveQuery {
+                                name: self.promote_name.trim().to_string(),
+                                sql,
+                            });
+                            self.promote_sql = None;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.promote_sql = None;
+                        }
+                    });
+                }
+
+                let entries = library.search_history(&self.search);
+                ui.label(format!("{} of {} statements", entries.len(), library.history().len()));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for (i, entry) in entries.iter().enumerate() {
+                            ui.push_id(i, |ui| {
+                                ui.group(|ui| {
+                                    ui.horizontal(|ui| {
+                                        if entry.succeeded() {
+                                            ui.colored_label(egui::Color32::from_rgb(100, 200, 100), "✓");
+                                        } else {
+                                            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "✗");
+                                        }
+                                        ui.label(entry.executed_at_display());
+                                        ui.label(format!("{} ms", entry.duration_ms));
+                                        if let Some(rows) = entry.row_count {
+                                            ui.label(format!("{} rows", rows));
+                                        }
+
+                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                            if ui.add_enabled(!library.is_readonly(), egui::Button::new("Save..."))
+                                                .on_disabled_hover_text("Queries cannot be saved in a read-only project")
+                                                .clicked() {
+                                                self.promote_sql = Some(entry.sql.clone());
+                                                self.promote_name.clear();
+                                            }
+                                            if ui.button("Open").clicked() {
+                                                action = Some(QueryHistoryAction::Open(entry.sql.clone()));
+                                            }
+                                        });
+                                    });
+
+                                    ui.label(egui::RichText::new(&entry.sql).monospace());
+                                    if let Some(error) = &entry.error {
+                                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                                    }
+                                });
+                            });
+                        }
+                    });
+            });
+
+        self.visible = visible;
+        action
+    }
+}
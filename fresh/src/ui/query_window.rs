@@ -1,26 +1,63 @@
-use std::sync::Arc;
-use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use egui;
-use crate::core::{Database, QueryResult};
+use crate::core::{export, script, Database, ExportOptions, QueryExecutor, QueryHistoryEntry, QueryParameter, QueryResult, RunningQuery, SavedQuery, ScriptResult, StatementKind, StatementStatus};
+use crate::ui::parameter_panel;
+use crate::ui::sql_editor::SqlEditor;
+use crate::ui::explain_window::ExplainWindow;
+use crate::ui::export_dialog;
+use crate::ui::plot_window::PlotSource;
+
+type ScriptSlot = Arc<Mutex<Option<ScriptResult>>>;
+type MaterializeSlot = Arc<Mutex<Option<crate::core::error::Result<()>>>>;
+type ExportSlot = Arc<Mutex<Option<String>>>;
 
 pub struct QueryWindow {
     id: egui::Id,
     title: String,
     query: String,
+    editor: SqlEditor,
+    parameters: Vec<QueryParameter>,
+    // Planning error of the query as currently written
+    diagnostic: Option<String>,
+    // Query text the parameters and diagnostic were last derived from
+    analyzed_sql: Option<String>,
+    // Whether the analyzed query runs as a script
+    analyzed_script: bool,
     result: Option<QueryResult>,
+    explain_window: ExplainWindow,
+    running: Option<RunningQuery>,
+    // Script running on a worker thread, with the time it started
+    running_script: Option<(ScriptSlot, Instant)>,
+    // Statuses of the last script; its last query's rows are paged locally
+    script_result: Option<ScriptResult>,
     error: Option<String>,
     page: usize,
     page_size: usize,
-    export_format: ExportFormat,
+    export_options: ExportOptions,
     show_export_menu: bool,
     export_mode: ExportMode,
+    // Outcome of the last export
+    export_message: Option<String>,
+    // Query being exported on a worker thread, with its cancel flag and the time it started
+    running_export: Option<(ExportSlot, Arc<AtomicBool>, Instant)>,
     add_plot_requested: bool,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ExportFormat {
-    Csv,
-    Json,
+    show_save_view: bool,
+    view_name: String,
+    show_materialize: bool,
+    table_name: String,
+    // Table being materialized on a worker thread, with the time it started
+    running_materialize: Option<(MaterializeSlot, Instant)>,
+    // Set when a view or table was created, so the sidebar lists it
+    views_changed: bool,
+    show_save_query: bool,
+    saved_query_name: String,
+    save_query_request: Option<SavedQuery>,
+    // Statement to record in the history once the running query finishes
+    history_sql: Option<String>,
+    history_entries: Vec<QueryHistoryEntry>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -31,23 +68,54 @@ enum ExportMode {
 
 impl QueryWindow {
     pub fn new(window_id: usize, title: String, initial_query: String) -> Self {
+        let id = egui::Id::new(format!("query_window_{}", window_id));
         Self {
-            id: egui::Id::new(format!("query_window_{}", window_id)),
+            id,
             title,
             query: initial_query,
+            editor: SqlEditor::new(id.with("sql")),
+            parameters: Vec::new(),
+            diagnostic: None,
+            analyzed_sql: None,
+            analyzed_script: false,
             result: None,
+            explain_window: ExplainWindow::new(id.with("explain")),
+            running: None,
+            running_script: None,
+            script_result: None,
             error: None,
             page: 0,
             page_size: 25,
-            export_format: ExportFormat::Csv,
+            export_options: ExportOptions::default(),
             show_export_menu: false,
             export_mode: ExportMode::Page,
+            export_message: None,
+            running_export: None,
             add_plot_requested: false,
+            show_save_view: false,
+            view_name: String::new(),
+            show_materialize: false,
+            table_name: String::new(),
+            running_materialize: None,
+            views_changed: false,
+            show_save_query: false,
+            saved_query_name: String::new(),
+            save_query_request: None,
+            history_sql: None,
+            history_entries: Vec::new(),
         }
     }
     
+    /// Start with values for the query's parameters, e.g. those of a saved query
+    pub fn with_parameters(mut self, parameters: Vec<QueryParameter>) -> Self {
+        self.parameters = parameters;
+        self
+    }
+    
     pub fn show(&mut self, ctx: &egui::Context, db: Arc<Database>) -> bool {
         let mut open = true;
+        self.poll_running(ctx);
+        self.analyze_query(&db);
         
         egui::Window::new(&self.title)
             .id(self.id)
@@ -65,33 +133,163 @@ impl QueryWindow {
                     
                     ui.label("SQL Query:");
                     
-                    let text_height = 16.0;
-                    let line_count = self.query.lines().count().max(1);
-                    let _desired_height = text_height * line_count as f32 + 8.0;
-                    let _max_height = 150.0; // Cap the maximum height
-                    
-                    let response = egui::TextEdit::multiline(&mut self.query)
-                        .font(egui::TextStyle::Monospace)
-                        .desired_width(f32::INFINITY)
-                        .min_size(egui::vec2(0.0, text_height + 8.0))
-                        .desired_rows(line_count.min(8))
-                        .show(ui);
+                    let error = self.diagnostic.as_deref().or(self.error.as_deref());
+                    let response = self.editor.show(ui, &mut self.query, error, &db);
                     
                     // Execute on Ctrl+Enter
-                    if response.response.has_focus() 
+                    if response.has_focus() 
                         && ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.ctrl) {
                         self.execute_query(db.clone());
                     }
                 });
                 
+                // Save the current query as a named view
+                if self.show_save_view {
+                    ui.horizontal(|ui| {
+                        ui.label("View name:");
+                        ui.add(egui::TextEdit::singleline(&mut self.view_name)
+                            .desired_width(200.0)
+                            .hint_text("my_view"));
+                        if ui.button("Save").clicked() {
+                            self.save_as_view(&db);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_save_view = false;
+                        }
+                    });
+                }
+                
+                // Save every row of the current query as a table in the project
+                if self.show_materialize {
+                    ui.horizontal(|ui| {
+                        ui.label("Table name:");
+                        ui.add(egui::TextEdit::singleline(&mut self.table_name)
+                            .desired_width(200.0)
+                            .hint_text("my_table"));
+                        if ui.add_enabled(self.running_materialize.is_none(), egui::Button::new("Create")).clicked() {
+                            self.materialize(db.clone());
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_materialize = false;
+                        }
+                    });
+                }
+                
+                // Write the current page or every row of the query to a file
+                if self.show_export_menu {
+                    ui.group(|ui| {
+                        export_dialog::show_export_options(ui, self.id, &mut self.export_options);
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.export_mode, ExportMode::Page, "Current page");
+                            ui.radio_value(&mut self.export_mode, ExportMode::All, "All rows");
+                            if ui.add_enabled(self.running_export.is_none(), egui::Button::new("Export")).clicked() {
+                                self.export(db.clone());
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.show_export_menu = false;
+                            }
+                        });
+                        if let Some(message) = &self.export_message {
+                            ui.label(message);
+                        }
+                    });
+                }
+                
+                // Promote the current query to the project's saved queries
+                if self.show_save_query {
+                    ui.horizontal(|ui| {
+                        ui.label("Query name:");
+                        ui.add(egui::TextEdit::singleline(&mut self.saved_query_name)
+                            .desired_width(200.0)
+                            .hint_text("daily_summary"));
+                        if ui.button("Save").clicked() && !self.saved_query_name.trim().is_empty() {
+                            self.save_query_request = Some(SavedQuery {
+                                name: self.saved_query_name.trim().to_string(),
+                                sql: self.query.clone(),
+                                parameters: self.parameters.clone(),
+                            });
+                            self.show_save_query = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_save_query = false;
+                        }
+                    });
+                }
+                
+                // Typed inputs for the query's parameters
+                if !self.parameters.is_empty() {
+                    let mut run = false;
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Parameters:");
+                            run = ui.button("Run").on_hover_text("Run the query with these values").clicked();
+                        });
+                        run |= parameter_panel::show(ui, self.id.with("parameters"), &mut self.parameters);
+                    });
+                    if run {
+                        self.execute_query(db.clone());
+                    }
+                }
+                
                 ui.separator();
                 
+                // Progress of a query still running in the background
+                if let Some(running) = &self.running {
+                    let mut cancel = false;
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!(
+                            "Running... {:.1}s, {} rows received",
+                            running.elapsed().as_secs_f32(),
+                            running.rows_received()
+                        ));
+                        cancel = ui.button("Cancel").clicked();
+                    });
+                    if cancel {
+                        self.cancel_query();
+                    }
+                    ui.separator();
+                }
+                
+                if let Some((_, started)) = &self.running_script {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Running script... {:.1}s", started.elapsed().as_secs_f32()));
+                    });
+                    ui.separator();
+                }
+                
+                if let Some((_, started)) = &self.running_materialize {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Materializing table... {:.1}s", started.elapsed().as_secs_f32()));
+                    });
+                    ui.separator();
+                }
+                
+                if let Some((_, cancelled, started)) = &self.running_export {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Exporting... {:.1}s", started.elapsed().as_secs_f32()));
+                        if ui.button("Cancel").clicked() {
+                            cancelled.store(true, Ordering::Relaxed);
+                        }
+                    });
+                    ui.separator();
+                }
+                
                 // Error display
                 if let Some(error) = &self.error {
                     ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("✗ Error: {}", error));
                     ui.separator();
                 }
                 
+                // Status of each statement of the last script
+                if let Some(script) = &self.script_result {
+                    Self::show_script_statuses(ui, self.id, script);
+                    ui.separator();
+                }
+                
                 // Results section with darker background
                 if let Some(results) = &self.result {
                     let available_height = ui.available_height() - 60.0; // Reserve space for controls
@@ -102,8 +300,8 @@ impl QueryWindow {
                         
                                                  // Results header
                          ui.horizontal(|ui| {
-                             let total_rows = results.total_rows.unwrap_or(results.rows.len());
-                             let actual_rows_returned = results.rows.len();
+                             let total_rows = results.total_rows.unwrap_or(results.num_rows());
+                             let actual_rows_returned = results.num_rows();
                              
                              ui.label(format!(
                                  "Results: {} rows (showing {}-{} of page {})",
@@ -153,7 +351,7 @@ impl QueryWindow {
                                 .on_hover_text("Go to previous page")
                                 .clicked() && self.page > 0 {
                                 self.page = self.page.saturating_sub(1);
-                                self.execute_query(db.clone());
+                                self.run_page(db.clone());
                             }
                             
                             let total_pages = if let Some(result) = &self.result {
@@ -172,7 +370,7 @@ impl QueryWindow {
                                 .on_hover_text("Go to next page")
                                 .clicked() && self.page + 1 < total_pages {
                                 self.page += 1;
-                                self.execute_query(db.clone());
+                                self.run_page(db.clone());
                             }
                             
                             ui.separator();
@@ -186,30 +384,67 @@ impl QueryWindow {
                                      if new_size > 0 && new_size <= 10000 {
                                          self.page_size = new_size;
                                          // Re-execute query with new page size
-                                         self.execute_query(db.clone());
+                                         self.page = 0;
+                                         self.run_page(db.clone());
                                      }
                                  }
                              }
                         });
                         
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.add_enabled(!self.query.trim().is_empty(), egui::Button::new("Explain"))
+                                .on_hover_text("Run EXPLAIN ANALYZE and show the time and rows of each operator")
+                                .clicked() {
+                                self.explain_window.open(db.clone(), &self.title, &self.query, &self.parameters);
+                            }
+                            
                             if ui.button("Add Plot").clicked() {
                                 self.add_plot_requested = true;
                             }
                             
-                            if ui.button("Export All").clicked() {
-                                self.export_all_csv(db.clone());
+                            if ui.add_enabled(!db.is_readonly(), egui::Button::new("Save as View"))
+                                .on_disabled_hover_text("Views cannot be created in a read-only project")
+                                .clicked() {
+                                self.show_save_view = !self.show_save_view;
+                            }
+                            
+                            if ui.add_enabled(!db.is_readonly(), egui::Button::new("Materialize as Table"))
+                                .on_hover_text("Run the full query and save its rows as a table that can be refreshed later")
+                                .on_disabled_hover_text("Tables cannot be created in a read-only project")
+                                .clicked() {
+                                self.show_materialize = !self.show_materialize;
                             }
                             
-                            if ui.button("Export Page").clicked() {
-                                self.export_page_csv();
+                            if ui.add_enabled(!db.is_readonly(), egui::Button::new("Save Query"))
+                                .on_disabled_hover_text("Queries cannot be saved in a read-only project")
+                                .clicked() {
+                                self.show_save_query = !self.show_save_query;
+                                if self.saved_query_name.is_empty() {
+                                    self.saved_query_name = self.title.clone();
+                                }
+                            }
+                            
+                            if ui.button("Export")
+                                .on_hover_text("Save the current page or all rows as CSV, Parquet, Arrow or JSON")
+                                .clicked() {
+                                self.show_export_menu = !self.show_export_menu;
+                                self.export_message = None;
                             }
                         });
                     });
                 });
         
+        if open {
+            self.explain_window.show(ctx);
+        }
+        
         // Auto-execute initial query when window opens
-        if self.page == 0 && self.result.is_none() && !self.query.is_empty() {
+        // Scripts and statements that change data only run when asked to
+        if self.page == 0 && self.result.is_none() && self.running.is_none()
+            && self.running_script.is_none() && self.script_result.is_none()
+            && self.error.is_none() && !self.query.is_empty()
+            && self.parameters.iter().all(|p| p.validate().is_ok())
+            && !self.is_script(&db) {
             self.execute_query(db);
         }
         
@@ -222,10 +457,88 @@ impl QueryWindow {
         requested
     }
     
+    /// Statements that finished since the last call, for the project's query history
+    pub fn take_history_entries(&mut self) -> Vec<QueryHistoryEntry> {
+        std::mem::take(&mut self.history_entries)
+    }
+    
+    pub fn check_save_query_request(&mut self) -> Option<SavedQuery> {
+        self.save_query_request.take()
+    }
+    
     pub fn get_current_result(&self) -> Option<&QueryResult> {
         self.result.as_ref()
     }
     
+    /// The query and parameter values behind the current result, so a plot can re-run it
+    pub fn plot_source(&self) -> PlotSource {
+        PlotSource {
+            sql: self.query.clone(),
+            parameters: self.parameters.clone(),
+        }
+    }
+    
+    // Re-derive the parameter list, the planning error and whether the query runs
+    // as a script after the query text changed, keeping entered parameter values
+    fn analyze_query(&mut self, db: &Database) {
+        if self.analyzed_sql.as_deref() == Some(self.query.as_str()) {
+            return;
+        }
+        self.parameters = db.query_parameters(&self.query, &self.parameters);
+        // Several statements, or one that changes tables or the session, run as a script
+        self.analyzed_script = script::is_script(&self.query)
+            || db.classify_statement(&crate::core::params::prepare_sql(&self.query))
+                .is_ok_and(|kind| kind != StatementKind::Query);
+        // Statements of a script may use tables created by earlier ones, so they are
+        // only checked when run
+        self.diagnostic = match self.query.trim() {
+            "" => None,
+            query if script::is_script(query) => None,
+            query => db.check_query(query).err().map(|e| e.to_string()),
+        };
+        self.analyzed_sql = Some(self.query.clone());
+    }
+    
+    /// Returns true once after this window created a view
+    pub fn check_views_changed(&mut self) -> bool {
+        let changed = self.views_changed;
+        self.views_changed = false;
+        changed
+    }
+    
+    fn save_as_view(&mut self, db: &Arc<Database>) {
+        let name = self.view_name.trim().to_string();
+        match db.create_view(&name, &self.query) {
+            Ok(_) => {
+                self.error = None;
+                self.show_save_view = false;
+                self.view_name.clear();
+                self.views_changed = true;
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+    
+    /// Run the full query and write its rows as a table on a worker thread
+    fn materialize(&mut self, db: Arc<Database>) {
+        self.error = None;
+        
+        let slot: MaterializeSlot = Arc::new(Mutex::new(None));
+        let worker_slot = slot.clone();
+        let name = self.table_name.trim().to_string();
+        let sql = self.query.clone();
+        let parameters = self.parameters.clone();
+        std::thread::spawn(move || {
+            let result = db.materialize_query(&name, &sql, &parameters);
+            if let Ok(mut slot) = worker_slot.lock() {
+                *slot = Some(result);
+            }
+        });
+        self.running_materialize = Some((slot, Instant::now()));
+    }
+    
     fn render_results_table(&self, ui: &mut egui::Ui, result: &QueryResult) {
         let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
         let available_width = ui.available_width();
@@ -249,146 +562,289 @@ impl QueryWindow {
                         });
                     }
                 })
-                .body(|mut body| {
-                    for row in &result.rows {
-                        body.row(text_height + 8.0, |mut row_ui| {
-                            for value in row {
-                                row_ui.col(|ui| {
-                                    ui.label(value);
-                                });
-                            }
-                        });
-                    }
+                .body(|body| {
+                    // Only visible rows are formatted
+                    body.rows(text_height + 8.0, result.num_rows(), |mut row_ui| {
+                        let row_idx = row_ui.index();
+                        for col_idx in 0..num_columns {
+                            row_ui.col(|ui| {
+                                if result.is_null(row_idx, col_idx) {
+                                    ui.label(egui::RichText::new("NULL").italics().color(egui::Color32::from_gray(110)));
+                                } else {
+                                    ui.label(result.display_value(row_idx, col_idx));
+                                }
+                            });
+                        }
+                    });
                 });
         });
     }
     
     fn execute_query(&mut self, db: Arc<Database>) {
-        self.error = None;
+        if self.running_script.is_some() {
+            return;
+        }
+        self.cancel_query();
+        self.history_sql = Some(self.query.clone());
         // Reset to first page when executing a new query
         self.page = 0;
-        // self.is_executing = true; // This line was removed from imports, so it's removed here.
+        self.script_result = None;
+        if self.is_script(&db) {
+            self.run_script(db);
+        } else {
+            self.run_page(db);
+        }
+    }
+    
+    // The query may have been edited since the frame started, so it is analyzed
+    // again if needed
+    fn is_script(&mut self, db: &Database) -> bool {
+        self.analyze_query(db);
+        self.analyzed_script
+    }
+    
+    /// Run the query as a script on a worker thread
+    fn run_script(&mut self, db: Arc<Database>) {
+        self.error = None;
+        self.result = None;
         
-        match crate::core::QueryExecutor::execute_with_pagination(&db, &self.query, self.page, self.page_size) {
-            Ok(result) => {
-                self.result = Some(result);
+        let slot: ScriptSlot = Arc::new(Mutex::new(None));
+        let worker_slot = slot.clone();
+        let sql = self.query.clone();
+        let parameters = self.parameters.clone();
+        std::thread::spawn(move || {
+            let result = script::run_script(&db, &sql, &parameters);
+            if let Ok(mut slot) = worker_slot.lock() {
+                *slot = Some(result);
+            }
+        });
+        self.running_script = Some((slot, Instant::now()));
+    }
+    
+    /// Start fetching the current page in the background, replacing any query still running
+    fn run_page(&mut self, db: Arc<Database>) {
+        self.cancel_query();
+        self.error = None;
+        
+        // A script's rows are already all here
+        if self.script_result.is_some() {
+            self.load_script_page();
+            return;
+        }
+        
+        match QueryExecutor::spawn_with_params(&db, &self.query, &self.parameters, self.page, self.page_size) {
+            Ok(running) => {
+                if Database::parse_create_view(&self.query).is_some()
+                    || Database::parse_create_table_as(&self.query).is_some() {
+                    self.views_changed = true;
+                }
+                self.running = Some(running);
             }
             Err(e) => {
+                self.record_history(std::time::Duration::ZERO, None, Some(e.to_string()));
                 self.error = Some(e.to_string());
                 self.result = None;
             }
         }
-        
-        // self.is_executing = false; // This line was removed from imports, so it's removed here.
     }
     
-    fn export_page_csv(&self) {
-        if let Some(result) = &self.result {
-            if let Some(path) = rfd::FileDialog::new()
-                .add_filter("csv", &["csv"])
-                .set_file_name(&format!("{}_page_{}.csv", self.title, self.page + 1))
-                .save_file()
-            {
-                if let Ok(mut writer) = crate::core::CsvWriter::from_path(&path) {
-                    // Write headers
-                    let _ = writer.write_headers(&result.columns);
-                    
-                    // Write rows from current page
-                    for row in &result.rows {
-                        let _ = writer.write_record(row);
-                    }
-                    
-                    let _ = writer.flush();
-                }
+    fn record_history(&mut self, duration: std::time::Duration, row_count: Option<usize>, error: Option<String>) {
+        if let Some(sql) = self.history_sql.take() {
+            self.history_entries.push(QueryHistoryEntry::new(&sql, duration, row_count, error));
+        }
+    }
+    
+    fn cancel_query(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.cancel();
+            if let Some(Err(e)) = running.result() {
+                self.record_history(running.elapsed(), None, Some(e.to_string()));
+                self.error = Some(e.to_string());
             }
         }
     }
     
-    fn export_page_json(&self) {
-        if let Some(result) = &self.result {
-            if let Some(path) = rfd::FileDialog::new()
-                .add_filter("json", &["json"])
-                .set_file_name(&format!("{}_page_{}.json", self.title, self.page + 1))
-                .save_file()
-            {
-                let json_data: Vec<serde_json::Map<String, serde_json::Value>> = result.rows
-                    .iter()
-                    .map(|row| {
-                        let mut map = serde_json::Map::new();
-                        for (i, value) in row.iter().enumerate() {
-                            if let Some(column) = result.columns.get(i) {
-                                map.insert(column.clone(), serde_json::Value::String(value.clone()));
-                            }
-                        }
-                        map
-                    })
-                    .collect();
-                
-                if let Ok(json_string) = serde_json::to_string_pretty(&json_data) {
-                    let _ = std::fs::File::create(path).and_then(|mut file| file.write_all(json_string.as_bytes()));
-                }
+    /// Pick up the result of a background query once it finishes
+    fn poll_running(&mut self, ctx: &egui::Context) {
+        self.poll_script(ctx);
+        self.poll_materialize(ctx);
+        self.poll_export(ctx);
+        let Some(running) = &self.running else {
+            return;
+        };
+        let elapsed = running.elapsed();
+        match running.result() {
+            Some(Ok(result)) => {
+                self.record_history(elapsed, result.total_rows, None);
+                self.result = Some(result);
+                self.running = None;
             }
+            Some(Err(e)) => {
+                self.record_history(elapsed, None, Some(e.to_string()));
+                self.error = Some(e.to_string());
+                self.result = None;
+                self.running = None;
+            }
+            None => ctx.request_repaint(),
         }
     }
     
-    fn export_all_csv(&self, db: Arc<Database>) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("csv", &["csv"])
-            .set_file_name(&format!("{}_all.csv", self.title))
-            .save_file()
-        {
-            // Execute query without pagination to get all results
-            match crate::core::QueryExecutor::execute(&db, &self.query) {
-                Ok(all_results) => {
-                    if let Ok(mut writer) = crate::core::CsvWriter::from_path(&path) {
-                        // Write headers
-                        let _ = writer.write_headers(&all_results.columns);
-                        
-                        // Write all rows
-                        for row in &all_results.rows {
-                            let _ = writer.write_record(row);
-                        }
-                        
-                        let _ = writer.flush();
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to export all results: {}", e);
-                }
+    fn poll_script(&mut self, ctx: &egui::Context) {
+        let Some((slot, _)) = &self.running_script else {
+            return;
+        };
+        let Some(script) = slot.lock().ok().and_then(|mut slot| slot.take()) else {
+            ctx.request_repaint();
+            return;
+        };
+        
+        self.running_script = None;
+        let row_count = script.result.as_ref().map(|r| r.num_rows());
+        self.record_history(script.elapsed, row_count, script.error());
+        self.error = script.error();
+        self.views_changed |= script.changed_catalog();
+        self.script_result = Some(script);
+        self.load_script_page();
+    }
+    
+    fn poll_materialize(&mut self, ctx: &egui::Context) {
+        let Some((slot, _)) = &self.running_materialize else {
+            return;
+        };
+        let Some(result) = slot.lock().ok().and_then(|mut slot| slot.take()) else {
+            ctx.request_repaint();
+            return;
+        };
+        
+        self.running_materialize = None;
+        match result {
+            Ok(()) => {
+                self.error = None;
+                self.show_materialize = false;
+                self.table_name.clear();
+                self.views_changed = true;
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
             }
         }
     }
     
-    fn export_all_json(&self, db: Arc<Database>) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("json", &["json"])
-            .set_file_name(&format!("{}_all.json", self.title))
-            .save_file()
-        {
-            // Execute query without pagination to get all results
-            match crate::core::QueryExecutor::execute(&db, &self.query) {
-                Ok(all_results) => {
-                    let json_data: Vec<serde_json::Map<String, serde_json::Value>> = all_results.rows
-                        .iter()
-                        .map(|row| {
-                            let mut map = serde_json::Map::new();
-                            for (i, value) in row.iter().enumerate() {
-                                if let Some(column) = all_results.columns.get(i) {
-                                    map.insert(column.clone(), serde_json::Value::String(value.clone()));
-                                }
+    fn poll_export(&mut self, ctx: &egui::Context) {
+        let Some((slot, _, _)) = &self.running_export else {
+            return;
+        };
+        let Some(message) = slot.lock().ok().and_then(|mut slot| slot.take()) else {
+            ctx.request_repaint();
+            return;
+        };
+        
+        self.running_export = None;
+        self.export_message = Some(message);
+    }
+    
+    // Slice the current page out of the last query of the script
+    fn load_script_page(&mut self) {
+        let page = self.script_result.as_ref()
+            .and_then(|s| s.result.as_ref())
+            .map(|full| full.page(self.page, self.page_size));
+        match page {
+            Some(Ok(result)) => self.result = Some(result),
+            Some(Err(e)) => self.error = Some(e.to_string()),
+            None => self.result = None,
+        }
+    }
+    
+    fn show_script_statuses(ui: &mut egui::Ui, id: egui::Id, script: &ScriptResult) {
+        let failed = script.statements.iter().any(|s| matches!(s.status, StatementStatus::Failed(_)));
+        let header = format!(
+            "Script: {} statements in {:.1} ms{}",
+            script.statements.len(),
+            script.elapsed.as_secs_f64() * 1000.0,
+            if failed { ", failed" } else { "" }
+        );
+        egui::CollapsingHeader::new(header)
+            .id_salt(id.with("script"))
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                    egui::Grid::new(id.with("script_statuses"))
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for statement in &script.statements {
+                                let (icon, color, message) = match &statement.status {
+                                    StatementStatus::Succeeded(message) => ("✓", egui::Color32::from_rgb(100, 200, 100), message.as_str()),
+                                    StatementStatus::Failed(message) => ("✗", egui::Color32::from_rgb(255, 100, 100), message.as_str()),
+                                    StatementStatus::Skipped => ("–", egui::Color32::from_gray(120), "Skipped"),
+                                };
+                                ui.colored_label(color, icon);
+                                ui.label(format!("line {}", statement.line));
+                                ui.label(egui::RichText::new(statement_preview(&statement.sql)).monospace())
+                                    .on_hover_text(&statement.sql);
+                                ui.colored_label(color, message);
+                                ui.label(format!("{:.1} ms", statement.elapsed.as_secs_f64() * 1000.0));
+                                ui.end_row();
                             }
-                            map
-                        })
-                        .collect();
-                    
-                    if let Ok(json_string) = serde_json::to_string_pretty(&json_data) {
-                        let _ = std::fs::File::create(path).and_then(|mut file| file.write_all(json_string.as_bytes()));
+                        });
+                });
+            });
+    }
+    
+    /// Write the current page, or every row of the query, to a file. Rows not yet in
+    /// memory are exported on a worker thread.
+    fn export(&mut self, db: Arc<Database>) {
+        let file_stem = match self.export_mode {
+            ExportMode::Page => format!("{}_page_{}", self.title, self.page + 1),
+            ExportMode::All => format!("{}_all", self.title),
+        };
+        let Some(path) = export_dialog::save_file_dialog(self.export_options.format, &file_stem) else {
+            return;
+        };
+        
+        let options = &self.export_options;
+        let script_rows = self.script_result.as_ref().and_then(|s| s.result.as_ref());
+        let exported = match (self.export_mode, &self.result, script_rows) {
+            (ExportMode::Page, Some(result), _) => export::export_batches(&result.batches, &path, options),
+            (ExportMode::Page, None, _) => Err(crate::core::error::FreshError::Custom("No results to export".to_string())),
+            // A script's last query isn't run again
+            (ExportMode::All, _, Some(result)) => export::export_batches(&result.batches, &path, options),
+            (ExportMode::All, _, None) => {
+                let slot: ExportSlot = Arc::new(Mutex::new(None));
+                let worker_slot = slot.clone();
+                let cancelled = Arc::new(AtomicBool::new(false));
+                let worker_cancelled = cancelled.clone();
+                let sql = self.query.clone();
+                let parameters = self.parameters.clone();
+                let options = options.clone();
+                std::thread::spawn(move || {
+                    let exported = db.export_query_cancellable(&sql, &parameters, &path, &options, &worker_cancelled);
+                    if let Ok(mut slot) = worker_slot.lock() {
+                        *slot = Some(export_message(exported, &path));
                     }
-                }
-                Err(e) => {
-                    eprintln!("Failed to export all results: {}", e);
-                }
+                });
+                self.export_message = None;
+                self.running_export = Some((slot, cancelled, Instant::now()));
+                return;
             }
-        }
+        };
+        self.export_message = Some(export_message(exported, &path));
     }
-} 
\ No newline at end of file
+}
+
+fn export_message(exported: crate::core::error::Result<usize>, path: &Path) -> String {
+    match exported {
+        Ok(rows) => format!("Exported {} rows to {}", rows, path.display()),
+        Err(e) => format!("✗ {}", e),
+    }
+}
+
+// First line of a statement, shortened to fit a status row
+fn statement_preview(sql: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let line = sql.lines().next().unwrap_or_default();
+    if line.chars().count() > MAX_CHARS || sql.contains('\n') {
+        format!("{}…", line.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
@@ -1,21 +1,44 @@
 use egui;
 use crate::core::database::TableInfo;
+use crate::core::{SavedQuery, TableKeys};
 
 #[derive(Debug, PartialEq)]
 pub enum SidebarAction {
     None,
     OpenTable(String),
+    ProfileTable(String),
+    OpenDataQuality(String),
     OpenDuplicateDetection,
     RefreshDatabase,
     AddDerivedField,
     AddTimeBinColumn,
     AddRowIdColumns,
+    OpenQueryHistory,
+    OpenSavedQuery(String),
+    DeleteSavedQuery(String),
+    RenameTable { table: String, new_name: String },
+    CopyTable { source: String, target: String },
+    TruncateTable(String),
+    DropTable(String),
+    RefreshTable(String),
+    SetTableKeys { table: String, keys: TableKeys },
+}
+
+// Table operation from the context menu waiting for a name or a confirmation
+enum TablePrompt {
+    Rename { table: String, name: String },
+    Copy { table: String, name: String },
+    Truncate(String),
+    Drop { table: String, is_external: bool },
+    // Columns with their checkbox state, and the keys being edited
+    Keys { table: String, columns: Vec<(String, bool)>, keys: TableKeys },
 }
 
 pub struct Sidebar {
     selected_table: Option<String>,
     selected_view: Option<String>,
     duplicate_detection_clicked: bool,
+    table_prompt: Option<TablePrompt>,
 }
 
 impl Sidebar {
@@ -24,10 +47,11 @@ impl Sidebar {
             selected_table: None,
             selected_view: None,
             duplicate_detection_clicked: false,
+            table_prompt: None,
         }
     }
     
-    pub fn show(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui, tables: &[TableInfo], views: &[String]) -> SidebarAction {
+    pub fn show(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, tables: &[TableInfo], views: &[String], saved_queries: &[SavedQuery], readonly: bool) -> SidebarAction {
         let mut table_to_open = None;
         let mut action = SidebarAction::None;
         self.duplicate_detection_clicked = false;
@@ -59,6 +83,10 @@ impl Sidebar {
                 action = SidebarAction::AddRowIdColumns;
             }
             
+            if ui.button("🕘 Query History").clicked() {
+                action = SidebarAction::OpenQueryHistory;
+            }
+            
             ui.add_space(10.0);
             
             // Tables section
@@ -103,7 +131,14 @@ impl Sidebar {
                                     ui.visuals().text_color(),
                                 );
                                 
-                                let info_text = format!("{} rows, {} columns", table.row_count, table.columns.len());
+                                let mut info_text = if table.is_external {
+                                    format!("external, {} columns", table.columns.len())
+                                } else {
+                                    format!("{} rows, {} columns", table.row_count, table.columns.len())
+                                };
+                                if let Some(violations) = table.quality_violations.filter(|v| *v > 0) {
+                                    info_text.push_str(&format!(", ⚠ {} rule violations", violations));
+                                }
                                 let info_pos = text_pos + egui::vec2(0.0, 20.0);
                                 ui.painter().text(
                                     info_pos,
@@ -112,6 +147,65 @@ impl Sidebar {
                                     egui::FontId::proportional(12.0),
                                     egui::Color32::from_gray(140),
                                 );
+                                
+                                let response = response.on_hover_ui(|ui| Self::show_table_schema(ui, table));
+                                response.context_menu(|ui| {
+                                    if ui.button("Open").clicked() {
+                                        table_to_open = Some(table.name.clone());
+                                        ui.close_menu();
+                                    }
+                                    if ui.button("Profile").clicked() {
+                                        action = SidebarAction::ProfileTable(table.name.clone());
+                                        ui.close_menu();
+                                    }
+                                    if ui.button("Data Quality...").clicked() {
+                                        action = SidebarAction::OpenDataQuality(table.name.clone());
+                                        ui.close_menu();
+                                    }
+                                    ui.separator();
+                                    ui.add_enabled_ui(!readonly, |ui| {
+                                        if ui.button("Rename...").clicked() {
+                                            self.table_prompt = Some(TablePrompt::Rename {
+                                                table: table.name.clone(),
+                                                name: table.name.clone(),
+                                            });
+                                            ui.close_menu();
+                                        }
+                                        if ui.button("Duplicate...").clicked() {
+                                            self.table_prompt = Some(TablePrompt::Copy {
+                                                table: table.name.clone(),
+                                                name: format!("{}_copy", table.name),
+                                            });
+                                            ui.close_menu();
+                                        }
+                                        if ui.button("Keys...").clicked() {
+                                            self.table_prompt = Some(TablePrompt::Keys {
+                                                table: table.name.clone(),
+                                                columns: table.columns.iter().map(|c| (c.name.clone(), false)).collect(),
+                                                keys: table.keys.clone(),
+                                            });
+                                            ui.close_menu();
+                                        }
+                                        if table.lineage.is_some() && ui.button("Refresh from Query")
+                                            .on_hover_text("Run the query the table was created from again")
+                                            .clicked() {
+                                            action = SidebarAction::RefreshTable(table.name.clone());
+                                            ui.close_menu();
+                                        }
+                                        if !table.is_external && ui.button("Truncate...").clicked() {
+                                            self.table_prompt = Some(TablePrompt::Truncate(table.name.clone()));
+                                            ui.close_menu();
+                                        }
+                                        let drop_label = if table.is_external { "Detach..." } else { "Drop..." };
+                                        if ui.button(drop_label).clicked() {
+                                            self.table_prompt = Some(TablePrompt::Drop {
+                                                table: table.name.clone(),
+                                                is_external: table.is_external,
+                                            });
+                                            ui.close_menu();
+                                        }
+                                    }).response.on_disabled_hover_text("Tables cannot be changed in a read-only project");
+                                });
                             }
                         });
                 });
@@ -162,8 +256,71 @@ impl Sidebar {
                             }
                         });
                 });
+            
+            ui.add_space(10.0);
+            
+            // Saved queries section
+            egui::CollapsingHeader::new(format!("Saved Queries ({})", saved_queries.len()))
+                .default_open(false)
+                .show(ui, |ui| {
+                    ui.visuals_mut().widgets.noninteractive.bg_fill = egui::Color32::from_gray(20);
+                    
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            for query in saved_queries {
+                                let response = ui.allocate_response(
+                                    egui::vec2(ui.available_width(), 30.0),
+                                    egui::Sense::click(),
+                                );
+                                
+                                if response.clicked() {
+                                    action = SidebarAction::OpenSavedQuery(query.name.clone());
+                                }
+                                
+                                let visuals = if response.hovered() {
+                                    ui.visuals().widgets.hovered
+                                } else {
+                                    ui.visuals().widgets.inactive
+                                };
+                                
+                                ui.painter().rect(
+                                    response.rect,
+                                    visuals.rounding,
+                                    visuals.bg_fill,
+                                    visuals.bg_stroke,
+                                );
+                                
+                                let text_pos = response.rect.min + egui::vec2(8.0, 8.0);
+                                ui.painter().text(
+                                    text_pos,
+                                    egui::Align2::LEFT_CENTER,
+                                    &query.name,
+                                    egui::FontId::proportional(14.0),
+                                    ui.visuals().text_color(),
+                                );
+                                
+                                response
+                                    .on_hover_text(egui::RichText::new(&query.sql).monospace())
+                                    .context_menu(|ui| {
+                                        if ui.button("Open").clicked() {
+                                            action = SidebarAction::OpenSavedQuery(query.name.clone());
+                                            ui.close_menu();
+                                        }
+                                        if ui.button("Delete").clicked() {
+                                            action = SidebarAction::DeleteSavedQuery(query.name.clone());
+                                            ui.close_menu();
+                                        }
+                                    });
+                            }
+                        });
+                });
         });
         
+        if let Some(prompt_action) = self.show_table_prompt(ctx) {
+            action = prompt_action;
+        }
+        
         if action != SidebarAction::None {
             action
         } else if self.duplicate_detection_clicked {
@@ -174,4 +331,162 @@ impl Sidebar {
             SidebarAction::None
         }
     }
-} 
\ No newline at end of file
+    
+    // Ask for the new name, or a confirmation, of the pending table operation
+    fn show_table_prompt(&mut self, ctx: &egui::Context) -> Option<SidebarAction> {
+        let prompt = self.table_prompt.as_mut()?;
+        let mut action = None;
+        let mut close = false;
+        
+        let title = match prompt {
+            TablePrompt::Rename { .. } => "Rename Table",
+            TablePrompt::Copy { .. } => "Duplicate Table",
+            TablePrompt::Truncate(_) => "Truncate Table",
+            TablePrompt::Drop { is_external: true, .. } => "Detach Table",
+            TablePrompt::Drop { .. } => "Drop Table",
+            TablePrompt::Keys { .. } => "Table Keys",
+        };
+        
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let confirmed = match prompt {
+                    TablePrompt::Rename { table, name } | TablePrompt::Copy { table, name } => {
+                        ui.label(format!("New name for '{}':", table));
+                        let response = ui.text_edit_singleline(name);
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    }
+                    TablePrompt::Truncate(table) => {
+                        ui.label(format!("Delete every row of '{}'? Its columns are kept.", table));
+                        false
+                    }
+                    TablePrompt::Drop { table, is_external: true } => {
+                        ui.label(format!("Stop querying '{}'? Its source files are not deleted.", table));
+                        false
+                    }
+                    TablePrompt::Drop { table, .. } => {
+                        ui.label(format!("Drop '{}' and delete its files from the project?", table));
+                        false
+                    }
+                    TablePrompt::Keys { table, columns, keys } => {
+                        Self::show_keys_editor(ui, table, columns, keys);
+                        false
+                    }
+                };
+                
+                ui.horizontal(|ui| {
+                    let confirm_label = match prompt {
+                        TablePrompt::Rename { .. } => "Rename",
+                        TablePrompt::Copy { .. } => "Duplicate",
+                        TablePrompt::Truncate(_) => "Truncate",
+                        TablePrompt::Drop { is_external: true, .. } => "Detach",
+                        TablePrompt::Drop { .. } => "Drop",
+                        TablePrompt::Keys { .. } => "Save",
+                    };
+                    if ui.button(confirm_label).clicked() || confirmed {
+                        action = Some(match prompt {
+                            TablePrompt::Rename { table, name } => SidebarAction::RenameTable {
+                                table: table.clone(),
+                                new_name: name.trim().to_string(),
+                            },
+                            TablePrompt::Copy { table, name } => SidebarAction::CopyTable {
+                                source: table.clone(),
+                                target: name.trim().to_string(),
+                            },
+                            TablePrompt::Truncate(table) => SidebarAction::TruncateTable(table.clone()),
+                            TablePrompt::Drop { table, .. } => SidebarAction::DropTable(table.clone()),
+                            TablePrompt::Keys { table, keys, .. } => SidebarAction::SetTableKeys {
+                                table: table.clone(),
+                                keys: keys.clone(),
+                            },
+                        });
+                        close = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        
+        if close {
+            self.table_prompt = None;
+        }
+        action
+    }
+    
+    // Columns and declared keys of a table, shown when hovering it
+    fn show_table_schema(ui: &mut egui::Ui, table: &TableInfo) {
+        egui::Grid::new(("table_schema", &table.name)).num_columns(2).show(ui, |ui| {
+            for column in &table.columns {
+                let name = if column.is_primary_key {
+                    format!("🔑 {}", column.name)
+                } else {
+                    column.name.clone()
+                };
+                ui.label(name);
+                ui.label(egui::RichText::new(&column.data_type).color(egui::Color32::from_gray(140)));
+                ui.end_row();
+            }
+        });
+        for unique in &table.keys.unique {
+            ui.label(format!("UNIQUE ({})", unique.join(", ")));
+        }
+        if let Some(lineage) = &table.lineage {
+            ui.separator();
+            ui.label(format!("Materialized {} from:", lineage.materialized_at_display()));
+            ui.monospace(&lineage.sql);
+        }
+    }
+    
+    fn show_keys_editor(ui: &mut egui::Ui, table: &str, columns: &mut [(String, bool)], keys: &mut TableKeys) {
+        ui.label(format!("Keys of '{}' are checked on every import.", table));
+        ui.separator();
+        
+        ui.horizontal(|ui| {
+            ui.label("Primary key:");
+            if keys.primary_key.is_empty() {
+                ui.label("none");
+            } else {
+                ui.label(format!("({})", keys.primary_key.join(", ")));
+                if ui.small_button("✖").on_hover_text("Remove the primary key").clicked() {
+                    keys.primary_key.clear();
+                }
+            }
+        });
+        let mut remove = None;
+        for (i, unique) in keys.unique.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Unique: ({})", unique.join(", ")));
+                if ui.small_button("✖").on_hover_text("Remove the constraint").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            keys.unique.remove(i);
+        }
+        ui.separator();
+        
+        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+            for (column, checked) in columns.iter_mut() {
+                ui.checkbox(checked, column.as_str());
+            }
+        });
+        let checked: Vec<String> = columns.iter()
+            .filter(|(_, checked)| *checked)
+            .map(|(column, _)| column.clone())
+            .collect();
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!checked.is_empty(), |ui| {
+                if ui.button("Set as Primary Key").clicked() {
+                    keys.primary_key = checked.clone();
+                }
+                if ui.button("Add Unique Constraint").clicked() && !keys.unique.contains(&checked) {
+                    keys.unique.push(checked.clone());
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,307 @@
+use std::ops::Range;
+use egui::text::{CCursor, CCursorRange, LayoutJob, TextFormat};
+use egui::{Color32, Key, Modifiers, Stroke};
+use crate::core::sql_assist::{self, SqlTokenKind};
+use crate::core::{Completions, Database, SqlSchema};
+
+const ERROR_COLOR: Color32 = Color32::from_rgb(255, 100, 100);
+const INDENT: &str = "    ";
+
+/// Multi-line SQL editor with syntax highlighting, bracket matching, auto-indent,
+/// completion of tables, columns and functions, and an underline under the part of
+/// the query an error points at
+pub struct SqlEditor {
+    id: egui::Id,
+    schema: Option<SqlSchema>,
+    completions: Option<Completions>,
+    selected: usize,
+    // Byte offset of the cursor after the last frame
+    cursor: usize,
+    popup_rect: Option<egui::Rect>,
+    // Error span of the last (sql, error) pair, which costs a parse to find
+    error_span: Option<ErrorSpan>,
+}
+
+struct ErrorSpan {
+    sql: String,
+    message: String,
+    span: Option<Range<usize>>,
+}
+
+impl SqlEditor {
+    pub fn new(id: egui::Id) -> Self {
+        Self {
+            id,
+            schema: None,
+            completions: None,
+            selected: 0,
+            cursor: 0,
+            popup_rect: None,
+            error_span: None,
+        }
+    }
+
+    /// Show the editor. `error` is a DataFusion error message about `sql`; the text it
+    /// points at is underlined and the message is shown when hovering the editor.
+    pub fn show(&mut self, ui: &mut egui::Ui, sql: &mut String, error: Option<&str>, db: &Database) -> egui::Response {
+        if self.schema.is_none() {
+            self.refresh_schema(db);
+        }
+
+        // Keys that drive the completion list must not reach the text edit
+        let focused = ui.memory(|m| m.has_focus(self.id));
+        let mut accept = None;
+        let mut force = false;
+        if focused {
+            ui.input_mut(|i| {
+                if let Some(completions) = &self.completions {
+                    let last = completions.items.len().saturating_sub(1);
+                    if i.consume_key(Modifiers::NONE, Key::ArrowDown) {
+                        self.selected = (self.selected + 1).min(last);
+                    }
+                    if i.consume_key(Modifiers::NONE, Key::ArrowUp) {
+                        self.selected = self.selected.saturating_sub(1);
+                    }
+                    if i.consume_key(Modifiers::NONE, Key::Enter) || i.consume_key(Modifiers::NONE, Key::Tab) {
+                        accept = Some(self.selected);
+                    }
+                    if i.consume_key(Modifiers::NONE, Key::Escape) {
+                        self.completions = None;
+                    }
+                }
+                force = i.consume_key(Modifiers::CTRL, Key::Space);
+            });
+        }
+
+        let before = sql.clone();
+        let brackets = sql_assist::matching_bracket(sql, self.cursor);
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let span = error.and_then(|message| cached_error_span(&mut self.error_span, text, message));
+            let mut job = highlight(ui, text, span, brackets);
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|f| f.layout_job(job))
+        };
+        let line_count = sql.lines().count().max(1);
+        let text_height = 16.0;
+        let mut output = egui::TextEdit::multiline(sql)
+            .id(self.id)
+            .code_editor()
+            .desired_width(f32::INFINITY)
+            .min_size(egui::vec2(0.0, text_height + 8.0))
+            .desired_rows(line_count.min(8))
+            .layouter(&mut layouter)
+            .show(ui);
+
+        let mut cursor_chars = output.cursor_range.map(|r| r.primary.ccursor.index);
+        let changed = output.response.changed();
+        let mut edited = false;
+
+        // Tabs become spaces and new lines keep the indentation of the line above,
+        // one level deeper after an opening parenthesis
+        if let Some(chars) = cursor_chars.filter(|_| changed && sql.len() == before.len() + 1) {
+            let at = byte_offset(sql, chars);
+            match sql[..at].chars().last() {
+                Some('\t') => {
+                    sql.replace_range(at - 1..at, INDENT);
+                    cursor_chars = Some(chars + INDENT.len() - 1);
+                    edited = true;
+                }
+                Some('\n') => {
+                    let line = sql[..at - 1].rsplit('\n').next().unwrap_or_default();
+                    let mut indent: String = line.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+                    if line.trim_end().ends_with('(') {
+                        indent.push_str(INDENT);
+                    }
+                    sql.insert_str(at, &indent);
+                    cursor_chars = Some(chars + indent.chars().count());
+                    edited = true;
+                }
+                _ => {}
+            }
+        }
+
+        let mut cursor = cursor_chars.map(|chars| byte_offset(sql, chars)).unwrap_or(self.cursor);
+        if let Some(index) = accept {
+            if let Some(end) = self.accept(sql, index) {
+                cursor = end;
+                edited = true;
+            }
+        } else if force || (changed && sql.len() > before.len() && continues_word(sql, cursor)) {
+            self.completions = self.schema.as_ref().and_then(|schema| sql_assist::complete(sql, cursor, schema));
+            self.selected = 0;
+        } else if changed || cursor != self.cursor {
+            self.completions = None;
+        }
+
+        // Clicking anywhere but the list closes it
+        let pressed_elsewhere = ui.input(|i| {
+            i.pointer.any_pressed() && i.pointer.interact_pos()
+                .is_some_and(|p| !self.popup_rect.is_some_and(|r| r.contains(p)) && !output.response.rect.contains(p))
+        });
+        if pressed_elsewhere {
+            self.completions = None;
+        }
+
+        if let Some(clicked) = self.show_completions(ui, &output, sql, cursor) {
+            if let Some(end) = self.accept(sql, clicked) {
+                cursor = end;
+                edited = true;
+            }
+            ui.memory_mut(|m| m.request_focus(self.id));
+        }
+
+        // Move the text cursor after edits made here
+        if edited {
+            output.state.cursor.set_char_range(Some(CCursorRange::one(CCursor::new(sql[..cursor].chars().count()))));
+            output.state.clone().store(ui.ctx(), self.id);
+        }
+        self.cursor = cursor;
+
+        // Tables may have been added since the editor last had focus
+        if output.response.gained_focus() {
+            self.refresh_schema(db);
+        }
+        match error {
+            Some(message) if cached_error_span(&mut self.error_span, sql, message).is_some() => {
+                output.response.on_hover_text(message)
+            }
+            _ => output.response,
+        }
+    }
+
+    /// Reload the tables, views and functions offered by completion
+    pub fn refresh_schema(&mut self, db: &Database) {
+        match db.sql_schema() {
+            Ok(schema) => self.schema = Some(schema),
+            Err(e) => {
+                eprintln!("[SqlEditor] Failed to load schema for completion: {}", e);
+                self.schema.get_or_insert_with(SqlSchema::default);
+            }
+        }
+    }
+
+    // Replace the word being completed; returns the byte offset after the inserted text
+    fn accept(&mut self, sql: &mut String, index: usize) -> Option<usize> {
+        let completions = self.completions.take()?;
+        let item = completions.items.get(index)?;
+        if completions.replace.end > sql.len() {
+            return None;
+        }
+        sql.replace_range(completions.replace.clone(), &item.insert);
+        Some(completions.replace.start + item.insert.len())
+    }
+
+    // List of completions under the cursor; returns the index of a clicked entry
+    fn show_completions(
+        &mut self,
+        ui: &egui::Ui,
+        output: &egui::text_edit::TextEditOutput,
+        sql: &str,
+        cursor: usize,
+    ) -> Option<usize> {
+        let Some(completions) = &self.completions else {
+            self.popup_rect = None;
+            return None;
+        };
+        let caret = output.galley.pos_from_ccursor(CCursor::new(sql[..cursor].chars().count()));
+        let position = output.galley_pos + caret.left_bottom().to_vec2();
+
+        let mut clicked = None;
+        let area = egui::Area::new(self.id.with("completions"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(position)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_min_width(220.0);
+                    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for (index, item) in completions.items.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                let label = egui::RichText::new(&item.label).monospace();
+                                let response = ui.selectable_label(index == self.selected, label);
+                                if index == self.selected {
+                                    response.scroll_to_me(None);
+                                }
+                                if response.clicked() {
+                                    clicked = Some(index);
+                                }
+                                ui.weak(item.kind.name());
+                                if !item.detail.is_empty() {
+                                    ui.weak(&item.detail);
+                                }
+                            });
+                        }
+                    });
+                });
+            });
+        self.popup_rect = Some(area.response.rect);
+        clicked
+    }
+}
+
+// Byte offset of the character at `chars`
+fn byte_offset(text: &str, chars: usize) -> usize {
+    text.char_indices().nth(chars).map_or(text.len(), |(offset, _)| offset)
+}
+
+// Whether the character just typed continues a name or starts a qualified one
+fn continues_word(sql: &str, cursor: usize) -> bool {
+    sql[..cursor].chars().last().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+fn token_color(kind: SqlTokenKind) -> Option<Color32> {
+    match kind {
+        SqlTokenKind::Keyword => Some(Color32::from_rgb(86, 156, 214)),
+        SqlTokenKind::Function => Some(Color32::from_rgb(220, 220, 170)),
+        SqlTokenKind::QuotedIdentifier => Some(Color32::from_rgb(156, 220, 254)),
+        SqlTokenKind::String => Some(Color32::from_rgb(206, 145, 120)),
+        SqlTokenKind::Number => Some(Color32::from_rgb(181, 206, 168)),
+        SqlTokenKind::Comment => Some(Color32::from_rgb(106, 153, 85)),
+        SqlTokenKind::Parameter => Some(Color32::from_rgb(197, 134, 192)),
+        SqlTokenKind::Operator | SqlTokenKind::Bracket | SqlTokenKind::Punctuation => Some(Color32::from_gray(180)),
+        SqlTokenKind::Identifier | SqlTokenKind::Whitespace => None,
+    }
+}
+
+// The text an error points at, found again only when the query or the error changed
+fn cached_error_span(cache: &mut Option<ErrorSpan>, sql: &str, message: &str) -> Option<Range<usize>> {
+    match cache {
+        Some(cached) if cached.sql == sql && cached.message == message => cached.span.clone(),
+        _ => {
+            let span = sql_assist::error_span(sql, message);
+            *cache = Some(ErrorSpan { sql: sql.to_string(), message: message.to_string(), span: span.clone() });
+            span
+        }
+    }
+}
+
+// Colour each token; underline the error span and shade the matched brackets
+fn highlight(ui: &egui::Ui, text: &str, error_span: Option<Range<usize>>, brackets: Option<(usize, usize)>) -> LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let default_color = ui.visuals().text_color();
+
+    let mut job = LayoutJob::default();
+    for token in sql_assist::tokenize(text) {
+        let mut format = TextFormat::simple(font_id.clone(), token_color(token.kind).unwrap_or(default_color));
+        if token.kind == SqlTokenKind::Bracket
+            && brackets.is_some_and(|(open, close)| token.range.start == open || token.range.start == close) {
+            format.background = Color32::from_gray(70);
+        }
+
+        // The error span may start or end inside a token
+        let mut cuts = vec![token.range.start, token.range.end];
+        if let Some(span) = &error_span {
+            cuts.extend([span.start, span.end].into_iter()
+                .filter(|p| token.range.contains(p) && text.is_char_boundary(*p)));
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+        for piece in cuts.windows(2) {
+            let mut format = format.clone();
+            if error_span.as_ref().is_some_and(|span| span.start <= piece[0] && piece[1] <= span.end) {
+                format.underline = Stroke::new(1.5, ERROR_COLOR);
+            }
+            job.append(&text[piece[0]..piece[1]], 0.0, format);
+        }
+    }
+    job
+}
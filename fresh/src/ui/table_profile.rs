@@ -0,0 +1,163 @@
+use std::sync::{Arc, Mutex};
+use egui_plot::{Bar, BarChart, Plot};
+use crate::core::{ColumnProfile, Database, TableProfile};
+
+type ProfileSlot = Arc<Mutex<Option<Result<Arc<TableProfile>, String>>>>;
+
+/// Column statistics of one table, computed on a worker thread
+#[derive(Default)]
+pub struct TableProfileWindow {
+    pub visible: bool,
+    table: String,
+    profile: ProfileSlot,
+}
+
+impl TableProfileWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start profiling `table` and show the window; a profile still running for
+    /// another table is left to finish and discarded
+    pub fn open(&mut self, db: Arc<Database>, table: &str) {
+        self.visible = true;
+        self.table = table.to_string();
+        self.profile = Arc::new(Mutex::new(None));
+
+        let slot = self.profile.clone();
+        let table = table.to_string();
+        std::thread::spawn(move || {
+            let result = db.profile_table(&table).map_err(|e| e.to_string());
+            if let Ok(mut slot) = slot.lock() {
+                *slot = Some(result);
+            }
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.visible {
+            return;
+        }
+
+        let result = self.profile.lock().ok().and_then(|slot| slot.clone());
+        let mut visible = self.visible;
+
+        egui::Window::new(format!("Profile: {}", self.table))
+            .id(egui::Id::new("table_profile"))
+            .open(&mut visible)
+            .resizable(true)
+            .default_size([700.0, 600.0])
+            .show(ctx, |ui| match &result {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Profiling columns...");
+                    });
+                    ctx.request_repaint();
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("Failed to profile table: {}", e));
+                }
+                Some(Ok(profile)) => Self::show_profile(ui, profile),
+            });
+
+        self.visible = visible;
+    }
+
+    fn show_profile(ui: &mut egui::Ui, profile: &TableProfile) {
+        ui.label(format!(
+            "{} rows, {} columns (profiled in {} ms)",
+            profile.row_count,
+            profile.columns.len(),
+            profile.elapsed.as_millis()
+        ));
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (i, column) in profile.columns.iter().enumerate() {
+                    let header = format!(
+                        "{}  ({}, {:.1}% null{})",
+                        column.name,
+                        column.data_type,
+                        column.null_fraction(profile.row_count) * 100.0,
+                        column.distinct_count
+                            .map(|n| format!(", {}{} distinct", if column.distinct_exact { "" } else { "~" }, n))
+                            .unwrap_or_default()
+                    );
+                    egui::CollapsingHeader::new(header)
+                        .id_salt(("profile_column", i))
+                        .default_open(i == 0)
+                        .show(ui, |ui| Self::show_column(ui, column, i));
+                }
+            });
+    }
+
+    fn show_column(ui: &mut egui::Ui, column: &ColumnProfile, index: usize) {
+        egui::Grid::new(("profile_stats", index))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                let mut row = |label: &str, value: String| {
+                    ui.label(label);
+                    ui.label(value);
+                    ui.end_row();
+                };
+                row("Nulls", column.null_count.to_string());
+                if let Some(distinct) = column.distinct_count {
+                    let kind = if column.distinct_exact { "" } else { " (HyperLogLog estimate)" };
+                    row("Distinct", format!("{}{}", distinct, kind));
+                }
+                if let Some(min) = &column.min {
+                    row("Min", min.clone());
+                }
+                if let Some(max) = &column.max {
+                    row("Max", max.clone());
+                }
+                if let Some(mean) = column.mean {
+                    row("Mean", format!("{:.4}", mean));
+                }
+                if let Some(stddev) = column.stddev {
+                    row("Std dev", format!("{:.4}", stddev));
+                }
+                for (quantile, value) in &column.quantiles {
+                    row(&format!("p{}", (quantile * 100.0).round()), format!("{:.4}", value));
+                }
+                if let Some(length) = column.length {
+                    row("Length", format!("{} to {} (mean {:.1})", length.min, length.max, length.mean));
+                }
+            });
+
+        if !column.top_values.is_empty() {
+            ui.add_space(4.0);
+            ui.label(egui::RichText::new("Most frequent values").strong());
+            let highest = column.top_values[0].1.max(1) as f32;
+            for (value, count) in &column.top_values {
+                ui.horizontal(|ui| {
+                    ui.add(egui::ProgressBar::new(*count as f32 / highest)
+                        .desired_width(120.0)
+                        .text(count.to_string()));
+                    ui.label(if value.is_empty() { "(empty)" } else { value.as_str() });
+                });
+            }
+        }
+
+        if !column.histogram.is_empty() {
+            ui.add_space(4.0);
+            ui.label(egui::RichText::new("Histogram").strong());
+            let bars: Vec<Bar> = column.histogram.iter()
+                .map(|bin| {
+                    let width = (bin.end - bin.start).max(f64::EPSILON);
+                    Bar::new((bin.start + bin.end) / 2.0, bin.count as f64).width(width)
+                })
+                .collect();
+            Plot::new(("profile_histogram", index))
+                .height(120.0)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new(bars)));
+        }
+    }
+}
@@ -0,0 +1,1267 @@
+use fresh::core::{CsvIngestOptions, Database, ExternalFormat, ExternalTableOptions, QueryExecutor, QueryHistoryEntry, QueryLibrary};
+use datafusion::arrow::array::{Float64Array, Int64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn create_batch(start: i64, len: i64) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let ids: Vec<i64> = (start..start + len).collect();
+    let names: Vec<String> = ids.iter().map(|i| format!("row_{}", i)).collect();
+    RecordBatch::try_new(schema, vec![
+        Arc::new(Int64Array::from(ids)),
+        Arc::new(StringArray::from(names)),
+    ]).unwrap()
+}
+
+fn temp_project_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fresh_test_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Test that tables made of several record batches keep every row
+#[test]
+fn test_multi_batch_table_round_trip() {
+    let dir = temp_project_dir("multi_batch");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("events", vec![create_batch(0, 3), create_batch(3, 4)]).unwrap();
+
+    let tables = db.get_tables().unwrap();
+    assert_eq!(tables[0].row_count, 7, "Row count should cover every batch");
+    assert_eq!(db.execute_query("SELECT * FROM events").unwrap().len(), 7);
+    assert_eq!(db.get_table_arrow_batch("events").unwrap().num_rows(), 7);
+
+    // Save to Arrow IPC and load back into a fresh database
+    db.save_table_dual("events", &dir).unwrap();
+    let reloaded = Database::open_writable(&dir).unwrap();
+    reloaded.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reloaded.get_table_arrow_batches("events").unwrap().len(), 2);
+    assert_eq!(reloaded.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 7);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Multi-batch table test passed");
+}
+
+/// Test that Parquet is written alongside Arrow IPC and used when the cache is missing
+#[test]
+fn test_parquet_persistence_fallback() {
+    let dir = temp_project_dir("parquet");
+    let db = Database::open_writable(&dir).unwrap();
+    db.set_parquet_compression(fresh::core::ParquetCompression::Snappy);
+    db.insert_record_batches("readings", vec![create_batch(0, 5), create_batch(5, 5)]).unwrap();
+    db.save_table_dual("readings", &dir).unwrap();
+
+    assert!(dir.join("readings.parquet").exists(), "Parquet file should be written");
+    assert!(dir.join("readings.arrow").exists(), "Arrow cache should be written");
+
+    // Remove the cache and reload from Parquet only
+    std::fs::remove_file(dir.join("readings.arrow")).unwrap();
+    let reloaded = Database::open_writable(&dir).unwrap();
+    let loaded = reloaded.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(loaded, vec!["readings".to_string()]);
+    assert_eq!(reloaded.execute_count_query("SELECT COUNT(*) FROM readings").unwrap(), 10);
+    assert!(dir.join("readings.arrow").exists(), "Arrow cache should be rebuilt from Parquet");
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Parquet persistence test passed");
+}
+
+/// Test that views are registered, listed, persisted and restored on open
+#[test]
+fn test_views_persist_in_project() {
+    let dir = temp_project_dir("views");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("events", vec![create_batch(0, 10)]).unwrap();
+    db.save_table_dual("events", &dir).unwrap();
+
+    let (name, sql) = Database::parse_create_view("CREATE VIEW recent AS SELECT * FROM events WHERE id >= 5").unwrap();
+    assert_eq!(name, "recent");
+    db.create_view(&name, &sql).unwrap();
+
+    let views = db.get_views().unwrap();
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].name, "recent");
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM recent").unwrap(), 5);
+
+    let reopened = Database::open_writable(&dir).unwrap();
+    reopened.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reopened.get_views().unwrap().len(), 1);
+    assert_eq!(reopened.execute_count_query("SELECT COUNT(*) FROM recent").unwrap(), 5);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ View persistence test passed");
+}
+
+/// Test that read-only projects reject mutations by statement kind, not by keyword
+#[test]
+fn test_read_only_validation_uses_logical_plan() {
+    let dir = temp_project_dir("readonly");
+    let writable = Database::open_writable(&dir).unwrap();
+    let schema = Arc::new(Schema::new(vec![Field::new("created_date", DataType::Utf8, true)]));
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(StringArray::from(vec!["2024-01-01"]))]).unwrap();
+    writable.insert_record_batch("updates", &batch).unwrap();
+    writable.save_table_dual("updates", &dir).unwrap();
+
+    let db = Database::open_readonly(&dir).unwrap();
+    assert!(db.is_readonly());
+    db.load_all_tables_from_directory(&dir).unwrap();
+    let db = Arc::new(db);
+
+    // Identifiers containing keywords are fine
+    assert!(QueryExecutor::validate_read_only(&db, "SELECT created_date FROM updates").is_ok());
+    assert!(QueryExecutor::execute(&db, "SELECT created_date FROM updates").is_ok());
+
+    // Statements that mutate are rejected
+    assert!(QueryExecutor::validate_read_only(&db, "CREATE TABLE t AS SELECT * FROM updates").is_err());
+    assert!(QueryExecutor::validate_read_only(&db, "INSERT INTO updates VALUES ('x')").is_err());
+    assert!(QueryExecutor::validate_read_only(&db, "DROP TABLE updates").is_err());
+    assert!(db.create_view("v", "SELECT * FROM updates").is_err());
+
+    assert!(Database::open_readonly(dir.join("missing")).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Read-only validation test passed");
+}
+
+/// Test that CSV files are streamed in bounded batches with progress and proper quoting
+#[test]
+fn test_streaming_csv_ingestion() {
+    let dir = temp_project_dir("csv_ingest");
+    let csv_path = dir.join("people.csv");
+    std::fs::write(&csv_path, "exported by sensor tool\n\
+        id;name;score;seen\n\
+        1;\"Smith; John\";1.5;12:00:00.5\n\
+        2;\"Multi\nline\";N/A;12:00:01\n\
+        3;Alice;2.25;\n\
+        4;Bob;3;12:00:02.125\n\
+        5;Carol;4.5;12:00:03\n").unwrap();
+
+    let db = Database::open_writable(&dir).unwrap();
+    let options = CsvIngestOptions {
+        infer_delimiter: true,
+        header_row: Some(1),
+        batch_size: 2,
+        ..CsvIngestOptions::default()
+    };
+    let mut updates = Vec::new();
+    let delimiter = db.ingest_csv("people", &csv_path, &options, &mut |p| updates.push(p)).unwrap();
+
+    assert_eq!(delimiter, ';');
+    assert_eq!(db.get_table_arrow_batches("people").unwrap().len(), 3, "Rows should be split into batches of 2");
+    let last = updates.last().unwrap();
+    assert_eq!(last.rows_read, 5);
+    assert_eq!(last.bytes_read, last.total_bytes);
+    assert!(updates.windows(2).all(|w| w[0].rows_read <= w[1].rows_read));
+
+    // Quoted delimiters and newlines stay inside their field
+    let rows = db.execute_query("SELECT name FROM people ORDER BY id").unwrap();
+    assert_eq!(rows[0][0], "Smith; John");
+    assert_eq!(rows[1][0], "Multi\nline");
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM people WHERE score IS NULL").unwrap(), 1);
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM people WHERE seen IS NULL").unwrap(), 1);
+
+    // The memory ceiling aborts oversized imports
+    let limited = CsvIngestOptions { memory_limit_bytes: Some(16), ..options };
+    assert!(db.ingest_csv("people_small", &csv_path, &limited, &mut |_| {}).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Streaming CSV ingestion test passed");
+}
+
+/// Test that query results keep Arrow batches and expose typed, NULL-aware columns
+#[test]
+fn test_query_result_typed_accessors() {
+    let dir = temp_project_dir("query_result");
+    let db = Database::open_writable(&dir).unwrap();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("reading", DataType::Float64, true),
+    ]));
+    let batch = |ids: Vec<i64>, readings: Vec<Option<f64>>| RecordBatch::try_new(schema.clone(), vec![
+        Arc::new(Int64Array::from(ids)),
+        Arc::new(Float64Array::from(readings)),
+    ]).unwrap();
+    db.insert_record_batches("sensors", vec![
+        batch(vec![1, 2], vec![Some(1.5), None]),
+        batch(vec![3], vec![Some(-2.0)]),
+    ]).unwrap();
+    let db = Arc::new(db);
+
+    let result = QueryExecutor::execute(&db, "SELECT id, reading FROM sensors ORDER BY id").unwrap();
+    assert_eq!(result.columns, vec!["id", "reading"]);
+    assert_eq!(result.column_types, vec![DataType::Int64, DataType::Float64]);
+    assert_eq!(result.num_rows(), 3);
+
+    let reading_idx = result.column_index("reading").unwrap();
+    assert_eq!(result.f64_column(reading_idx), vec![Some(1.5), None, Some(-2.0)]);
+    assert_eq!(result.f64_column(0), vec![Some(1.0), Some(2.0), Some(3.0)]);
+    assert_eq!(result.display_value(2, reading_idx), "-2");
+    assert_eq!(result.to_record_batch().unwrap().num_rows(), 3);
+    assert_eq!(result.sample_every(2).unwrap().num_rows(), 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Typed query result test passed");
+}
+
+/// Test background query execution, pagination totals and cancellation
+#[test]
+fn test_background_query_and_cancel() {
+    let dir = temp_project_dir("background_query");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("numbers", vec![create_batch(0, 50), create_batch(50, 50)]).unwrap();
+    let db = Arc::new(db);
+
+    let wait = |running: &fresh::core::RunningQuery| {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        while !running.is_finished() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        running.result().expect("query did not finish")
+    };
+
+    let running = QueryExecutor::spawn_with_pagination(&db, "SELECT id, name FROM numbers ORDER BY id", 1, 30).unwrap();
+    let result = wait(&running).unwrap();
+    assert_eq!(result.num_rows(), 30);
+    assert_eq!(result.total_rows, Some(100));
+    assert_eq!(result.f64_column(0).first(), Some(&Some(30.0)));
+
+    // Cancelling reports an error instead of a partial result
+    let slow = QueryExecutor::spawn_with_pagination(
+        &db,
+        "SELECT MAX(a.id + b.id + c.id + d.id) FROM numbers a \
+         CROSS JOIN numbers b CROSS JOIN numbers c CROSS JOIN numbers d WHERE a.id < 30 AND b.id < 30",
+        0,
+        25,
+    ).unwrap();
+    slow.cancel();
+    assert!(slow.is_finished());
+    assert!(slow.result().unwrap().is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Background query test passed");
+}
+
+/// Test that query history and saved queries persist in the project folder
+#[test]
+fn test_query_library_persistence() {
+    let dir = temp_project_dir("query_library");
+
+    let mut library = QueryLibrary::open(&dir, false).unwrap();
+    let ms = std::time::Duration::from_millis;
+    library.record(QueryHistoryEntry::new("SELECT * FROM sales", ms(12), Some(40), None)).unwrap();
+    library.record(QueryHistoryEntry::new("SELECT nope FROM sales", ms(1), None, Some("No field named nope".to_string()))).unwrap();
+    library.record(QueryHistoryEntry::new("SELECT region, SUM(total) FROM sales GROUP BY region", ms(30), Some(4), None)).unwrap();
+    library.save_query("by region", "SELECT region, SUM(total) FROM sales GROUP BY region").unwrap();
+    library.save_query("all sales", "SELECT * FROM sales").unwrap();
+    library.save_query("by region", "SELECT region FROM sales").unwrap();
+
+    let reopened = QueryLibrary::open(&dir, false).unwrap();
+    assert_eq!(reopened.history().len(), 3);
+    assert_eq!(reopened.history()[0].row_count, Some(40));
+    assert!(!reopened.history()[1].succeeded());
+
+    // Search is case-insensitive, matches errors and returns newest first
+    let matches = reopened.search_history("group by");
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].duration_ms, 30);
+    assert_eq!(reopened.search_history("NOPE").len(), 1);
+    assert_eq!(reopened.search_history("").first().unwrap().row_count, Some(4));
+
+    let names: Vec<&str> = reopened.saved_queries().iter().map(|q| q.name.as_str()).collect();
+    assert_eq!(names, vec!["all sales", "by region"]);
+    assert_eq!(reopened.saved_query("by region").unwrap().sql, "SELECT region FROM sales");
+
+    // Read-only projects keep history in memory and refuse to change saved queries
+    let mut readonly = QueryLibrary::open(&dir, true).unwrap();
+    readonly.record(QueryHistoryEntry::new("SELECT 1", ms(1), Some(1), None)).unwrap();
+    assert!(readonly.save_query("one", "SELECT 1").is_err());
+    assert!(readonly.delete_saved_query("all sales").is_err());
+    assert_eq!(QueryLibrary::open(&dir, false).unwrap().history().len(), 3);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Query library test passed");
+}
+
+/// Test attaching CSV, Parquet and NDJSON files as lazily scanned tables
+#[test]
+fn test_external_tables() {
+    let dir = temp_project_dir("external_tables");
+    let data_dir = temp_project_dir("external_tables_data");
+    std::fs::write(data_dir.join("sensors.csv"), "id;reading;ok\n1;1.5;true\n2;;false\n3;4.25;true\n").unwrap();
+    let logs = data_dir.join("logs");
+    std::fs::create_dir_all(&logs).unwrap();
+    std::fs::write(logs.join("a.csv"), "level,count\nINFO,3\nWARN,1\n").unwrap();
+    std::fs::write(logs.join("b.csv"), "level,count\nINFO,5\n").unwrap();
+    std::fs::write(logs.join("notes.txt"), "ignored").unwrap();
+    std::fs::write(data_dir.join("events.ndjson"), "{\"kind\":\"open\",\"n\":1}\n{\"kind\":\"close\",\"n\":2}\n").unwrap();
+
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("numbers", vec![create_batch(0, 10)]).unwrap();
+    db.save_table_parquet("numbers", &data_dir.join("numbers.parquet")).unwrap();
+
+    let options = ExternalTableOptions::default();
+    db.attach_external_table("sensors", &data_dir.join("sensors.csv"), &options).unwrap();
+    db.attach_external_table("events", &data_dir.join("events.ndjson"), &options).unwrap();
+    db.attach_external_table("numbers_file", &data_dir.join("numbers.parquet"), &options).unwrap();
+    let folder_options = ExternalTableOptions { format: Some(ExternalFormat::Csv), ..Default::default() };
+    db.attach_external_table("logs", &logs, &folder_options).unwrap();
+    assert!(db.attach_external_table("numbers", &data_dir.join("sensors.csv"), &options).is_err());
+    assert!(db.insert_record_batches("sensors", vec![create_batch(0, 1)]).is_err());
+
+    let types = db.get_column_types("SELECT * FROM sensors").unwrap();
+    assert_eq!(types, vec![DataType::Int64, DataType::Float64, DataType::Boolean]);
+    let rows = db.execute_query("SELECT id FROM sensors WHERE reading IS NULL").unwrap();
+    assert_eq!(rows, vec![vec!["2".to_string()]]);
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM numbers_file WHERE id >= 5").unwrap(), 5);
+    assert_eq!(db.execute_count_query("SELECT SUM(count) FROM logs WHERE level = 'INFO'").unwrap(), 8);
+    assert_eq!(db.execute_count_query("SELECT SUM(n) FROM events").unwrap(), 3);
+
+    let tables = db.get_tables().unwrap();
+    let sensors = tables.iter().find(|t| t.name == "sensors").unwrap();
+    assert!(sensors.is_external);
+    assert_eq!(sensors.columns.len(), 3);
+    assert!(!tables.iter().find(|t| t.name == "numbers").unwrap().is_external);
+
+    // Attached tables are re-attached when the project is opened again
+    db.detach_external_table("events").unwrap();
+    let reopened = Database::open_writable(&dir).unwrap();
+    let loaded = reopened.load_all_tables_from_directory(&dir).unwrap();
+    assert!(loaded.contains(&"logs".to_string()));
+    assert!(!loaded.contains(&"events".to_string()));
+    assert_eq!(reopened.external_tables().len(), 3);
+    assert_eq!(reopened.execute_count_query("SELECT COUNT(*) FROM logs").unwrap(), 3);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&data_dir);
+    println!("✅ External table test passed");
+}
+
+/// Test that every Arrow type is formatted and NULLs never show a placeholder value
+#[test]
+fn test_value_formatting_handles_nulls_and_all_types() {
+    use datafusion::arrow::array::{ArrayRef, BooleanArray, Date32Array, Decimal128Array, DictionaryArray, DurationMillisecondArray, Float32Array, Int32Array, ListArray, StructArray, UInt8Array};
+    use datafusion::arrow::datatypes::{Int32Type, Int8Type};
+    use fresh::core::QueryResult;
+
+    let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+        Some(vec![Some(1), None, Some(3)]),
+        None,
+    ]);
+    let structs = StructArray::from(vec![
+        (Arc::new(Field::new("a", DataType::Int64, true)), Arc::new(Int64Array::from(vec![Some(7), None])) as ArrayRef),
+    ]);
+    let dictionary: DictionaryArray<Int8Type> = vec![Some("north"), None].into_iter().collect();
+    let columns: Vec<(&str, ArrayRef)> = vec![
+        ("int", Arc::new(Int64Array::from(vec![Some(0), None]))),
+        ("float", Arc::new(Float64Array::from(vec![Some(2.0), None]))),
+        ("bool", Arc::new(BooleanArray::from(vec![Some(false), None]))),
+        ("text", Arc::new(StringArray::from(vec![Some("x"), None]))),
+        ("date", Arc::new(Date32Array::from(vec![Some(19_783), None]))),
+        ("decimal", Arc::new(Decimal128Array::from(vec![Some(12_345), None]).with_precision_and_scale(10, 2).unwrap())),
+        ("int32", Arc::new(Int32Array::from(vec![Some(-4), None]))),
+        ("uint8", Arc::new(UInt8Array::from(vec![Some(255), None]))),
+        ("float32", Arc::new(Float32Array::from(vec![Some(0.5), None]))),
+        ("list", Arc::new(list)),
+        ("struct", Arc::new(structs)),
+        ("dictionary", Arc::new(dictionary)),
+        ("duration", Arc::new(DurationMillisecondArray::from(vec![Some(1_500), None]))),
+    ];
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let result = QueryResult::from_batches(vec![batch]);
+
+    let rows = result.rows();
+    assert_eq!(rows[0][..9], ["0", "2", "false", "x", "2024-03-01", "123.45", "-4", "255", "0.5"]);
+    assert_eq!(rows[0][9], "[1, NULL, 3]");
+    assert_eq!(rows[0][10], "{a: 7}");
+    assert_eq!(rows[0][11], "north");
+    assert!(!rows[0][12].is_empty());
+    // The struct row itself is valid; only its field is NULL
+    assert_eq!(rows[1][10], "{a: NULL}");
+    assert!(rows[1].iter().enumerate().all(|(i, value)| i == 10 || value.is_empty()), "{:?}", rows[1]);
+    assert!(result.is_null(1, 0) && !result.is_null(0, 0));
+
+    // Aggregates produce decimals and other types the formatter used to debug-print
+    let dir = temp_project_dir("value_formatting");
+    let db = Arc::new(Database::open_writable(&dir).unwrap());
+    let result = QueryExecutor::execute(&db, "SELECT CAST(1.5 AS DECIMAL(10, 2)) AS d, CAST(NULL AS INT) AS n, DATE '2024-02-29' AS day").unwrap();
+    assert_eq!(result.rows()[0], vec!["1.50", "", "2024-02-29"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Value formatting test passed");
+}
+
+/// Test that a rolled back transaction leaves memory and the project folder untouched
+#[test]
+fn test_transaction_rollback_and_commit() {
+    let dir = temp_project_dir("transactions");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("events", vec![create_batch(0, 10)]).unwrap();
+    db.save_table_dual("events", &dir).unwrap();
+    db.create_view("recent", "SELECT * FROM events WHERE id >= 5").unwrap();
+
+    db.begin_transaction().unwrap();
+    assert!(db.begin_transaction().is_err());
+    db.insert_record_batches("events", vec![create_batch(10, 5)]).unwrap();
+    db.insert_record_batches("staging", vec![create_batch(0, 3)]).unwrap();
+    db.save_all_tables(&dir).unwrap();
+    assert!(!dir.join("staging.parquet").exists());
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 5);
+
+    db.rollback_transaction().unwrap();
+    assert!(!db.in_transaction());
+    let names: Vec<String> = db.get_tables().unwrap().into_iter().map(|t| t.name).collect();
+    assert!(!names.contains(&"staging".to_string()), "{:?}", names);
+    assert!(db.execute_count_query("SELECT COUNT(*) FROM staging").is_err());
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 10);
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM recent").unwrap(), 5);
+    assert!(!dir.join("staging.parquet").exists());
+
+    db.begin_transaction().unwrap();
+    db.insert_record_batches("staging", vec![create_batch(0, 3)]).unwrap();
+    db.save_table_dual("staging", &dir).unwrap();
+    db.commit_transaction().unwrap();
+    assert!(dir.join("staging.parquet").exists() && dir.join("staging.arrow").exists());
+    assert!(!dir.join(".fresh_transaction").exists());
+    assert!(db.commit_transaction().is_err());
+
+    // A commit that fails partway puts back every file it already replaced or removed
+    let views = std::fs::read_to_string(dir.join("views.json")).unwrap();
+    db.begin_transaction().unwrap();
+    db.insert_record_batches("events", vec![create_batch(10, 5)]).unwrap();
+    db.save_table_dual("events", &dir).unwrap();
+    db.drop_table("staging").unwrap();
+    db.drop_view("recent").unwrap();
+    db.insert_record_batches("zzz", vec![create_batch(0, 1)]).unwrap();
+    db.save_table_dual("zzz", &dir).unwrap();
+    std::fs::create_dir_all(dir.join("zzz.arrow").join("blocked")).unwrap();
+    assert!(db.commit_transaction().is_err());
+    std::fs::remove_dir_all(dir.join("zzz.arrow")).unwrap();
+    assert!(!db.in_transaction());
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 10);
+    assert_eq!(std::fs::read_to_string(dir.join("views.json")).unwrap(), views);
+    assert!(dir.join("staging.parquet").exists() && !dir.join("zzz.parquet").exists());
+    assert!(!dir.join(".fresh_transaction").exists() && !dir.join(".fresh_transaction_backup").exists());
+
+    let reopened = Database::open_writable(&dir).unwrap();
+    reopened.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reopened.execute_count_query("SELECT COUNT(*) FROM staging").unwrap(), 3);
+    assert_eq!(reopened.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 10);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Transaction test passed");
+}
+
+/// Test that clones can query every table and view, and that Arc<Database> holders can mutate
+#[test]
+fn test_clone_keeps_tables_queryable() {
+    let dir = temp_project_dir("clone");
+    let db = Arc::new(Database::open_writable(&dir).unwrap());
+    db.insert_record_batches("events", vec![create_batch(0, 10), create_batch(10, 5)]).unwrap();
+    db.save_table_dual("events", &dir).unwrap();
+    // "a_recent" selects from "b_filtered", which sorts after it
+    db.create_view("b_filtered", "SELECT * FROM events WHERE id >= 5").unwrap();
+    db.create_view("a_recent", "SELECT * FROM b_filtered WHERE id < 12").unwrap();
+
+    let clone = (*db).clone();
+    assert_eq!(clone.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 15);
+    assert_eq!(clone.execute_count_query("SELECT COUNT(*) FROM a_recent").unwrap(), 7);
+    assert_eq!(clone.get_views().unwrap().len(), 2);
+
+    // Replacing a table in the clone leaves the original alone
+    clone.insert_record_batches("events", vec![create_batch(0, 2)]).unwrap();
+    assert_eq!(clone.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 2);
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM events").unwrap(), 15);
+
+    // Views load in dependency order when the project is reopened
+    let reopened = Arc::new(Database::open_writable(&dir).unwrap());
+    let loaded = reopened.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(loaded, vec!["events".to_string()]);
+    assert_eq!(reopened.execute_count_query("SELECT COUNT(*) FROM a_recent").unwrap(), 7);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Clone test passed");
+}
+
+/// Test that dropping, renaming, copying and truncating tables keeps the project files in sync
+#[test]
+fn test_table_management() {
+    let dir = temp_project_dir("table_management");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("events", vec![create_batch(0, 10)]).unwrap();
+    db.save_table_dual("events", &dir).unwrap();
+
+    db.rename_table("events", "log").unwrap();
+    assert!(!dir.join("events.arrow").exists() && dir.join("log.arrow").exists() && dir.join("log.parquet").exists());
+    assert!(db.execute_count_query("SELECT COUNT(*) FROM events").is_err());
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM log").unwrap(), 10);
+
+    db.copy_table("log", "log_copy").unwrap();
+    assert!(db.copy_table("log", "log_copy").is_err());
+    db.truncate_table("log").unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM log").unwrap(), 0);
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM log_copy").unwrap(), 10);
+
+    // Tables that views select from can't be dropped or renamed until the views are gone
+    db.create_view("recent", "SELECT * FROM log_copy").unwrap();
+    assert!(db.drop_table("recent").is_err());
+    let error = db.drop_table("log_copy").unwrap_err().to_string();
+    assert!(error.contains("recent"), "{}", error);
+    assert!(db.rename_table("log_copy", "log_copy2").is_err());
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM recent").unwrap(), 10);
+    db.drop_view("recent").unwrap();
+    db.drop_table("log_copy").unwrap();
+    assert!(!dir.join("log_copy.arrow").exists() && !dir.join("log_copy.parquet").exists());
+    assert!(db.drop_table("log_copy").is_err());
+
+    // Inside a transaction, files only change on commit
+    db.begin_transaction().unwrap();
+    db.drop_table("log").unwrap();
+    assert!(dir.join("log.arrow").exists());
+    db.rollback_transaction().unwrap();
+    assert!(dir.join("log.arrow").exists());
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM log").unwrap(), 0);
+
+    let reopened = Database::open_writable(&dir).unwrap();
+    let mut loaded = reopened.load_all_tables_from_directory(&dir).unwrap();
+    loaded.sort();
+    assert_eq!(loaded, vec!["log".to_string()]);
+    assert_eq!(reopened.get_tables().unwrap()[0].columns.len(), 2);
+
+    let readonly = Database::open_readonly(&dir).unwrap();
+    readonly.load_all_tables_from_directory(&dir).unwrap();
+    assert!(readonly.drop_table("log").is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Table management test passed");
+}
+
+/// Test that column profiles cover every statistic and are cached until the table changes
+#[test]
+fn test_profile_table() {
+    use datafusion::arrow::array::ArrayRef;
+
+    let dir = temp_project_dir("profile");
+    let db = Database::open_writable(&dir).unwrap();
+    let columns: Vec<(&str, ArrayRef)> = vec![
+        ("amount", Arc::new(Float64Array::from(vec![Some(1.0), Some(2.0), Some(3.0), Some(4.0), None, Some(2.0)]))),
+        ("city", Arc::new(StringArray::from(vec![Some("Oslo"), Some("Rome"), Some("Oslo"), None, Some("Lima"), Some("Oslo")]))),
+    ];
+    db.insert_record_batches("sales", vec![RecordBatch::try_from_iter(columns).unwrap()]).unwrap();
+
+    let profile = db.profile_table("sales").unwrap();
+    assert_eq!(profile.row_count, 6);
+    let amount = &profile.columns[0];
+    assert_eq!(amount.null_count, 1);
+    assert_eq!(amount.distinct_count, Some(4));
+    assert!(amount.distinct_exact);
+    assert_eq!((amount.min.as_deref(), amount.max.as_deref()), (Some("1"), Some("4")));
+    assert!((amount.mean.unwrap() - 2.4).abs() < 1e-9);
+    assert!(amount.stddev.unwrap() > 0.0);
+    assert_eq!(amount.quantiles.len(), fresh::core::PROFILE_QUANTILES.len());
+    assert_eq!(amount.top_values[0], ("2".to_string(), 2));
+    assert_eq!(amount.histogram.iter().map(|bin| bin.count).sum::<usize>(), 5);
+    assert_eq!(amount.histogram.last().unwrap().end, 4.0);
+    assert!(amount.length.is_none());
+
+    let city = &profile.columns[1];
+    assert_eq!(city.null_count, 1);
+    assert_eq!(city.distinct_count, Some(3));
+    // Ties are ordered by value
+    assert_eq!(city.top_values, [("Oslo".to_string(), 3), ("Lima".to_string(), 1), ("Rome".to_string(), 1)]);
+    assert_eq!(city.length.map(|l| (l.min, l.max)), Some((4, 4)));
+    assert!(city.mean.is_none() && city.histogram.is_empty());
+
+    // Cached until the table is replaced
+    assert!(Arc::ptr_eq(&profile, &db.profile_table("sales").unwrap()));
+    db.truncate_table("sales").unwrap();
+    let truncated = db.profile_table("sales").unwrap();
+    assert_eq!(truncated.row_count, 0);
+    assert!(truncated.columns[1].top_values.is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Profile test passed");
+}
+
+/// Test that data-quality rules find violating rows, persist, and re-run after imports
+#[test]
+fn test_quality_rules() {
+    use fresh::core::QualityRule;
+
+    let dir = temp_project_dir("quality");
+    let db = Database::open_writable(&dir).unwrap();
+    let csv_path = dir.join("orders.csv");
+    std::fs::write(&csv_path, "id,customer,amount,status,ts\n1,10,5.5,open,1\n2,11,-1,open,2\n2,,250,void,2\n4,99,20,OPEN,1\n").unwrap();
+    db.stream_insert_csv_with_header_row("orders", &csv_path, ',', 0).unwrap();
+    db.insert_record_batches("customers", vec![create_batch(10, 2)]).unwrap();
+
+    let rules = vec![
+        QualityRule::NotNull { column: "customer".to_string() },
+        QualityRule::Unique { column: "id".to_string() },
+        QualityRule::Range { column: "amount".to_string(), min: Some(0.0), max: Some(100.0) },
+        QualityRule::Regex { column: "status".to_string(), pattern: "^[a-z]+$".to_string() },
+        QualityRule::AllowedValues { column: "status".to_string(), values: vec!["open".to_string(), "closed".to_string()] },
+        QualityRule::ForeignKey { column: "customer".to_string(), referenced_table: "customers".to_string(), referenced_column: "id".to_string() },
+        QualityRule::Monotonic { column: "ts".to_string(), strict: true },
+        QualityRule::NotNull { column: "missing".to_string() },
+    ];
+    for rule in rules {
+        db.add_quality_rule("orders", rule).unwrap();
+    }
+
+    let report = db.validate_table("orders").unwrap();
+    let rows: Vec<Vec<usize>> = report.results.iter().map(|r| r.violating_rows.clone()).collect();
+    assert_eq!(rows[..7], [vec![2], vec![1, 2], vec![1, 2], vec![3], vec![2, 3], vec![3], vec![2, 3]]);
+    assert!(report.results[7].error.is_some());
+    assert!(!report.passed());
+    assert_eq!(db.get_tables().unwrap().iter().find(|t| t.name == "orders").unwrap().quality_violations, Some(11));
+
+    // The violations table keeps the row index and every column
+    db.create_violations_table("orders", 2, "bad_amounts").unwrap();
+    let violations = db.execute_query("SELECT row_index, amount FROM bad_amounts ORDER BY row_index").unwrap();
+    assert_eq!(violations, vec![vec!["1", "-1"], vec!["2", "250"]]);
+
+    // Rules persist and re-run when the table is imported again
+    std::fs::write(&csv_path, "id,customer,amount,status,ts\n1,10,5,open,1\n2,11,6,open,2\n").unwrap();
+    let reopened = Database::open_writable(&dir).unwrap();
+    reopened.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reopened.quality_rules("orders").len(), 8);
+    reopened.insert_record_batches("customers", vec![create_batch(10, 2)]).unwrap();
+    reopened.stream_insert_csv_with_header_row("orders", &csv_path, ',', 0).unwrap();
+    let report = reopened.quality_report("orders").unwrap();
+    assert_eq!(report.violation_count(), 0);
+    assert_eq!(report.results.iter().filter(|r| !r.passed()).count(), 1);
+
+    reopened.rename_table("orders", "sales").unwrap();
+    assert_eq!(reopened.quality_rules("sales").len(), 8);
+    reopened.remove_quality_rule("sales", 7).unwrap();
+    assert!(reopened.validate_table("sales").unwrap().passed());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Quality rules test passed");
+}
+
+/// Test that declared keys are checked on import, persist, and mark the key columns
+#[test]
+fn test_table_keys() {
+    use fresh::core::DuplicateDetectionConfig;
+
+    let dir = temp_project_dir("keys");
+    let db = Database::open_writable(&dir).unwrap();
+    let csv_path = dir.join("orders.csv");
+    std::fs::write(&csv_path, "region,number,code\neu,1,a\neu,2,b\nus,1,c\n").unwrap();
+    db.stream_insert_csv_with_header_row("orders", &csv_path, ',', 0).unwrap();
+
+    // A single column that repeats is rejected; the composite key is accepted
+    let error = db.set_primary_key("orders", &["number".to_string()]).unwrap_err();
+    assert!(error.to_string().contains("duplicated"), "{}", error);
+    db.set_primary_key("orders", &["region".to_string(), "number".to_string()]).unwrap();
+    db.add_unique_constraint("orders", &["code".to_string()]).unwrap();
+    assert!(db.set_primary_key("orders", &["missing".to_string()]).is_err());
+
+    let tables = db.get_tables().unwrap();
+    let orders = tables.iter().find(|t| t.name == "orders").unwrap();
+    let key_columns: Vec<&str> = orders.columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.as_str()).collect();
+    assert_eq!(key_columns, vec!["region", "number"]);
+    assert_eq!(orders.keys.unique, vec![vec!["code".to_string()]]);
+    assert_eq!(db.match_key("orders"), vec!["region", "number"]);
+    assert!(DuplicateDetectionConfig::for_key(&db.match_key("orders")).ignore_columns.contains("region"));
+
+    // Importing rows that break a key fails and keeps the previous rows
+    std::fs::write(&csv_path, "region,number,code\neu,1,a\neu,1,b\n").unwrap();
+    assert!(db.stream_insert_csv_with_header_row("orders", &csv_path, ',', 0).is_err());
+    std::fs::write(&csv_path, "region,number,code\neu,1,a\n,2,b\n").unwrap();
+    let error = db.stream_insert_csv_with_header_row("orders", &csv_path, ',', 0).unwrap_err();
+    assert!(error.to_string().contains("NULL"), "{}", error);
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM orders").unwrap(), 3);
+
+    // Keys persist with the project and follow renames
+    db.save_all_tables(&dir).unwrap();
+    let reopened = Database::open_writable(&dir).unwrap();
+    reopened.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reopened.table_keys("orders").primary_key, vec!["region", "number"]);
+    reopened.rename_table("orders", "sales").unwrap();
+    assert_eq!(reopened.match_key("sales"), vec!["region", "number"]);
+    assert!(reopened.table_keys("orders").is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Table keys test passed");
+}
+
+/// Test the SQL function library and registering functions from outside the crate
+#[test]
+fn test_sql_functions() {
+    use datafusion::logical_expr::{create_udf, ColumnarValue, Volatility};
+
+    let db = Database::open_writable(temp_project_dir("functions")).unwrap();
+    let value = |sql: &str| db.execute_query(sql).unwrap()[0][0].clone();
+
+    assert_eq!(value("SELECT seconds_since_midnight('01:02:03.5')"), "3723.5");
+    assert_eq!(value("SELECT seconds_since_midnight(CAST('2024-03-01T10:30:00' AS TIMESTAMP))"), "37800");
+    assert_eq!(value("SELECT time_of_day(CAST('2024-03-01T10:30:00' AS TIMESTAMP))"), "10:30:00");
+
+    // A small step back is not a new day; a large one is
+    let elapsed = db.execute_query(
+        "SELECT unwrap_midnight(t) OVER (ORDER BY i) FROM (VALUES (1, '23:00:00'), (2, '23:30:00'), (3, '00:15:00'), (4, '00:10:00'), (5, '01:00:00')) AS v(i, t) ORDER BY i"
+    ).unwrap();
+    let elapsed: Vec<&str> = elapsed.iter().map(|row| row[0].as_str()).collect();
+    assert_eq!(elapsed, vec!["82800", "84600", "87300", "87000", "90000"]);
+
+    let distance: f64 = value("SELECT haversine(0, 0, 0, 1)").parse().unwrap();
+    assert!((distance - 111.195).abs() < 0.01, "{}", distance);
+
+    assert_eq!(value(r"SELECT regexp_extract('order-123-x', 'order-(\d+)')"), "123");
+    assert_eq!(value(r"SELECT regexp_extract('order-123-x', 'order-(\d+)', 0)"), "order-123");
+    assert_eq!(value(r"SELECT regexp_extract('none', '\d+') IS NULL"), "true");
+
+    assert_eq!(value("SELECT percentile_cont(x, 0.5) FROM (VALUES (4), (1), (3), (2)) AS v(x)"), "2.5");
+    assert_eq!(value("SELECT percentile_cont(x, 0.9) FROM (VALUES (10), (20)) AS v(x)"), "19");
+    assert!(db.execute_query("SELECT percentile_cont(x, 2) FROM (VALUES (1)) AS v(x)").is_err());
+    assert_eq!(value("SELECT mode(x) FROM (VALUES ('a'), ('b'), ('b'), (NULL)) AS v(x)"), "b");
+    assert_eq!(value("SELECT mode(x) FROM (VALUES (2), (1)) AS v(x)"), "1");
+    db.insert_record_batches("people", vec![create_batch(0, 6), create_batch(0, 3)]).unwrap();
+    let modes = db.execute_query("SELECT id % 2, mode(id), percentile_cont(id, 0.5) FROM people GROUP BY id % 2 ORDER BY 1").unwrap();
+    assert_eq!(modes, vec![vec!["0", "0", "2"], vec!["1", "1", "2"]]);
+
+    // Registered functions survive clones
+    let double = create_udf(
+        "double_it",
+        vec![DataType::Float64],
+        Arc::new(DataType::Float64),
+        Volatility::Immutable,
+        Arc::new(|args: &[ColumnarValue]| match &args[0] {
+            ColumnarValue::Array(array) => {
+                let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+                Ok(ColumnarValue::Array(Arc::new(array.iter().map(|v| v.map(|v| v * 2.0)).collect::<Float64Array>())))
+            }
+            ColumnarValue::Scalar(datafusion::scalar::ScalarValue::Float64(v)) => {
+                Ok(ColumnarValue::Scalar(datafusion::scalar::ScalarValue::Float64(v.map(|v| v * 2.0))))
+            }
+            ColumnarValue::Scalar(other) => panic!("unexpected {:?}", other),
+        }),
+    );
+    db.register_function(double);
+    assert_eq!(value("SELECT double_it(2.5)"), "5");
+    assert_eq!(db.clone().execute_query("SELECT double_it(1)").unwrap()[0][0], "2");
+
+    println!("✅ SQL functions test passed");
+}
+
+/// Test that date-times keep their date and zone from import to display
+#[test]
+fn test_datetime_time_zones() {
+    use datafusion::arrow::datatypes::TimeUnit;
+    use fresh::core::datetime::DateTimeFormat;
+
+    let dir = temp_project_dir("datetime");
+    let csv_path = dir.join("log.csv");
+    std::fs::write(&csv_path, "at,local,clock,value\n\
+        2024-03-01T23:30:00+01:00,2024-03-01 23:30:00,23:30:00,1\n\
+        2024-03-02T00:15:00.5+01:00,2024-03-02 00:15:00.5,00:15:00.5,2\n\
+        2024-03-03T08:00:00+01:00,2024-03-03 08:00:00,08:00,3\n").unwrap();
+
+    // The zone is detected from the offsets in the file
+    let db = Database::open_writable(&dir).unwrap();
+    db.ingest_csv("log", &csv_path, &CsvIngestOptions::default(), &mut |_| {}).unwrap();
+    let schema = db.get_table_arrow_batch("log").unwrap().schema();
+    assert_eq!(schema.field(0).data_type(), &DataType::Timestamp(TimeUnit::Microsecond, Some("+01:00".into())));
+    assert_eq!(schema.field(2).data_type(), &DataType::Time64(TimeUnit::Microsecond));
+
+    let rows = db.execute_query("SELECT at, local, clock FROM log ORDER BY value").unwrap();
+    assert_eq!(rows[0], vec!["2024-03-01 23:30:00", "2024-03-01 23:30:00", "23:30:00"]);
+    assert_eq!(rows[1], vec!["2024-03-02 00:15:00.500", "2024-03-02 00:15:00.500", "00:15:00.500"]);
+    assert_eq!(rows[2][2], "08:00:00");
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM log WHERE at = local").unwrap(), 3);
+    assert_eq!(db.execute_query("SELECT seconds_since_midnight(at) FROM log ORDER BY value").unwrap()[0][0], "84600");
+
+    // A chosen zone reads naive values in that zone and shows instants in it
+    let new_york = CsvIngestOptions { time_zone: Some("America/New_York".to_string()), ..CsvIngestOptions::default() };
+    db.ingest_csv("log_ny", &csv_path, &new_york, &mut |_| {}).unwrap();
+    let rows = db.execute_query("SELECT at, local FROM log_ny ORDER BY value").unwrap();
+    assert_eq!(rows[0], vec!["2024-03-01 17:30:00", "2024-03-01 23:30:00"]);
+    let unknown = CsvIngestOptions { time_zone: Some("Mars/Olympus".to_string()), ..CsvIngestOptions::default() };
+    assert!(db.ingest_csv("log_bad", &csv_path, &unknown, &mut |_| {}).is_err());
+
+    // Dates and zones survive saving and reopening the project, including zone-less
+    // timestamps that all fall on 1970-01-01
+    let seen_schema = Arc::new(Schema::new(vec![Field::new("seen", DataType::Timestamp(TimeUnit::Second, None), true)]));
+    let seen = |values: Vec<Option<i64>>| RecordBatch::try_new(seen_schema.clone(), vec![
+        Arc::new(datafusion::arrow::array::TimestampSecondArray::from(values)) as _,
+    ]).unwrap();
+    db.insert_record_batches("epoch", vec![seen(vec![Some(0), Some(3_600)])]).unwrap();
+    db.save_all_tables(&dir).unwrap();
+
+    // Times of day saved as timestamps on 1970-01-01 by versions that did not record
+    // the file format come back as Time64
+    let legacy_file = std::fs::File::create(dir.join("legacy.parquet")).unwrap();
+    let mut writer = datafusion::parquet::arrow::ArrowWriter::try_new(legacy_file, seen_schema.clone(), None).unwrap();
+    writer.write(&seen(vec![Some(43_200), None, Some(43_201)])).unwrap();
+    writer.close().unwrap();
+
+    let reopened = Database::open_writable(&dir).unwrap();
+    reopened.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reopened.execute_query("SELECT at FROM log ORDER BY value").unwrap()[2][0], "2024-03-03 08:00:00");
+    assert_eq!(reopened.get_table_arrow_batch("epoch").unwrap().schema().field(0).data_type(), &DataType::Timestamp(TimeUnit::Second, None));
+    assert_eq!(reopened.execute_query("SELECT seen FROM epoch").unwrap()[1][0], "1970-01-01 01:00:00");
+    assert_eq!(reopened.get_table_arrow_batch("legacy").unwrap().schema().field(0).data_type(), &DataType::Time64(TimeUnit::Microsecond));
+    assert_eq!(reopened.execute_query("SELECT seen FROM legacy").unwrap()[2][0], "12:00:01");
+    assert!(reopened.get_table_arrow_batch("legacy").unwrap().schema().metadata().is_empty());
+
+    // The display format is configurable per database
+    db.set_datetime_format(DateTimeFormat::IsoWithOffset).unwrap();
+    assert_eq!(db.execute_query("SELECT at FROM log ORDER BY value").unwrap()[0][0], "2024-03-01 23:30:00 +01:00");
+    assert!(db.set_datetime_format(DateTimeFormat::Custom("%Q".to_string())).is_err());
+    let result = QueryExecutor::execute(&Arc::new(db.clone()), "SELECT at FROM log ORDER BY value").unwrap();
+    assert_eq!(result.display_value(0, 0), "2024-03-01 23:30:00 +01:00");
+    assert_eq!(reopened.execute_query("SELECT at FROM log ORDER BY value").unwrap()[0][0], "2024-03-01 23:30:00");
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Date-time and time zone test passed");
+}
+
+/// Test that named and positional parameters are typed, bound and remembered
+#[test]
+fn test_parameterized_queries() {
+    use fresh::core::params::{self, ParameterType, QueryParameter};
+
+    let dir = temp_project_dir("parameters");
+    let csv_path = dir.join("readings.csv");
+    std::fs::write(&csv_path, "device,at,value\n\
+        sensor-1,2024-03-01T10:00:00+01:00,1.5\n\
+        sensor-2,2024-03-01T11:00:00+01:00,2.5\n\
+        sensor-1,2024-03-01T12:00:00+01:00,3.5\n").unwrap();
+    let db = Arc::new(Database::open_writable(&dir).unwrap());
+    db.ingest_csv("readings", &csv_path, &CsvIngestOptions::default(), &mut |_| {}).unwrap();
+
+    // Parameters are found outside strings, comments and casts, and `:name` becomes `$name`
+    let sql = "-- @param device text = 'sensor-1'\n\
+        SELECT value, ':skip' AS s FROM readings /* :skip */ \
+        WHERE device = :device AND at >= :start_time AND value > $1 AND value::double < $limit";
+    assert_eq!(params::parameter_names(sql), vec!["device", "start_time", "1", "limit"]);
+    assert!(params::prepare_sql(sql).contains("device = $device AND at >= $start_time"));
+
+    // Types come from the comment header, then from the columns they are compared with
+    let mut parameters = db.query_parameters(sql, &[]);
+    let types: Vec<ParameterType> = parameters.iter().map(|p| p.param_type).collect();
+    assert_eq!(types, vec![ParameterType::Text, ParameterType::DateTime, ParameterType::Float, ParameterType::Float]);
+    assert_eq!(parameters[0].value, "sensor-1");
+    assert_eq!(parameters[2].label(), "$1");
+
+    // Date-times without an offset are read in the column's zone
+    parameters[1].value = "2024-03-01 11:00:00".to_string();
+    parameters[2].value = "0".to_string();
+    parameters[3].value = "10".to_string();
+    let result = QueryExecutor::execute_with_params(&db, sql, &parameters).unwrap();
+    assert_eq!(result.rows(), vec![vec!["3.5".to_string(), ":skip".to_string()]]);
+
+    // Values are bound, not spliced into the SQL
+    parameters[0].value = "x' OR '1'='1".to_string();
+    assert!(QueryExecutor::execute_with_params(&db, sql, &parameters).unwrap().is_empty());
+
+    // Paginated runs bind the page and the row count
+    parameters[0].value = "sensor-1".to_string();
+    parameters[1].value = "2024-03-01 00:00:00".to_string();
+    let running = QueryExecutor::spawn_with_params(&db, sql, &parameters, 0, 1).unwrap();
+    let result = loop {
+        if let Some(result) = running.result() {
+            break result.unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    };
+    assert_eq!((result.num_rows(), result.total_rows), (1, Some(2)));
+
+    // Invalid or missing values are reported per parameter
+    parameters[3].value = "ten".to_string();
+    assert!(parameters[3].validate().is_err());
+    assert!(QueryExecutor::execute_with_params(&db, sql, &parameters).is_err());
+    assert!(QueryParameter::new("limit", ParameterType::Integer).validate().is_err());
+
+    // Entered values survive edits to the query and are saved with it
+    let edited = db.query_parameters(&sql.replace("$limit", ":limit"), &parameters);
+    assert_eq!(edited[0].value, "sensor-1");
+    let mut library = QueryLibrary::open(&dir, false).unwrap();
+    library.save_query_with_parameters("by device", sql, &edited).unwrap();
+    let reopened = QueryLibrary::open(&dir, false).unwrap();
+    assert_eq!(reopened.saved_query("by device").unwrap().parameters, edited);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Parameterized query test passed");
+}
+
+/// Test the SQL editor's lexing, completion, bracket matching and error positions
+#[test]
+fn test_sql_editor_assist() {
+    use fresh::core::sql_assist::{self, CompletionKind, SqlTokenKind};
+
+    let dir = temp_project_dir("sql_assist");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("numbers", vec![create_batch(0, 3)]).unwrap();
+    let mixed = Arc::new(Schema::new(vec![Field::new("Reading Value", DataType::Float64, true)]));
+    let batch = RecordBatch::try_new(mixed, vec![Arc::new(Float64Array::from(vec![1.0]))]).unwrap();
+    db.insert_record_batches("sensors", vec![batch]).unwrap();
+    db.create_view("small_numbers", "SELECT id FROM numbers WHERE id < 2").unwrap();
+    let schema = db.sql_schema().unwrap();
+    assert!(schema.tables.iter().any(|t| t.name == "small_numbers" && t.columns[0].name == "id"));
+    assert!(schema.functions.iter().any(|f| f == "haversine"));
+    assert!(schema.functions.iter().any(|f| f == "count"));
+
+    // Half-typed SQL still lexes, and casts are not parameters
+    let kinds: Vec<SqlTokenKind> = sql_assist::tokenize("SELECT upper(name), x::date, :p FROM t -- note\nWHERE s = 'open")
+        .into_iter()
+        .filter(|t| t.kind != SqlTokenKind::Whitespace)
+        .map(|t| t.kind)
+        .collect();
+    assert_eq!(kinds, vec![
+        SqlTokenKind::Keyword, SqlTokenKind::Function, SqlTokenKind::Bracket, SqlTokenKind::Identifier,
+        SqlTokenKind::Bracket, SqlTokenKind::Punctuation, SqlTokenKind::Identifier, SqlTokenKind::Operator,
+        SqlTokenKind::Operator, SqlTokenKind::Identifier, SqlTokenKind::Punctuation, SqlTokenKind::Parameter,
+        SqlTokenKind::Keyword, SqlTokenKind::Identifier, SqlTokenKind::Comment, SqlTokenKind::Keyword,
+        SqlTokenKind::Identifier, SqlTokenKind::Operator, SqlTokenKind::String,
+    ]);
+
+    // Columns of the queried tables come first; names that need quotes are quoted
+    let sql = "SELECT n FROM numbers";
+    let completions = sql_assist::complete(sql, 8, &schema).unwrap();
+    assert_eq!(completions.replace, 7..8);
+    assert_eq!((completions.items[0].label.as_str(), completions.items[0].kind), ("name", CompletionKind::Column));
+    assert!(completions.items.iter().any(|c| c.label == "nullif" && c.insert == "nullif("));
+    let sql = "SELECT s.R FROM sensors AS s";
+    let completions = sql_assist::complete(sql, 10, &schema).unwrap();
+    assert_eq!(completions.items.len(), 1);
+    assert_eq!(completions.items[0].insert, "\"Reading Value\"");
+
+    // Only tables after FROM, nothing inside strings
+    let sql = "SELECT * FROM s";
+    let labels: Vec<String> = sql_assist::complete(sql, sql.len(), &schema).unwrap().items.into_iter().map(|c| c.label).collect();
+    assert_eq!(labels, vec!["sensors", "small_numbers"]);
+    assert!(sql_assist::complete("SELECT 'nu", 10, &schema).is_none());
+
+    // Brackets match across nesting
+    let sql = "SELECT max(abs(id)) FROM numbers";
+    assert_eq!(sql_assist::matching_bracket(sql, 11), Some((10, 18)));
+    assert_eq!(sql_assist::matching_bracket(sql, 19), Some((10, 18)));
+    assert_eq!(sql_assist::matching_bracket(sql, 3), None);
+
+    // DataFusion errors point at the unknown column, table or function, or the parser's position
+    for (sql, expected) in [
+        ("SELECT nmae FROM numbers", "nmae"),
+        ("SELECT n.nmae FROM numbers n", "nmae"),
+        ("SELECT * FROM numbrs", "numbrs"),
+        ("SELECT upperr(name) FROM numbers", "upperr"),
+        ("SELECT id\nFROM numbers WHERE id >", ""),
+    ] {
+        let message = db.check_query(sql).unwrap_err().to_string();
+        let span = sql_assist::error_span(sql, &message).unwrap_or_else(|| panic!("no span for {}", message));
+        if !expected.is_empty() {
+            assert_eq!(&sql[span], expected, "{}", message);
+        }
+    }
+    assert!(db.check_query("SELECT name FROM numbers WHERE id = :id").is_ok());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ SQL editor assist test passed");
+}
+
+/// Test that EXPLAIN ANALYZE returns the executed plan with per-operator metrics
+#[test]
+fn test_explain_analyze() {
+    use fresh::core::QueryParameter;
+
+    let dir = temp_project_dir("explain");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("numbers", vec![create_batch(0, 100), create_batch(100, 100)]).unwrap();
+
+    let sql = "SELECT name, COUNT(*) AS n FROM numbers WHERE id < :limit GROUP BY name ORDER BY name";
+    let mut limit = QueryParameter::new("limit", fresh::core::ParameterType::Integer);
+    limit.value = "50".to_string();
+    let plan = db.explain_analyze(sql, &[limit]).unwrap();
+
+    // The root produced the result rows; in-memory scans record no metrics
+    assert_eq!(plan.root.output_rows, Some(50));
+    let nodes = plan.nodes();
+    assert_eq!(nodes[0].id, 0);
+    assert_eq!(nodes.iter().map(|n| n.id).collect::<Vec<_>>(), (0..nodes.len()).collect::<Vec<_>>());
+    let filter = nodes.iter().find(|n| n.operator == "FilterExec").expect("no filter operator");
+    assert_eq!(filter.output_rows, Some(50));
+    assert!(filter.details.starts_with("FilterExec: id@0 < 50"), "{}", filter.details);
+    assert!(nodes.iter().any(|n| n.operator.starts_with("AggregateExec")));
+    let scan = nodes.iter().find(|n| n.children.is_empty()).unwrap();
+    assert_eq!((scan.operator.as_str(), scan.output_rows), ("MemoryExec", None));
+    assert!(nodes.iter().all(|n| n.partitions >= 1));
+    assert!(plan.text.contains("output_rows=50"));
+
+    // The slowest operators come first and all recorded time
+    let slowest = plan.slowest(3);
+    assert!(!slowest.is_empty() && slowest.len() <= 3);
+    let times: Vec<_> = slowest.iter().map(|id| nodes.iter().find(|n| n.id == *id).unwrap().elapsed_compute.unwrap()).collect();
+    assert!(times.windows(2).all(|w| w[0] >= w[1]));
+    let fractions: f64 = nodes.iter().map(|n| n.compute_fraction(plan.total_compute())).sum();
+    assert!((fractions - 1.0).abs() < 1e-6);
+
+    assert!(db.explain_analyze("SELECT nope FROM numbers", &[]).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Explain analyze test passed");
+}
+
+/// Test that query results can be materialized as tables, through the API and CREATE TABLE AS,
+/// and refreshed from their recorded source query
+#[test]
+fn test_materialize_query_as_table() {
+    use fresh::core::{ParameterType, QueryParameter};
+
+    let dir = temp_project_dir("materialize");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("numbers", vec![create_batch(0, 10)]).unwrap();
+
+    let mut limit = QueryParameter::new("limit", ParameterType::Integer);
+    limit.value = "4".to_string();
+    db.materialize_query("small", "SELECT id, name FROM numbers WHERE id < :limit", &[limit]).unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM small").unwrap(), 4);
+    assert!(dir.join("small.parquet").exists() && dir.join("small.arrow").exists());
+    let lineage = db.table_lineage("small").unwrap();
+    assert_eq!(lineage.sql, "SELECT id, name FROM numbers WHERE id < :limit");
+    assert_eq!(lineage.parameters[0].value, "4");
+    assert!(db.materialize_query("small", "SELECT 1", &[]).is_err());
+
+    // CREATE TABLE AS collects the query; OR REPLACE and IF NOT EXISTS are honoured
+    let statement = Database::parse_create_table_as("CREATE OR REPLACE TABLE evens AS SELECT id FROM numbers WHERE id % 2 = 0").unwrap();
+    assert_eq!((statement.name.as_str(), statement.or_replace, statement.if_not_exists), ("evens", true, false));
+    assert!(Database::parse_create_table_as("CREATE TABLE t (id INT)").is_none());
+    db.execute_sql("CREATE TABLE evens AS SELECT id FROM numbers WHERE id % 2 = 0;").unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM evens").unwrap(), 5);
+    assert!(db.execute_sql("CREATE TABLE evens AS SELECT 1 AS id").is_err());
+    db.execute_sql("CREATE TABLE IF NOT EXISTS evens AS SELECT 1 AS id").unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM evens").unwrap(), 5);
+    db.execute_sql("CREATE OR REPLACE TABLE evens AS SELECT id FROM numbers WHERE id % 2 = 0 AND id > 4").unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM evens").unwrap(), 2);
+    let tables = db.get_tables().unwrap();
+    assert!(tables.iter().any(|t| t.name == "evens" && t.lineage.is_some()));
+    assert!(tables.iter().any(|t| t.name == "numbers" && t.lineage.is_none()));
+
+    // Refreshing picks up new source rows; lineage follows renames and survives reloads
+    db.insert_record_batches("numbers", vec![create_batch(0, 20)]).unwrap();
+    db.refresh_table("evens").unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM evens").unwrap(), 7);
+    assert!(db.refresh_table("numbers").is_err());
+    db.rename_table("evens", "even_numbers").unwrap();
+    let reloaded = Database::open_writable(&dir).unwrap();
+    reloaded.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reloaded.execute_count_query("SELECT COUNT(*) FROM even_numbers").unwrap(), 7);
+    assert!(reloaded.table_lineage("even_numbers").unwrap().sql.contains("id % 2 = 0"));
+    assert_eq!(reloaded.table_lineage("small"), Some(lineage));
+    reloaded.drop_table("small").unwrap();
+    assert!(reloaded.table_lineage("small").is_none());
+
+    // Statements typed in a query window create the table the same way
+    let result = QueryExecutor::execute(&Arc::new(reloaded), "CREATE TABLE big_evens AS SELECT id FROM even_numbers WHERE id > 10").unwrap();
+    assert_eq!(result.num_rows(), 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Materialize query test passed");
+}
+
+/// Test that scripts run DDL and DML statement by statement, persist changed tables,
+/// report a status per statement and keep the rows of the last query
+#[test]
+fn test_sql_scripts() {
+    use fresh::core::script::{self, StatementStatus};
+
+    let dir = temp_project_dir("scripts");
+    let db = Database::open_writable(&dir).unwrap();
+
+    let statements = script::split_statements("SELECT ';' AS s; -- trailing; comment\n\n  SELECT 2;\n-- only a comment;\n");
+    assert_eq!(statements.len(), 2);
+    assert_eq!((statements[0].sql.as_str(), statements[0].line), ("SELECT ';' AS s", 1));
+    assert_eq!((statements[1].sql.as_str(), statements[1].line), ("SELECT 2", 3));
+    assert!(!script::is_script("SELECT 1;"));
+
+    let result = script::run_script(&db, "
+        CREATE TABLE items (id BIGINT, name VARCHAR, price DOUBLE, PRIMARY KEY (id));
+        INSERT INTO items VALUES (1, 'pen', 1.5), (2, 'ink', 4.0), (3, 'pad', 2.25);
+        UPDATE items SET price = price * 2 WHERE price > 2;
+        DELETE FROM items WHERE name = 'pen';
+        SELECT * FROM items ORDER BY id;
+        INSERT INTO items SELECT id + 10, name, price FROM items;
+    ", &[]);
+    let statuses: Vec<&StatementStatus> = result.statements.iter().map(|s| &s.status).collect();
+    assert_eq!(statuses, vec![
+        &StatementStatus::Succeeded("Table 'items' created".to_string()),
+        &StatementStatus::Succeeded("Inserted 3 rows into 'items'".to_string()),
+        &StatementStatus::Succeeded("Updated 2 rows in 'items'".to_string()),
+        &StatementStatus::Succeeded("Deleted 1 rows from 'items'".to_string()),
+        &StatementStatus::Succeeded("2 rows".to_string()),
+        &StatementStatus::Succeeded("Inserted 2 rows into 'items'".to_string()),
+    ]);
+    assert!(result.error().is_none() && result.changed_catalog());
+    let last = result.result.unwrap();
+    assert_eq!(last.rows(), &[
+        vec!["2".to_string(), "ink".to_string(), "8".to_string()],
+        vec!["3".to_string(), "pad".to_string(), "4.5".to_string()],
+    ]);
+    assert_eq!(db.table_keys("items").primary_key, vec!["id".to_string()]);
+
+    // Changed tables are saved in the project
+    let reloaded = Database::open_writable(&dir).unwrap();
+    reloaded.load_all_tables_from_directory(&dir).unwrap();
+    assert_eq!(reloaded.execute_count_query("SELECT COUNT(*) FROM items").unwrap(), 4);
+
+    // A failure skips the rest and undoes the transaction the script started
+    let result = script::run_script(&db, "BEGIN; DELETE FROM items WHERE id = 3; INSERT INTO items VALUES (2, 'dup', 1.0); SELECT 1", &[]);
+    assert!(matches!(result.statements[2].status, StatementStatus::Failed(ref e) if e.contains("rolled back")), "{:?}", result.statements[2].status);
+    assert_eq!(result.statements[3].status, StatementStatus::Skipped);
+    assert!(result.error().unwrap().starts_with("Line 1:"));
+    assert!(!db.in_transaction());
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM items").unwrap(), 4);
+
+    // execute_sql runs whole scripts; DROP removes the table and its files
+    db.execute_sql("CREATE TABLE IF NOT EXISTS items (id BIGINT); DROP TABLE items; DROP TABLE IF EXISTS items").unwrap();
+    assert!(!db.table_exists("items").unwrap());
+    assert!(!dir.join("items.parquet").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ SQL script test passed");
+}
+
+/// Test that query output is streamed to every export format with its types intact
+#[test]
+fn test_typed_exports() {
+    use datafusion::arrow::ipc::reader::{FileReader, StreamReader};
+    use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use fresh::core::{export, ExportFormat, ExportOptions};
+
+    let dir = temp_project_dir("exports");
+    let db = Database::open_writable(&dir).unwrap();
+    db.insert_record_batches("events", vec![create_batch(0, 3), create_batch(3, 4)]).unwrap();
+    let query = "SELECT id, CASE WHEN id = 1 THEN NULL ELSE name END AS name, id % 2 = 0 AS even, id * 1.5 AS score \
+                 FROM events WHERE id < :limit ORDER BY id";
+    let mut limit = fresh::core::QueryParameter::new("limit", fresh::core::ParameterType::Integer);
+    limit.value = "3".to_string();
+    let parameters = vec![limit];
+    let export = |format: ExportFormat| {
+        let path = dir.join(format!("out.{}", format.extension()));
+        let options = ExportOptions { format, ..Default::default() };
+        assert_eq!(db.export_query(query, &parameters, &path, &options).unwrap(), 3);
+        path
+    };
+
+    // JSON keeps numbers, booleans and NULLs
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(export(ExportFormat::Json)).unwrap()).unwrap();
+    assert_eq!(json[1], serde_json::json!({"id": 1, "name": null, "even": false, "score": 1.5}));
+    let ndjson = std::fs::read_to_string(export(ExportFormat::NdJson)).unwrap();
+    assert_eq!(ndjson.lines().count(), 3);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(ndjson.lines().nth(2).unwrap()).unwrap()["even"], true);
+
+    let parquet = std::fs::File::open(export(ExportFormat::Parquet)).unwrap();
+    let rows: usize = ParquetRecordBatchReaderBuilder::try_new(parquet).unwrap().build().unwrap()
+        .map(|batch| batch.unwrap().num_rows())
+        .sum();
+    assert_eq!(rows, 3);
+    let file = FileReader::try_new(std::fs::File::open(export(ExportFormat::ArrowFile)).unwrap(), None).unwrap();
+    assert_eq!(file.schema().field(2).data_type(), &DataType::Boolean);
+    let stream = StreamReader::try_new(std::fs::File::open(export(ExportFormat::ArrowStream)).unwrap(), None).unwrap();
+    assert_eq!(stream.map(|batch| batch.unwrap().num_rows()).sum::<usize>(), 3);
+
+    // CSV delimiter, quoting and NULL token are configurable
+    let mut options = ExportOptions::default();
+    options.csv.delimiter = b';';
+    options.csv.quote = b'\'';
+    options.csv.null_value = "NULL".to_string();
+    let path = dir.join("out.csv");
+    db.export_query("SELECT id, CASE WHEN id = 1 THEN NULL ELSE 'a;b' END AS name FROM events WHERE id < 2 ORDER BY id", &[], &path, &options).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "id;name\n0;'a;b'\n1;NULL\n");
+
+    // Results without rows still get a header or an empty array
+    db.export_query("SELECT id FROM events WHERE id < 0", &[], &path, &ExportOptions::default()).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "id\n");
+    let empty = db.execute_query_batches("SELECT id FROM events WHERE id < 0").unwrap();
+    let json_options = ExportOptions { format: ExportFormat::Json, ..Default::default() };
+    assert_eq!(export::export_batches(&empty, &dir.join("empty.json"), &json_options).unwrap(), 0);
+    assert_eq!(std::fs::read_to_string(dir.join("empty.json")).unwrap(), "[]");
+
+    // A query that fails while rows are written leaves no file behind
+    let missing = dir.join("missing.csv");
+    assert!(db.export_query("SELECT CAST(name AS INT) FROM events", &[], &missing, &ExportOptions::default()).is_err());
+    assert!(!missing.exists());
+
+    // A cancelled export stops before writing rows and leaves no file behind
+    let cancelled = std::sync::atomic::AtomicBool::new(true);
+    let path = dir.join("cancelled.csv");
+    assert!(db.export_query_cancellable("SELECT id FROM events", &[], &path, &ExportOptions::default(), &cancelled).is_err());
+    assert!(!path.exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ Typed export test passed");
+}
+
+/// Test that the headless CLI imports, queries, deduplicates and transforms a project
+#[test]
+fn test_cli_pipeline() {
+    let dir = temp_project_dir("cli_pipeline");
+    let project = dir.join("project");
+    let csv = dir.join("readings.csv");
+    std::fs::write(&csv, "id,run,value\n1,a,10\n2,a,12\n3,b,10\n4,b,12\n5,c,7\n").unwrap();
+    let run = |args: &[&str]| fresh::cli::run(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>());
+    let path = |p: &PathBuf| p.to_str().unwrap().to_string();
+
+    assert_eq!(run(&["import", &path(&project), &path(&csv), "--primary-key", "id"]), 0);
+    let db = Database::open_readonly(&project).unwrap();
+    db.load_all_tables_from_directory(&project).unwrap();
+    assert_eq!(db.match_key("readings"), vec!["id".to_string()]);
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM readings").unwrap(), 5);
+
+    // The key columns of a file with another delimiter are found in its detected header
+    let semi = dir.join("semi.csv");
+    std::fs::write(&semi, "id;name\n1;a\n2;b\n").unwrap();
+    assert_eq!(run(&["import", &path(&project), &path(&semi), "--primary-key", "id"]), 0);
+    let db = Database::open_readonly(&project).unwrap();
+    db.load_all_tables_from_directory(&project).unwrap();
+    assert_eq!(db.match_key("semi"), vec!["id".to_string()]);
+    assert_eq!(db.execute_count_query("SELECT COUNT(name) FROM semi").unwrap(), 2);
+
+    // An explicit comma is used even where detection would pick semicolons
+    let tags = dir.join("tags.csv");
+    std::fs::write(&tags, "id,a;b;c\n1,x;y;z\n").unwrap();
+    assert_eq!(run(&["import", &path(&project), &path(&tags), "--delimiter", ","]), 0);
+    let db = Database::open_readonly(&project).unwrap();
+    db.load_all_tables_from_directory(&project).unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(*) FROM tags WHERE \"a;b;c\" = 'x;y;z'").unwrap(), 1);
+
+    // Query output is exported in the format of the file extension
+    let output = dir.join("big.json");
+    assert_eq!(run(&["query", &path(&project), "SELECT id FROM readings WHERE value > :min ORDER BY id",
+        "--param", "min=11", "--output", &path(&output)]), 0);
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(json, serde_json::json!([{"id": 2}, {"id": 4}]));
+    assert_eq!(run(&["query", &path(&project), "DROP TABLE readings", "--read-only"]), 1);
+    assert_eq!(run(&["tables", &path(&project)]), 0);
+    assert_eq!(run(&["schema", &path(&project), "missing"]), 1);
+
+    // The key differs between copies, so runs a and b repeat each other and only run c is clean
+    let config = dir.join("dedupe.json");
+    std::fs::write(&config, r#"{"group_column": "run"}"#).unwrap();
+    let clean = dir.join("clean.arrow");
+    assert_eq!(run(&["dedupe", &path(&project), "readings", "--config", &path(&config), "--output", &path(&clean)]), 0);
+    let reader = datafusion::arrow::ipc::reader::FileReader::try_new(std::fs::File::open(&clean).unwrap(), None).unwrap();
+    assert_eq!(reader.map(|batch| batch.unwrap().num_rows()).sum::<usize>(), 1);
+
+    let steps = dir.join("transform.json");
+    std::fs::write(&steps, r#"[{"transformation_type": "row_id", "output_column_name": "row_number"}]"#).unwrap();
+    assert_eq!(run(&["transform", &path(&project), "readings", "--config", &path(&steps), "--table", "numbered"]), 0);
+    let db = Database::open_readonly(&project).unwrap();
+    db.load_all_tables_from_directory(&project).unwrap();
+    assert_eq!(db.execute_count_query("SELECT COUNT(row_number) FROM numbered").unwrap(), 5);
+
+    // Malformed arguments are usage errors
+    assert_eq!(run(&["import", &path(&project)]), fresh::cli::EXIT_USAGE);
+    assert_eq!(run(&["query", &path(&project), "SELECT 1", "--unknown"]), fresh::cli::EXIT_USAGE);
+    assert_eq!(run(&["frobnicate"]), fresh::cli::EXIT_USAGE);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    println!("✅ CLI pipeline test passed");
+}
@@ -37,12 +37,9 @@ fn create_test_data() -> QueryResult {
         vec!["10".to_string(), "55".to_string(), "B".to_string()],
     ];
     
-    QueryResult {
-        columns,
-        rows,
-        column_types: vec![DataType::Int64, DataType::Int64, DataType::Utf8],
-        total_rows: Some(10),
-    }
+    let mut result = QueryResult::from_rows(columns, vec![DataType::Int64, DataType::Int64, DataType::Utf8], rows);
+    result.total_rows = Some(10);
+    result
 }
 
 fn test_line_chart(data: &QueryResult) {
@@ -244,12 +241,8 @@ fn test_large_dataset_handling() {
         rows.push(vec![i.to_string(), (i * 2).to_string()]);
     }
     
-    let data = QueryResult {
-        columns,
-        rows,
-        column_types: vec![DataType::Int64, DataType::Int64],
-        total_rows: Some(1000),
-    };
+    let mut data = QueryResult::from_rows(columns, vec![DataType::Int64, DataType::Int64], rows);
+    data.total_rows = Some(1000);
 
     let config = PlotConfiguration {
         title: "Large Dataset Test".to_string(),